@@ -0,0 +1,364 @@
+//! Character budgets
+//!
+//! Counts characters the way agent limits measure them, measures a pack composition
+//! against an agent's limit, and answers "what would adding this pack cost?", "how many
+//! of these packs fit?", "where do the characters go?" and "which agents can take this
+//! composition?" on top of that composition budget.
+
+use crate::command_registry;
+use crate::deployment;
+use crate::fs_manager;
+use crate::types::{BudgetInfo, PackBudgetItem, DEFAULT_WARN_THRESHOLD_PCT};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Number of characters in `text`
+///
+/// Agent limits are measured in characters, not bytes, so multibyte content must
+/// not be counted by `len()`.
+pub fn char_count(text: &str) -> u64 {
+    text.chars().count() as u64
+}
+
+/// Budget of an empty composition with no limit
+pub fn empty_budget_info() -> BudgetInfo {
+    BudgetInfo {
+        total_chars: 0,
+        max_chars: None,
+        percentage: None,
+        within_limit: true,
+        pack_breakdown: Vec::new(),
+    }
+}
+
+/// Character limit of an agent, from the registry or a built-in default
+pub fn agent_char_limit(agent_id: &str) -> Option<u64> {
+    if let Ok(agents) = fs_manager::load_agent_registry() {
+        if let Some(agent) = agents
+            .iter()
+            .find(|a| a.id.eq_ignore_ascii_case(agent_id))
+        {
+            if let Some(max) = agent.character_limits.max_chars {
+                return Some(max);
+            }
+        }
+    }
+
+    match agent_id.to_lowercase().as_str() {
+        "cursor" => Some(1_000_000),
+        "claude" => Some(200_000),
+        "copilot" => Some(8_000),
+        "gemini" => Some(1_000_000),
+        "codex" => Some(50_000),
+        _ => None,
+    }
+}
+
+/// Share of `max_chars` that `total_chars` uses, as `(percentage, within_limit)`
+///
+/// Compositions without a limit have no percentage and are always within it.
+pub fn limit_usage(total_chars: u64, max_chars: Option<u64>) -> (Option<u64>, bool) {
+    match max_chars {
+        Some(max) => (
+            Some(((total_chars as f64 / max as f64) * 100.0).round() as u64),
+            total_chars <= max,
+        ),
+        None => (None, true),
+    }
+}
+
+/// Percentage of an agent's limit past which a composition is flagged
+pub fn agent_warn_threshold(agent_id: &str) -> u64 {
+    fs_manager::load_agent_registry()
+        .ok()
+        .and_then(|agents| {
+            agents
+                .into_iter()
+                .find(|a| a.id.eq_ignore_ascii_case(agent_id))
+                .map(|a| a.character_limits.warn_threshold_pct)
+        })
+        .unwrap_or(DEFAULT_WARN_THRESHOLD_PCT)
+}
+
+/// Budget of a pack composition, with dependencies counted once
+///
+/// Measured against `agent_id`'s character limit when given.
+pub fn calculate(
+    pack_ids: &[String],
+    agent_id: Option<String>,
+) -> Result<BudgetInfo, String> {
+    let mut pack_breakdown: Vec<PackBudgetItem> = Vec::new();
+    let mut total_chars: u64 = 0;
+    let mut total_words: u64 = 0;
+    let mut seen: HashSet<String> = HashSet::new();
+
+    for pack_id in pack_ids {
+        let resolution = fs_manager::resolve_dependencies(pack_id)?;
+        if !resolution.success {
+            return Err(resolution.error.unwrap_or_else(|| "Failed to resolve dependencies".into()));
+        }
+
+        for id in resolution.order {
+            if seen.insert(id.clone()) {
+                let pack = fs_manager::load_pack_full(&id)?;
+                total_chars += pack.actual_character_count;
+                total_words += pack.actual_word_count;
+                pack_breakdown.push(PackBudgetItem {
+                    pack_id: id,
+                    chars: pack.actual_character_count,
+                    words: pack.actual_word_count,
+                    percentage_of_total: 0,
+                });
+            }
+        }
+    }
+
+    for item in pack_breakdown.iter_mut() {
+        if total_chars > 0 {
+            item.percentage_of_total = ((item.chars as f64 / total_chars as f64) * 100.0).round() as u64;
+        }
+    }
+
+    let max_chars = agent_id
+        .as_ref()
+        .and_then(|id| agent_char_limit(id));
+    let (percentage, within_limit) = limit_usage(total_chars, max_chars);
+
+    Ok(BudgetInfo {
+        total_chars,
+        max_chars,
+        percentage,
+        within_limit,
+        pack_breakdown,
+    })
+}
+
+/// Additional budget a candidate pack would add to a composition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarginalCost {
+    pub candidate_pack_id: String,
+    /// Packs the candidate would bring in (itself plus new dependencies), in load order
+    pub added_packs: Vec<String>,
+    pub added_chars: u64,
+    pub added_words: u64,
+    /// Total characters of the composition with the candidate added
+    pub resulting_chars: u64,
+    /// Percentage of the agent's limit used with the candidate added
+    pub resulting_percentage: Option<u64>,
+    pub within_limit: bool,
+}
+
+/// Estimate the cost of adding `candidate_pack_id` to `current_pack_ids`
+///
+/// Dependencies already pulled in by the current set are not counted again.
+pub fn marginal_cost(
+    current_pack_ids: &[String],
+    candidate_pack_id: &str,
+    agent_id: Option<String>,
+) -> Result<MarginalCost, String> {
+    let current = calculate(current_pack_ids, agent_id.clone())?;
+
+    let mut with_candidate_ids = current_pack_ids.to_vec();
+    with_candidate_ids.push(candidate_pack_id.to_string());
+    let with_candidate = calculate(&with_candidate_ids, agent_id)?;
+
+    let added: Vec<_> = with_candidate
+        .pack_breakdown
+        .iter()
+        .filter(|item| !includes_pack(&current, &item.pack_id))
+        .collect();
+
+    Ok(MarginalCost {
+        candidate_pack_id: candidate_pack_id.to_string(),
+        added_packs: added.iter().map(|item| item.pack_id.clone()).collect(),
+        added_chars: added.iter().map(|item| item.chars).sum(),
+        added_words: added.iter().map(|item| item.words).sum(),
+        resulting_chars: with_candidate.total_chars,
+        resulting_percentage: with_candidate.percentage,
+        within_limit: with_candidate.within_limit,
+    })
+}
+
+/// One item's share of a full budget
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetShare {
+    /// Pack id, command id, or out-reference path
+    pub id: String,
+    pub chars: u64,
+    /// Share of the grand total, in percent
+    pub percentage_of_total: u64,
+}
+
+/// Budget of a whole deployment: packs, commands, and the out-references they pull in
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FullBudgetInfo {
+    pub total_chars: u64,
+    pub max_chars: Option<u64>,
+    pub percentage: Option<u64>,
+    pub within_limit: bool,
+    /// Packs in load order, dependencies included
+    pub packs: Vec<BudgetShare>,
+    pub commands: Vec<BudgetShare>,
+    pub out_references: Vec<BudgetShare>,
+}
+
+/// Break a deployment's budget down into packs, commands, and out-references
+///
+/// The grand total of all three sections is checked against `agent_id`'s limit.
+pub fn full_budget(
+    pack_ids: &[String],
+    command_ids: &[String],
+    agent_id: Option<String>,
+) -> Result<FullBudgetInfo, String> {
+    let packs = calculate(pack_ids, agent_id)?;
+    let commands = command_registry::calculate_command_budget(command_ids)?;
+    let out_references = deployment::collect_out_references_for_selection(command_ids, pack_ids)
+        .map_err(|e| e.to_string())?;
+
+    let mut pack_shares: Vec<BudgetShare> = packs
+        .pack_breakdown
+        .iter()
+        .map(|item| share(&item.pack_id, item.chars))
+        .collect();
+    let mut command_shares: Vec<BudgetShare> = commands
+        .command_breakdown
+        .iter()
+        .map(|item| share(&item.command_id, item.chars))
+        .collect();
+    let mut out_reference_shares: Vec<BudgetShare> = out_references
+        .iter()
+        .map(|resolved| share(&resolved.file_path, char_count(&resolved.content)))
+        .collect();
+
+    let total_chars: u64 = pack_shares
+        .iter()
+        .chain(&command_shares)
+        .chain(&out_reference_shares)
+        .map(|item| item.chars)
+        .sum();
+    if total_chars > 0 {
+        for item in pack_shares
+            .iter_mut()
+            .chain(command_shares.iter_mut())
+            .chain(out_reference_shares.iter_mut())
+        {
+            item.percentage_of_total = ((item.chars as f64 / total_chars as f64) * 100.0).round() as u64;
+        }
+    }
+
+    let max_chars = packs.max_chars;
+    let (percentage, within_limit) = limit_usage(total_chars, max_chars);
+    Ok(FullBudgetInfo {
+        total_chars,
+        max_chars,
+        percentage,
+        within_limit,
+        packs: pack_shares,
+        commands: command_shares,
+        out_references: out_reference_shares,
+    })
+}
+
+fn share(id: &str, chars: u64) -> BudgetShare {
+    BudgetShare {
+        id: id.to_string(),
+        chars,
+        percentage_of_total: 0,
+    }
+}
+
+/// How many of a list of candidate packs fit within an agent's limit
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PackFit {
+    /// Candidates that fit, in the order given
+    pub fitting: Vec<String>,
+    /// The first candidate that would push the composition over the limit
+    pub overflow_at: Option<String>,
+}
+
+/// Add candidate packs in order until the next one would exceed `agent_id`'s limit
+///
+/// Each step budgets the whole composition, so dependencies shared between candidates
+/// are only counted once. Agents without a limit fit every candidate.
+pub fn fit_packs(candidate_pack_ids: &[String], agent_id: &str) -> Result<PackFit, String> {
+    let mut fitting: Vec<String> = Vec::new();
+
+    for candidate in candidate_pack_ids {
+        let mut with_candidate = fitting.clone();
+        with_candidate.push(candidate.clone());
+        let budget = calculate(&with_candidate, Some(agent_id.to_string()))?;
+
+        if !budget.within_limit {
+            return Ok(PackFit {
+                fitting,
+                overflow_at: Some(candidate.clone()),
+            });
+        }
+        fitting.push(candidate.clone());
+    }
+
+    Ok(PackFit {
+        fitting,
+        overflow_at: None,
+    })
+}
+
+/// Budget of one composition (packs plus commands) against each agent's limit
+///
+/// Keyed by agent id. Agents without a limit are always within it.
+pub fn report_for_agents(
+    pack_ids: &[String],
+    command_ids: &[String],
+    agent_ids: &[String],
+) -> Result<HashMap<String, BudgetInfo>, String> {
+    let packs = calculate(pack_ids, None)?;
+    let commands = command_registry::calculate_command_budget(command_ids)?;
+    let total_chars = packs.total_chars + commands.total_chars;
+
+    let agents = fs_manager::load_agent_registry()
+        .map_err(|e| format!("Failed to load agent registry: {}", e))?;
+
+    let mut report = HashMap::new();
+    for agent_id in agent_ids {
+        let agent = agents
+            .iter()
+            .find(|a| a.id.eq_ignore_ascii_case(agent_id))
+            .ok_or_else(|| format!("Agent not found: {}", agent_id))?;
+
+        let max_chars = agent_char_limit(&agent.id);
+        let (percentage, within_limit) = limit_usage(total_chars, max_chars);
+        report.insert(
+            agent_id.clone(),
+            BudgetInfo {
+                total_chars,
+                max_chars,
+                percentage,
+                within_limit,
+                pack_breakdown: packs.pack_breakdown.clone(),
+            },
+        );
+    }
+
+    Ok(report)
+}
+
+fn includes_pack(budget: &BudgetInfo, pack_id: &str) -> bool {
+    budget.pack_breakdown.iter().any(|item| item.pack_id == pack_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_char_count_is_not_byte_length() {
+        let content = "# ルール\n\nコミット前にテストを実行する 🚀\n";
+        assert_ne!(char_count(content), content.len() as u64);
+        assert_eq!(char_count(content), content.chars().count() as u64);
+        assert_eq!(char_count("plain ascii"), 11);
+    }
+}
@@ -1,10 +1,13 @@
+use crate::deployment::converters::MarkdownConverter;
+use crate::deployment::StateManager;
+use crate::budget;
 use crate::fs_manager;
 use crate::types::*;
 use once_cell::sync::Lazy;
 use regex::Regex;
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 use serde_json;
 
@@ -12,8 +15,9 @@ use serde_json;
 static COMMAND_CACHE: Lazy<Mutex<Option<Vec<CommandMetadata>>>> = Lazy::new(|| Mutex::new(None));
 
 /// Get the commands source directory path
-pub fn get_commands_directory() -> PathBuf {
-    fs_manager::get_agentsmd_home().join("commands").join("src")
+pub fn get_commands_directory() -> Result<PathBuf, String> {
+    let home = fs_manager::get_agentsmd_home().map_err(|e| e.to_string())?;
+    Ok(home.join("commands").join("src"))
 }
 
 /// Count words in text
@@ -21,14 +25,31 @@ fn count_words(text: &str) -> u64 {
     text.split_whitespace().count() as u64
 }
 
-/// Convert filename to command ID (kebab-case)
-fn filename_to_id(filename: &str) -> String {
-    filename.trim_end_matches(".md").to_string()
+/// Convert a command file's path under the commands directory to its ID
+///
+/// Files in subfolders get nested IDs joined with `/` (`git/status.md` -> `git/status`).
+fn path_to_id(relative: &Path) -> String {
+    relative
+        .with_extension("")
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Check that a command ID is usable as a relative path under the commands directory
+fn is_valid_command_id(id: &str) -> bool {
+    !id.is_empty()
+        && !id.contains('\\')
+        && id
+            .split('/')
+            .all(|segment| !segment.is_empty() && !segment.starts_with('.'))
 }
 
-/// Convert command ID to display name (Title Case)
+/// Convert command ID to display name (Title Case), using the last segment of nested IDs
 fn id_to_name(id: &str) -> String {
-    id.split('-')
+    let leaf = id.rsplit('/').next().unwrap_or(id);
+    leaf.split('-')
         .map(|word| {
             let mut chars = word.chars();
             match chars.next() {
@@ -40,6 +61,35 @@ fn id_to_name(id: &str) -> String {
         .join(" ")
 }
 
+/// Extract aliases declared in command frontmatter (`aliases: st, stat` or `aliases: [st, stat]`)
+fn extract_aliases(frontmatter: Option<&HashMap<String, String>>) -> Vec<String> {
+    let raw = match frontmatter.and_then(|fm| fm.get("aliases").or_else(|| fm.get("alias"))) {
+        Some(value) => value,
+        None => return Vec::new(),
+    };
+
+    let mut aliases = Vec::new();
+    for alias in raw.trim_start_matches('[').trim_end_matches(']').split(',') {
+        let alias = alias.trim().trim_matches('"').trim_matches('\'').trim();
+        if !alias.is_empty() && !aliases.iter().any(|a: &String| a == alias) {
+            aliases.push(alias.to_string());
+        }
+    }
+
+    aliases
+}
+
+/// Extract a category override from command frontmatter (`category: testing`)
+fn extract_category(frontmatter: Option<&HashMap<String, String>>) -> Option<String> {
+    let raw = frontmatter.and_then(|fm| fm.get("category"))?;
+    let category = raw.trim().trim_matches('"').trim_matches('\'').trim().to_lowercase();
+    if category.is_empty() {
+        None
+    } else {
+        Some(category)
+    }
+}
+
 /// Extract script path from command markdown content
 fn extract_script_path(content: &str) -> String {
     static PATTERNS: Lazy<Vec<Regex>> = Lazy::new(|| {
@@ -90,15 +140,20 @@ fn extract_out_references(content: &str) -> Vec<String> {
 }
 
 /// Path to persisted out-reference overrides for commands
-fn command_out_ref_overrides_path() -> PathBuf {
-    fs_manager::get_agentsmd_home()
-        .join("commands")
-        .join("out-references.json")
+fn command_out_ref_overrides_path() -> Result<PathBuf, String> {
+    let home = fs_manager::get_agentsmd_home().map_err(|e| e.to_string())?;
+    Ok(home.join("commands").join("out-references.json"))
 }
 
 /// Load persisted out-reference overrides for commands
 fn load_command_out_ref_overrides() -> HashMap<String, Vec<String>> {
-    let path = command_out_ref_overrides_path();
+    let path = match command_out_ref_overrides_path() {
+        Ok(path) => path,
+        Err(e) => {
+            log::warn!("Failed to locate command out-reference overrides: {}", e);
+            return HashMap::new();
+        }
+    };
     if !path.exists() {
         return HashMap::new();
     }
@@ -114,7 +169,7 @@ fn load_command_out_ref_overrides() -> HashMap<String, Vec<String>> {
 
 /// Persist command out-reference overrides
 fn save_command_out_ref_overrides(map: &HashMap<String, Vec<String>>) -> Result<(), String> {
-    let path = command_out_ref_overrides_path();
+    let path = command_out_ref_overrides_path()?;
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)
             .map_err(|e| format!("Failed to create overrides directory: {}", e))?;
@@ -142,6 +197,112 @@ pub fn update_command_out_references(command_id: &str, references: Vec<String>)
     Ok(())
 }
 
+/// Drop a command's out-reference override so references are extracted from its content again
+pub fn clear_command_out_references(command_id: &str) -> Result<(), String> {
+    let mut overrides = load_command_out_ref_overrides();
+    if overrides.remove(command_id).is_some() {
+        save_command_out_ref_overrides(&overrides)?;
+    }
+
+    clear_cache();
+    Ok(())
+}
+
+/// Point command out-references at an out-reference's new path after it moves
+///
+/// Rewrites saved overrides and the targets of markdown links in command files, matching
+/// paths like `fs_manager::migrate_out_ref_path`. Returns the ids of the commands that
+/// changed, sorted.
+pub fn migrate_command_out_references(old_path: &str, new_path: &str) -> Result<Vec<String>, String> {
+    static LINK_TARGET: Lazy<Regex> = Lazy::new(|| Regex::new(r"(\[[^\]]+\]\()([^)]+)(\))").unwrap());
+
+    let mut changed = Vec::new();
+
+    let mut overrides = load_command_out_ref_overrides();
+    let mut overrides_changed = false;
+    for (command_id, paths) in overrides.iter_mut() {
+        for path in paths.iter_mut() {
+            if let Some(migrated) = fs_manager::migrate_out_ref_path(path, old_path, new_path) {
+                *path = migrated;
+                overrides_changed = true;
+                changed.push(command_id.clone());
+            }
+        }
+    }
+    if overrides_changed {
+        save_command_out_ref_overrides(&overrides)?;
+    }
+
+    for command in load_commands()? {
+        let content = fs::read_to_string(&command.source_path)
+            .map_err(|e| format!("Failed to read command {}: {}", command.id, e))?;
+        let rewritten = LINK_TARGET.replace_all(&content, |caps: &regex::Captures| {
+            let target = fs_manager::migrate_out_ref_path(&caps[2], old_path, new_path)
+                .unwrap_or_else(|| caps[2].to_string());
+            format!("{}{}{}", &caps[1], target, &caps[3])
+        });
+        if rewritten != content {
+            fs::write(&command.source_path, rewritten.as_ref())
+                .map_err(|e| format!("Failed to update command {}: {}", command.id, e))?;
+            changed.push(command.id);
+        }
+    }
+
+    clear_cache();
+    changed.sort();
+    changed.dedup();
+    Ok(changed)
+}
+
+/// Rename a command's source file and carry its out-reference override over
+///
+/// `old_id` may be an alias; the canonical file is renamed. Fails when `new_id` is
+/// already taken by another command's id or alias. Out-reference `linked_from`
+/// entries are derived from loaded commands, so they follow the new id once the
+/// cache is cleared.
+pub fn rename_command(old_id: &str, new_id: &str) -> Result<CommandMetadata, String> {
+    let new_id = new_id.trim();
+    if !is_valid_command_id(new_id) {
+        return Err(format!("Invalid command id: '{}'", new_id));
+    }
+
+    let command = get_command_by_id(old_id)?;
+    if command.id == new_id {
+        return Ok(command);
+    }
+
+    let commands_dir = get_commands_directory()?;
+    let old_path = commands_dir.join(format!("{}.md", command.id));
+    let new_path = commands_dir.join(format!("{}.md", new_id));
+    let taken = load_commands()?
+        .iter()
+        .any(|c| c.id == new_id || c.aliases.iter().any(|a| a == new_id));
+    if taken || new_path.exists() {
+        return Err(format!("Command already exists: {}", new_id));
+    }
+
+    if let Some(parent) = new_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create command folder: {}", e))?;
+    }
+    fs::rename(&old_path, &new_path)
+        .map_err(|e| format!("Failed to rename command file: {}", e))?;
+
+    let mut overrides = load_command_out_ref_overrides();
+    if let Some(refs) = overrides.remove(&command.id) {
+        overrides.insert(new_id.to_string(), refs);
+        save_command_out_ref_overrides(&overrides)?;
+    }
+
+    // Later redeploys select commands by the ids recorded here
+    StateManager::new()
+        .and_then(|state| state.rename_command(&command.id, new_id))
+        .map_err(|e| format!("Failed to update deployment state: {}", e))?;
+
+    clear_cache();
+    get_command_by_id(new_id)
+}
+
 /// Determine command category based on content and purpose
 fn determine_category(id: &str, content: &str) -> String {
     let lower_content = content.to_lowercase();
@@ -225,8 +386,8 @@ fn extract_template(content: &str) -> Option<String> {
     None
 }
 
-/// Load a command from a markdown file
-fn load_command_from_file(file_path: &PathBuf) -> Result<CommandMetadata, String> {
+/// Load a command from a markdown file under `commands_dir`
+fn load_command_from_file(file_path: &PathBuf, commands_dir: &Path) -> Result<CommandMetadata, String> {
     if !file_path.exists() {
         return Err(format!("Command file not found: {:?}", file_path));
     }
@@ -234,15 +395,17 @@ fn load_command_from_file(file_path: &PathBuf) -> Result<CommandMetadata, String
     let content = fs::read_to_string(file_path)
         .map_err(|e| format!("Failed to read command file: {}", e))?;
 
-    let filename = file_path
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("");
-    let id = filename_to_id(filename);
+    let relative = file_path.strip_prefix(commands_dir).unwrap_or(file_path);
+    let id = path_to_id(relative);
     let name = id_to_name(&id);
+    let subfolder = id.rsplit_once('/').map(|(folder, _)| folder.to_string());
+
+    // Optional frontmatter declares aliases and category; the description follows it
+    let (frontmatter, body) = MarkdownConverter::parse_frontmatter(&content);
+    let aliases = extract_aliases(frontmatter.as_ref());
 
     // First line is the description
-    let description = content
+    let description = body
         .lines()
         .next()
         .unwrap_or("")
@@ -251,7 +414,8 @@ fn load_command_from_file(file_path: &PathBuf) -> Result<CommandMetadata, String
 
     let script_path = extract_script_path(&content);
     let out_references = extract_out_references(&content);
-    let category = determine_category(&id, &content);
+    let category = extract_category(frontmatter.as_ref())
+        .unwrap_or_else(|| determine_category(&id, &content));
     let template = extract_template(&content);
 
     Ok(CommandMetadata {
@@ -264,12 +428,37 @@ fn load_command_from_file(file_path: &PathBuf) -> Result<CommandMetadata, String
         out_references,
         category,
         template,
-        character_count: content.len() as u64,
+        character_count: budget::char_count(&content),
         word_count: count_words(&content),
         source_path: file_path.to_string_lossy().to_string(),
+        aliases,
+        subfolder,
     })
 }
 
+/// Collect command markdown files, descending into subfolders (hidden folders are skipped)
+fn collect_command_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("Failed to read commands directory: {}", e))?;
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            let hidden = path
+                .file_name()
+                .map(|n| n.to_string_lossy().starts_with('.'))
+                .unwrap_or(false);
+            if !hidden {
+                collect_command_files(&path, files)?;
+            }
+        } else if path.extension().map(|e| e == "md").unwrap_or(false) {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
 /// Load all commands from the commands directory
 pub fn load_commands() -> Result<Vec<CommandMetadata>, String> {
     // Check cache first
@@ -281,7 +470,7 @@ pub fn load_commands() -> Result<Vec<CommandMetadata>, String> {
 
     let overrides = load_command_out_ref_overrides();
 
-    let commands_dir = get_commands_directory();
+    let commands_dir = get_commands_directory()?;
 
     if !commands_dir.exists() {
         return Ok(Vec::new());
@@ -289,22 +478,19 @@ pub fn load_commands() -> Result<Vec<CommandMetadata>, String> {
 
     let mut commands = Vec::new();
 
-    let entries =
-        fs::read_dir(&commands_dir).map_err(|e| format!("Failed to read commands directory: {}", e))?;
+    let mut files = Vec::new();
+    collect_command_files(&commands_dir, &mut files)?;
 
-    for entry in entries.flatten() {
-        let path = entry.path();
-        if path.extension().map(|e| e == "md").unwrap_or(false) {
-            match load_command_from_file(&path) {
-                Ok(mut cmd) => {
-                    if let Some(override_refs) = overrides.get(&cmd.id) {
-                        cmd.out_references = override_refs.clone();
-                    }
-                    commands.push(cmd)
-                }
-                Err(e) => {
-                    log::warn!("Skipping invalid command file {:?}: {}", path, e);
+    for path in files {
+        match load_command_from_file(&path, &commands_dir) {
+            Ok(mut cmd) => {
+                if let Some(override_refs) = overrides.get(&cmd.id) {
+                    cmd.out_references = override_refs.clone();
                 }
+                commands.push(cmd)
+            }
+            Err(e) => {
+                log::warn!("Skipping invalid command file {:?}: {}", path, e);
             }
         }
     }
@@ -317,12 +503,21 @@ pub fn load_commands() -> Result<Vec<CommandMetadata>, String> {
     Ok(commands)
 }
 
-/// Get a command by its ID
-pub fn get_command_by_id(command_id: &str) -> Result<CommandMetadata, String> {
-    let commands = load_commands()?;
+/// Find a command by canonical ID, falling back to any declared alias
+fn find_command(commands: Vec<CommandMetadata>, command_id: &str) -> Option<CommandMetadata> {
+    if let Some(idx) = commands.iter().position(|c| c.id == command_id) {
+        return commands.into_iter().nth(idx);
+    }
+
     commands
         .into_iter()
-        .find(|c| c.id == command_id)
+        .find(|c| c.aliases.iter().any(|a| a == command_id))
+}
+
+/// Get a command by its ID or alias
+pub fn get_command_by_id(command_id: &str) -> Result<CommandMetadata, String> {
+    let commands = load_commands()?;
+    find_command(commands, command_id)
         .ok_or_else(|| format!("Command not found: {}", command_id))
 }
 
@@ -340,29 +535,76 @@ pub fn get_commands_for_agent(agent_id: &str) -> Result<Vec<CommandMetadata>, St
 
     Ok(commands
         .into_iter()
-        .filter(|cmd| {
-            // Empty agent_compatibility means all agents
-            if cmd.agent_compatibility.is_empty() {
-                return true;
-            }
-            cmd.agent_compatibility.contains(&agent_id.to_string())
-        })
+        .filter(|cmd| supports_agent(cmd, agent_id))
         .collect())
 }
 
-/// Get commands by category
+/// Whether a command can be deployed to an agent (empty agent_compatibility means all agents)
+fn supports_agent(cmd: &CommandMetadata, agent_id: &str) -> bool {
+    cmd.agent_compatibility.is_empty() || cmd.agent_compatibility.iter().any(|a| a == agent_id)
+}
+
+/// Get commands by category (built-in or custom, case-insensitive)
 pub fn get_commands_by_category(category: &str) -> Result<Vec<CommandMetadata>, String> {
     let commands = load_commands()?;
     Ok(commands
         .into_iter()
-        .filter(|cmd| cmd.category == category)
+        .filter(|cmd| in_category(cmd, category))
         .collect())
 }
 
+/// Whether a command belongs to a category (case-insensitive)
+fn in_category(cmd: &CommandMetadata, category: &str) -> bool {
+    cmd.category.eq_ignore_ascii_case(category.trim())
+}
+
+/// List one page of commands matching `filter`, sorted by id
+///
+/// An agent filter goes through the registry like `get_commands_for_agent`, so an
+/// unknown agent is an error rather than an empty page.
+pub fn list_commands(filter: &CommandFilter, offset: usize, limit: usize) -> Result<CommandPage, String> {
+    let mut commands = match &filter.agent_id {
+        Some(agent_id) => get_commands_for_agent(agent_id)?,
+        None => load_commands()?,
+    };
+    commands.retain(|cmd| {
+        filter.category.as_deref().is_none_or(|category| in_category(cmd, category))
+            && filter.requires_github.is_none_or(|github| cmd.requires_github == github)
+    });
+    commands.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let total = commands.len();
+    let commands = commands.into_iter().skip(offset).take(limit).collect();
+
+    Ok(CommandPage {
+        commands,
+        total: total as u64,
+        offset: offset as u64,
+        limit: limit as u64,
+    })
+}
+
+/// List the distinct categories used by loaded commands, sorted
+pub fn list_command_categories() -> Result<Vec<String>, String> {
+    let mut categories: Vec<String> = load_commands()?
+        .into_iter()
+        .map(|cmd| cmd.category)
+        .collect();
+    categories.sort();
+    categories.dedup();
+    Ok(categories)
+}
+
 /// Read raw command content
 pub fn get_command_content(command_id: &str) -> Result<String, String> {
-    let commands_dir = get_commands_directory();
-    let file_path = commands_dir.join(format!("{}.md", command_id));
+    let commands_dir = get_commands_directory()?;
+    let mut file_path = commands_dir.join(format!("{}.md", command_id));
+
+    // Resolve aliases to the canonical command file
+    if !file_path.exists() {
+        let command = get_command_by_id(command_id)?;
+        file_path = commands_dir.join(format!("{}.md", command.id));
+    }
 
     if !file_path.exists() {
         return Err(format!("Command not found: {}", command_id));
@@ -426,6 +668,24 @@ pub fn validate_command_for_agent(
     })
 }
 
+/// Check a command against every agent in the registry
+pub fn command_compatibility_matrix(command_id: &str) -> Result<Vec<AgentCommandCompatibility>, String> {
+    let agents = fs_manager::load_agent_registry()
+        .map_err(|e| format!("Failed to load agent registry: {}", e))?;
+
+    agents
+        .iter()
+        .map(|agent| {
+            let result = validate_command_for_agent(command_id, &agent.id)?;
+            Ok(AgentCommandCompatibility {
+                agent_id: agent.id.clone(),
+                compatible: result.compatible,
+                reason: result.reason,
+            })
+        })
+        .collect()
+}
+
 /// Clear the command cache (useful after file changes)
 pub fn clear_cache() {
     if let Ok(mut cache) = COMMAND_CACHE.lock() {
@@ -433,6 +693,197 @@ pub fn clear_cache() {
     }
 }
 
+/// Resolve a `~/.agentsmd/...` script path against the active toolkit home
+pub fn resolve_script_path(script_path: &str, agentsmd_home: &Path) -> PathBuf {
+    if let Some(rest) = script_path.strip_prefix("~/.agentsmd/") {
+        return agentsmd_home.join(rest);
+    }
+
+    if let Some(rest) = script_path.strip_prefix("~/") {
+        if let Ok(home) = fs_manager::home_dir() {
+            return home.join(rest);
+        }
+    }
+
+    PathBuf::from(script_path)
+}
+
+/// Check a command's markdown structure
+///
+/// Flags a missing description line, an unresolvable script, a template marker with no
+/// parseable template, and malformed markdown links.
+pub fn lint_command(command_id: &str) -> Result<Vec<CommandLint>, String> {
+    let command = get_command_by_id(command_id)?;
+    let content = get_command_content(&command.id)?;
+    let agentsmd_home = fs_manager::get_agentsmd_home().map_err(|e| e.to_string())?;
+    Ok(lint_command_content(&command, &content, &agentsmd_home))
+}
+
+fn lint_command_content(command: &CommandMetadata, content: &str, agentsmd_home: &Path) -> Vec<CommandLint> {
+    let mut lints = Vec::new();
+    let mut push = |rule: &str, message: String, line: Option<usize>| {
+        lints.push(CommandLint {
+            rule: rule.to_string(),
+            message,
+            line: line.map(|l| l as u64),
+        });
+    };
+
+    if command.description.is_empty() {
+        push(
+            "missing-description",
+            "The first line after any frontmatter should describe the command".to_string(),
+            None,
+        );
+    }
+
+    if !command.script_path.is_empty() && !resolve_script_path(&command.script_path, agentsmd_home).exists() {
+        push(
+            "missing-script",
+            format!("Script '{}' does not exist", command.script_path),
+            None,
+        );
+    }
+
+    if content.contains("Template:**") && command.template.as_deref().map(str::is_empty).unwrap_or(true) {
+        push(
+            "empty-template",
+            "Template marker is not followed by any template content".to_string(),
+            None,
+        );
+    }
+
+    let mut open_fence: Option<usize> = None;
+    for (idx, line) in content.lines().enumerate() {
+        if line.trim_start().starts_with("```") {
+            open_fence = match open_fence {
+                Some(_) => None,
+                None => Some(idx + 1),
+            };
+            continue;
+        }
+        if open_fence.is_some() {
+            continue;
+        }
+
+        for (pos, _) in line.match_indices("](") {
+            let target = &line[pos + 2..];
+            match target.find(')') {
+                Some(end) if !target[..end].trim().is_empty() && !target[..end].contains(' ') => {}
+                Some(_) => push(
+                    "malformed-link",
+                    "Link target is empty or contains spaces".to_string(),
+                    Some(idx + 1),
+                ),
+                None => push(
+                    "malformed-link",
+                    "Link target is missing its closing ')'".to_string(),
+                    Some(idx + 1),
+                ),
+            }
+        }
+    }
+
+    if let Some(line) = open_fence {
+        push(
+            "unclosed-code-block",
+            "Code block is never closed".to_string(),
+            Some(line),
+        );
+    }
+
+    lints
+}
+
+/// Check that a command's template section is well-formed markdown
+///
+/// Reports an empty template, code fences that are never closed, and unterminated
+/// HTML comments. Commands without a template have nothing to report.
+pub fn validate_template(command_id: &str) -> Result<Vec<String>, String> {
+    let command = get_command_by_id(command_id)?;
+    let content = get_command_content(&command.id)?;
+
+    let section = match template_section(&content) {
+        Some(section) => section,
+        None => match command.template {
+            Some(template) => template,
+            None => return Ok(Vec::new()),
+        },
+    };
+    Ok(template_issues(&section))
+}
+
+/// Text following a template marker, up to the next bold label or heading outside a fence
+///
+/// Unlike `extract_template`, headings inside a fenced template don't end the section.
+fn template_section(content: &str) -> Option<String> {
+    static MARKER_PATTERN: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"(?im)\*\*(?:Walkthrough\s+)?Template:\*\*|^##\s+Template\s*$").unwrap());
+
+    let marker = MARKER_PATTERN.find(content)?;
+    let mut section = Vec::new();
+    let mut fence: Option<String> = None;
+    for (idx, line) in content[marker.end()..].split('\n').enumerate() {
+        let trimmed = line.trim_start();
+        if idx > 0 && fence.is_none() && (trimmed.starts_with("**") || trimmed.starts_with("##")) {
+            break;
+        }
+        fence = next_fence_state(fence, trimmed);
+        section.push(line);
+    }
+
+    Some(section.join("\n").trim().to_string())
+}
+
+/// Track whether a line opens or closes a ``` / ~~~ fence
+///
+/// A fence closes on a line of the same character at least as long as its opener.
+fn next_fence_state(fence: Option<String>, trimmed: &str) -> Option<String> {
+    let marker: String = match trimmed.chars().next() {
+        Some(c @ ('`' | '~')) => trimmed.chars().take_while(|&m| m == c).collect(),
+        _ => return fence,
+    };
+    if marker.len() < 3 {
+        return fence;
+    }
+
+    match fence {
+        None => Some(marker),
+        Some(open) if marker.starts_with(&open) && trimmed[marker.len()..].trim().is_empty() => None,
+        Some(open) => Some(open),
+    }
+}
+
+fn template_issues(template: &str) -> Vec<String> {
+    let mut issues = Vec::new();
+    if template.trim().is_empty() {
+        issues.push("Template is empty".to_string());
+        return issues;
+    }
+
+    let mut fence: Option<String> = None;
+    let mut fence_line = 0;
+    for (idx, line) in template.lines().enumerate() {
+        let was_open = fence.is_some();
+        fence = next_fence_state(fence, line.trim_start());
+        if !was_open && fence.is_some() {
+            fence_line = idx + 1;
+        }
+    }
+    if fence.is_some() {
+        issues.push(format!(
+            "Code fence opened on template line {} is never closed",
+            fence_line
+        ));
+    }
+
+    if template.matches("<!--").count() > template.matches("-->").count() {
+        issues.push("HTML comment is never closed".to_string());
+    }
+
+    issues
+}
+
 /// Calculate total character budget for a set of commands
 pub fn calculate_command_budget(command_ids: &[String]) -> Result<CommandBudgetInfo, String> {
     let mut total_chars: u64 = 0;
@@ -453,3 +904,80 @@ pub fn calculate_command_budget(command_ids: &[String]) -> Result<CommandBudgetI
         command_breakdown,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_template_section_keeps_fenced_headings() {
+        let content = "Ship it.\n\n**Template:**\n```markdown\n## Summary\n- change\n```\n\n## Notes\nDone.\n";
+        let section = template_section(content).unwrap();
+        assert_eq!(section, "```markdown\n## Summary\n- change\n```");
+        assert!(template_issues(&section).is_empty());
+
+        assert_eq!(
+            template_issues("````\n```\nstill open"),
+            vec!["Code fence opened on template line 1 is never closed".to_string()]
+        );
+        assert_eq!(template_issues("  \n"), vec!["Template is empty".to_string()]);
+        assert_eq!(template_issues("<!-- todo\n## Summary"), vec!["HTML comment is never closed".to_string()]);
+    }
+
+    #[test]
+    fn test_extract_aliases_formats() {
+        let mut fm = HashMap::new();
+        fm.insert("aliases".to_string(), "[st, \"stat\"]".to_string());
+        assert_eq!(extract_aliases(Some(&fm)), vec!["st", "stat"]);
+
+        fm.insert("aliases".to_string(), "st, st".to_string());
+        assert_eq!(extract_aliases(Some(&fm)), vec!["st"]);
+
+        assert!(extract_aliases(None).is_empty());
+    }
+
+    #[test]
+    fn test_get_command_by_alias() {
+        let temp = tempdir().unwrap();
+        let status_path = temp.path().join("status.md");
+        fs::write(
+            &status_path,
+            "---\naliases: [st]\n---\n\nShow current workflow status.\n",
+        )
+        .unwrap();
+        let push_path = temp.path().join("push.md");
+        fs::write(&push_path, "Push the current branch.\n").unwrap();
+
+        let commands = vec![
+            load_command_from_file(&push_path, temp.path()).unwrap(),
+            load_command_from_file(&status_path, temp.path()).unwrap(),
+        ];
+
+        let command = find_command(commands.clone(), "st").unwrap();
+        assert_eq!(command.id, "status");
+        assert_eq!(command.description, "Show current workflow status.");
+        assert_eq!(command.aliases, vec!["st"]);
+
+        assert_eq!(find_command(commands.clone(), "status").unwrap().id, "status");
+        assert!(find_command(commands, "missing").is_none());
+    }
+
+    #[test]
+    fn test_nested_command_id() {
+        let temp = tempdir().unwrap();
+        let git_dir = temp.path().join("git");
+        fs::create_dir_all(&git_dir).unwrap();
+        let status_path = git_dir.join("status.md");
+        fs::write(&status_path, "Show repository status.\n").unwrap();
+
+        let command = load_command_from_file(&status_path, temp.path()).unwrap();
+        assert_eq!(command.id, "git/status");
+        assert_eq!(command.name, "Status");
+        assert_eq!(command.subfolder.as_deref(), Some("git"));
+
+        assert!(is_valid_command_id("git/status"));
+        assert!(!is_valid_command_id("git//status"));
+        assert!(!is_valid_command_id("../status"));
+    }
+}
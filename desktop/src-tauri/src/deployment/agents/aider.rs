@@ -6,8 +6,8 @@ use std::fs;
 use std::path::PathBuf;
 
 use crate::deployment::deployer::{
-    AgentDeployer, AgentStatus, DeploymentConfig, DeploymentOutput,
-    PreparedDeployment, TargetLevel, ValidationReport,
+    AgentDeployer, AgentStatus, DeploymentConfig, DeploymentOutput, DeploymentPhase,
+    PreparedDeployment, ProgressSink, TargetLevel, ValidationReport,
 };
 use crate::deployment::error::{DeploymentError, DeploymentResult};
 use crate::deployment::project::ProjectDetector;
@@ -30,10 +30,8 @@ impl AiderDeployer {
     }
 
     /// Get the Aider config file path (user-level)
-    fn get_config_path(&self) -> PathBuf {
-        dirs::home_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join(".aider.conf.yml")
+    fn get_config_path(&self) -> DeploymentResult<PathBuf> {
+        Ok(fs_manager::home_dir()?.join(".aider.conf.yml"))
     }
 
     /// Get project-level config path
@@ -85,7 +83,7 @@ impl AgentDeployer for AiderDeployer {
         prepared.command_format = "yaml".to_string();
 
         // Add AGENTS.md path to target_paths for backup
-        let agentsmd_home = fs_manager::get_agentsmd_home();
+        let agentsmd_home = fs_manager::get_agentsmd_home()?;
         let agents_md_path = agentsmd_home.join("AGENTS.md");
         prepared.add_target_path(agents_md_path.clone());
 
@@ -114,7 +112,7 @@ impl AgentDeployer for AiderDeployer {
             }
             TargetLevel::User => {
                 // User-level: ~/.aider.conf.yml
-                prepared.add_target_path(self.get_config_path());
+                prepared.add_target_path(self.get_config_path()?);
             }
         }
 
@@ -127,6 +125,7 @@ impl AgentDeployer for AiderDeployer {
         let validation = DeploymentValidator::validate_character_budget(
             &prepared.agents_md_content,
             limit,
+            self.warn_threshold_pct(),
         );
 
         let mut warnings = validation.warnings;
@@ -158,7 +157,12 @@ impl AgentDeployer for AiderDeployer {
         Ok(ValidationReport::success(validation.budget).with_warnings(warnings))
     }
 
-    fn deploy(&self, prepared: PreparedDeployment, config: &DeploymentConfig) -> DeploymentResult<DeploymentOutput> {
+    fn deploy(
+        &self,
+        prepared: PreparedDeployment,
+        config: &DeploymentConfig,
+        progress: &mut ProgressSink<'_>,
+    ) -> DeploymentResult<DeploymentOutput> {
         let mut deployed_files = Vec::new();
         let mut warnings = Vec::new();
         let mut manual_steps = Vec::new();
@@ -172,6 +176,7 @@ impl AgentDeployer for AiderDeployer {
             DeploymentError::fs_error(&agents_md_path, format!("Failed to write AGENTS.md: {}", e))
         })?;
         deployed_files.push(agents_md_path.to_string_lossy().to_string());
+        progress(DeploymentPhase::WriteAgentsMd, None);
 
         // Determine target config path based on target level
         let target_config_path = match config.target_level {
@@ -179,7 +184,7 @@ impl AgentDeployer for AiderDeployer {
                 let project_root = self.resolve_project_path(config)?;
                 self.get_project_config_path(&project_root)
             }
-            TargetLevel::User => self.get_config_path(),
+            TargetLevel::User => self.get_config_path()?,
         };
 
         // Check if config file already exists
@@ -251,7 +256,7 @@ impl AgentDeployer for AiderDeployer {
 
     fn get_status(&self) -> DeploymentResult<AgentStatus> {
         // Check if aider config exists
-        let config_path = self.get_config_path();
+        let config_path = self.get_config_path()?;
         
         if config_path.exists() {
             // Check if it references AGENTS.md
@@ -6,8 +6,8 @@ use std::fs;
 use std::path::PathBuf;
 
 use crate::deployment::deployer::{
-    AgentDeployer, AgentStatus, DeploymentConfig, DeploymentOutput,
-    PreparedDeployment, TargetLevel, ValidationReport,
+    AgentDeployer, AgentStatus, DeploymentConfig, DeploymentOutput, DeploymentPhase,
+    PreparedDeployment, ProgressSink, TargetLevel, ValidationReport,
 };
 use crate::deployment::error::{DeploymentError, DeploymentResult};
 use crate::deployment::project::ProjectDetector;
@@ -30,11 +30,14 @@ impl AzureDevOpsDeployer {
         }
     }
 
+    /// Rules filename from the agent definition, defaulting to `agents.md`
+    fn rules_filename(&self) -> String {
+        self.base.rules_filename("agents.md")
+    }
+
     /// Get the Azure DevOps config directory (user-level)
-    fn get_azure_devops_dir(&self) -> PathBuf {
-        dirs::home_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join(".azure-devops")
+    fn get_azure_devops_dir(&self) -> DeploymentResult<PathBuf> {
+        Ok(fs_manager::home_dir()?.join(".azure-devops"))
     }
 
     /// Get project-level Azure Pipelines config path
@@ -44,7 +47,7 @@ impl AzureDevOpsDeployer {
 
     /// Get project-level agents.md path
     fn get_project_agents_path(&self, project_root: &PathBuf) -> PathBuf {
-        self.get_project_pipelines_path(project_root).join("agents.md")
+        self.get_project_pipelines_path(project_root).join(self.rules_filename())
     }
 
     /// Resolve project path from config or detect automatically
@@ -75,7 +78,7 @@ impl AzureDevOpsDeployer {
 
     /// Get the build output directory
     fn get_build_dir(&self) -> DeploymentResult<PathBuf> {
-        let agentsmd_home = fs_manager::get_agentsmd_home();
+        let agentsmd_home = fs_manager::get_agentsmd_home()?;
         let build_dir = agentsmd_home.join("build").join("azure-devops");
         fs::create_dir_all(&build_dir).map_err(|e| {
             DeploymentError::fs_error(&build_dir, format!("Failed to create build directory: {}", e))
@@ -101,7 +104,7 @@ impl AgentDeployer for AzureDevOpsDeployer {
         prepared.command_format = "markdown".to_string();
 
         // Add AGENTS.md path to target_paths for backup
-        let agentsmd_home = fs_manager::get_agentsmd_home();
+        let agentsmd_home = fs_manager::get_agentsmd_home()?;
         let agents_md_path = agentsmd_home.join("AGENTS.md");
         prepared.add_target_path(agents_md_path);
 
@@ -115,8 +118,8 @@ impl AgentDeployer for AzureDevOpsDeployer {
             }
             TargetLevel::User => {
                 // User-level: ~/.azure-devops/agents.md
-                let azure_devops_dir = self.get_azure_devops_dir();
-                prepared.add_target_path(azure_devops_dir.join("agents.md"));
+                let azure_devops_dir = self.get_azure_devops_dir()?;
+                prepared.add_target_path(azure_devops_dir.join(self.rules_filename()));
             }
         }
 
@@ -129,6 +132,7 @@ impl AgentDeployer for AzureDevOpsDeployer {
         let validation = DeploymentValidator::validate_character_budget(
             &prepared.agents_md_content,
             limit,
+            self.warn_threshold_pct(),
         );
 
         let warnings = validation.warnings;
@@ -141,9 +145,15 @@ impl AgentDeployer for AzureDevOpsDeployer {
         Ok(ValidationReport::success(validation.budget).with_warnings(warnings))
     }
 
-    fn deploy(&self, prepared: PreparedDeployment, config: &DeploymentConfig) -> DeploymentResult<DeploymentOutput> {
+    fn deploy(
+        &self,
+        prepared: PreparedDeployment,
+        config: &DeploymentConfig,
+        progress: &mut ProgressSink<'_>,
+    ) -> DeploymentResult<DeploymentOutput> {
         let mut deployed_files = Vec::new();
         let mut warnings = Vec::new();
+        let mut link_methods = Vec::new();
         let mut manual_steps = Vec::new();
 
         // Write AGENTS.md to ~/.agentsmd/
@@ -155,6 +165,7 @@ impl AgentDeployer for AzureDevOpsDeployer {
             DeploymentError::fs_error(&agents_md_source, format!("Failed to write AGENTS.md: {}", e))
         })?;
         deployed_files.push(agents_md_source.to_string_lossy().to_string());
+        progress(DeploymentPhase::WriteAgentsMd, None);
 
         match config.target_level {
             TargetLevel::Project => {
@@ -169,8 +180,9 @@ impl AgentDeployer for AzureDevOpsDeployer {
                 })?;
 
                 // Create symlink from .azure-pipelines/agents.md to ~/.agentsmd/AGENTS.md
-                match symlink::create_link(project_agents_path.clone(), agents_md_source.clone(), config.force_overwrite) {
-                    Ok((_, warning)) => {
+                match symlink::create_link(project_agents_path.clone(), agents_md_source.clone(), config.force_overwrite, config.link_strategy, prepared.deployed_before(&project_agents_path)) {
+                    Ok((method, warning)) => {
+                        link_methods.push(method);
                         deployed_files.push(project_agents_path.to_string_lossy().to_string());
                         if let Some(w) = warning {
                             warnings.push(w);
@@ -195,7 +207,7 @@ impl AgentDeployer for AzureDevOpsDeployer {
             }
             TargetLevel::User => {
                 // User-level deployment
-                let azure_devops_dir = self.get_azure_devops_dir();
+                let azure_devops_dir = self.get_azure_devops_dir()?;
 
                 // Ensure directory exists
                 fs::create_dir_all(&azure_devops_dir).map_err(|e| {
@@ -203,9 +215,10 @@ impl AgentDeployer for AzureDevOpsDeployer {
                 })?;
 
                 // Create symlink at ~/.azure-devops/agents.md pointing to AGENTS.md
-                let agents_link_path = azure_devops_dir.join("agents.md");
-                match symlink::create_link(agents_link_path.clone(), agents_md_source.clone(), config.force_overwrite) {
-                    Ok((_, warning)) => {
+                let agents_link_path = azure_devops_dir.join(self.rules_filename());
+                match symlink::create_link(agents_link_path.clone(), agents_md_source.clone(), config.force_overwrite, config.link_strategy, prepared.deployed_before(&agents_link_path)) {
+                    Ok((method, warning)) => {
+                        link_methods.push(method);
                         deployed_files.push(agents_link_path.to_string_lossy().to_string());
                         if let Some(w) = warning {
                             warnings.push(w);
@@ -229,7 +242,7 @@ impl AgentDeployer for AzureDevOpsDeployer {
             }
         }
 
-        Ok(DeploymentOutput::success("symlink", deployed_files)
+        Ok(DeploymentOutput::success(config.link_strategy.deployment_method(&link_methods), deployed_files)
             .with_warnings(warnings)
             .with_manual_steps(manual_steps))
     }
@@ -253,14 +266,14 @@ impl AgentDeployer for AzureDevOpsDeployer {
     }
 
     fn get_status(&self) -> DeploymentResult<AgentStatus> {
-        let azure_devops_dir = self.get_azure_devops_dir();
+        let azure_devops_dir = self.get_azure_devops_dir()?;
 
         if !azure_devops_dir.exists() {
             return Ok(AgentStatus::NotInstalled);
         }
 
         // Check if agents.md exists
-        let agents_md = azure_devops_dir.join("agents.md");
+        let agents_md = azure_devops_dir.join(self.rules_filename());
         if agents_md.exists() {
             return Ok(AgentStatus::Configured);
         }
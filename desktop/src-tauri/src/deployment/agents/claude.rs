@@ -5,17 +5,23 @@
 use std::fs;
 use std::path::PathBuf;
 
+use crate::budget;
 use crate::deployment::command_loader;
-use crate::deployment::converters::MarkdownConverter;
+use crate::deployment::converters::{
+    MarkdownConverter, REQUIRED_COMMAND_FRONTMATTER, REQUIRED_SKILL_FRONTMATTER,
+};
 use crate::deployment::deployer::{
-    AgentDeployer, AgentStatus, DeploymentConfig, DeploymentOutput,
-    PreparedDeployment, TargetLevel, ValidationReport,
+    AgentDeployer, AgentStatus, DeploymentConfig, DeploymentOutput, DeploymentPhase,
+    PreparedDeployment, ProgressSink, TargetLevel, ValidationReport,
 };
 use crate::deployment::error::{DeploymentError, DeploymentResult};
 use crate::deployment::project::ProjectDetector;
 use crate::deployment::state::DeploymentState;
 use crate::deployment::validator::DeploymentValidator;
-use crate::deployment::{collect_out_references_for_selection, generate_agents_md_content, BaseDeployer};
+use crate::deployment::{
+    collect_out_references_for_selection, create_parent_dirs, generate_agents_md_content, BaseDeployer,
+    COMMANDS_ONLY_WARNING,
+};
 use crate::fs_manager;
 use crate::symlink;
 use crate::types::AgentDefinition;
@@ -23,35 +29,61 @@ use crate::types::AgentDefinition;
 /// Deployer for Claude CLI
 pub struct ClaudeDeployer {
     base: BaseDeployer,
+    /// Emit custom commands as skills (`~/.claude/skills/<name>/SKILL.md`) instead of slash commands
+    emit_skills: bool,
 }
 
 impl ClaudeDeployer {
     pub fn new(agent: AgentDefinition) -> Self {
         Self {
             base: BaseDeployer::new(agent),
+            emit_skills: false,
         }
     }
 
+    /// Deploy custom commands as Claude skills rather than plain commands
+    pub fn with_skills(mut self, emit_skills: bool) -> Self {
+        self.emit_skills = emit_skills;
+        self
+    }
+
+    /// Rules filename from the agent definition, defaulting to `CLAUDE.md`
+    fn rules_filename(&self) -> String {
+        self.base.rules_filename("CLAUDE.md")
+    }
+
     /// Get the Claude out-references directory
-    fn get_out_references_dir(&self) -> PathBuf {
-        self.get_claude_dir().join("references")
+    fn get_out_references_dir(&self) -> DeploymentResult<PathBuf> {
+        Ok(self.get_claude_dir()?.join("references"))
     }
 
     /// Get the Claude config directory
-    fn get_claude_dir(&self) -> PathBuf {
-        dirs::home_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join(".claude")
+    fn get_claude_dir(&self) -> DeploymentResult<PathBuf> {
+        Ok(fs_manager::home_dir()?.join(".claude"))
     }
 
     /// Get the Claude commands directory
-    fn get_commands_dir(&self) -> PathBuf {
-        self.get_claude_dir().join("commands")
+    fn get_commands_dir(&self) -> DeploymentResult<PathBuf> {
+        Ok(self.get_claude_dir()?.join("commands"))
+    }
+
+    /// Get the Claude skills directory
+    fn get_skills_dir(&self) -> DeploymentResult<PathBuf> {
+        Ok(self.get_claude_dir()?.join("skills"))
+    }
+
+    /// Get the directory custom commands are deployed into
+    fn get_command_output_dir(&self) -> DeploymentResult<PathBuf> {
+        if self.emit_skills {
+            self.get_skills_dir()
+        } else {
+            self.get_commands_dir()
+        }
     }
 
     /// Get project-level CLAUDE.md path
     fn get_project_claude_path(&self, project_root: &PathBuf) -> PathBuf {
-        project_root.join(".claude").join("CLAUDE.md")
+        project_root.join(".claude").join(self.rules_filename())
     }
 
     /// Resolve project path from config or detect automatically
@@ -80,10 +112,11 @@ impl ClaudeDeployer {
         }
     }
 
-    /// Get the build output directory for Claude commands
+    /// Get the build output directory for Claude commands or skills
     fn get_build_dir(&self) -> DeploymentResult<PathBuf> {
-        let agentsmd_home = fs_manager::get_agentsmd_home();
-        let build_dir = agentsmd_home.join("build").join("claude").join("commands");
+        let agentsmd_home = fs_manager::get_agentsmd_home()?;
+        let kind = if self.emit_skills { "skills" } else { "commands" };
+        let build_dir = agentsmd_home.join("build").join("claude").join(kind);
         fs::create_dir_all(&build_dir).map_err(|e| {
             DeploymentError::fs_error(&build_dir, format!("Failed to create build directory: {}", e))
         })?;
@@ -101,23 +134,28 @@ impl AgentDeployer for ClaudeDeployer {
     }
 
     fn prepare(&self, config: &DeploymentConfig) -> DeploymentResult<PreparedDeployment> {
-        // Generate AGENTS.md content with YAML frontmatter
-        let agents_md_content = generate_agents_md_content(&config.pack_ids, false)?;
+        // Generate AGENTS.md content with YAML frontmatter, unless only commands were selected
+        let content_with_frontmatter = if config.commands_only() {
+            String::new()
+        } else {
+            let agents_md_content = generate_agents_md_content(&config.pack_ids, false)?;
 
-        // Add frontmatter for Claude
-        let mut frontmatter = std::collections::HashMap::new();
-        frontmatter.insert("name".to_string(), "AGENTS.md Rules".to_string());
-        frontmatter.insert("version".to_string(), "2.0".to_string());
+            // Add frontmatter for Claude
+            let mut frontmatter = std::collections::HashMap::new();
+            frontmatter.insert("name".to_string(), "AGENTS.md Rules".to_string());
+            frontmatter.insert("version".to_string(), "2.0".to_string());
 
-        let content_with_frontmatter = MarkdownConverter::add_frontmatter(&agents_md_content, frontmatter);
+            MarkdownConverter::add_frontmatter(&agents_md_content, frontmatter)
+        };
 
         let mut prepared = PreparedDeployment::new(content_with_frontmatter);
         prepared.command_format = "markdown-frontmatter".to_string();
 
         // Add AGENTS.md path to target_paths for backup
-        let agentsmd_home = fs_manager::get_agentsmd_home();
-        let agents_md_source = agentsmd_home.join("AGENTS.md");
-        prepared.add_target_path(agents_md_source);
+        if !config.commands_only() {
+            let agentsmd_home = fs_manager::get_agentsmd_home()?;
+            prepared.add_target_path(agentsmd_home.join("AGENTS.md"));
+        }
 
         // Collect out-references used by commands/packs
         let resolved_refs = collect_out_references_for_selection(
@@ -125,7 +163,7 @@ impl AgentDeployer for ClaudeDeployer {
             &config.pack_ids,
         )?;
         if !resolved_refs.is_empty() {
-            let out_ref_dir = self.get_out_references_dir();
+            let out_ref_dir = self.get_out_references_dir()?;
             prepared.add_target_path(out_ref_dir.clone());
             for resolved in &resolved_refs {
                 prepared.add_out_reference(resolved.file_path.clone(), resolved.content.clone());
@@ -135,6 +173,9 @@ impl AgentDeployer for ClaudeDeployer {
 
         // Branch on target level for destination paths
         match config.target_level {
+            TargetLevel::Project if config.commands_only() => {
+                // No AGENTS.md to link into the project
+            }
             TargetLevel::Project => {
                 // Project-level deployment: .claude/CLAUDE.md
                 let project_root = self.resolve_project_path(config)?;
@@ -143,15 +184,32 @@ impl AgentDeployer for ClaudeDeployer {
             }
             TargetLevel::User => {
                 // User-level: CLAUDE.md symlink in ~/.claude/
-                let claude_dir = self.get_claude_dir();
-                prepared.add_target_path(claude_dir.join("CLAUDE.md"));
+                if !config.commands_only() {
+                    let claude_dir = self.get_claude_dir()?;
+                    prepared.add_target_path(claude_dir.join(self.rules_filename()));
+                }
 
-                // Prepare custom commands with frontmatter
-                let commands_dir = self.get_commands_dir();
+                // Prepare custom commands (or skills) with frontmatter
+                let commands_dir = self.get_command_output_dir()?;
                 for command_id in &config.custom_command_ids {
-                    match command_loader::load_command_for_deployment(command_id, self.agent_id()) {
+                    let loaded = if self.emit_skills {
+                        command_loader::load_command_as_claude_skill(command_id)
+                    } else {
+                        command_loader::load_command_for_deployment(command_id, self.agent_id())
+                    };
+                    match loaded {
                         Ok((filename, content)) => {
-                            prepared.add_command(filename.clone(), content);
+                            let required_keys = if self.emit_skills {
+                                REQUIRED_SKILL_FRONTMATTER
+                            } else {
+                                REQUIRED_COMMAND_FRONTMATTER
+                            };
+                            prepared.add_command_with_frontmatter(
+                                filename.clone(),
+                                content,
+                                required_keys,
+                                config.fix_frontmatter,
+                            );
 
                             // Add each command file path for backup
                             let command_path = commands_dir.join(&filename);
@@ -163,12 +221,16 @@ impl AgentDeployer for ClaudeDeployer {
                                 command_id,
                                 e
                             );
-                            let fallback_content = MarkdownConverter::to_claude_command(
-                                command_id,
-                                &format!("Custom command: {}", command_id),
-                                "Execute this command to perform the specified action.",
-                            );
-                            let fallback_name = format!("{}.md", command_id);
+                            let description = format!("Custom command: {}", command_id);
+                            let body = "Execute this command to perform the specified action.";
+                            let (fallback_name, fallback_content) = if self.emit_skills {
+                                let skill =
+                                    MarkdownConverter::to_claude_skill(command_id, &description, Vec::new(), body)?;
+                                (format!("{}/SKILL.md", command_id), skill)
+                            } else {
+                                let command = MarkdownConverter::to_claude_command(command_id, &description, body);
+                                (format!("{}.md", command_id), command)
+                            };
                             prepared.add_command(fallback_name.clone(), fallback_content);
 
                             let command_path = commands_dir.join(fallback_name);
@@ -190,21 +252,21 @@ impl AgentDeployer for ClaudeDeployer {
     fn validate(&self, prepared: &PreparedDeployment) -> DeploymentResult<ValidationReport> {
         // Check character limit (200K for Claude)
         let limit = self.character_limit();
-        let agents_chars = prepared.agents_md_content.len() as u64;
+        let agents_chars = budget::char_count(&prepared.agents_md_content);
         let command_chars: u64 = prepared
             .commands
             .values()
-            .map(|c| c.len() as u64)
+            .map(|c| budget::char_count(c))
             .sum();
         let validation =
-            DeploymentValidator::validate_full_budget(agents_chars, command_chars, prepared.out_reference_chars(), limit);
+            DeploymentValidator::validate_full_budget(agents_chars, command_chars, prepared.out_reference_chars(), limit, self.warn_threshold_pct());
 
         let mut warnings = validation.warnings;
         let mut errors = validation.errors;
 
-        // Validate frontmatter presence
+        // Validate frontmatter presence (commands-only deployments have no AGENTS.md)
         let fm_validation = DeploymentValidator::validate_frontmatter(&prepared.agents_md_content);
-        if !fm_validation.valid {
+        if !prepared.agents_md_content.is_empty() && !fm_validation.valid {
             warnings.extend(fm_validation.errors); // Frontmatter is recommended, not required
         }
 
@@ -214,6 +276,24 @@ impl AgentDeployer for ClaudeDeployer {
             if !cmd_validation.valid {
                 warnings.push(format!("Command '{}' should have YAML frontmatter", name));
             }
+
+            // Skills without their required keys are ignored by Claude
+            if self.emit_skills {
+                let (frontmatter, _) = MarkdownConverter::parse_frontmatter(content);
+                let frontmatter = frontmatter.unwrap_or_default();
+                let missing: Vec<&str> = REQUIRED_SKILL_FRONTMATTER
+                    .iter()
+                    .copied()
+                    .filter(|key| !frontmatter.contains_key(*key))
+                    .collect();
+                if !missing.is_empty() {
+                    errors.push(format!(
+                        "Skill '{}' is missing required frontmatter: {}",
+                        name,
+                        missing.join(", ")
+                    ));
+                }
+            }
         }
 
         if !errors.is_empty() {
@@ -223,22 +303,38 @@ impl AgentDeployer for ClaudeDeployer {
         Ok(ValidationReport::success(validation.budget).with_warnings(warnings))
     }
 
-    fn deploy(&self, prepared: PreparedDeployment, config: &DeploymentConfig) -> DeploymentResult<DeploymentOutput> {
+    fn deploy(
+        &self,
+        prepared: PreparedDeployment,
+        config: &DeploymentConfig,
+        progress: &mut ProgressSink<'_>,
+    ) -> DeploymentResult<DeploymentOutput> {
         let mut deployed_files = Vec::new();
+        let mut created_dirs = Vec::new();
         let mut warnings = Vec::new();
+        let mut link_methods = Vec::new();
+        let mut out_reference_results = Vec::new();
         let mut manual_steps = Vec::new();
 
         // Write AGENTS.md content to ~/.agentsmd/AGENTS.md
         let agentsmd_home = fs_manager::ensure_agentsmd_dir()
             .map_err(|e| DeploymentError::fs_error(PathBuf::new(), e.to_string()))?;
         let agents_md_source = agentsmd_home.join("AGENTS.md");
-        
-        fs::write(&agents_md_source, &prepared.agents_md_content).map_err(|e| {
-            DeploymentError::fs_error(&agents_md_source, format!("Failed to write AGENTS.md: {}", e))
-        })?;
-        deployed_files.push(agents_md_source.to_string_lossy().to_string());
+
+        if config.commands_only() {
+            warnings.push(COMMANDS_ONLY_WARNING.to_string());
+        } else {
+            fs::write(&agents_md_source, &prepared.agents_md_content).map_err(|e| {
+                DeploymentError::fs_error(&agents_md_source, format!("Failed to write AGENTS.md: {}", e))
+            })?;
+            deployed_files.push(agents_md_source.to_string_lossy().to_string());
+            progress(DeploymentPhase::WriteAgentsMd, None);
+        }
 
         match config.target_level {
+            TargetLevel::Project if config.commands_only() => {
+                // No AGENTS.md to link into the project
+            }
             TargetLevel::Project => {
                 // Project-level deployment: create .claude/CLAUDE.md
                 let project_root = self.resolve_project_path(config)?;
@@ -252,8 +348,9 @@ impl AgentDeployer for ClaudeDeployer {
                 }
 
                 // Create symlink from .claude/CLAUDE.md to ~/.agentsmd/AGENTS.md
-                match symlink::create_link(project_claude_path.clone(), agents_md_source.clone(), config.force_overwrite) {
-                    Ok((_, warning)) => {
+                match symlink::create_link(project_claude_path.clone(), agents_md_source.clone(), config.force_overwrite, config.link_strategy, prepared.deployed_before(&project_claude_path)) {
+                    Ok((method, warning)) => {
+                        link_methods.push(method);
                         deployed_files.push(project_claude_path.to_string_lossy().to_string());
                         if let Some(w) = warning {
                             warnings.push(w);
@@ -274,7 +371,7 @@ impl AgentDeployer for ClaudeDeployer {
             }
             TargetLevel::User => {
                 // User-level deployment
-                let claude_dir = self.get_claude_dir();
+                let claude_dir = self.get_claude_dir()?;
 
                 // Ensure Claude directory exists
                 fs::create_dir_all(&claude_dir).map_err(|e| {
@@ -282,43 +379,56 @@ impl AgentDeployer for ClaudeDeployer {
                 })?;
 
                 // Create symlink at ~/.claude/CLAUDE.md pointing to AGENTS.md
-                let claude_md_path = claude_dir.join("CLAUDE.md");
-                match symlink::create_link(claude_md_path.clone(), agents_md_source.clone(), config.force_overwrite) {
-                    Ok((_, warning)) => {
-                        deployed_files.push(claude_md_path.to_string_lossy().to_string());
-                        if let Some(w) = warning {
-                            warnings.push(w);
+                if !config.commands_only() {
+                    let claude_md_path = claude_dir.join(self.rules_filename());
+                    match symlink::create_link(claude_md_path.clone(), agents_md_source.clone(), config.force_overwrite, config.link_strategy, prepared.deployed_before(&claude_md_path)) {
+                        Ok((method, warning)) => {
+                            link_methods.push(method);
+                            deployed_files.push(claude_md_path.to_string_lossy().to_string());
+                            if let Some(w) = warning {
+                                warnings.push(w);
+                            }
+                        }
+                        Err(e) => {
+                            return Err(DeploymentError::fs_error(
+                                &claude_md_path,
+                                format!("Failed to create symlink: {}", e),
+                            ));
                         }
-                    }
-                    Err(e) => {
-                        return Err(DeploymentError::fs_error(
-                            &claude_md_path,
-                            format!("Failed to create symlink: {}", e),
-                        ));
                     }
                 }
 
-                // Deploy custom commands
+                // Deploy custom commands (or skills)
                 if !prepared.commands.is_empty() {
                     let build_dir = self.get_build_dir()?;
-                    let commands_dir = self.get_commands_dir();
+                    let commands_dir = self.get_command_output_dir()?;
 
                     fs::create_dir_all(&commands_dir).map_err(|e| {
                         DeploymentError::fs_error(&commands_dir, format!("Failed to create commands directory: {}", e))
                     })?;
 
                     for (name, content) in &prepared.commands {
-                        // Write to build directory
+                        // Write to build directory (skills live in their own subdirectory)
                         let build_path = build_dir.join(name);
+                        if let Some(parent) = build_path.parent() {
+                            fs::create_dir_all(parent).map_err(|e| {
+                                DeploymentError::fs_error(parent, format!("Failed to create directory: {}", e))
+                            })?;
+                        }
                         fs::write(&build_path, content).map_err(|e| {
                             DeploymentError::fs_error(&build_path, format!("Failed to write command: {}", e))
                         })?;
 
-                        // Create symlink in commands directory
+                        // Create symlink in commands directory, keeping nested commands in subfolders
                         let link_path = commands_dir.join(name);
-                        match symlink::create_link(link_path.clone(), build_path.clone(), config.force_overwrite) {
-                            Ok((_, warning)) => {
+                        for dir in create_parent_dirs(&commands_dir, &link_path)? {
+                            created_dirs.push(dir.to_string_lossy().to_string());
+                        }
+                        match symlink::create_link(link_path.clone(), build_path.clone(), config.force_overwrite, config.link_strategy, prepared.deployed_before(&link_path)) {
+                            Ok((method, warning)) => {
+                                link_methods.push(method);
                                 deployed_files.push(link_path.to_string_lossy().to_string());
+                                progress(DeploymentPhase::Command, Some(name.clone()));
                                 if let Some(w) = warning {
                                     warnings.push(w);
                                 }
@@ -337,41 +447,23 @@ impl AgentDeployer for ClaudeDeployer {
 
         // Deploy out-references (symlinks into ~/.claude/references)
         if !prepared.out_references.is_empty() {
-            let out_ref_dir = self.get_out_references_dir();
+            let out_ref_dir = self.get_out_references_dir()?;
             fs::create_dir_all(&out_ref_dir).map_err(|e| {
                 DeploymentError::fs_error(&out_ref_dir, format!("Failed to create references directory: {}", e))
             })?;
 
-            for (rel_path, _content) in &prepared.out_references {
-                let source_path = fs_manager::get_agentsmd_home()
-                    .join("out-references")
-                    .join(rel_path);
-                let dest_path = out_ref_dir.join(rel_path);
-
-                if let Some(parent) = dest_path.parent() {
-                    fs::create_dir_all(parent).ok();
-                }
-
-                match symlink::create_link(dest_path.clone(), source_path.clone(), config.force_overwrite) {
-                    Ok((_, warning)) => {
-                        deployed_files.push(dest_path.to_string_lossy().to_string());
-                        if let Some(w) = warning {
-                            warnings.push(w);
-                        }
-                    }
-                    Err(e) => {
-                        return Err(DeploymentError::fs_error(
-                            &dest_path,
-                            format!("Failed to deploy out-reference: {}", e),
-                        ));
-                    }
-                }
-            }
+            let (deployed, results) =
+                self.base.deploy_out_references(&prepared, &out_ref_dir, config, &mut warnings, progress)?;
+            deployed_files.extend(deployed);
+            link_methods.extend(results.iter().map(|r| r.status));
+            out_reference_results = results;
         }
 
-        Ok(DeploymentOutput::success("symlink", deployed_files)
+        Ok(DeploymentOutput::success(config.link_strategy.deployment_method(&link_methods), deployed_files)
             .with_warnings(warnings)
-            .with_manual_steps(manual_steps))
+            .with_manual_steps(manual_steps)
+            .with_created_dirs(created_dirs)
+            .with_out_reference_results(out_reference_results))
     }
 
     fn rollback(&self, state: &DeploymentState) -> DeploymentResult<()> {
@@ -393,14 +485,14 @@ impl AgentDeployer for ClaudeDeployer {
     }
 
     fn get_status(&self) -> DeploymentResult<AgentStatus> {
-        let claude_dir = self.get_claude_dir();
+        let claude_dir = self.get_claude_dir()?;
 
         if !claude_dir.exists() {
             return Ok(AgentStatus::NotInstalled);
         }
 
         // Check if CLAUDE.md exists
-        let claude_md = claude_dir.join("CLAUDE.md");
+        let claude_md = claude_dir.join(self.rules_filename());
         if claude_md.exists() {
             return Ok(AgentStatus::Configured);
         }
@@ -3,14 +3,15 @@
 //! Handles deployment of AGENTS.md to Cline (VS Code extension).
 //! Cline uses project-level .cline/config.json.
 
+use std::collections::HashSet;
 use std::fs;
 use std::path::PathBuf;
 
-use serde_json::{json, Value};
+use serde_json::{json, Map, Value};
 
 use crate::deployment::deployer::{
-    AgentDeployer, AgentStatus, DeploymentConfig, DeploymentOutput,
-    PreparedDeployment, TargetLevel, ValidationReport,
+    AgentDeployer, AgentStatus, DeploymentConfig, DeploymentOutput, DeploymentPhase,
+    PreparedDeployment, ProgressSink, TargetLevel, ValidationReport,
 };
 use crate::deployment::error::{DeploymentError, DeploymentResult};
 use crate::deployment::project::ProjectDetector;
@@ -98,7 +99,7 @@ impl AgentDeployer for ClineDeployer {
             prepared.add_target_path(self.get_config_path(&project_root));
         } else {
             // User-level: ~/.cline/config.json
-            let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+            let home = fs_manager::home_dir()?;
             prepared.add_target_path(home.join(".cline").join("config.json"));
         }
 
@@ -111,6 +112,7 @@ impl AgentDeployer for ClineDeployer {
         let validation = DeploymentValidator::validate_character_budget(
             &prepared.agents_md_content,
             limit,
+            self.warn_threshold_pct(),
         );
 
         let mut warnings = validation.warnings;
@@ -135,9 +137,15 @@ impl AgentDeployer for ClineDeployer {
         Ok(ValidationReport::success(validation.budget).with_warnings(warnings))
     }
 
-    fn deploy(&self, prepared: PreparedDeployment, config: &DeploymentConfig) -> DeploymentResult<DeploymentOutput> {
+    fn deploy(
+        &self,
+        prepared: PreparedDeployment,
+        config: &DeploymentConfig,
+        progress: &mut ProgressSink<'_>,
+    ) -> DeploymentResult<DeploymentOutput> {
         let mut deployed_files = Vec::new();
-        let warnings = Vec::new();
+        let mut modified_files = Vec::new();
+        let mut warnings = Vec::new();
 
         // Write AGENTS.md to ~/.agentsmd/
         let agentsmd_home = fs_manager::ensure_agentsmd_dir()
@@ -148,6 +156,7 @@ impl AgentDeployer for ClineDeployer {
             DeploymentError::fs_error(&agents_md_path, format!("Failed to write AGENTS.md: {}", e))
         })?;
         deployed_files.push(agents_md_path.to_string_lossy().to_string());
+        progress(DeploymentPhase::WriteAgentsMd, None);
 
         // Determine config directory
         let config_dir = if config.target_level == TargetLevel::Project {
@@ -159,9 +168,7 @@ impl AgentDeployer for ClineDeployer {
             };
             self.get_config_dir(&project_root)
         } else {
-            dirs::home_dir()
-                .unwrap_or_else(|| PathBuf::from("."))
-                .join(".cline")
+            fs_manager::home_dir()?.join(".cline")
         };
 
         // Create config directory
@@ -169,16 +176,50 @@ impl AgentDeployer for ClineDeployer {
             DeploymentError::fs_error(&config_dir, format!("Failed to create .cline directory: {}", e))
         })?;
 
-        // Write config files
+        // Write config files, merging into any existing config.json
         for (name, content) in &prepared.config_files {
             let config_path = config_dir.join(name);
+            let existed = config_path.exists();
+            let content = if name == "config.json" && existed {
+                let existing = fs::read_to_string(&config_path).map_err(|e| {
+                    DeploymentError::fs_error(&config_path, format!("Failed to read existing config: {}", e))
+                })?;
+                match serde_json::from_str::<Value>(&existing) {
+                    Ok(Value::Object(existing)) => {
+                        let managed: Map<String, Value> = serde_json::from_str(content)?;
+                        serde_json::to_string_pretty(&merge_config(existing, &managed))?
+                    }
+                    _ if config.force_overwrite => {
+                        warnings.push(format!(
+                            "Replaced unparseable Cline config at {}",
+                            config_path.display()
+                        ));
+                        content.clone()
+                    }
+                    _ => {
+                        return Err(DeploymentError::ConfigurationError(format!(
+                            "Existing Cline config at {} is not a JSON object; fix it or deploy with force overwrite",
+                            config_path.display()
+                        )));
+                    }
+                }
+            } else {
+                content.clone()
+            };
             fs::write(&config_path, content).map_err(|e| {
                 DeploymentError::fs_error(&config_path, format!("Failed to write config: {}", e))
             })?;
-            deployed_files.push(config_path.to_string_lossy().to_string());
+            // A config the user already had is restored from the backup, never removed
+            if existed {
+                modified_files.push(config_path.to_string_lossy().to_string());
+            } else {
+                deployed_files.push(config_path.to_string_lossy().to_string());
+            }
         }
 
-        Ok(DeploymentOutput::success("copy", deployed_files).with_warnings(warnings))
+        Ok(DeploymentOutput::success("copy", deployed_files)
+            .with_warnings(warnings)
+            .with_modified_files(modified_files))
     }
 
     fn rollback(&self, state: &DeploymentState) -> DeploymentResult<()> {
@@ -199,7 +240,7 @@ impl AgentDeployer for ClineDeployer {
 
     fn get_status(&self) -> DeploymentResult<AgentStatus> {
         // Check user-level config
-        let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+        let home = fs_manager::home_dir()?;
         let user_config = home.join(".cline").join("config.json");
 
         if user_config.exists() {
@@ -226,3 +267,43 @@ impl AgentDeployer for ClineDeployer {
         true
     }
 }
+
+/// Merge toolkit-managed keys into an existing Cline config
+///
+/// `agentsMdPath` and `rules` are replaced, existing commands sharing a name with a
+/// deployed command are replaced by it, and every other key is kept as the user left it.
+pub fn merge_config(mut merged: Map<String, Value>, managed: &Map<String, Value>) -> Map<String, Value> {
+    for key in ["agentsMdPath", "rules"] {
+        if let Some(value) = managed.get(key) {
+            merged.insert(key.to_string(), value.clone());
+        }
+    }
+    if let Some(version) = managed.get("version") {
+        merged.entry("version").or_insert_with(|| version.clone());
+    }
+
+    let new_commands: &[Value] = match managed.get("commands") {
+        Some(Value::Array(new_commands)) => new_commands,
+        _ => &[],
+    };
+    let new_names: HashSet<&str> = new_commands
+        .iter()
+        .filter_map(|cmd| cmd.get("name").and_then(Value::as_str))
+        .collect();
+
+    let mut commands: Vec<Value> = match merged.remove("commands") {
+        Some(Value::Array(existing)) => existing
+            .into_iter()
+            .filter(|cmd| {
+                !cmd.get("name")
+                    .and_then(Value::as_str)
+                    .is_some_and(|name| new_names.contains(name))
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    commands.extend(new_commands.iter().cloned());
+    merged.insert("commands".to_string(), Value::Array(commands));
+
+    merged
+}
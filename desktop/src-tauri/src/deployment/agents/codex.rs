@@ -5,15 +5,15 @@
 use std::fs;
 use std::path::PathBuf;
 
-use crate::deployment::converters::MarkdownConverter;
+use crate::deployment::converters::{MarkdownConverter, REQUIRED_COMMAND_FRONTMATTER};
 use crate::deployment::deployer::{
-    AgentDeployer, AgentStatus, DeploymentConfig, DeploymentOutput,
-    PreparedDeployment, ValidationReport,
+    AgentDeployer, AgentStatus, DeploymentConfig, DeploymentOutput, DeploymentPhase,
+    PreparedDeployment, ProgressSink, ValidationReport,
 };
 use crate::deployment::error::{DeploymentError, DeploymentResult};
 use crate::deployment::state::DeploymentState;
 use crate::deployment::validator::DeploymentValidator;
-use crate::deployment::{generate_agents_md_content, BaseDeployer};
+use crate::deployment::{generate_agents_md_content, BaseDeployer, COMMANDS_ONLY_WARNING};
 use crate::fs_manager;
 use crate::symlink;
 use crate::types::AgentDefinition;
@@ -30,21 +30,24 @@ impl CodexDeployer {
         }
     }
 
+    /// Rules filename from the agent definition, defaulting to `agents.md`
+    fn rules_filename(&self) -> String {
+        self.base.rules_filename("agents.md")
+    }
+
     /// Get the Codex config directory
-    fn get_codex_dir(&self) -> PathBuf {
-        dirs::home_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join(".codex")
+    fn get_codex_dir(&self) -> DeploymentResult<PathBuf> {
+        Ok(fs_manager::home_dir()?.join(".codex"))
     }
 
     /// Get the prompts directory
-    fn get_prompts_dir(&self) -> PathBuf {
-        self.get_codex_dir().join("prompts")
+    fn get_prompts_dir(&self) -> DeploymentResult<PathBuf> {
+        Ok(self.get_codex_dir()?.join("prompts"))
     }
 
     /// Get the build output directory
     fn get_build_dir(&self) -> DeploymentResult<PathBuf> {
-        let agentsmd_home = fs_manager::get_agentsmd_home();
+        let agentsmd_home = fs_manager::get_agentsmd_home()?;
         let build_dir = agentsmd_home.join("build").join("codex").join("prompts");
         fs::create_dir_all(&build_dir).map_err(|e| {
             DeploymentError::fs_error(&build_dir, format!("Failed to create build directory: {}", e))
@@ -63,27 +66,31 @@ impl AgentDeployer for CodexDeployer {
     }
 
     fn prepare(&self, config: &DeploymentConfig) -> DeploymentResult<PreparedDeployment> {
-        // Generate AGENTS.md content with frontmatter
-        let agents_md_content = generate_agents_md_content(&config.pack_ids, false)?;
+        // Generate AGENTS.md content with frontmatter, unless only commands were selected
+        let content_with_frontmatter = if config.commands_only() {
+            String::new()
+        } else {
+            let agents_md_content = generate_agents_md_content(&config.pack_ids, false)?;
 
-        let mut frontmatter = std::collections::HashMap::new();
-        frontmatter.insert("name".to_string(), "/prompts:agents".to_string());
-        frontmatter.insert("description".to_string(), "AGENTS.md mandatory rules".to_string());
+            let mut frontmatter = std::collections::HashMap::new();
+            frontmatter.insert("name".to_string(), "/prompts:agents".to_string());
+            frontmatter.insert("description".to_string(), "AGENTS.md mandatory rules".to_string());
 
-        let content_with_frontmatter = MarkdownConverter::add_frontmatter(&agents_md_content, frontmatter);
+            MarkdownConverter::add_frontmatter(&agents_md_content, frontmatter)
+        };
 
         let mut prepared = PreparedDeployment::new(content_with_frontmatter);
         prepared.command_format = "prompts-prefix".to_string();
 
-        // Add AGENTS.md path to target_paths for backup
-        let agentsmd_home = fs_manager::get_agentsmd_home();
-        let agents_md_source = agentsmd_home.join("AGENTS.md");
-        prepared.add_target_path(agents_md_source);
+        let prompts_dir = self.get_prompts_dir()?;
+        if !config.commands_only() {
+            // Add AGENTS.md path to target_paths for backup
+            let agentsmd_home = fs_manager::get_agentsmd_home()?;
+            prepared.add_target_path(agentsmd_home.join("AGENTS.md"));
 
-        // Add agents.md prompt symlink path for backup
-        let prompts_dir = self.get_prompts_dir();
-        let agents_prompt_path = prompts_dir.join("agents.md");
-        prepared.add_target_path(agents_prompt_path);
+            // Add agents.md prompt symlink path for backup
+            prepared.add_target_path(prompts_dir.join(self.rules_filename()));
+        }
 
         // Prepare custom commands with /prompts: prefix
         for command_id in &config.custom_command_ids {
@@ -92,7 +99,12 @@ impl AgentDeployer for CodexDeployer {
                 &format!("Custom prompt: {}", command_id),
                 "Execute this prompt to perform the specified action.",
             );
-            prepared.add_command(format!("{}.md", command_id), command_content);
+            prepared.add_command_with_frontmatter(
+                format!("{}.md", command_id),
+                command_content,
+                REQUIRED_COMMAND_FRONTMATTER,
+                config.fix_frontmatter,
+            );
             
             // Add each prompt file path for backup
             let prompt_path = prompts_dir.join(format!("{}.md", command_id));
@@ -113,6 +125,7 @@ impl AgentDeployer for CodexDeployer {
         let validation = DeploymentValidator::validate_character_budget(
             &prepared.agents_md_content,
             limit,
+            self.warn_threshold_pct(),
         );
 
         let mut warnings = validation.warnings;
@@ -120,7 +133,7 @@ impl AgentDeployer for CodexDeployer {
 
         // Validate frontmatter presence
         let fm_validation = DeploymentValidator::validate_frontmatter(&prepared.agents_md_content);
-        if !fm_validation.valid {
+        if !prepared.agents_md_content.is_empty() && !fm_validation.valid {
             warnings.push("Content should have YAML frontmatter for Codex".to_string());
         }
 
@@ -139,42 +152,54 @@ impl AgentDeployer for CodexDeployer {
         Ok(ValidationReport::success(validation.budget).with_warnings(warnings))
     }
 
-    fn deploy(&self, prepared: PreparedDeployment, config: &DeploymentConfig) -> DeploymentResult<DeploymentOutput> {
+    fn deploy(
+        &self,
+        prepared: PreparedDeployment,
+        config: &DeploymentConfig,
+        progress: &mut ProgressSink<'_>,
+    ) -> DeploymentResult<DeploymentOutput> {
         let mut deployed_files = Vec::new();
         let mut warnings = Vec::new();
+        let mut link_methods = Vec::new();
 
-        let codex_dir = self.get_codex_dir();
-        let prompts_dir = self.get_prompts_dir();
+        let codex_dir = self.get_codex_dir()?;
+        let prompts_dir = self.get_prompts_dir()?;
 
         // Ensure directories exist
         fs::create_dir_all(&prompts_dir).map_err(|e| {
             DeploymentError::fs_error(&prompts_dir, format!("Failed to create prompts directory: {}", e))
         })?;
 
-        // Write AGENTS.md to ~/.agentsmd/
-        let agentsmd_home = fs_manager::ensure_agentsmd_dir()
-            .map_err(|e| DeploymentError::fs_error(PathBuf::new(), e.to_string()))?;
-        let agents_md_source = agentsmd_home.join("AGENTS.md");
-        
-        fs::write(&agents_md_source, &prepared.agents_md_content).map_err(|e| {
-            DeploymentError::fs_error(&agents_md_source, format!("Failed to write AGENTS.md: {}", e))
-        })?;
-        deployed_files.push(agents_md_source.to_string_lossy().to_string());
-
-        // Create agents.md prompt symlink
-        let agents_prompt_path = prompts_dir.join("agents.md");
-        match symlink::create_link(agents_prompt_path.clone(), agents_md_source.clone(), config.force_overwrite) {
-            Ok((_, warning)) => {
-                deployed_files.push(agents_prompt_path.to_string_lossy().to_string());
-                if let Some(w) = warning {
-                    warnings.push(w);
+        if config.commands_only() {
+            warnings.push(COMMANDS_ONLY_WARNING.to_string());
+        } else {
+            // Write AGENTS.md to ~/.agentsmd/
+            let agentsmd_home = fs_manager::ensure_agentsmd_dir()
+                .map_err(|e| DeploymentError::fs_error(PathBuf::new(), e.to_string()))?;
+            let agents_md_source = agentsmd_home.join("AGENTS.md");
+
+            fs::write(&agents_md_source, &prepared.agents_md_content).map_err(|e| {
+                DeploymentError::fs_error(&agents_md_source, format!("Failed to write AGENTS.md: {}", e))
+            })?;
+            deployed_files.push(agents_md_source.to_string_lossy().to_string());
+            progress(DeploymentPhase::WriteAgentsMd, None);
+
+            // Create agents.md prompt symlink
+            let agents_prompt_path = prompts_dir.join(self.rules_filename());
+            match symlink::create_link(agents_prompt_path.clone(), agents_md_source.clone(), config.force_overwrite, config.link_strategy, prepared.deployed_before(&agents_prompt_path)) {
+                Ok((method, warning)) => {
+                    link_methods.push(method);
+                    deployed_files.push(agents_prompt_path.to_string_lossy().to_string());
+                    if let Some(w) = warning {
+                        warnings.push(w);
+                    }
+                }
+                Err(e) => {
+                    return Err(DeploymentError::fs_error(
+                        &agents_prompt_path,
+                        format!("Failed to create symlink: {}", e),
+                    ));
                 }
-            }
-            Err(e) => {
-                return Err(DeploymentError::fs_error(
-                    &agents_prompt_path,
-                    format!("Failed to create symlink: {}", e),
-                ));
             }
         }
 
@@ -189,9 +214,11 @@ impl AgentDeployer for CodexDeployer {
                 })?;
 
                 let link_path = prompts_dir.join(name);
-                match symlink::create_link(link_path.clone(), build_path.clone(), config.force_overwrite) {
-                    Ok((_, warning)) => {
+                match symlink::create_link(link_path.clone(), build_path.clone(), config.force_overwrite, config.link_strategy, prepared.deployed_before(&link_path)) {
+                    Ok((method, warning)) => {
+                        link_methods.push(method);
                         deployed_files.push(link_path.to_string_lossy().to_string());
+                        progress(DeploymentPhase::Command, Some(name.clone()));
                         if let Some(w) = warning {
                             warnings.push(w);
                         }
@@ -206,7 +233,7 @@ impl AgentDeployer for CodexDeployer {
             }
         }
 
-        Ok(DeploymentOutput::success("symlink", deployed_files).with_warnings(warnings))
+        Ok(DeploymentOutput::success(config.link_strategy.deployment_method(&link_methods), deployed_files).with_warnings(warnings))
     }
 
     fn rollback(&self, state: &DeploymentState) -> DeploymentResult<()> {
@@ -228,15 +255,15 @@ impl AgentDeployer for CodexDeployer {
     }
 
     fn get_status(&self) -> DeploymentResult<AgentStatus> {
-        let codex_dir = self.get_codex_dir();
+        let codex_dir = self.get_codex_dir()?;
 
         if !codex_dir.exists() {
             return Ok(AgentStatus::NotInstalled);
         }
 
-        let prompts_dir = self.get_prompts_dir();
+        let prompts_dir = self.get_prompts_dir()?;
         if prompts_dir.exists() {
-            let agents_prompt = prompts_dir.join("agents.md");
+            let agents_prompt = prompts_dir.join(self.rules_filename());
             if agents_prompt.exists() {
                 return Ok(AgentStatus::Configured);
             }
@@ -6,16 +6,17 @@
 use std::fs;
 use std::path::PathBuf;
 
+use crate::budget;
 use crate::deployment::deployer::{
-    AgentDeployer, AgentStatus, DeploymentConfig, DeploymentOutput,
-    PreparedDeployment, TargetLevel, ValidationReport,
+    AgentDeployer, AgentStatus, DeploymentConfig, DeploymentOutput, DeploymentPhase,
+    PreparedDeployment, ProgressSink, TargetLevel, ValidationReport,
 };
 use crate::deployment::error::{DeploymentError, DeploymentResult};
 use crate::deployment::project::ProjectDetector;
 use crate::deployment::state::DeploymentState;
 use crate::deployment::validator::DeploymentValidator;
 use crate::deployment::{generate_agents_md_content, BaseDeployer};
-use crate::types::AgentDefinition;
+use crate::types::{AgentDefinition, PackBudgetItem};
 
 /// Deployer for GitHub Copilot
 pub struct CopilotDeployer {
@@ -29,9 +30,36 @@ impl CopilotDeployer {
         }
     }
 
+    /// Rules filename from the agent definition, defaulting to `copilot-instructions.md`
+    fn rules_filename(&self) -> String {
+        self.base.rules_filename("copilot-instructions.md")
+    }
+
     /// Get the copilot instructions path for a project
     fn get_instructions_path(&self, project_root: &PathBuf) -> PathBuf {
-        project_root.join(".github").join("copilot-instructions.md")
+        project_root.join(".github").join(self.rules_filename())
+    }
+
+    /// Suggest the largest packs whose removal frees at least `excess` characters
+    fn suggest_packs_to_drop(breakdown: &[PackBudgetItem], excess: u64) -> Option<String> {
+        let mut packs: Vec<&PackBudgetItem> = breakdown.iter().collect();
+        packs.sort_by(|a, b| b.chars.cmp(&a.chars).then_with(|| a.pack_id.cmp(&b.pack_id)));
+
+        let mut freed = 0;
+        let mut suggestions = Vec::new();
+        for pack in packs {
+            if freed >= excess {
+                break;
+            }
+            freed += pack.chars;
+            suggestions.push(format!("remove pack {} ({} chars)", pack.pack_id, pack.chars));
+        }
+
+        if suggestions.is_empty() {
+            None
+        } else {
+            Some(format!("To fit Copilot's limit: {}", suggestions.join(", ")))
+        }
     }
 }
 
@@ -54,10 +82,17 @@ impl AgentDeployer for CopilotDeployer {
 
         // Generate AGENTS.md content - inline (no file references) due to 8K limit
         let agents_md_content = generate_agents_md_content(&config.pack_ids, true)?;
+        // Compact before budgeting so the shrunk size is what gets checked
+        let agents_md_content = config.compaction.apply(&agents_md_content);
 
         let mut prepared = PreparedDeployment::new(agents_md_content);
         prepared.command_format = "inline".to_string();
 
+        // Record per-pack sizes so validation can suggest what to drop
+        if let Ok(budget) = budget::calculate(&config.pack_ids, None) {
+            prepared.pack_breakdown = budget.pack_breakdown;
+        }
+
         // Copilot doesn't support custom commands
         if !config.custom_command_ids.is_empty() {
             // We'll add a warning in validation
@@ -84,10 +119,21 @@ impl AgentDeployer for CopilotDeployer {
         let validation = DeploymentValidator::validate_character_budget(
             &prepared.agents_md_content,
             limit,
+            self.warn_threshold_pct(),
         );
 
         let mut warnings = validation.warnings.clone();
-        let mut errors = validation.errors.clone();
+        let errors = validation.errors.clone();
+
+        // Over budget: name the packs to drop
+        let suggestion = validation.budget.max_chars.and_then(|max| {
+            let current = validation.budget.current_chars;
+            if current > max {
+                Self::suggest_packs_to_drop(&prepared.pack_breakdown, current - max)
+            } else {
+                None
+            }
+        });
 
         // Warn about custom commands
         if !prepared.commands.is_empty() {
@@ -107,13 +153,18 @@ impl AgentDeployer for CopilotDeployer {
         }
 
         if !errors.is_empty() {
-            return Ok(ValidationReport::failure(errors, validation.budget));
+            return Ok(ValidationReport::failure(errors, validation.budget).with_suggestion(suggestion));
         }
 
         Ok(ValidationReport::success(validation.budget).with_warnings(warnings))
     }
 
-    fn deploy(&self, prepared: PreparedDeployment, config: &DeploymentConfig) -> DeploymentResult<DeploymentOutput> {
+    fn deploy(
+        &self,
+        prepared: PreparedDeployment,
+        config: &DeploymentConfig,
+        progress: &mut ProgressSink<'_>,
+    ) -> DeploymentResult<DeploymentOutput> {
         let mut deployed_files = Vec::new();
         let warnings = Vec::new();
 
@@ -140,6 +191,7 @@ impl AgentDeployer for CopilotDeployer {
             DeploymentError::fs_error(&instructions_path, format!("Failed to write instructions: {}", e))
         })?;
         deployed_files.push(instructions_path.to_string_lossy().to_string());
+        progress(DeploymentPhase::WriteAgentsMd, None);
 
         Ok(DeploymentOutput::success("inline", deployed_files).with_warnings(warnings))
     }
@@ -182,3 +234,71 @@ impl AgentDeployer for CopilotDeployer {
         false // Copilot doesn't support user-level configuration
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{CharacterLimits, DEFAULT_WARN_THRESHOLD_PCT};
+
+    fn copilot_agent(max_chars: u64) -> AgentDefinition {
+        AgentDefinition {
+            id: "copilot".to_string(),
+            name: "GitHub Copilot".to_string(),
+            config_paths: vec![".github/copilot-instructions.md".to_string()],
+            agents_md_support: "config".to_string(),
+            command_format: "inline".to_string(),
+            character_limits: CharacterLimits {
+                max_chars: Some(max_chars),
+                supports_out_references: false,
+                warn_threshold_pct: DEFAULT_WARN_THRESHOLD_PCT,
+            },
+            deployment_strategy: "inline".to_string(),
+            build_output: String::new(),
+            file_format: "markdown".to_string(),
+            requires_frontmatter: None,
+            sandbox_script_path: None,
+            notes: None,
+            rules_filename: None,
+        }
+    }
+
+    fn pack_item(pack_id: &str, chars: u64) -> PackBudgetItem {
+        PackBudgetItem {
+            pack_id: pack_id.to_string(),
+            chars,
+            words: 0,
+            percentage_of_total: 0,
+        }
+    }
+
+    #[test]
+    fn test_validate_over_budget_names_biggest_pack() {
+        let deployer = CopilotDeployer::new(copilot_agent(8000));
+        let mut prepared = PreparedDeployment::new("x".repeat(9000));
+        prepared.pack_breakdown = vec![
+            pack_item("core", 1500),
+            pack_item("github-hygiene", 6000),
+            pack_item("testing", 1500),
+        ];
+
+        let report = deployer.validate(&prepared).unwrap();
+
+        assert!(!report.valid);
+        assert!(report.only_over_budget());
+        let suggestion = report.suggestion.unwrap();
+        assert!(suggestion.contains("remove pack github-hygiene (6000 chars)"));
+        assert!(!suggestion.contains("remove pack core"));
+    }
+
+    #[test]
+    fn test_suggest_packs_to_drop_accumulates_until_fit() {
+        let breakdown = vec![pack_item("a", 500), pack_item("b", 400), pack_item("c", 100)];
+
+        let suggestion = CopilotDeployer::suggest_packs_to_drop(&breakdown, 700).unwrap();
+        assert_eq!(
+            suggestion,
+            "To fit Copilot's limit: remove pack a (500 chars), remove pack b (400 chars)"
+        );
+        assert!(CopilotDeployer::suggest_packs_to_drop(&[], 700).is_none());
+    }
+}
@@ -5,17 +5,21 @@
 use std::fs;
 use std::path::PathBuf;
 
+use crate::budget;
 use crate::deployment::command_loader;
 use crate::deployment::converters::MarkdownConverter;
 use crate::deployment::deployer::{
-    AgentDeployer, AgentStatus, BudgetUsage, DeploymentConfig, DeploymentOutput,
-    PreparedDeployment, TargetLevel, ValidationReport,
+    AgentDeployer, AgentStatus, BudgetUsage, DeploymentConfig, DeploymentOutput, DeploymentPhase,
+    PreparedDeployment, ProgressSink, TargetLevel, ValidationReport,
 };
 use crate::deployment::error::{DeploymentError, DeploymentResult};
 use crate::deployment::project::ProjectDetector;
 use crate::deployment::state::DeploymentState;
 use crate::deployment::validator::DeploymentValidator;
-use crate::deployment::{collect_out_references_for_selection, generate_agents_md_content, BaseDeployer};
+use crate::deployment::{
+    collect_out_references_for_selection, create_parent_dirs, generate_agents_md_content, BaseDeployer,
+    COMMANDS_ONLY_WARNING,
+};
 use crate::fs_manager;
 use crate::symlink;
 use crate::types::AgentDefinition;
@@ -32,25 +36,24 @@ impl CursorDeployer {
         }
     }
 
+    /// Rules filename from the agent definition, defaulting to `rules.md`
+    fn rules_filename(&self) -> String {
+        self.base.rules_filename("rules.md")
+    }
+
     /// Get the Cursor out-references directory (user-level)
-    fn get_out_references_dir(&self) -> PathBuf {
-        dirs::home_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join(".cursor")
-            .join("out-references")
+    fn get_out_references_dir(&self) -> DeploymentResult<PathBuf> {
+        Ok(fs_manager::home_dir()?.join(".cursor").join("out-references"))
     }
 
     /// Get the Cursor commands directory (user-level)
-    fn get_commands_dir(&self) -> PathBuf {
-        dirs::home_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join(".cursor")
-            .join("commands")
+    fn get_commands_dir(&self) -> DeploymentResult<PathBuf> {
+        Ok(fs_manager::home_dir()?.join(".cursor").join("commands"))
     }
 
     /// Get project-level rules.md path
     fn get_project_rules_path(&self, project_root: &PathBuf) -> PathBuf {
-        project_root.join(".cursor").join("rules.md")
+        project_root.join(".cursor").join(self.rules_filename())
     }
 
     /// Resolve project path from config or detect automatically
@@ -81,7 +84,7 @@ impl CursorDeployer {
 
     /// Get the build output directory for Cursor commands
     fn get_build_dir(&self) -> DeploymentResult<PathBuf> {
-        let agentsmd_home = fs_manager::get_agentsmd_home();
+        let agentsmd_home = fs_manager::get_agentsmd_home()?;
         let build_dir = agentsmd_home.join("build").join("cursor").join("commands");
         fs::create_dir_all(&build_dir).map_err(|e| {
             DeploymentError::fs_error(&build_dir, format!("Failed to create build directory: {}", e))
@@ -100,16 +103,21 @@ impl AgentDeployer for CursorDeployer {
     }
 
     fn prepare(&self, config: &DeploymentConfig) -> DeploymentResult<PreparedDeployment> {
-        // Generate AGENTS.md content
-        let agents_md_content = generate_agents_md_content(&config.pack_ids, false)?;
+        // Generate AGENTS.md content, unless only commands were selected
+        let agents_md_content = if config.commands_only() {
+            String::new()
+        } else {
+            generate_agents_md_content(&config.pack_ids, false)?
+        };
 
         let mut prepared = PreparedDeployment::new(agents_md_content);
         prepared.command_format = "markdown".to_string();
 
         // Add AGENTS.md path to target_paths for backup
-        let agentsmd_home = fs_manager::get_agentsmd_home();
-        let agents_md_path = agentsmd_home.join("AGENTS.md");
-        prepared.add_target_path(agents_md_path);
+        if !config.commands_only() {
+            let agentsmd_home = fs_manager::get_agentsmd_home()?;
+            prepared.add_target_path(agentsmd_home.join("AGENTS.md"));
+        }
 
         // Collect out-references used by commands/packs
         let resolved_refs = collect_out_references_for_selection(
@@ -117,7 +125,7 @@ impl AgentDeployer for CursorDeployer {
             &config.pack_ids,
         )?;
         if !resolved_refs.is_empty() {
-            let cursor_out_ref_dir = self.get_out_references_dir();
+            let cursor_out_ref_dir = self.get_out_references_dir()?;
             prepared.add_target_path(cursor_out_ref_dir.clone());
 
             for resolved in &resolved_refs {
@@ -128,6 +136,9 @@ impl AgentDeployer for CursorDeployer {
 
         // Branch on target level for destination paths
         match config.target_level {
+            TargetLevel::Project if config.commands_only() => {
+                // No AGENTS.md to link into the project
+            }
             TargetLevel::Project => {
                 // Project-level deployment: .cursor/rules.md
                 let project_root = self.resolve_project_path(config)?;
@@ -136,7 +147,7 @@ impl AgentDeployer for CursorDeployer {
             }
             TargetLevel::User => {
                 // User-level: prepare custom commands as markdown files
-                let commands_dir = self.get_commands_dir();
+                let commands_dir = self.get_commands_dir()?;
                 for command_id in &config.custom_command_ids {
                     // Load and convert command from registry
                     match command_loader::load_command_for_deployment(command_id, "cursor") {
@@ -176,15 +187,15 @@ impl AgentDeployer for CursorDeployer {
     fn validate(&self, prepared: &PreparedDeployment) -> DeploymentResult<ValidationReport> {
         // Check character limit (1M for Cursor)
         let limit = self.character_limit();
-        let agents_chars = prepared.agents_md_content.len() as u64;
+        let agents_chars = budget::char_count(&prepared.agents_md_content);
         let command_chars: u64 = prepared
             .commands
             .values()
-            .map(|c| c.len() as u64)
+            .map(|c| budget::char_count(c))
             .sum();
         let out_reference_chars = prepared.out_reference_chars();
         let validation =
-            DeploymentValidator::validate_full_budget(agents_chars, command_chars, out_reference_chars, limit);
+            DeploymentValidator::validate_full_budget(agents_chars, command_chars, out_reference_chars, limit, self.warn_threshold_pct());
 
         let mut warnings = validation.warnings;
         let mut errors = validation.errors;
@@ -203,22 +214,38 @@ impl AgentDeployer for CursorDeployer {
         Ok(ValidationReport::success(validation.budget).with_warnings(warnings))
     }
 
-    fn deploy(&self, prepared: PreparedDeployment, config: &DeploymentConfig) -> DeploymentResult<DeploymentOutput> {
+    fn deploy(
+        &self,
+        prepared: PreparedDeployment,
+        config: &DeploymentConfig,
+        progress: &mut ProgressSink<'_>,
+    ) -> DeploymentResult<DeploymentOutput> {
         let mut deployed_files = Vec::new();
+        let mut created_dirs = Vec::new();
         let mut warnings = Vec::new();
+        let mut link_methods = Vec::new();
+        let mut out_reference_results = Vec::new();
         let mut manual_steps = Vec::new();
 
         // Ensure ~/.agentsmd/AGENTS.md exists with content
         let agentsmd_home = fs_manager::ensure_agentsmd_dir()
             .map_err(|e| DeploymentError::fs_error(PathBuf::new(), e.to_string()))?;
         let agents_md_path = agentsmd_home.join("AGENTS.md");
-        
-        fs::write(&agents_md_path, &prepared.agents_md_content).map_err(|e| {
-            DeploymentError::fs_error(&agents_md_path, format!("Failed to write AGENTS.md: {}", e))
-        })?;
-        deployed_files.push(agents_md_path.to_string_lossy().to_string());
+
+        if config.commands_only() {
+            warnings.push(COMMANDS_ONLY_WARNING.to_string());
+        } else {
+            fs::write(&agents_md_path, &prepared.agents_md_content).map_err(|e| {
+                DeploymentError::fs_error(&agents_md_path, format!("Failed to write AGENTS.md: {}", e))
+            })?;
+            deployed_files.push(agents_md_path.to_string_lossy().to_string());
+            progress(DeploymentPhase::WriteAgentsMd, None);
+        }
 
         match config.target_level {
+            TargetLevel::Project if config.commands_only() => {
+                // No AGENTS.md to link into the project
+            }
             TargetLevel::Project => {
                 // Project-level deployment: create .cursor/rules.md
                 let project_root = self.resolve_project_path(config)?;
@@ -232,8 +259,9 @@ impl AgentDeployer for CursorDeployer {
                 }
 
                 // Create symlink from .cursor/rules.md to ~/.agentsmd/AGENTS.md
-                match symlink::create_link(project_rules_path.clone(), agents_md_path.clone(), config.force_overwrite) {
-                    Ok((_, warning)) => {
+                match symlink::create_link(project_rules_path.clone(), agents_md_path.clone(), config.force_overwrite, config.link_strategy, prepared.deployed_before(&project_rules_path)) {
+                    Ok((method, warning)) => {
+                        link_methods.push(method);
                         deployed_files.push(project_rules_path.to_string_lossy().to_string());
                         if let Some(w) = warning {
                             warnings.push(w);
@@ -256,7 +284,7 @@ impl AgentDeployer for CursorDeployer {
                 // User-level deployment: create build directory and write command files
                 if !prepared.commands.is_empty() {
                     let build_dir = self.get_build_dir()?;
-                    let commands_dir = self.get_commands_dir();
+                    let commands_dir = self.get_commands_dir()?;
 
                     // Ensure commands directory exists
                     fs::create_dir_all(&commands_dir).map_err(|e| {
@@ -266,15 +294,25 @@ impl AgentDeployer for CursorDeployer {
                     for (name, content) in &prepared.commands {
                         // Write to build directory
                         let build_path = build_dir.join(name);
+                        if let Some(parent) = build_path.parent() {
+                            fs::create_dir_all(parent).map_err(|e| {
+                                DeploymentError::fs_error(parent, format!("Failed to create directory: {}", e))
+                            })?;
+                        }
                         fs::write(&build_path, content).map_err(|e| {
                             DeploymentError::fs_error(&build_path, format!("Failed to write command: {}", e))
                         })?;
 
-                        // Create symlink in commands directory
+                        // Create symlink in commands directory, keeping nested commands in subfolders
                         let link_path = commands_dir.join(name);
-                        match symlink::create_link(link_path.clone(), build_path.clone(), config.force_overwrite) {
-                            Ok((_, warning)) => {
+                        for dir in create_parent_dirs(&commands_dir, &link_path)? {
+                            created_dirs.push(dir.to_string_lossy().to_string());
+                        }
+                        match symlink::create_link(link_path.clone(), build_path.clone(), config.force_overwrite, config.link_strategy, prepared.deployed_before(&link_path)) {
+                            Ok((method, warning)) => {
+                                link_methods.push(method);
                                 deployed_files.push(link_path.to_string_lossy().to_string());
+                                progress(DeploymentPhase::Command, Some(name.clone()));
                                 if let Some(w) = warning {
                                     warnings.push(w);
                                 }
@@ -290,53 +328,37 @@ impl AgentDeployer for CursorDeployer {
                 }
 
                 // Add manual step for User Rule configuration
-                manual_steps.push(
-                    "To complete setup, add the following to your Cursor User Rule (Settings > Rules for AI):\n\
-                     \n\
-                     Always read and follow ~/.agentsmd/AGENTS.md\n\
-                     \n\
-                     Or reference it directly using @~/.agentsmd/AGENTS.md in your prompts.".to_string()
-                );
+                if !config.commands_only() {
+                    manual_steps.push(
+                        "To complete setup, add the following to your Cursor User Rule (Settings > Rules for AI):\n\
+                         \n\
+                         Always read and follow ~/.agentsmd/AGENTS.md\n\
+                         \n\
+                         Or reference it directly using @~/.agentsmd/AGENTS.md in your prompts.".to_string()
+                    );
+                }
             }
         }
 
         // Deploy out-references (symlinks to ~/.agentsmd/out-references)
         if !prepared.out_references.is_empty() {
-            let out_ref_dir = self.get_out_references_dir();
+            let out_ref_dir = self.get_out_references_dir()?;
             fs::create_dir_all(&out_ref_dir).map_err(|e| {
                 DeploymentError::fs_error(&out_ref_dir, format!("Failed to create out-references directory: {}", e))
             })?;
 
-            for (rel_path, _content) in &prepared.out_references {
-                let source_path = fs_manager::get_agentsmd_home()
-                    .join("out-references")
-                    .join(rel_path);
-                let dest_path = out_ref_dir.join(rel_path);
-
-                if let Some(parent) = dest_path.parent() {
-                    fs::create_dir_all(parent).ok();
-                }
-
-                match symlink::create_link(dest_path.clone(), source_path.clone(), config.force_overwrite) {
-                    Ok((_, warning)) => {
-                        deployed_files.push(dest_path.to_string_lossy().to_string());
-                        if let Some(w) = warning {
-                            warnings.push(w);
-                        }
-                    }
-                    Err(e) => {
-                        return Err(DeploymentError::fs_error(
-                            &dest_path,
-                            format!("Failed to deploy out-reference: {}", e),
-                        ));
-                    }
-                }
-            }
+            let (deployed, results) =
+                self.base.deploy_out_references(&prepared, &out_ref_dir, config, &mut warnings, progress)?;
+            deployed_files.extend(deployed);
+            link_methods.extend(results.iter().map(|r| r.status));
+            out_reference_results = results;
         }
 
-        Ok(DeploymentOutput::success("symlink", deployed_files)
+        Ok(DeploymentOutput::success(config.link_strategy.deployment_method(&link_methods), deployed_files)
             .with_warnings(warnings)
-            .with_manual_steps(manual_steps))
+            .with_manual_steps(manual_steps)
+            .with_created_dirs(created_dirs)
+            .with_out_reference_results(out_reference_results))
     }
 
     fn rollback(&self, state: &DeploymentState) -> DeploymentResult<()> {
@@ -366,9 +388,7 @@ impl AgentDeployer for CursorDeployer {
     }
 
     fn get_status(&self) -> DeploymentResult<AgentStatus> {
-        let cursor_dir = dirs::home_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join(".cursor");
+        let cursor_dir = fs_manager::home_dir()?.join(".cursor");
 
         if !cursor_dir.exists() {
             return Ok(AgentStatus::NotInstalled);
@@ -5,17 +5,20 @@
 use std::fs;
 use std::path::PathBuf;
 
+use crate::budget;
 use crate::deployment::command_loader;
 use crate::deployment::converters::MarkdownConverter;
 use crate::deployment::deployer::{
-    AgentDeployer, AgentStatus, DeploymentConfig, DeploymentOutput,
-    PreparedDeployment, TargetLevel, ValidationReport,
+    AgentDeployer, AgentStatus, DeploymentConfig, DeploymentOutput, DeploymentPhase,
+    PreparedDeployment, ProgressSink, TargetLevel, ValidationReport,
 };
 use crate::deployment::error::{DeploymentError, DeploymentResult};
 use crate::deployment::project::ProjectDetector;
 use crate::deployment::state::DeploymentState;
 use crate::deployment::validator::DeploymentValidator;
-use crate::deployment::{collect_out_references_for_selection, generate_agents_md_content, BaseDeployer};
+use crate::deployment::{
+    collect_out_references_for_selection, generate_agents_md_content, BaseDeployer, COMMANDS_ONLY_WARNING,
+};
 use crate::fs_manager;
 use crate::symlink;
 use crate::types::AgentDefinition;
@@ -41,36 +44,48 @@ impl GeminiDeployer {
         }
     }
 
+    /// Rules filename from the agent definition, defaulting to `GEMINI.md`
+    fn rules_filename(&self) -> String {
+        self.base.rules_filename("GEMINI.md")
+    }
+
     /// Get the Gemini out-references directory
-    fn get_out_references_dir(&self) -> PathBuf {
-        self.get_gemini_dir().join("references")
+    fn get_out_references_dir(&self) -> DeploymentResult<PathBuf> {
+        Ok(self.get_gemini_dir()?.join("references"))
     }
 
     /// Get the Gemini config directory
-    fn get_gemini_dir(&self) -> PathBuf {
-        dirs::home_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join(".gemini")
+    fn get_gemini_dir(&self) -> DeploymentResult<PathBuf> {
+        Ok(fs_manager::home_dir()?.join(".gemini"))
     }
 
     /// Get the commands directory
-    fn get_commands_dir(&self) -> PathBuf {
-        self.get_gemini_dir().join("commands")
+    fn get_commands_dir(&self) -> DeploymentResult<PathBuf> {
+        Ok(self.get_gemini_dir()?.join("commands"))
     }
 
     /// Get the scripts directory for sandbox access
-    fn get_scripts_dir(&self) -> PathBuf {
-        self.get_gemini_dir().join("scripts")
+    fn get_scripts_dir(&self) -> DeploymentResult<PathBuf> {
+        Ok(self.get_gemini_dir()?.join("scripts"))
     }
 
     /// Get the Antigravity workflows directory
-    fn get_workflows_dir(&self) -> PathBuf {
-        self.get_gemini_dir().join("antigravity").join("global_workflows")
+    fn get_workflows_dir(&self) -> DeploymentResult<PathBuf> {
+        Ok(self.get_gemini_dir()?.join("antigravity").join("global_workflows"))
+    }
+
+    /// Get the directory commands are linked into (workflows for Antigravity)
+    fn get_command_output_dir(&self) -> DeploymentResult<PathBuf> {
+        if self.is_antigravity {
+            self.get_workflows_dir()
+        } else {
+            self.get_commands_dir()
+        }
     }
 
     /// Get project-level GEMINI.md path
     fn get_project_gemini_path(&self, project_root: &PathBuf) -> PathBuf {
-        project_root.join(".gemini").join("GEMINI.md")
+        project_root.join(".gemini").join(self.rules_filename())
     }
 
     /// Resolve project path from config or detect automatically
@@ -101,8 +116,12 @@ impl GeminiDeployer {
 
     /// Get the build output directory
     fn get_build_dir(&self) -> DeploymentResult<PathBuf> {
-        let agentsmd_home = fs_manager::get_agentsmd_home();
-        let build_dir = agentsmd_home.join("build").join("gemini").join("commands");
+        let agentsmd_home = fs_manager::get_agentsmd_home()?;
+        let build_dir = if self.is_antigravity {
+            agentsmd_home.join("build").join("antigravity").join("global_workflows")
+        } else {
+            agentsmd_home.join("build").join("gemini").join("commands")
+        };
         fs::create_dir_all(&build_dir).map_err(|e| {
             DeploymentError::fs_error(&build_dir, format!("Failed to create build directory: {}", e))
         })?;
@@ -120,16 +139,21 @@ impl AgentDeployer for GeminiDeployer {
     }
 
     fn prepare(&self, config: &DeploymentConfig) -> DeploymentResult<PreparedDeployment> {
-        // Generate AGENTS.md content
-        let agents_md_content = generate_agents_md_content(&config.pack_ids, false)?;
+        // Generate AGENTS.md content, unless only commands were selected
+        let agents_md_content = if config.commands_only() {
+            String::new()
+        } else {
+            generate_agents_md_content(&config.pack_ids, false)?
+        };
 
         let mut prepared = PreparedDeployment::new(agents_md_content);
         prepared.command_format = "toml".to_string();
 
         // Add AGENTS.md path to target_paths for backup
-        let agentsmd_home = fs_manager::get_agentsmd_home();
-        let agents_md_source = agentsmd_home.join("AGENTS.md");
-        prepared.add_target_path(agents_md_source.clone());
+        let agentsmd_home = fs_manager::get_agentsmd_home()?;
+        if !config.commands_only() {
+            prepared.add_target_path(agentsmd_home.join("AGENTS.md"));
+        }
 
         // Collect out-references used by commands/packs
         let resolved_refs = collect_out_references_for_selection(
@@ -137,7 +161,7 @@ impl AgentDeployer for GeminiDeployer {
             &config.pack_ids,
         )?;
         if !resolved_refs.is_empty() {
-            let out_ref_dir = self.get_out_references_dir();
+            let out_ref_dir = self.get_out_references_dir()?;
             prepared.add_target_path(out_ref_dir.clone());
             for resolved in &resolved_refs {
                 prepared.add_out_reference(resolved.file_path.clone(), resolved.content.clone());
@@ -147,6 +171,9 @@ impl AgentDeployer for GeminiDeployer {
 
         // Branch on target level for destination paths
         match config.target_level {
+            TargetLevel::Project if config.commands_only() => {
+                // No AGENTS.md to import into the project
+            }
             TargetLevel::Project => {
                 // Project-level deployment: .gemini/GEMINI.md
                 let project_root = self.resolve_project_path(config)?;
@@ -155,17 +182,19 @@ impl AgentDeployer for GeminiDeployer {
             }
             TargetLevel::User => {
                 // User-level: GEMINI.md in ~/.gemini/
-                let gemini_dir = self.get_gemini_dir();
-                prepared.add_target_path(gemini_dir.join("GEMINI.md"));
+                let gemini_dir = self.get_gemini_dir()?;
+                if !config.commands_only() {
+                    prepared.add_target_path(gemini_dir.join(self.rules_filename()));
+                }
 
                 // Add scripts symlink path for backup if it exists
                 let scripts_source = agentsmd_home.join("scripts");
                 if scripts_source.exists() {
-                    prepared.add_target_path(self.get_scripts_dir());
+                    prepared.add_target_path(self.get_scripts_dir()?);
                 }
 
-                // Prepare custom commands as TOML files
-                let commands_dir = self.get_commands_dir();
+                // Prepare custom commands as TOML files (workflows for Antigravity)
+                let commands_dir = self.get_command_output_dir()?;
                 for command_id in &config.custom_command_ids {
                     match command_loader::load_command_for_deployment(command_id, self.agent_id()) {
                         Ok((filename, content)) => {
@@ -188,11 +217,22 @@ impl AgentDeployer for GeminiDeployer {
                                 .insert("description".to_string(), format!("Custom command: {}", command_id));
                             frontmatter.insert("type".to_string(), "command".to_string());
 
-                            let fallback_content = MarkdownConverter::to_toml(
-                                "Execute this command to perform the specified action.",
-                                Some(frontmatter),
-                            )?;
-                            let fallback_name = format!("{}.toml", command_id);
+                            let fallback_body = "Execute this command to perform the specified action.";
+                            let (fallback_name, fallback_content) = if self.is_antigravity {
+                                (
+                                    format!("{}.md", command_id),
+                                    MarkdownConverter::to_antigravity_workflow(
+                                        command_id,
+                                        &format!("Custom command: {}", command_id),
+                                        fallback_body,
+                                    ),
+                                )
+                            } else {
+                                (
+                                    format!("{}.toml", command_id),
+                                    MarkdownConverter::to_toml(fallback_body, Some(frontmatter))?,
+                                )
+                            };
                             prepared.add_command(fallback_name.clone(), fallback_content);
 
                             let command_path = commands_dir.join(fallback_name);
@@ -205,10 +245,6 @@ impl AgentDeployer for GeminiDeployer {
                 if !config.custom_command_ids.is_empty() {
                     prepared.add_target_path(commands_dir);
                 }
-
-                if self.is_antigravity {
-                    prepared.add_target_path(self.get_workflows_dir());
-                }
             }
         }
 
@@ -218,14 +254,14 @@ impl AgentDeployer for GeminiDeployer {
     fn validate(&self, prepared: &PreparedDeployment) -> DeploymentResult<ValidationReport> {
         // Check character limit (1M for Gemini)
         let limit = self.character_limit();
-        let agents_chars = prepared.agents_md_content.len() as u64;
+        let agents_chars = budget::char_count(&prepared.agents_md_content);
         let command_chars: u64 = prepared
             .commands
             .values()
-            .map(|c| c.len() as u64)
+            .map(|c| budget::char_count(c))
             .sum();
         let validation =
-            DeploymentValidator::validate_full_budget(agents_chars, command_chars, prepared.out_reference_chars(), limit);
+            DeploymentValidator::validate_full_budget(agents_chars, command_chars, prepared.out_reference_chars(), limit, self.warn_threshold_pct());
 
         let mut warnings = validation.warnings;
         let mut errors = validation.errors;
@@ -249,22 +285,36 @@ impl AgentDeployer for GeminiDeployer {
         Ok(ValidationReport::success(validation.budget).with_warnings(warnings))
     }
 
-    fn deploy(&self, prepared: PreparedDeployment, config: &DeploymentConfig) -> DeploymentResult<DeploymentOutput> {
+    fn deploy(
+        &self,
+        prepared: PreparedDeployment,
+        config: &DeploymentConfig,
+        progress: &mut ProgressSink<'_>,
+    ) -> DeploymentResult<DeploymentOutput> {
         let mut deployed_files = Vec::new();
         let mut warnings = Vec::new();
+        let mut link_methods = Vec::new();
         let mut manual_steps = Vec::new();
 
         // Write AGENTS.md to ~/.agentsmd/
         let agentsmd_home = fs_manager::ensure_agentsmd_dir()
             .map_err(|e| DeploymentError::fs_error(PathBuf::new(), e.to_string()))?;
         let agents_md_source = agentsmd_home.join("AGENTS.md");
-        
-        fs::write(&agents_md_source, &prepared.agents_md_content).map_err(|e| {
-            DeploymentError::fs_error(&agents_md_source, format!("Failed to write AGENTS.md: {}", e))
-        })?;
-        deployed_files.push(agents_md_source.to_string_lossy().to_string());
+
+        if config.commands_only() {
+            warnings.push(COMMANDS_ONLY_WARNING.to_string());
+        } else {
+            fs::write(&agents_md_source, &prepared.agents_md_content).map_err(|e| {
+                DeploymentError::fs_error(&agents_md_source, format!("Failed to write AGENTS.md: {}", e))
+            })?;
+            deployed_files.push(agents_md_source.to_string_lossy().to_string());
+            progress(DeploymentPhase::WriteAgentsMd, None);
+        }
 
         match config.target_level {
+            TargetLevel::Project if config.commands_only() => {
+                // No AGENTS.md to import into the project
+            }
             TargetLevel::Project => {
                 // Project-level deployment: create .gemini/GEMINI.md
                 let project_root = self.resolve_project_path(config)?;
@@ -296,7 +346,7 @@ impl AgentDeployer for GeminiDeployer {
             }
             TargetLevel::User => {
                 // User-level deployment
-                let gemini_dir = self.get_gemini_dir();
+                let gemini_dir = self.get_gemini_dir()?;
 
                 // Ensure directories exist
                 fs::create_dir_all(&gemini_dir).map_err(|e| {
@@ -304,25 +354,28 @@ impl AgentDeployer for GeminiDeployer {
                 })?;
 
                 // Create GEMINI.md with import reference
-                let gemini_md_path = gemini_dir.join("GEMINI.md");
-                let gemini_md_content = format!(
-                    "# Gemini Configuration\n\n\
-                     This file imports AGENTS.md rules.\n\n\
-                     @{}\n",
-                    agents_md_source.to_string_lossy()
-                );
-                fs::write(&gemini_md_path, gemini_md_content).map_err(|e| {
-                    DeploymentError::fs_error(&gemini_md_path, format!("Failed to write GEMINI.md: {}", e))
-                })?;
-                deployed_files.push(gemini_md_path.to_string_lossy().to_string());
+                if !config.commands_only() {
+                    let gemini_md_path = gemini_dir.join(self.rules_filename());
+                    let gemini_md_content = format!(
+                        "# Gemini Configuration\n\n\
+                         This file imports AGENTS.md rules.\n\n\
+                         @{}\n",
+                        agents_md_source.to_string_lossy()
+                    );
+                    fs::write(&gemini_md_path, gemini_md_content).map_err(|e| {
+                        DeploymentError::fs_error(&gemini_md_path, format!("Failed to write GEMINI.md: {}", e))
+                    })?;
+                    deployed_files.push(gemini_md_path.to_string_lossy().to_string());
+                }
 
                 // Symlink scripts directory for sandbox access
                 let scripts_source = agentsmd_home.join("scripts");
-                let scripts_target = self.get_scripts_dir();
+                let scripts_target = self.get_scripts_dir()?;
                 if scripts_source.exists() {
                     fs::create_dir_all(scripts_target.parent().unwrap_or(&gemini_dir)).ok();
-                    match symlink::create_link(scripts_target.clone(), scripts_source.clone(), config.force_overwrite) {
-                        Ok((_, warning)) => {
+                    match symlink::create_link(scripts_target.clone(), scripts_source.clone(), config.force_overwrite, config.link_strategy, prepared.deployed_before(&scripts_target)) {
+                        Ok((method, warning)) => {
+                            link_methods.push(method);
                             deployed_files.push(scripts_target.to_string_lossy().to_string());
                             if let Some(w) = warning {
                                 warnings.push(w);
@@ -334,10 +387,10 @@ impl AgentDeployer for GeminiDeployer {
                     }
                 }
 
-                // Deploy custom commands
+                // Deploy custom commands (Antigravity links them as global workflows)
                 if !prepared.commands.is_empty() {
                     let build_dir = self.get_build_dir()?;
-                    let commands_dir = self.get_commands_dir();
+                    let commands_dir = self.get_command_output_dir()?;
 
                     fs::create_dir_all(&commands_dir).map_err(|e| {
                         DeploymentError::fs_error(&commands_dir, format!("Failed to create commands directory: {}", e))
@@ -350,9 +403,11 @@ impl AgentDeployer for GeminiDeployer {
                         })?;
 
                         let link_path = commands_dir.join(name);
-                        match symlink::create_link(link_path.clone(), build_path.clone(), config.force_overwrite) {
-                            Ok((_, warning)) => {
+                        match symlink::create_link(link_path.clone(), build_path.clone(), config.force_overwrite, config.link_strategy, prepared.deployed_before(&link_path)) {
+                            Ok((method, warning)) => {
+                                link_methods.push(method);
                                 deployed_files.push(link_path.to_string_lossy().to_string());
+                                progress(DeploymentPhase::Command, Some(name.clone()));
                                 if let Some(w) = warning {
                                     warnings.push(w);
                                 }
@@ -366,27 +421,18 @@ impl AgentDeployer for GeminiDeployer {
                         }
                     }
                 }
-
-                // Antigravity-specific: Link global workflows
-                if self.is_antigravity {
-                    let workflows_dir = self.get_workflows_dir();
-                    fs::create_dir_all(&workflows_dir).map_err(|e| {
-                        DeploymentError::fs_error(&workflows_dir, format!("Failed to create workflows directory: {}", e))
-                    })?;
-                    // Workflows would be linked from the build directory if they exist
-                }
             }
         }
 
         // Deploy out-references (symlinks into ~/.gemini/references)
         if !prepared.out_references.is_empty() {
-            let out_ref_dir = self.get_out_references_dir();
+            let out_ref_dir = self.get_out_references_dir()?;
             fs::create_dir_all(&out_ref_dir).map_err(|e| {
                 DeploymentError::fs_error(&out_ref_dir, format!("Failed to create references directory: {}", e))
             })?;
 
             for (rel_path, _content) in &prepared.out_references {
-                let source_path = fs_manager::get_agentsmd_home()
+                let source_path = fs_manager::get_agentsmd_home()?
                     .join("out-references")
                     .join(rel_path);
                 let dest_path = out_ref_dir.join(rel_path);
@@ -395,8 +441,9 @@ impl AgentDeployer for GeminiDeployer {
                     fs::create_dir_all(parent).ok();
                 }
 
-                match symlink::create_link(dest_path.clone(), source_path.clone(), config.force_overwrite) {
-                    Ok((_, warning)) => {
+                match symlink::create_link(dest_path.clone(), source_path.clone(), config.force_overwrite, config.link_strategy, prepared.deployed_before(&dest_path)) {
+                    Ok((method, warning)) => {
+                        link_methods.push(method);
                         deployed_files.push(dest_path.to_string_lossy().to_string());
                         if let Some(w) = warning {
                             warnings.push(w);
@@ -406,10 +453,11 @@ impl AgentDeployer for GeminiDeployer {
                         warnings.push(format!("Failed to link out-reference {}: {}", rel_path, e));
                     }
                 }
+                progress(DeploymentPhase::OutReference, Some(rel_path.clone()));
             }
         }
 
-        Ok(DeploymentOutput::success("symlink", deployed_files)
+        Ok(DeploymentOutput::success(config.link_strategy.deployment_method(&link_methods), deployed_files)
             .with_warnings(warnings)
             .with_manual_steps(manual_steps))
     }
@@ -440,13 +488,13 @@ impl AgentDeployer for GeminiDeployer {
     }
 
     fn get_status(&self) -> DeploymentResult<AgentStatus> {
-        let gemini_dir = self.get_gemini_dir();
+        let gemini_dir = self.get_gemini_dir()?;
 
         if !gemini_dir.exists() {
             return Ok(AgentStatus::NotInstalled);
         }
 
-        let gemini_md = gemini_dir.join("GEMINI.md");
+        let gemini_md = gemini_dir.join(self.rules_filename());
         if gemini_md.exists() {
             return Ok(AgentStatus::Configured);
         }
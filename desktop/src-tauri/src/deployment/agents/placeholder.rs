@@ -7,8 +7,8 @@ use std::fs;
 use std::path::PathBuf;
 
 use crate::deployment::deployer::{
-    AgentDeployer, AgentStatus, DeploymentConfig, DeploymentOutput,
-    PreparedDeployment, ValidationReport, BudgetUsage,
+    AgentDeployer, AgentStatus, DeploymentConfig, DeploymentOutput, DeploymentPhase,
+    PreparedDeployment, ProgressSink, ValidationReport, BudgetUsage,
 };
 use crate::deployment::error::{DeploymentError, DeploymentResult};
 use crate::deployment::state::DeploymentState;
@@ -29,19 +29,17 @@ impl PlaceholderDeployer {
         }
     }
 
-    /// Get the agent's config path (may be a placeholder)
-    fn get_config_path(&self) -> Option<PathBuf> {
+    /// Get the agent's config paths (may be placeholders)
+    fn get_config_paths(&self) -> DeploymentResult<Vec<PathBuf>> {
         let agent = self.base.agent();
-        agent.config_paths.first().map(|p| {
+        agent.config_paths.iter().map(|p| {
             // Expand ~ to home directory
             if p.starts_with("~/") {
-                dirs::home_dir()
-                    .unwrap_or_else(|| PathBuf::from("."))
-                    .join(&p[2..])
+                Ok(fs_manager::home_dir()?.join(&p[2..]))
             } else {
-                PathBuf::from(p)
+                Ok(PathBuf::from(p))
             }
-        })
+        }).collect()
     }
 
     /// Check if this agent's paths have been verified
@@ -75,8 +73,8 @@ impl AgentDeployer for PlaceholderDeployer {
         let mut prepared = PreparedDeployment::new(agents_md_content);
         prepared.command_format = self.base.agent().file_format.clone();
 
-        // Add config path as target if available
-        if let Some(config_path) = self.get_config_path() {
+        // Add config paths as targets
+        for config_path in self.get_config_paths()? {
             prepared.add_target_path(config_path);
         }
 
@@ -88,6 +86,7 @@ impl AgentDeployer for PlaceholderDeployer {
         let validation = DeploymentValidator::validate_character_budget(
             &prepared.agents_md_content,
             limit,
+            self.warn_threshold_pct(),
         );
 
         let mut warnings = validation.warnings;
@@ -103,8 +102,8 @@ impl AgentDeployer for PlaceholderDeployer {
             ));
         }
 
-        // Warn if config path doesn't exist
-        if let Some(config_path) = self.get_config_path() {
+        // Warn if config paths don't exist
+        for config_path in self.get_config_paths()? {
             if let Some(parent) = config_path.parent() {
                 if !parent.exists() {
                     warnings.push(format!(
@@ -122,7 +121,12 @@ impl AgentDeployer for PlaceholderDeployer {
         Ok(ValidationReport::success(validation.budget).with_warnings(warnings))
     }
 
-    fn deploy(&self, prepared: PreparedDeployment, _config: &DeploymentConfig) -> DeploymentResult<DeploymentOutput> {
+    fn deploy(
+        &self,
+        prepared: PreparedDeployment,
+        _config: &DeploymentConfig,
+        progress: &mut ProgressSink<'_>,
+    ) -> DeploymentResult<DeploymentOutput> {
         let mut deployed_files = Vec::new();
         let mut warnings = Vec::new();
         let mut manual_steps = Vec::new();
@@ -144,9 +148,10 @@ impl AgentDeployer for PlaceholderDeployer {
             DeploymentError::fs_error(&agents_md_path, format!("Failed to write AGENTS.md: {}", e))
         })?;
         deployed_files.push(agents_md_path.to_string_lossy().to_string());
+        progress(DeploymentPhase::WriteAgentsMd, None);
 
-        // Attempt to write to config path if it exists
-        if let Some(config_path) = self.get_config_path() {
+        // Attempt to write to each config path whose directory exists
+        for config_path in self.get_config_paths()? {
             if let Some(parent) = config_path.parent() {
                 if parent.exists() {
                     // Try to write/link
@@ -202,8 +207,8 @@ impl AgentDeployer for PlaceholderDeployer {
     }
 
     fn get_status(&self) -> DeploymentResult<AgentStatus> {
-        // Check if config path exists
-        if let Some(config_path) = self.get_config_path() {
+        // Check if the primary config path exists
+        if let Some(config_path) = self.get_config_paths()?.into_iter().next() {
             if let Some(parent) = config_path.parent() {
                 if parent.exists() {
                     if config_path.exists() {
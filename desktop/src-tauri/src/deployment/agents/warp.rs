@@ -7,13 +7,13 @@ use std::path::PathBuf;
 
 use crate::deployment::converters::MarkdownConverter;
 use crate::deployment::deployer::{
-    AgentDeployer, AgentStatus, DeploymentConfig, DeploymentOutput,
-    PreparedDeployment, ValidationReport,
+    AgentDeployer, AgentStatus, DeploymentConfig, DeploymentOutput, DeploymentPhase,
+    PreparedDeployment, ProgressSink, ValidationReport,
 };
 use crate::deployment::error::{DeploymentError, DeploymentResult};
 use crate::deployment::state::DeploymentState;
 use crate::deployment::validator::DeploymentValidator;
-use crate::deployment::{generate_agents_md_content, BaseDeployer};
+use crate::deployment::{generate_agents_md_content, BaseDeployer, COMMANDS_ONLY_WARNING};
 use crate::fs_manager;
 use crate::types::AgentDefinition;
 
@@ -30,16 +30,13 @@ impl WarpDeployer {
     }
 
     /// Get the Warp workflows directory
-    fn get_workflows_dir(&self) -> PathBuf {
-        dirs::home_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join(".warp")
-            .join("workflows")
+    fn get_workflows_dir(&self) -> DeploymentResult<PathBuf> {
+        Ok(fs_manager::home_dir()?.join(".warp").join("workflows"))
     }
 
     /// Get the build output directory
     fn get_build_dir(&self) -> DeploymentResult<PathBuf> {
-        let agentsmd_home = fs_manager::get_agentsmd_home();
+        let agentsmd_home = fs_manager::get_agentsmd_home()?;
         let build_dir = agentsmd_home.join("build").join("warp").join("workflows");
         fs::create_dir_all(&build_dir).map_err(|e| {
             DeploymentError::fs_error(&build_dir, format!("Failed to create build directory: {}", e))
@@ -58,19 +55,24 @@ impl AgentDeployer for WarpDeployer {
     }
 
     fn prepare(&self, config: &DeploymentConfig) -> DeploymentResult<PreparedDeployment> {
-        // Generate AGENTS.md content
-        let agents_md_content = generate_agents_md_content(&config.pack_ids, false)?;
+        // Generate AGENTS.md content, unless only commands were selected
+        let agents_md_content = if config.commands_only() {
+            String::new()
+        } else {
+            generate_agents_md_content(&config.pack_ids, false)?
+        };
 
         let mut prepared = PreparedDeployment::new(agents_md_content);
         prepared.command_format = "yaml".to_string();
 
         // Add AGENTS.md path to target_paths for backup
-        let agentsmd_home = fs_manager::get_agentsmd_home();
-        let agents_md_path = agentsmd_home.join("AGENTS.md");
-        prepared.add_target_path(agents_md_path);
+        if !config.commands_only() {
+            let agentsmd_home = fs_manager::get_agentsmd_home()?;
+            prepared.add_target_path(agentsmd_home.join("AGENTS.md"));
+        }
 
         // Convert custom commands to Warp workflow YAML format
-        let workflows_dir = self.get_workflows_dir();
+        let workflows_dir = self.get_workflows_dir()?;
         for command_id in &config.custom_command_ids {
             let workflow_content = MarkdownConverter::to_warp_workflow(
                 command_id,
@@ -98,6 +100,7 @@ impl AgentDeployer for WarpDeployer {
         let validation = DeploymentValidator::validate_character_budget(
             &prepared.agents_md_content,
             limit,
+            self.warn_threshold_pct(),
         );
 
         let mut warnings = validation.warnings;
@@ -122,12 +125,17 @@ impl AgentDeployer for WarpDeployer {
         Ok(ValidationReport::success(validation.budget).with_warnings(warnings))
     }
 
-    fn deploy(&self, prepared: PreparedDeployment, config: &DeploymentConfig) -> DeploymentResult<DeploymentOutput> {
+    fn deploy(
+        &self,
+        prepared: PreparedDeployment,
+        config: &DeploymentConfig,
+        progress: &mut ProgressSink<'_>,
+    ) -> DeploymentResult<DeploymentOutput> {
         let mut deployed_files = Vec::new();
         let mut warnings = Vec::new();
         let mut manual_steps = Vec::new();
 
-        let workflows_dir = self.get_workflows_dir();
+        let workflows_dir = self.get_workflows_dir()?;
 
         // Ensure workflows directory exists
         fs::create_dir_all(&workflows_dir).map_err(|e| {
@@ -135,14 +143,19 @@ impl AgentDeployer for WarpDeployer {
         })?;
 
         // Write AGENTS.md to ~/.agentsmd/
-        let agentsmd_home = fs_manager::ensure_agentsmd_dir()
-            .map_err(|e| DeploymentError::fs_error(PathBuf::new(), e.to_string()))?;
-        let agents_md_path = agentsmd_home.join("AGENTS.md");
-        
-        fs::write(&agents_md_path, &prepared.agents_md_content).map_err(|e| {
-            DeploymentError::fs_error(&agents_md_path, format!("Failed to write AGENTS.md: {}", e))
-        })?;
-        deployed_files.push(agents_md_path.to_string_lossy().to_string());
+        if config.commands_only() {
+            warnings.push(COMMANDS_ONLY_WARNING.to_string());
+        } else {
+            let agentsmd_home = fs_manager::ensure_agentsmd_dir()
+                .map_err(|e| DeploymentError::fs_error(PathBuf::new(), e.to_string()))?;
+            let agents_md_path = agentsmd_home.join("AGENTS.md");
+
+            fs::write(&agents_md_path, &prepared.agents_md_content).map_err(|e| {
+                DeploymentError::fs_error(&agents_md_path, format!("Failed to write AGENTS.md: {}", e))
+            })?;
+            deployed_files.push(agents_md_path.to_string_lossy().to_string());
+            progress(DeploymentPhase::WriteAgentsMd, None);
+        }
 
         // Deploy workflow files
         if !prepared.commands.is_empty() {
@@ -161,6 +174,7 @@ impl AgentDeployer for WarpDeployer {
                     DeploymentError::fs_error(&workflow_path, format!("Failed to copy workflow: {}", e))
                 })?;
                 deployed_files.push(workflow_path.to_string_lossy().to_string());
+                progress(DeploymentPhase::Command, Some(name.clone()));
             }
         }
 
@@ -194,15 +208,13 @@ impl AgentDeployer for WarpDeployer {
     }
 
     fn get_status(&self) -> DeploymentResult<AgentStatus> {
-        let warp_dir = dirs::home_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join(".warp");
+        let warp_dir = fs_manager::home_dir()?.join(".warp");
 
         if !warp_dir.exists() {
             return Ok(AgentStatus::NotInstalled);
         }
 
-        let workflows_dir = self.get_workflows_dir();
+        let workflows_dir = self.get_workflows_dir()?;
         if workflows_dir.exists() && workflows_dir.read_dir().map(|mut d| d.next().is_some()).unwrap_or(false) {
             return Ok(AgentStatus::Configured);
         }
@@ -6,10 +6,57 @@ use std::path::PathBuf;
 
 use crate::command_registry::{self, get_command_content};
 use crate::types::CommandMetadata;
+use crate::util;
 
-use super::converters::MarkdownConverter;
+use serde::{Deserialize, Serialize};
+
+use super::converters::{self, FileFormat, FormatDetector, MarkdownConverter, WarpWorkflowSummary};
 use super::error::{DeploymentError, DeploymentResult};
 
+/// A single command converted for an agent, as it would be deployed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandPreview {
+    pub filename: String,
+    pub content: String,
+    pub format: FileFormat,
+}
+
+/// Preview the agent-specific file a command would deploy as
+pub fn preview_command_for_agent(command_id: &str, agent_id: &str) -> DeploymentResult<CommandPreview> {
+    let (filename, content) = load_command_for_deployment(command_id, agent_id)?;
+    let format = FormatDetector::from_extension(&filename);
+
+    Ok(CommandPreview {
+        filename,
+        content,
+        format,
+    })
+}
+
+/// A command converted to a Warp workflow, with its parsed structure
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WarpWorkflowPreview {
+    pub filename: String,
+    pub content: String,
+    pub summary: WarpWorkflowSummary,
+}
+
+/// Preview the Warp workflow a command would deploy as
+///
+/// The generated YAML is parsed back to confirm Warp will be able to load it.
+pub fn preview_warp_workflow(command_id: &str) -> DeploymentResult<WarpWorkflowPreview> {
+    let (filename, content) = load_command_for_deployment(command_id, "warp")?;
+    let summary = converters::summarize_warp_workflow(&content)?;
+
+    Ok(WarpWorkflowPreview {
+        filename,
+        content,
+        summary,
+    })
+}
+
 /// Load and convert a command for deployment to a specific agent
 pub fn load_command_for_deployment(
     command_id: &str,
@@ -19,12 +66,13 @@ pub fn load_command_for_deployment(
     let command = command_registry::get_command_by_id(command_id)
         .map_err(|e| DeploymentError::ConfigurationError(e))?;
 
-    // Load raw content
-    let content = get_command_content(command_id)
+    // Load raw content from the canonical command file (command_id may be an alias)
+    let content = get_command_content(&command.id)
         .map_err(|e| DeploymentError::ConfigurationError(e))?;
+    let (_, content) = MarkdownConverter::parse_frontmatter(&content);
 
     // Validate compatibility
-    let compatibility = command_registry::validate_command_for_agent(command_id, agent_id)
+    let compatibility = command_registry::validate_command_for_agent(&command.id, agent_id)
         .map_err(|e| DeploymentError::ConfigurationError(e))?;
 
     if !compatibility.compatible {
@@ -42,18 +90,77 @@ pub fn load_command_for_deployment(
     Ok((filename, formatted_content))
 }
 
+/// Load a command as a Claude skill (`<command>/SKILL.md`)
+///
+/// Tools come from the command's `tools` frontmatter (`tools: Read, Grep` or `tools: [Read, Grep]`).
+pub fn load_command_as_claude_skill(command_id: &str) -> DeploymentResult<(String, String)> {
+    let command = command_registry::get_command_by_id(command_id)
+        .map_err(DeploymentError::ConfigurationError)?;
+
+    let content = get_command_content(&command.id)
+        .map_err(DeploymentError::ConfigurationError)?;
+    let (frontmatter, content) = MarkdownConverter::parse_frontmatter(&content);
+
+    let compatibility = command_registry::validate_command_for_agent(&command.id, "claude")
+        .map_err(DeploymentError::ConfigurationError)?;
+    if !compatibility.compatible {
+        return Err(DeploymentError::ValidationFailed(
+            compatibility.reason.unwrap_or_else(|| format!(
+                "Command {} is not compatible with agent claude",
+                command_id
+            )),
+        ));
+    }
+
+    let tools = frontmatter
+        .as_ref()
+        .and_then(|fm| fm.get("tools"))
+        .map(|raw| {
+            raw.trim_start_matches('[')
+                .trim_end_matches(']')
+                .split(',')
+                .map(|tool| tool.trim().trim_matches('"').trim_matches('\'').trim().to_string())
+                .filter(|tool| !tool.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let skill = MarkdownConverter::to_claude_skill(&command.id, &command.description, tools, &content)?;
+    Ok((format!("{}/SKILL.md", flat_file_stem(&command)), skill))
+}
+
+/// File stem for agents that keep commands in one flat folder (`git/status` -> `git-status`)
+fn flat_file_stem(command: &CommandMetadata) -> String {
+    util::safe_file_stem(&command.id.replace('/', "-"))
+}
+
+/// File stem for agents that support command subfolders (`git/status` stays nested)
+fn nested_file_stem(command: &CommandMetadata) -> String {
+    let leaf = command.id.rsplit('/').next().unwrap_or(&command.id);
+    match &command.subfolder {
+        Some(subfolder) => subfolder
+            .split('/')
+            .chain(std::iter::once(leaf))
+            .map(util::safe_file_stem)
+            .collect::<Vec<_>>()
+            .join("/"),
+        None => util::safe_file_stem(leaf),
+    }
+}
+
 /// Convert command content to agent-specific format
 fn convert_command_for_agent(
     command: &CommandMetadata,
     content: &str,
     agent_id: &str,
 ) -> DeploymentResult<(String, String)> {
+    let stem = flat_file_stem(command);
     let filename: String;
     let formatted_content: String;
 
     match agent_id.to_lowercase().as_str() {
         "cursor" => {
-            filename = format!("{}.md", command.id);
+            filename = format!("{}.md", nested_file_stem(command));
             formatted_content = MarkdownConverter::to_cursor_command(
                 &command.id,
                 &command.description,
@@ -61,7 +168,7 @@ fn convert_command_for_agent(
             );
         }
         "claude" => {
-            filename = format!("{}.md", command.id);
+            filename = format!("{}.md", nested_file_stem(command));
             formatted_content = MarkdownConverter::to_claude_command(
                 &command.id,
                 &command.description,
@@ -69,15 +176,23 @@ fn convert_command_for_agent(
             );
         }
         "gemini" => {
-            filename = format!("{}.toml", command.id);
+            filename = format!("{}.toml", stem);
             formatted_content = MarkdownConverter::to_gemini_command(
                 &command.id,
                 &command.description,
                 content,
             )?;
         }
+        "antigravity" => {
+            filename = format!("{}.md", stem);
+            formatted_content = MarkdownConverter::to_antigravity_workflow(
+                &command.id,
+                &command.description,
+                content,
+            );
+        }
         "aider" => {
-            filename = format!("{}.yaml", command.id);
+            filename = format!("{}.yaml", stem);
             formatted_content = MarkdownConverter::to_aider_command(
                 &command.id,
                 &command.description,
@@ -85,7 +200,7 @@ fn convert_command_for_agent(
             )?;
         }
         "warp" => {
-            filename = format!("{}.yaml", command.id);
+            filename = format!("{}.yaml", stem);
             formatted_content = MarkdownConverter::to_warp_command(
                 &command.id,
                 &command.description,
@@ -93,7 +208,7 @@ fn convert_command_for_agent(
             )?;
         }
         "cline" => {
-            filename = format!("{}.json", command.id);
+            filename = format!("{}.json", stem);
             formatted_content = MarkdownConverter::to_cline_command(
                 &command.id,
                 &command.description,
@@ -101,7 +216,7 @@ fn convert_command_for_agent(
             )?;
         }
         "codex" => {
-            filename = format!("{}.md", command.id);
+            filename = format!("{}.md", stem);
             formatted_content = MarkdownConverter::to_codex_prompt(
                 &command.id,
                 &command.description,
@@ -110,7 +225,7 @@ fn convert_command_for_agent(
         }
         "copilot" => {
             // Copilot uses inline format, content embedded in instructions
-            filename = format!("{}.md", command.id);
+            filename = format!("{}.md", stem);
             formatted_content = format!(
                 "## Command: /{}\n\n{}\n\n---\n\n{}",
                 command.id, command.description, content
@@ -118,7 +233,7 @@ fn convert_command_for_agent(
         }
         _ => {
             // Default to markdown format
-            filename = format!("{}.md", command.id);
+            filename = format!("{}.md", stem);
             formatted_content = format!(
                 "# /{}\n\n{}\n\n---\n\n{}",
                 command.id, command.description, content
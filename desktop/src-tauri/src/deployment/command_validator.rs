@@ -88,7 +88,7 @@ pub fn validate_command_for_agent(
                 "Command '{}' exceeds character limit for '{}' ({} > {})",
                 command.id, agent.id, command.character_count, max_chars
             ));
-        } else if command.character_count > (max_chars * 80 / 100) {
+        } else if command.character_count > (max_chars * agent.character_limits.warn_threshold_pct / 100) {
             result = result.with_warning(format!(
                 "Command '{}' uses {}% of {}'s character limit",
                 command.id,
@@ -118,14 +118,24 @@ pub fn validate_command_for_agent(
     }
 
     // Validate out-references are accessible
-    for ref_path in &command.out_references {
-        let agentsmd_home = fs_manager::get_agentsmd_home();
-        let full_path = agentsmd_home.join(ref_path);
-        if !full_path.exists() {
-            result = result.with_warning(format!(
-                "Out-reference '{}' for command '{}' not found",
-                ref_path, command.id
-            ));
+    if !command.out_references.is_empty() {
+        match fs_manager::get_agentsmd_home() {
+            Ok(agentsmd_home) => {
+                for ref_path in &command.out_references {
+                    if !agentsmd_home.join(ref_path).exists() {
+                        result = result.with_warning(format!(
+                            "Out-reference '{}' for command '{}' not found",
+                            ref_path, command.id
+                        ));
+                    }
+                }
+            }
+            Err(e) => {
+                result = result.with_error(format!(
+                    "Out-references for command '{}' could not be checked: {}",
+                    command.id, e
+                ));
+            }
         }
     }
 
@@ -154,7 +164,7 @@ pub fn validate_command_set(
                 "Combined commands ({} chars) exceed {}'s character limit ({} chars)",
                 total_chars, agent.id, max_chars
             ));
-        } else if total_chars > (max_chars * 80 / 100) {
+        } else if total_chars > (max_chars * agent.character_limits.warn_threshold_pct / 100) {
             result = result.with_warning(format!(
                 "Combined commands use {}% of {}'s character limit",
                 (total_chars * 100) / max_chars,
@@ -171,17 +181,25 @@ pub fn validate_command_set(
 }
 
 /// Validate commands by IDs for an agent
+///
+/// With `inline_out_references`, out-references are treated as supported because they
+/// will be appended to AGENTS.md rather than linked.
 pub fn validate_commands_for_agent(
     command_ids: &[String],
     agent_id: &str,
+    inline_out_references: bool,
 ) -> DeploymentResult<CommandValidationResult> {
     let agents = fs_manager::load_agent_registry()
         .map_err(|e| DeploymentError::ConfigurationError(e.to_string()))?;
 
-    let agent = agents
+    let mut agent = agents
         .iter()
         .find(|a| a.id == agent_id)
+        .cloned()
         .ok_or_else(|| DeploymentError::agent_not_found(agent_id))?;
+    if inline_out_references {
+        agent.character_limits.supports_out_references = true;
+    }
 
     let mut commands = Vec::new();
     for command_id in command_ids {
@@ -190,13 +208,14 @@ pub fn validate_commands_for_agent(
         commands.push(command);
     }
 
-    Ok(validate_command_set(&commands, agent))
+    Ok(validate_command_set(&commands, &agent))
 }
 
-/// Expand ~ to home directory
+/// Expand environment variables and ~ to the home directory
 fn expand_path(path: &str) -> PathBuf {
+    let path = fs_manager::expand_env_vars(path);
     if let Some(stripped) = path.strip_prefix("~/") {
-        if let Some(home) = dirs::home_dir() {
+        if let Ok(home) = fs_manager::home_dir() {
             return home.join(stripped);
         }
     }
@@ -225,7 +244,7 @@ pub fn get_deprecated_command_replacement(command_id: &str) -> Option<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::CharacterLimits;
+    use crate::types::{CharacterLimits, DEFAULT_WARN_THRESHOLD_PCT};
 
     fn create_test_agent(supports_out_refs: bool, max_chars: Option<u64>) -> AgentDefinition {
         AgentDefinition {
@@ -237,6 +256,7 @@ mod tests {
             character_limits: CharacterLimits {
                 max_chars,
                 supports_out_references: supports_out_refs,
+                warn_threshold_pct: DEFAULT_WARN_THRESHOLD_PCT,
             },
             deployment_strategy: "symlink".to_string(),
             build_output: "test/commands".to_string(),
@@ -244,6 +264,7 @@ mod tests {
             requires_frontmatter: Some(false),
             sandbox_script_path: None,
             notes: None,
+            rules_filename: None,
         }
     }
 
@@ -265,6 +286,8 @@ mod tests {
             character_count: chars,
             word_count: chars / 5,
             source_path: "commands/src/test.md".to_string(),
+            aliases: Vec::new(),
+            subfolder: None,
         }
     }
 
@@ -297,4 +320,17 @@ mod tests {
         assert!(!result.valid);
         assert!(result.errors.iter().any(|e| e.contains("out-references")));
     }
+
+    #[test]
+    fn test_validate_command_custom_warn_threshold() {
+        let mut agent = create_test_agent(true, Some(1000));
+        agent.character_limits.warn_threshold_pct = 50;
+
+        let result = validate_command_for_agent(&create_test_command(600, false), &agent);
+        assert!(result.valid);
+        assert!(result.warnings.iter().any(|w| w.contains("character limit")));
+
+        let result = validate_command_for_agent(&create_test_command(400, false), &agent);
+        assert!(!result.warnings.iter().any(|w| w.contains("character limit")));
+    }
 }
@@ -3,14 +3,32 @@
 //! Handles conversion between Markdown and other formats (TOML, YAML, JSON)
 //! required by different agents.
 
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
 
 use super::error::{DeploymentError, DeploymentResult};
+use crate::budget;
+use crate::types::LoadedPack;
+
+/// Version of the payload produced by [`MarkdownConverter::to_rules_json`]
+pub const RULES_JSON_VERSION: &str = "1.0";
 
 /// Markdown format converter
 pub struct MarkdownConverter;
 
+/// Which value wins when added frontmatter repeats a key the content already declares
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FrontmatterMerge {
+    /// Added keys replace the existing values
+    #[default]
+    PreferNew,
+    /// Values already in the content are kept
+    PreferExisting,
+}
+
 impl MarkdownConverter {
     /// Convert markdown content to TOML format (for Gemini)
     /// 
@@ -47,28 +65,18 @@ impl MarkdownConverter {
         content: &str,
         frontmatter: Option<HashMap<String, String>>,
     ) -> DeploymentResult<String> {
-        let mut yaml_content = String::new();
-
-        yaml_content.push_str("---\n");
-
-        // Add frontmatter as YAML key-value pairs
-        if let Some(fm) = frontmatter {
-            for (key, value) in fm.iter() {
-                yaml_content.push_str(&format!("{}: \"{}\"\n", key, escape_yaml_string(value)));
-            }
-        }
-
-        // Add content as a multi-line string
-        yaml_content.push_str("content: |\n");
-        for line in content.lines() {
-            yaml_content.push_str("  ");
-            yaml_content.push_str(line);
-            yaml_content.push('\n');
-        }
-
-        yaml_content.push_str("---\n");
+        // The body always wins over a frontmatter key of the same name
+        let frontmatter = frontmatter
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(key, _)| key != "content")
+            .collect();
+        let document = YamlDocument {
+            frontmatter,
+            content: content.to_string(),
+        };
 
-        Ok(yaml_content)
+        to_yaml_document(&document)
     }
 
     /// Convert markdown content to JSON format (for Cline)
@@ -94,35 +102,115 @@ impl MarkdownConverter {
             .map_err(|e| DeploymentError::format_error(format!("JSON serialization failed: {}", e)))
     }
 
+    /// Convert loaded packs to a single JSON rules payload (for API-driven agents)
+    ///
+    /// Produces `{ version, packs: [{ id, name, version, content }], total_chars }`,
+    /// with packs in the order given and `total_chars` summing every pack's content.
+    pub fn to_rules_json(packs: &[LoadedPack]) -> DeploymentResult<String> {
+        let entries: Vec<Value> = packs
+            .iter()
+            .map(|pack| {
+                serde_json::json!({
+                    "id": pack.id,
+                    "name": pack.name,
+                    "version": pack.version,
+                    "content": pack.content,
+                })
+            })
+            .collect();
+        let total_chars: u64 = packs.iter().map(|pack| budget::char_count(&pack.content)).sum();
+
+        let payload = serde_json::json!({
+            "version": RULES_JSON_VERSION,
+            "packs": entries,
+            "total_chars": total_chars,
+        });
+
+        serde_json::to_string_pretty(&payload)
+            .map_err(|e| DeploymentError::format_error(format!("JSON serialization failed: {}", e)))
+    }
+
     /// Add YAML frontmatter to markdown content (for Claude, Antigravity, Codex)
     /// 
-    /// Prepends YAML frontmatter block to markdown content
+    /// Prepends YAML frontmatter block to markdown content. Content that already has
+    /// frontmatter is merged into a single block, with the added keys winning.
     pub fn add_frontmatter(content: &str, frontmatter: HashMap<String, String>) -> String {
-        let mut result = String::new();
+        Self::add_frontmatter_with(content, frontmatter, FrontmatterMerge::PreferNew)
+    }
+
+    /// Add YAML frontmatter, choosing which side wins for keys the content already declares
+    ///
+    /// Existing lines (including multi-line values) are kept as written unless replaced.
+    pub fn add_frontmatter_with(
+        content: &str,
+        frontmatter: HashMap<String, String>,
+        merge: FrontmatterMerge,
+    ) -> String {
+        let existing = Self::split_frontmatter(content).unwrap_or_else(|e| {
+            log::warn!("{}; keeping it as body text", e);
+            None
+        });
+        let body = existing.map(|(_, rest)| rest.trim_start()).unwrap_or(content);
 
+        let mut result = String::new();
         result.push_str("---\n");
-        for (key, value) in frontmatter.iter() {
+
+        // Keep the existing block, dropping keys (and their continuation lines) being replaced
+        let mut existing_keys = Vec::new();
+        if let Some((block, _)) = existing {
+            let mut replacing = false;
+            for line in block.lines() {
+                let top_level = !line.starts_with([' ', '\t', '-']);
+                if let (true, Some(colon_idx)) = (top_level, line.find(':')) {
+                    let key = line[..colon_idx].trim().to_string();
+                    replacing = merge == FrontmatterMerge::PreferNew && frontmatter.contains_key(&key);
+                    existing_keys.push(key);
+                }
+                if !replacing {
+                    result.push_str(line);
+                    result.push('\n');
+                }
+            }
+        }
+
+        // Sorted so the same frontmatter always produces the same bytes
+        let mut entries: Vec<(&String, &String)> = frontmatter.iter().collect();
+        entries.sort();
+
+        for (key, value) in entries {
+            if merge == FrontmatterMerge::PreferExisting && existing_keys.contains(key) {
+                continue;
+            }
             result.push_str(&format!("{}: \"{}\"\n", key, escape_yaml_string(value)));
         }
         result.push_str("---\n\n");
-        result.push_str(content);
+        result.push_str(body);
 
         result
     }
 
+    /// Split leading YAML frontmatter from the rest of the content
+    ///
+    /// Returns `(block, rest)` without the `---` fences, `None` when the content has no
+    /// frontmatter, or an error when the opening `---` is never closed.
+    pub fn split_frontmatter(content: &str) -> DeploymentResult<Option<(&str, &str)>> {
+        let Some(inner) = content.strip_prefix("---\n") else {
+            return Ok(None);
+        };
+
+        match inner.find("\n---") {
+            Some(end_idx) => Ok(Some((&inner[..end_idx], &inner[end_idx + 4..]))),
+            None => Err(DeploymentError::format_error(
+                "Frontmatter opened with --- is never closed",
+            )),
+        }
+    }
+
     /// Parse YAML frontmatter from markdown content
     /// 
     /// Returns (frontmatter, content_without_frontmatter)
     pub fn parse_frontmatter(content: &str) -> (Option<HashMap<String, String>>, String) {
-        if !content.starts_with("---\n") {
-            return (None, content.to_string());
-        }
-
-        // Find the closing ---
-        if let Some(end_idx) = content[4..].find("\n---") {
-            let frontmatter_str = &content[4..4 + end_idx];
-            let remaining_content = &content[4 + end_idx + 4..];
-
+        if let Ok(Some((frontmatter_str, remaining_content))) = Self::split_frontmatter(content) {
             // Parse simple key: value pairs
             let mut frontmatter = HashMap::new();
             for line in frontmatter_str.lines() {
@@ -139,6 +227,56 @@ impl MarkdownConverter {
         }
     }
 
+    /// Prepend metadata as leading HTML comments (for agents that only read comment blocks)
+    ///
+    /// Writes one `<!-- key: value -->` line per entry, sorted by key, then a blank line.
+    /// Keys and values are escaped so a `-->` or newline can't end the comment early, and
+    /// a `:` in a key can't be mistaken for the separator.
+    pub fn to_html_comment_meta(meta: HashMap<String, String>, content: &str) -> String {
+        let mut entries: Vec<(&String, &String)> = meta.iter().collect();
+        entries.sort();
+
+        let mut result = String::new();
+        for (key, value) in entries {
+            let key = escape_html_comment(key).replace(':', "\\:");
+            result.push_str(&format!("<!-- {}: {} -->\n", key, escape_html_comment(value)));
+        }
+        result.push('\n');
+        result.push_str(content);
+        result
+    }
+
+    /// Parse metadata from leading `<!-- key: value -->` comment lines
+    ///
+    /// Returns (metadata, content_without_metadata); metadata is `None` when the content
+    /// doesn't start with such a comment. Only the separator space after each `:` and the
+    /// blank line `to_html_comment_meta` writes are removed, so whitespace in values and
+    /// at the start of the content survives a round trip.
+    pub fn parse_html_comment_meta(content: &str) -> (Option<HashMap<String, String>>, String) {
+        let mut meta = HashMap::new();
+        let mut rest = content;
+        loop {
+            let (line, next) = rest.split_once('\n').unwrap_or((rest, ""));
+            let entry = line
+                .trim_end_matches('\r')
+                .strip_prefix("<!-- ")
+                .and_then(|line| line.strip_suffix(" -->"))
+                .and_then(split_html_comment_entry);
+            let Some((key, value)) = entry else { break };
+            meta.insert(unescape_html_comment(key), unescape_html_comment(value));
+            rest = next;
+        }
+
+        if meta.is_empty() {
+            return (None, content.to_string());
+        }
+        let body = rest
+            .strip_prefix("\r\n")
+            .or_else(|| rest.strip_prefix('\n'))
+            .unwrap_or(rest);
+        (Some(meta), body.to_string())
+    }
+
     /// Convert to Warp workflow YAML format
     /// 
     /// Creates a Warp-specific workflow structure
@@ -173,6 +311,40 @@ impl MarkdownConverter {
         Self::add_frontmatter(content, frontmatter)
     }
 
+    /// Convert command to a Claude skill with `name`, `description`, and a `tools` list
+    ///
+    /// Fails if the name or description is empty, since Claude ignores skills without them.
+    pub fn to_claude_skill(
+        name: &str,
+        description: &str,
+        tools: Vec<String>,
+        content: &str,
+    ) -> DeploymentResult<String> {
+        if name.trim().is_empty() || description.trim().is_empty() {
+            return Err(DeploymentError::format_error(format!(
+                "Claude skill '{}' requires a non-empty name and description",
+                name
+            )));
+        }
+
+        let mut result = String::new();
+        result.push_str("---\n");
+        result.push_str(&format!("name: \"{}\"\n", escape_yaml_string(name)));
+        result.push_str(&format!("description: \"{}\"\n", escape_yaml_string(description)));
+        if tools.is_empty() {
+            result.push_str("tools: []\n");
+        } else {
+            result.push_str("tools:\n");
+            for tool in &tools {
+                result.push_str(&format!("  - \"{}\"\n", escape_yaml_string(tool)));
+            }
+        }
+        result.push_str("---\n\n");
+        result.push_str(content);
+
+        Ok(result)
+    }
+
     /// Convert command to Cursor slash command format
     pub fn to_cursor_command(
         name: &str,
@@ -199,6 +371,20 @@ impl MarkdownConverter {
         Self::add_frontmatter(content, frontmatter)
     }
 
+    /// Convert command to an Antigravity workflow
+    ///
+    /// Antigravity lists global workflows by their `description` frontmatter.
+    pub fn to_antigravity_workflow(
+        name: &str,
+        description: &str,
+        content: &str,
+    ) -> String {
+        let mut frontmatter = HashMap::new();
+        frontmatter.insert("description".to_string(), description.to_string());
+
+        Self::add_frontmatter(&format!("# /{}\n\n{}", name, content), frontmatter)
+    }
+
     /// Convert command to Gemini TOML format
     /// 
     /// Creates a TOML command structure for Gemini CLI
@@ -223,23 +409,14 @@ impl MarkdownConverter {
         description: &str,
         content: &str,
     ) -> DeploymentResult<String> {
-        let mut yaml_content = String::new();
-
-        yaml_content.push_str("---\n");
-        yaml_content.push_str(&format!("name: \"{}\"\n", escape_yaml_string(name)));
-        yaml_content.push_str(&format!("description: \"{}\"\n", escape_yaml_string(description)));
-        yaml_content.push_str("type: command\n");
-        yaml_content.push_str("content: |\n");
-
-        for line in content.lines() {
-            yaml_content.push_str("  ");
-            yaml_content.push_str(line);
-            yaml_content.push('\n');
-        }
-
-        yaml_content.push_str("---\n");
+        let command = AiderCommand {
+            name: name.to_string(),
+            description: description.to_string(),
+            kind: "command".to_string(),
+            content: content.to_string(),
+        };
 
-        Ok(yaml_content)
+        to_yaml_document(&command)
     }
 
     /// Convert command to Warp workflow format
@@ -292,6 +469,61 @@ impl MarkdownConverter {
     }
 }
 
+/// YAML document with frontmatter keys followed by the markdown body
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct YamlDocument {
+    #[serde(flatten)]
+    frontmatter: BTreeMap<String, String>,
+    content: String,
+}
+
+/// Aider command structure
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct AiderCommand {
+    name: String,
+    description: String,
+    #[serde(rename = "type")]
+    kind: String,
+    content: String,
+}
+
+/// Serialize a single YAML document, prefixed with a `---` start marker
+///
+/// serde_yaml writes multi-line bodies as literal block scalars, falling back to a
+/// quoted string when they contain characters a block can't hold (such as tabs), so
+/// lines with `: `, leading dashes or tabs always round-trip unchanged.
+fn to_yaml_document<T: serde::Serialize>(value: &T) -> DeploymentResult<String> {
+    let yaml = serde_yaml::to_string(value)
+        .map_err(|e| DeploymentError::format_error(format!("YAML serialization failed: {}", e)))?;
+    Ok(format!("---\n{}", yaml))
+}
+
+/// Parsed outline of a Warp workflow
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WarpWorkflowSummary {
+    pub name: String,
+    pub description: String,
+    pub step_count: u64,
+    /// Description of each step, in order
+    pub step_descriptions: Vec<Option<String>>,
+}
+
+/// Parse Warp workflow YAML back into its structure and summarize it
+///
+/// Fails when the YAML doesn't deserialize as a `WarpWorkflow`.
+pub fn summarize_warp_workflow(yaml: &str) -> DeploymentResult<WarpWorkflowSummary> {
+    let workflow: WarpWorkflow = serde_yaml::from_str(yaml)
+        .map_err(|e| DeploymentError::format_error(format!("Invalid Warp workflow: {}", e)))?;
+
+    Ok(WarpWorkflowSummary {
+        name: workflow.name,
+        description: workflow.description,
+        step_count: workflow.steps.len() as u64,
+        step_descriptions: workflow.steps.into_iter().map(|step| step.description).collect(),
+    })
+}
+
 /// Warp workflow structure
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 struct WarpWorkflow {
@@ -324,6 +556,54 @@ fn escape_yaml_string(s: &str) -> String {
         .replace('\n', "\\n")
 }
 
+/// Escape characters that would end or split a single-line HTML comment
+///
+/// Every `>` is escaped, so `-->` can't appear in the output.
+fn escape_html_comment(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('>', "\\>")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+/// Split an escaped `key: value` comment entry at the first unescaped `:`
+///
+/// Exactly one space after the separator is dropped; `None` if there is no separator.
+fn split_html_comment_entry(entry: &str) -> Option<(&str, &str)> {
+    let mut escaped = false;
+    for (i, c) in entry.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            ':' => {
+                let value = &entry[i + 1..];
+                return Some((&entry[..i], value.strip_prefix(' ').unwrap_or(value)));
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Reverse `escape_html_comment`
+fn unescape_html_comment(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
 /// Escape special characters for shell strings
 fn escape_shell_string(s: &str) -> String {
     s.replace('\\', "\\\\")
@@ -332,21 +612,378 @@ fn escape_shell_string(s: &str) -> String {
         .replace('`', "\\`")
 }
 
+/// Frontmatter keys Claude and Codex expect on command files
+pub const REQUIRED_COMMAND_FRONTMATTER: &[&str] = &["name", "description"];
+
+/// Frontmatter keys every Claude skill must declare
+pub const REQUIRED_SKILL_FRONTMATTER: &[&str] = &["name", "description", "tools"];
+
+/// Ensure the required frontmatter keys are present
+///
+/// A missing `name` is filled in from `name` and a missing `description` from the first
+/// line of the body; other keys can't be derived and are left for validation to report.
+/// Returns the updated content and the keys that were added, in `required_keys` order,
+/// or an error when the content opens a frontmatter block that is never closed.
+pub fn ensure_frontmatter(
+    content: &str,
+    name: &str,
+    required_keys: &[&str],
+) -> DeploymentResult<(String, Vec<String>)> {
+    let existing = MarkdownConverter::split_frontmatter(content)?;
+    let (fm, body) = MarkdownConverter::parse_frontmatter(content);
+    let fm = fm.unwrap_or_default();
+
+    let description = body
+        .lines()
+        .map(|line| line.trim_start_matches('#').trim())
+        .find(|line| !line.is_empty());
+    let derived: Vec<(String, &str)> = required_keys
+        .iter()
+        .filter(|key| fm.get(**key).map(|v| v.trim().is_empty()).unwrap_or(true))
+        .filter_map(|key| {
+            let value = match *key {
+                "name" => Some(name),
+                "description" => description,
+                _ => None,
+            };
+            value.map(|value| (key.to_string(), value))
+        })
+        .collect();
+
+    if derived.is_empty() {
+        return Ok((content.to_string(), Vec::new()));
+    }
+
+    let injected: String = derived
+        .iter()
+        .map(|(key, value)| format!("{}: \"{}\"\n", key, escape_yaml_string(value)))
+        .collect();
+
+    // Keep the original block, minus the empty keys being filled in
+    let present: String = existing
+        .map(|(block, _)| block)
+        .unwrap_or_default()
+        .lines()
+        .filter(|line| {
+            let key = line.split(':').next().unwrap_or("").trim();
+            !derived.iter().any(|(added, _)| added == key)
+        })
+        .map(|line| format!("{}\n", line))
+        .collect();
+
+    let updated = format!("---\n{}{}---\n\n{}", present, injected, body);
+    Ok((updated, derived.into_iter().map(|(key, _)| key).collect()))
+}
+
+/// Normalize generated markdown so its length matches what agents ingest
+///
+/// Trims trailing whitespace from every line and collapses runs of blank lines into one.
+pub fn normalize(content: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    for line in content.lines() {
+        let line = line.trim_end();
+        if line.is_empty() && lines.last().map(|l| l.is_empty()).unwrap_or(false) {
+            continue;
+        }
+        lines.push(line);
+    }
+
+    let mut normalized = lines.join("\n");
+    if content.ends_with('\n') {
+        normalized.push('\n');
+    }
+    normalized
+}
+
+/// How generated content is shrunk for agents with tight character limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CompactionStrategy {
+    /// Content is deployed as generated
+    #[default]
+    None,
+    /// Redundant whitespace is removed with `minify_markdown`
+    Minify,
+}
+
+impl CompactionStrategy {
+    /// Apply this strategy to markdown content
+    pub fn apply(self, content: &str) -> String {
+        match self {
+            CompactionStrategy::None => content.to_string(),
+            CompactionStrategy::Minify => minify_markdown(content),
+        }
+    }
+}
+
+/// Remove whitespace that doesn't change how markdown renders
+///
+/// Collapses runs of spaces (outside inline code), trims trailing whitespace, drops
+/// blank lines around headings and repeated blank lines. Indentation, fenced code
+/// blocks and the two trailing spaces of a hard line break are kept.
+pub fn minify_markdown(content: &str) -> String {
+    // (line, is_heading)
+    let mut lines: Vec<(String, bool)> = Vec::new();
+    let mut in_fence = false;
+    let source: Vec<&str> = content.lines().collect();
+
+    for (i, line) in source.iter().copied().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            lines.push((line.to_string(), false));
+            continue;
+        }
+        if in_fence {
+            lines.push((line.to_string(), false));
+            continue;
+        }
+
+        // Trailing spaces only break the line when more of the paragraph follows
+        let hard_break = line.ends_with("  ")
+            && source.get(i + 1).is_some_and(|next| !next.trim().is_empty());
+        let mut line = collapse_spaces(line.trim_end());
+        let is_heading = is_atx_heading(&line);
+        if hard_break && !line.is_empty() && !is_heading {
+            line.push_str("  ");
+        }
+        if line.is_empty() {
+            if lines.last().map(|(prev, heading)| prev.is_empty() || *heading).unwrap_or(true) {
+                continue;
+            }
+        } else if is_heading {
+            while lines.last().map(|(prev, _)| prev.is_empty()).unwrap_or(false) {
+                lines.pop();
+            }
+        }
+        lines.push((line, is_heading));
+    }
+
+    while lines.last().map(|(prev, _)| prev.is_empty()).unwrap_or(false) {
+        lines.pop();
+    }
+
+    let mut minified = lines.into_iter().map(|(line, _)| line).collect::<Vec<_>>().join("\n");
+    if content.ends_with('\n') && !minified.is_empty() {
+        minified.push('\n');
+    }
+    minified
+}
+
+/// Collapse runs of spaces after the indentation, leaving inline code spans alone
+fn collapse_spaces(line: &str) -> String {
+    let body = line.trim_start_matches([' ', '\t']);
+    let indent = &line[..line.len() - body.len()];
+    let mut collapsed = String::with_capacity(line.len());
+    let mut in_code = false;
+    for c in body.chars() {
+        if c == '`' {
+            in_code = !in_code;
+        } else if c == ' ' && !in_code && collapsed.ends_with(' ') {
+            continue;
+        }
+        collapsed.push(c);
+    }
+    format!("{}{}", indent, collapsed)
+}
+
+/// Whether a line is an ATX heading (`#` through `######` followed by a space)
+fn is_atx_heading(line: &str) -> bool {
+    let hashes = line.chars().take_while(|c| *c == '#').count();
+    (1..=6).contains(&hashes) && line[hashes..].starts_with(' ')
+}
+
+/// Whether a link target is relative to the document it appears in
+fn is_relative_link(target: &str) -> bool {
+    let has_scheme = target
+        .split_once(':')
+        .map(|(scheme, _)| !scheme.is_empty() && !scheme.contains('/'))
+        .unwrap_or(false);
+    !(target.is_empty()
+        || has_scheme
+        || target.starts_with('#')
+        || target.starts_with('/')
+        || target.starts_with('\\')
+        || target.starts_with('~'))
+}
+
+/// Rewrite relative markdown link targets to absolute paths under `base_dir`
+///
+/// Generated files are linked into agent directories, where links relative to the
+/// source tree no longer resolve. URLs, anchors, absolute paths and links inside
+/// fenced code blocks are left alone.
+pub fn rewrite_relative_links(content: &str, base_dir: &Path) -> String {
+    static LINK_PATTERN: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r#"(!?\[[^\]]*\])\(([^)\s]+)((?:\s+"[^"]*")?)\)"#).unwrap());
+
+    let base = base_dir.to_string_lossy();
+    let base = base.trim_end_matches(['/', '\\']);
+    let mut in_fence = false;
+
+    content
+        .split_inclusive('\n')
+        .map(|line| {
+            if line.trim_start().starts_with("```") {
+                in_fence = !in_fence;
+            }
+            if in_fence {
+                return line.to_string();
+            }
+            LINK_PATTERN
+                .replace_all(line, |caps: &Captures| {
+                    let target = &caps[2];
+                    if !is_relative_link(target) {
+                        return caps[0].to_string();
+                    }
+                    format!(
+                        "{}({}/{}{})",
+                        &caps[1],
+                        base,
+                        target.trim_start_matches("./"),
+                        &caps[3]
+                    )
+                })
+                .into_owned()
+        })
+        .collect()
+}
+
+/// One pack's content recovered from an inline AGENTS.md
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackSection {
+    pub pack_id: String,
+    pub version: String,
+    pub content: String,
+}
+
+/// Parse a `<!-- Pack: id vX -->` marker line into its id and version
+fn parse_pack_marker(line: &str) -> Option<(String, String)> {
+    let inner = line.trim().strip_prefix("<!-- Pack:")?.strip_suffix("-->")?;
+    let (id, version) = inner.trim().split_once(char::is_whitespace)?;
+    let version = version.trim().strip_prefix('v')?;
+    if id.is_empty() || version.is_empty() {
+        return None;
+    }
+    Some((id.to_string(), version.to_string()))
+}
+
+/// Split an AGENTS.md generated with inline content back into per-pack markdown
+///
+/// Each section runs from its `<!-- Pack: id vX -->` marker to the next one; the
+/// last ends at the `---` separator the generator writes after the packs.
+pub fn split_agents_md(content: &str) -> DeploymentResult<Vec<PackSection>> {
+    let mut sections: Vec<(String, String, Vec<&str>)> = Vec::new();
+    for line in content.lines() {
+        if let Some((pack_id, version)) = parse_pack_marker(line) {
+            sections.push((pack_id, version, Vec::new()));
+        } else if let Some((_, _, lines)) = sections.last_mut() {
+            lines.push(line);
+        }
+    }
+
+    if sections.is_empty() {
+        return Err(DeploymentError::format_error(
+            "No <!-- Pack: id vX --> markers found; AGENTS.md must be generated with inline content",
+        ));
+    }
+
+    if let Some((_, _, lines)) = sections.last_mut() {
+        if let Some(end) = lines.iter().rposition(|line| line.trim_end() == "---") {
+            lines.truncate(end);
+        }
+    }
+
+    Ok(sections
+        .into_iter()
+        .map(|(pack_id, version, lines)| PackSection {
+            pack_id,
+            version,
+            content: format!("{}\n", lines.join("\n").trim_matches('\n')),
+        })
+        .collect())
+}
+
+/// A markdown heading in a document outline
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutlineEntry {
+    /// Heading level (1 for `#`, 2 for `##`, ...)
+    pub level: u8,
+    /// Heading text without the leading hashes
+    pub text: String,
+    /// UTF-16 code unit offset of the heading line within the content, so it can index
+    /// JavaScript strings directly
+    pub offset: usize,
+    /// Headings nested under this one
+    pub children: Vec<OutlineEntry>,
+}
+
+/// Parse markdown headings into a nested outline (fenced code blocks are skipped)
+pub fn outline(content: &str) -> Vec<OutlineEntry> {
+    let mut headings = Vec::new();
+    let mut in_fence = false;
+    let mut offset = 0;
+
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        if trimmed.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+        } else if !in_fence {
+            let level = trimmed.chars().take_while(|c| *c == '#').count();
+            let rest = &trimmed[level..];
+            if (1..=6).contains(&level) && (rest.is_empty() || rest.starts_with(' ')) {
+                headings.push(OutlineEntry {
+                    level: level as u8,
+                    text: rest.trim().trim_end_matches('#').trim_end().to_string(),
+                    offset,
+                    children: Vec::new(),
+                });
+            }
+        }
+        offset += line.encode_utf16().count();
+    }
+
+    nest_outline(headings)
+}
+
+/// Build the heading tree from a flat list in document order
+fn nest_outline(headings: Vec<OutlineEntry>) -> Vec<OutlineEntry> {
+    let mut roots: Vec<OutlineEntry> = Vec::new();
+    let mut stack: Vec<OutlineEntry> = Vec::new();
+
+    for heading in headings {
+        while stack.last().map(|top| top.level >= heading.level).unwrap_or(false) {
+            let done = stack.pop().unwrap();
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(done),
+                None => roots.push(done),
+            }
+        }
+        stack.push(heading);
+    }
+
+    while let Some(done) = stack.pop() {
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(done),
+            None => roots.push(done),
+        }
+    }
+
+    roots
+}
+
 /// Format detection utilities
 pub struct FormatDetector;
 
 impl FormatDetector {
-    /// Detect format from file extension
+    /// Detect format from file extension, defaulting to markdown
     pub fn from_extension(path: &str) -> FileFormat {
-        if path.ends_with(".toml") {
-            FileFormat::Toml
-        } else if path.ends_with(".yaml") || path.ends_with(".yml") {
-            FileFormat::Yaml
-        } else if path.ends_with(".json") {
-            FileFormat::Json
-        } else {
-            FileFormat::Markdown
-        }
+        Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(FileFormat::from_extension)
+            .unwrap_or(FileFormat::Markdown)
     }
 
     /// Detect format from content
@@ -367,7 +1004,7 @@ impl FormatDetector {
     }
 }
 
-/// Supported file formats
+/// Supported file formats, shared by deployed commands and out-references
 #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum FileFormat {
@@ -375,6 +1012,7 @@ pub enum FileFormat {
     Toml,
     Yaml,
     Json,
+    Text,
 }
 
 impl FileFormat {
@@ -384,7 +1022,28 @@ impl FileFormat {
             FileFormat::Toml => "toml",
             FileFormat::Yaml => "yaml",
             FileFormat::Json => "json",
+            FileFormat::Text => "txt",
+        }
+    }
+
+    /// Format for a file extension (without the leading dot)
+    pub fn from_extension(extension: &str) -> Option<FileFormat> {
+        match extension.to_lowercase().as_str() {
+            "md" | "markdown" => Some(FileFormat::Markdown),
+            "toml" => Some(FileFormat::Toml),
+            "yaml" | "yml" => Some(FileFormat::Yaml),
+            "json" => Some(FileFormat::Json),
+            "txt" => Some(FileFormat::Text),
+            _ => None,
+        }
+    }
+
+    /// Parse a format name or any extension accepted by `from_extension`
+    pub fn parse(value: &str) -> Option<FileFormat> {
+        if value.eq_ignore_ascii_case("text") {
+            return Some(FileFormat::Text);
         }
+        FileFormat::from_extension(value)
     }
 }
 
@@ -404,6 +1063,106 @@ mod tests {
         assert!(result.contains("# Content"));
     }
 
+    #[test]
+    fn test_add_frontmatter_merges_existing_block() {
+        let content = "---\nname: pack-rules\ntags:\n  - git\n---\n\n# Content\n";
+        let mut fm = HashMap::new();
+        fm.insert("name".to_string(), "AGENTS.md Rules".to_string());
+        fm.insert("version".to_string(), "2.0".to_string());
+
+        let merged = MarkdownConverter::add_frontmatter(content, fm.clone());
+        assert_eq!(
+            merged,
+            "---\ntags:\n  - git\nname: \"AGENTS.md Rules\"\nversion: \"2.0\"\n---\n\n# Content\n"
+        );
+        assert_eq!(merged.lines().filter(|line| *line == "---").count(), 2);
+
+        let kept = MarkdownConverter::add_frontmatter_with(content, fm, FrontmatterMerge::PreferExisting);
+        assert_eq!(
+            kept,
+            "---\nname: pack-rules\ntags:\n  - git\nversion: \"2.0\"\n---\n\n# Content\n"
+        );
+        let (parsed, body) = MarkdownConverter::parse_frontmatter(&kept);
+        assert_eq!(parsed.unwrap()["name"], "pack-rules");
+        assert_eq!(body, "# Content\n");
+    }
+
+    #[test]
+    fn test_rewrite_relative_links_skips_urls_and_code() {
+        let content = "See [ref](./docs/a.md#setup \"Docs\") and [site](https://example.com).\n\
+                       [top](#intro) ![logo](img/logo.png)\n\
+                       ```\n[x](docs/a.md)\n```\n";
+        let rewritten = rewrite_relative_links(content, Path::new("/home/me/.agentsmd/"));
+
+        assert!(rewritten.contains("[ref](/home/me/.agentsmd/docs/a.md#setup \"Docs\")"));
+        assert!(rewritten.contains("[site](https://example.com)"));
+        assert!(rewritten.contains("[top](#intro)"));
+        assert!(rewritten.contains("![logo](/home/me/.agentsmd/img/logo.png)"));
+        assert!(rewritten.contains("```\n[x](docs/a.md)\n```\n"));
+    }
+
+    #[test]
+    fn test_yaml_output_round_trips_tricky_content() {
+        let content = "- list item first\nkey: value\n\tindented: with tab\n\n# Heading: colon\n";
+
+        let aider = MarkdownConverter::to_aider_command("lint", "Lint: the code", content).unwrap();
+        let parsed: serde_yaml::Value = serde_yaml::from_str(&aider).unwrap();
+        assert_eq!(parsed["name"].as_str(), Some("lint"));
+        assert_eq!(parsed["description"].as_str(), Some("Lint: the code"));
+        assert_eq!(parsed["type"].as_str(), Some("command"));
+        assert_eq!(parsed["content"].as_str(), Some(content));
+
+        let mut fm = HashMap::new();
+        fm.insert("title".to_string(), "- not a list".to_string());
+        let yaml = MarkdownConverter::to_yaml(content, Some(fm)).unwrap();
+        let parsed: serde_yaml::Value = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(parsed["title"].as_str(), Some("- not a list"));
+        assert_eq!(parsed["content"].as_str(), Some(content));
+    }
+
+    fn loaded_pack(id: &str, content: &str) -> LoadedPack {
+        LoadedPack {
+            id: id.to_string(),
+            name: format!("{} rules", id),
+            version: "1.0.0".to_string(),
+            description: String::new(),
+            dependencies: Vec::new(),
+            target_agents: Vec::new(),
+            files: vec!["rules.md".to_string()],
+            out_references: Vec::new(),
+            metadata: crate::types::PackMetadata {
+                word_count: 0,
+                character_count: 0,
+                category: "universal".to_string(),
+                tags: Vec::new(),
+            },
+            path: String::new(),
+            content: content.to_string(),
+            actual_word_count: 0,
+            actual_character_count: 0,
+        }
+    }
+
+    #[test]
+    fn test_rules_json_has_one_entry_per_pack() {
+        let packs = vec![
+            loaded_pack("git-workflow", "# Git\n\nBranch per issue.\n"),
+            loaded_pack("testing", "# Tests\n\nテストを書く\n"),
+        ];
+
+        let json = MarkdownConverter::to_rules_json(&packs).unwrap();
+        let payload: Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(payload["version"], RULES_JSON_VERSION);
+        let entries = payload["packs"].as_array().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["id"], "git-workflow");
+        assert_eq!(entries[0]["name"], "git-workflow rules");
+        assert_eq!(entries[0]["version"], "1.0.0");
+        assert_eq!(entries[1]["content"], "# Tests\n\nテストを書く\n");
+        assert_eq!(payload["total_chars"], 25 + 16);
+    }
+
     #[test]
     fn test_parse_frontmatter() {
         let content = "---\nname: \"test\"\nversion: \"1.0\"\n---\n\n# Content";
@@ -415,11 +1174,181 @@ mod tests {
         assert!(body.contains("# Content"));
     }
 
+    #[test]
+    fn test_html_comment_meta_round_trip() {
+        let mut meta = HashMap::new();
+        meta.insert("title".to_string(), "AGENTS.md Rules".to_string());
+        meta.insert("note".to_string(), "ends early --> unless escaped\nsecond line".to_string());
+        meta.insert("path".to_string(), "C:\\rules\\next".to_string());
+        meta.insert("scope:agent".to_string(), "  indented value ".to_string());
+
+        let wrapped = MarkdownConverter::to_html_comment_meta(meta.clone(), "\n\n# Content\n");
+        assert!(wrapped.starts_with("<!-- note: "));
+        assert!(wrapped.contains("<!-- scope\\:agent:   indented value  -->\n"));
+        assert_eq!(wrapped.matches("-->").count(), 4);
+
+        let (parsed, body) = MarkdownConverter::parse_html_comment_meta(&wrapped);
+        assert_eq!(parsed.unwrap(), meta);
+        assert_eq!(body, "\n\n# Content\n");
+
+        let (none, body) = MarkdownConverter::parse_html_comment_meta("<!-- just a comment -->\n# Content");
+        assert!(none.is_none());
+        assert_eq!(body, "<!-- just a comment -->\n# Content");
+    }
+
+    #[test]
+    fn test_claude_skill_frontmatter() {
+        let skill = MarkdownConverter::to_claude_skill(
+            "review",
+            "Review the current diff",
+            vec!["Read".to_string(), "Grep".to_string()],
+            "# Review\n\nCheck the diff.",
+        )
+        .unwrap();
+
+        assert!(skill.contains("tools:\n  - \"Read\"\n  - \"Grep\"\n"));
+
+        let (fm, body) = MarkdownConverter::parse_frontmatter(&skill);
+        let fm = fm.unwrap();
+        for key in REQUIRED_SKILL_FRONTMATTER {
+            assert!(fm.contains_key(*key), "missing {}", key);
+        }
+        assert_eq!(fm.get("name"), Some(&"review".to_string()));
+        assert_eq!(body, "# Review\n\nCheck the diff.");
+
+        let yaml = &skill[4..skill[4..].find("\n---").unwrap() + 4];
+        let parsed: serde_yaml::Value = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(parsed["tools"].as_sequence().map(|t| t.len()), Some(2));
+
+        assert!(MarkdownConverter::to_claude_skill("review", "", vec![], "").is_err());
+    }
+
+    #[test]
+    fn test_antigravity_workflow_frontmatter() {
+        let workflow = MarkdownConverter::to_antigravity_workflow("ship", "Ship the branch", "Run the checks.");
+        assert_eq!(
+            workflow,
+            "---\ndescription: \"Ship the branch\"\n---\n\n# /ship\n\nRun the checks."
+        );
+    }
+
     #[test]
     fn test_format_detection() {
         assert_eq!(FormatDetector::from_extension("test.toml"), FileFormat::Toml);
         assert_eq!(FormatDetector::from_extension("test.yaml"), FileFormat::Yaml);
         assert_eq!(FormatDetector::from_extension("test.json"), FileFormat::Json);
         assert_eq!(FormatDetector::from_extension("test.md"), FileFormat::Markdown);
+        assert_eq!(FormatDetector::from_extension("test.txt"), FileFormat::Text);
+        assert_eq!(FormatDetector::from_extension("test.unknown"), FileFormat::Markdown);
+    }
+
+    #[test]
+    fn test_ensure_frontmatter_injects_missing_keys() {
+        let (content, added) =
+            ensure_frontmatter("# Deploy \"now\"\n\nShip it.", "deploy", &["name", "description", "tools"])
+                .unwrap();
+
+        // `tools` can't be derived, so it is left for validation to report
+        assert_eq!(added, vec!["name", "description"]);
+        let (fm, body) = MarkdownConverter::parse_frontmatter(&content);
+        let fm = fm.unwrap();
+        assert_eq!(fm.get("name"), Some(&"deploy".to_string()));
+        assert!(content.contains("description: \"Deploy \\\"now\\\"\"\n"));
+        assert!(!fm.contains_key("tools"));
+        assert_eq!(body, "# Deploy \"now\"\n\nShip it.");
+    }
+
+    #[test]
+    fn test_ensure_frontmatter_rejects_unclosed_block() {
+        let original = "---\nname: deploy\n\n# Deploy\n";
+        assert!(ensure_frontmatter(original, "deploy", &["name", "description"]).is_err());
+
+        // The content is kept whole rather than split at a fence that isn't there
+        let mut fm = HashMap::new();
+        fm.insert("version".to_string(), "2.0".to_string());
+        let added = MarkdownConverter::add_frontmatter(original, fm);
+        assert_eq!(added, format!("---\nversion: \"2.0\"\n---\n\n{}", original));
+    }
+
+    #[test]
+    fn test_ensure_frontmatter_keeps_existing_keys() {
+        let original = "---\nname: \"deploy\"\n---\n\n# Deploy";
+        let (content, added) = ensure_frontmatter(original, "other", &["name", "description"]).unwrap();
+
+        assert_eq!(added, vec!["description"]);
+        let (fm, _) = MarkdownConverter::parse_frontmatter(&content);
+        let fm = fm.unwrap();
+        assert_eq!(fm.get("name"), Some(&"deploy".to_string()));
+        assert_eq!(fm.get("description"), Some(&"Deploy".to_string()));
+
+        let (unchanged, added) = ensure_frontmatter(&content, "other", &["name", "description"]).unwrap();
+        assert!(added.is_empty());
+        assert_eq!(unchanged, content);
+    }
+
+    #[test]
+    fn test_normalize_collapses_blank_lines() {
+        let content = "# Title  \n\n\n\nIntro\t\n\n\n- item\n";
+        assert_eq!(normalize(content), "# Title\n\nIntro\n\n- item\n");
+        assert_eq!(normalize("no change"), "no change");
+    }
+
+    #[test]
+    fn test_outline_nesting() {
+        let content = "# Title\n\nIntro — café 🚀\n\n## Setup\n### Install\n### Configure\n```\n# not a heading\n```\n## Usage\n# Appendix\n";
+        let tree = outline(content);
+
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree[0].level, 1);
+        assert_eq!(tree[0].text, "Title");
+        assert_eq!(tree[0].offset, 0);
+        assert_eq!(tree[0].children.len(), 2);
+
+        let setup = &tree[0].children[0];
+        assert_eq!((setup.level, setup.text.as_str()), (2, "Setup"));
+        // The emoji is one char but two UTF-16 code units, as JavaScript counts it
+        let setup_bytes = content.find("## Setup").unwrap();
+        assert_eq!(setup.offset, content[..setup_bytes].encode_utf16().count());
+        assert_eq!(setup.offset, content[..setup_bytes].chars().count() + 1);
+        assert!(setup.offset < setup_bytes);
+        let nested: Vec<_> = setup.children.iter().map(|c| (c.level, c.text.as_str())).collect();
+        assert_eq!(nested, vec![(3, "Install"), (3, "Configure")]);
+
+        assert_eq!(tree[0].children[1].text, "Usage");
+        assert!(tree[0].children[1].children.is_empty());
+        assert_eq!(tree[1].text, "Appendix");
+    }
+
+    #[test]
+    fn test_minify_markdown_keeps_code_blocks() {
+        let code = "```bash\n# install   deps\nnpm   install    \n\n\nnpm test\n```";
+        let content = format!(
+            "# Title   \n\n\nSome    text  with `a   b` spacing.  \n\n\n## Next\n\n  - nested   item\n\n{}\n\nEnd\n\n",
+            code
+        );
+
+        let minified = minify_markdown(&content);
+
+        assert!(minified.contains(code), "{}", minified);
+        assert_eq!(
+            minified,
+            format!(
+                "# Title\nSome text with `a   b` spacing.\n## Next\n  - nested item\n\n{}\n\nEnd\n",
+                code
+            )
+        );
+        assert!(minified.len() < content.len());
+        assert_eq!(CompactionStrategy::None.apply(&content), content);
+        assert_eq!(CompactionStrategy::Minify.apply(&content), minified);
+    }
+
+    #[test]
+    fn test_minify_markdown_keeps_hard_line_breaks() {
+        let content = "Roses are red,    \nviolets   are blue.\nBackslash\\\nbreak too.  \n\nLast line.  \n";
+
+        assert_eq!(
+            minify_markdown(content),
+            "Roses are red,  \nviolets are blue.\nBackslash\\\nbreak too.\n\nLast line.\n"
+        );
     }
 }
@@ -2,12 +2,16 @@
 //! 
 //! Defines the core trait that all agent deployers must implement.
 
-use crate::types::AgentDefinition;
+use chrono::{DateTime, Utc};
+use crate::budget;
+use crate::types::{AgentDefinition, AgentDirInfo, LinkMethod, LinkStrategy, PackBudgetItem};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
+use super::converters::CompactionStrategy;
 use super::error::DeploymentResult;
+use super::state::DeploymentState;
 
 /// Configuration for a deployment operation
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,6 +29,119 @@ pub struct DeploymentConfig {
     pub force_overwrite: bool,
     /// Project path for project-level deployments
     pub project_path: Option<String>,
+    /// Add project-level deployed files to the project's .gitignore
+    #[serde(default)]
+    pub update_gitignore: bool,
+    /// Fill in missing `name` and `description` command frontmatter from the command itself
+    #[serde(default)]
+    pub fix_frontmatter: bool,
+    /// Read deployed files back and roll back if they don't match what was prepared
+    #[serde(default = "default_verify_after_deploy")]
+    pub verify_after_deploy: bool,
+    /// Append out-reference content to AGENTS.md for agents that can't link to it
+    #[serde(default)]
+    pub inline_out_references: bool,
+    /// Which linking methods deployers may use when placing files
+    #[serde(default)]
+    pub link_strategy: LinkStrategy,
+    /// Refuse to deploy when the agent application doesn't appear to be installed
+    #[serde(default)]
+    pub require_agent_installed: bool,
+    /// Deploy custom commands as skills for agents that support them (Claude)
+    #[serde(default)]
+    pub emit_skills: bool,
+    /// How generated instructions are shrunk for agents with tight limits (Copilot)
+    #[serde(default)]
+    pub compaction: CompactionStrategy,
+}
+
+fn default_verify_after_deploy() -> bool {
+    true
+}
+
+impl Default for DeploymentConfig {
+    fn default() -> Self {
+        Self {
+            agent_id: String::new(),
+            pack_ids: Vec::new(),
+            custom_command_ids: Vec::new(),
+            target_level: TargetLevel::User,
+            force_overwrite: false,
+            project_path: None,
+            update_gitignore: false,
+            fix_frontmatter: false,
+            verify_after_deploy: default_verify_after_deploy(),
+            inline_out_references: false,
+            link_strategy: LinkStrategy::default(),
+            require_agent_installed: false,
+            emit_skills: false,
+            compaction: CompactionStrategy::default(),
+        }
+    }
+}
+
+impl DeploymentConfig {
+    /// Config that repeats a recorded deployment: same selection, target and options
+    pub fn from_state(state: &DeploymentState) -> Self {
+        let options = &state.options;
+        Self {
+            agent_id: state.agent_id.clone(),
+            pack_ids: state.deployed_packs.clone(),
+            custom_command_ids: state.deployed_commands.clone(),
+            target_level: state.target_level.clone(),
+            force_overwrite: false,
+            project_path: state.project_path.clone(),
+            update_gitignore: options.update_gitignore,
+            fix_frontmatter: options.fix_frontmatter,
+            verify_after_deploy: options.verify_after_deploy,
+            inline_out_references: options.inline_out_references,
+            link_strategy: options.link_strategy,
+            require_agent_installed: options.require_agent_installed,
+            emit_skills: options.emit_skills,
+            compaction: options.compaction,
+        }
+    }
+
+    /// Options recorded with the deployment so redeploys can repeat them
+    pub fn options(&self) -> DeploymentOptions {
+        DeploymentOptions {
+            update_gitignore: self.update_gitignore,
+            fix_frontmatter: self.fix_frontmatter,
+            verify_after_deploy: self.verify_after_deploy,
+            inline_out_references: self.inline_out_references,
+            link_strategy: self.link_strategy,
+            require_agent_installed: self.require_agent_installed,
+            emit_skills: self.emit_skills,
+            compaction: self.compaction,
+        }
+    }
+
+    /// Commands were selected without any packs, so AGENTS.md is left untouched
+    ///
+    /// Inlined out-references are carried in AGENTS.md, so they still need it written.
+    pub fn commands_only(&self) -> bool {
+        self.pack_ids.is_empty() && !self.custom_command_ids.is_empty() && !self.inline_out_references
+    }
+}
+
+/// Deployment options beyond the selection and target, as recorded in deployment state
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DeploymentOptions {
+    pub update_gitignore: bool,
+    pub fix_frontmatter: bool,
+    pub verify_after_deploy: bool,
+    pub inline_out_references: bool,
+    pub link_strategy: LinkStrategy,
+    pub require_agent_installed: bool,
+    pub emit_skills: bool,
+    pub compaction: CompactionStrategy,
+}
+
+impl Default for DeploymentOptions {
+    fn default() -> Self {
+        DeploymentConfig::default().options()
+    }
 }
 
 /// Target level for deployment
@@ -41,6 +158,16 @@ impl Default for TargetLevel {
     }
 }
 
+impl TargetLevel {
+    /// Lowercase name, as serialized
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TargetLevel::User => "user",
+            TargetLevel::Project => "project",
+        }
+    }
+}
+
 /// Result of a successful deployment
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -57,6 +184,27 @@ pub struct DeploymentOutput {
     pub deployed_files: Vec<String>,
     /// Manual steps required (if any)
     pub manual_steps: Vec<String>,
+    /// Directories created to hold deployed files, removed again on rollback
+    #[serde(default)]
+    pub created_dirs: Vec<String>,
+    /// Existing files updated in place, restored from the backup on rollback
+    #[serde(default)]
+    pub modified_files: Vec<String>,
+    /// How each out-reference was deployed
+    #[serde(default)]
+    pub out_reference_results: Vec<OutReferenceResult>,
+}
+
+/// Outcome of deploying a single out-reference
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct OutReferenceResult {
+    /// Path relative to the out-references directory
+    pub file_path: String,
+    /// How the reference was placed
+    pub status: LinkMethod,
+    /// Warning raised while placing it, such as falling back to a copy
+    pub warning: Option<String>,
 }
 
 impl DeploymentOutput {
@@ -68,6 +216,9 @@ impl DeploymentOutput {
             errors: Vec::new(),
             deployed_files,
             manual_steps: Vec::new(),
+            created_dirs: Vec::new(),
+            modified_files: Vec::new(),
+            out_reference_results: Vec::new(),
         }
     }
 
@@ -81,6 +232,21 @@ impl DeploymentOutput {
         self
     }
 
+    pub fn with_created_dirs(mut self, dirs: Vec<String>) -> Self {
+        self.created_dirs = dirs;
+        self
+    }
+
+    pub fn with_modified_files(mut self, files: Vec<String>) -> Self {
+        self.modified_files = files;
+        self
+    }
+
+    pub fn with_out_reference_results(mut self, results: Vec<OutReferenceResult>) -> Self {
+        self.out_reference_results = results;
+        self
+    }
+
     pub fn failure(errors: Vec<String>) -> Self {
         Self {
             success: false,
@@ -89,10 +255,71 @@ impl DeploymentOutput {
             errors,
             deployed_files: Vec::new(),
             manual_steps: Vec::new(),
+            created_dirs: Vec::new(),
+            modified_files: Vec::new(),
+            out_reference_results: Vec::new(),
         }
     }
 }
 
+/// What rolling back a recorded deployment would change
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RollbackPlan {
+    pub agent_id: String,
+    /// Timestamp of the deployment being rolled back
+    pub timestamp: DateTime<Utc>,
+    /// Deployed files still on disk that will be removed
+    pub files_to_remove: Vec<String>,
+    /// Directories created by the deployment, removed when left empty
+    pub dirs_to_remove: Vec<String>,
+    /// Files that will be put back from the deployment's backup
+    pub files_to_restore: Vec<RestoredFile>,
+    pub backup_path: Option<String>,
+}
+
+/// A file restored from a backup during rollback
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoredFile {
+    /// Where the file is restored to
+    pub path: String,
+    /// The backed-up copy it is restored from
+    pub backup_path: String,
+}
+
+/// Phase of a deployment reported through progress events
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DeploymentPhase {
+    Prepare,
+    Validate,
+    Backup,
+    WriteAgentsMd,
+    Command,
+    OutReference,
+    Done,
+}
+
+/// Progress update emitted while a deployment runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeploymentProgress {
+    /// The agent being deployed to
+    pub agent_id: String,
+    /// Current phase
+    pub phase: DeploymentPhase,
+    /// Step number (1-based) within the whole deployment
+    pub current: usize,
+    /// Total number of steps, known once the deployment is prepared
+    pub total: usize,
+    /// Command or out-reference file for per-item phases
+    pub item: Option<String>,
+}
+
+/// Callback a deployer reports each file-level step to as it happens
+pub type ProgressSink<'a> = dyn FnMut(DeploymentPhase, Option<String>) + 'a;
+
 /// Prepared deployment artifacts ready for deployment
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -111,11 +338,23 @@ pub struct PreparedDeployment {
     pub character_count: u64,
     /// Format used for commands
     pub command_format: String,
+    /// Frontmatter keys injected per command file (when `fix_frontmatter` is enabled)
+    #[serde(default)]
+    pub frontmatter_fixes: HashMap<String, Vec<String>>,
+    /// Character contribution of each resolved pack (filled by budget-sensitive deployers)
+    #[serde(default)]
+    pub pack_breakdown: Vec<PackBudgetItem>,
+    /// Problems noticed while preparing that don't stop the deployment
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    /// Files recorded by the agent's previous deployment, replaceable in place
+    #[serde(default)]
+    pub previous_files: HashSet<String>,
 }
 
 impl PreparedDeployment {
     pub fn new(agents_md_content: String) -> Self {
-        let character_count = agents_md_content.len() as u64;
+        let character_count = budget::char_count(&agents_md_content);
         Self {
             agents_md_content,
             commands: HashMap::new(),
@@ -124,20 +363,60 @@ impl PreparedDeployment {
             target_paths: Vec::new(),
             character_count,
             command_format: "markdown".to_string(),
+            frontmatter_fixes: HashMap::new(),
+            pack_breakdown: Vec::new(),
+            warnings: Vec::new(),
+            previous_files: HashSet::new(),
         }
     }
 
     pub fn add_command(&mut self, name: String, content: String) {
-        self.character_count += content.len() as u64;
+        self.character_count += budget::char_count(&content);
         self.commands.insert(name, content);
     }
 
+    /// Add a command, injecting any missing required frontmatter keys when `fix` is set
+    pub fn add_command_with_frontmatter(
+        &mut self,
+        name: String,
+        content: String,
+        required_keys: &[&str],
+        fix: bool,
+    ) {
+        if !fix {
+            self.add_command(name, content);
+            return;
+        }
+
+        // `review.md` and `review/SKILL.md` are both named `review`
+        let stem = name.trim_end_matches(".md").trim_end_matches("/SKILL");
+        let stem = stem.rsplit('/').next().unwrap_or(stem);
+        match super::converters::ensure_frontmatter(&content, stem, required_keys) {
+            Ok((content, added)) => {
+                if !added.is_empty() {
+                    self.frontmatter_fixes.insert(name.clone(), added);
+                }
+                self.add_command(name, content);
+            }
+            Err(e) => {
+                self.warnings.push(format!("Could not fix frontmatter in {}: {}", name, e));
+                self.add_command(name, content);
+            }
+        }
+    }
+
+    /// Append a section to the AGENTS.md content, keeping the character count in step
+    pub fn append_to_agents_md(&mut self, section: &str) {
+        self.character_count += budget::char_count(section);
+        self.agents_md_content.push_str(section);
+    }
+
     pub fn add_config_file(&mut self, path: String, content: String) {
         self.config_files.insert(path, content);
     }
 
     pub fn add_out_reference(&mut self, path: String, content: String) {
-        self.character_count += content.len() as u64;
+        self.character_count += budget::char_count(&content);
         self.out_references.insert(path, content);
     }
 
@@ -145,9 +424,14 @@ impl PreparedDeployment {
         self.target_paths.push(path);
     }
 
+    /// Whether the agent's previous deployment placed `path`, so it may be replaced
+    pub fn deployed_before(&self, path: &Path) -> bool {
+        self.previous_files.contains(path.to_string_lossy().as_ref())
+    }
+
     /// Get total character count for out-references
     pub fn out_reference_chars(&self) -> u64 {
-        self.out_references.values().map(|c| c.len() as u64).sum()
+        self.out_references.values().map(|c| budget::char_count(c)).sum()
     }
 }
 
@@ -163,6 +447,9 @@ pub struct ValidationReport {
     pub warnings: Vec<String>,
     /// Budget usage information
     pub budget_usage: BudgetUsage,
+    /// How to get back under the character limit, when over it
+    #[serde(default)]
+    pub suggestion: Option<String>,
 }
 
 impl ValidationReport {
@@ -172,6 +459,7 @@ impl ValidationReport {
             errors: Vec::new(),
             warnings: Vec::new(),
             budget_usage,
+            suggestion: None,
         }
     }
 
@@ -181,6 +469,7 @@ impl ValidationReport {
             errors,
             warnings: Vec::new(),
             budget_usage,
+            suggestion: None,
         }
     }
 
@@ -188,6 +477,21 @@ impl ValidationReport {
         self.warnings = warnings;
         self
     }
+
+    pub fn with_suggestion(mut self, suggestion: Option<String>) -> Self {
+        self.suggestion = suggestion;
+        self
+    }
+
+    /// Whether the character budget is the only reason validation failed
+    pub fn only_over_budget(&self) -> bool {
+        match &self.budget_usage.limit_error {
+            Some(limit_error) => {
+                !self.errors.is_empty() && self.errors.iter().all(|error| error == limit_error)
+            }
+            None => false,
+        }
+    }
 }
 
 /// Budget usage information
@@ -202,6 +506,10 @@ pub struct BudgetUsage {
     pub percentage: Option<f64>,
     /// Whether within the limit
     pub within_limit: bool,
+    /// The validation error reported for exceeding the limit, kept so it can be told
+    /// apart from other errors in the same report
+    #[serde(default)]
+    pub limit_error: Option<String>,
 }
 
 impl BudgetUsage {
@@ -213,6 +521,7 @@ impl BudgetUsage {
             max_chars,
             percentage,
             within_limit,
+            limit_error: None,
         }
     }
 
@@ -222,6 +531,7 @@ impl BudgetUsage {
             max_chars: None,
             percentage: None,
             within_limit: true,
+            limit_error: None,
         }
     }
 }
@@ -240,6 +550,15 @@ pub enum AgentStatus {
     Outdated,
 }
 
+/// An agent's deployment status along with what its config directory looks like
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeploymentStatusInfo {
+    pub status: AgentStatus,
+    /// The agent's config directory, if its path could be resolved
+    pub agent_dir: Option<AgentDirInfo>,
+}
+
 impl AgentStatus {
     pub fn as_str(&self) -> &'static str {
         match self {
@@ -273,8 +592,14 @@ pub trait AgentDeployer: Send + Sync {
 
     /// Execute the deployment
     /// 
-    /// Writes files, creates symlinks, and updates configurations.
-    fn deploy(&self, prepared: PreparedDeployment, config: &DeploymentConfig) -> DeploymentResult<DeploymentOutput>;
+    /// Writes files, creates symlinks, and updates configurations, reporting the
+    /// AGENTS.md write and each command and out-reference to `progress` as it goes.
+    fn deploy(
+        &self,
+        prepared: PreparedDeployment,
+        config: &DeploymentConfig,
+        progress: &mut ProgressSink<'_>,
+    ) -> DeploymentResult<DeploymentOutput>;
 
     /// Rollback a deployment
     /// 
@@ -298,4 +623,9 @@ pub trait AgentDeployer: Send + Sync {
     fn character_limit(&self) -> Option<u64> {
         self.agent_definition().character_limits.max_chars
     }
+
+    /// Get the budget warning threshold (percent) for this agent
+    fn warn_threshold_pct(&self) -> u64 {
+        self.agent_definition().character_limits.warn_threshold_pct
+    }
 }
@@ -2,6 +2,7 @@
 //! 
 //! Defines custom error types for the deployment system with proper error context.
 
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use thiserror::Error;
 
@@ -32,18 +33,36 @@ pub enum DeploymentError {
     #[error("Configuration error: {0}")]
     ConfigurationError(String),
 
-    #[error("Character limit exceeded: {current} / {limit} characters")]
+    #[error("Character limit exceeded for {agent_id}: {current} / {limit} characters{}", suggestion_suffix(.suggestion))]
     CharacterLimitExceeded {
         current: u64,
         limit: u64,
+        agent_id: String,
+        /// What to drop to get back under the limit, when the deployer can tell
+        suggestion: Option<String>,
     },
 
+    #[error("Deployment verification failed: {0}")]
+    VerificationFailed(String),
+
     #[error("Backup failed: {0}")]
     BackupFailed(String),
 
     #[error("Agent not installed: {0}")]
     AgentNotInstalled(String),
 
+    #[error("Agent {agent_id} does not support {level}-level deployment")]
+    UnsupportedTargetLevel {
+        agent_id: String,
+        level: String,
+    },
+
+    #[error("Could not determine the home directory")]
+    HomeDirUnavailable,
+
+    #[error("Timed out waiting for deployment lock: {0}")]
+    LockTimeout(PathBuf),
+
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 
@@ -93,5 +112,93 @@ impl DeploymentError {
     }
 }
 
+/// Trailing `. <suggestion>` for character limit errors
+fn suggestion_suffix(suggestion: &Option<String>) -> String {
+    suggestion.as_ref().map(|s| format!(". {}", s)).unwrap_or_default()
+}
+
 /// Result type alias for deployment operations
 pub type DeploymentResult<T> = Result<T, DeploymentError>;
+
+/// Stable error codes reported to the frontend by deployment IPC commands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    AgentNotFound,
+    AgentNotInstalled,
+    UnsupportedOperation,
+    ValidationFailed,
+    BudgetExceeded,
+    VerificationFailed,
+    FileSystem,
+    FormatConversion,
+    Configuration,
+    State,
+    Backup,
+    RollbackFailed,
+    HomeDirUnavailable,
+    LockTimeout,
+    Serialization,
+    NotInitialized,
+    Internal,
+}
+
+/// Structured error returned across the deployment IPC surface
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IpcError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl IpcError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    /// The shared deployment manager failed to start
+    pub fn not_initialized() -> Self {
+        Self::new(ErrorCode::NotInitialized, "Deployment manager not initialized")
+    }
+}
+
+impl std::fmt::Display for IpcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl DeploymentError {
+    /// Stable code identifying the kind of failure
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            DeploymentError::ValidationFailed(_) => ErrorCode::ValidationFailed,
+            DeploymentError::FileSystemError { .. } | DeploymentError::IoError(_) => ErrorCode::FileSystem,
+            DeploymentError::FormatConversionError(_) => ErrorCode::FormatConversion,
+            DeploymentError::AgentNotFound(_) => ErrorCode::AgentNotFound,
+            DeploymentError::RollbackFailed(_) => ErrorCode::RollbackFailed,
+            DeploymentError::StateError(_) => ErrorCode::State,
+            DeploymentError::ConfigurationError(_) => ErrorCode::Configuration,
+            DeploymentError::CharacterLimitExceeded { .. } => ErrorCode::BudgetExceeded,
+            DeploymentError::VerificationFailed(_) => ErrorCode::VerificationFailed,
+            DeploymentError::BackupFailed(_) => ErrorCode::Backup,
+            DeploymentError::AgentNotInstalled(_) => ErrorCode::AgentNotInstalled,
+            DeploymentError::UnsupportedTargetLevel { .. } => ErrorCode::UnsupportedOperation,
+            DeploymentError::HomeDirUnavailable => ErrorCode::HomeDirUnavailable,
+            DeploymentError::LockTimeout(_) => ErrorCode::LockTimeout,
+            DeploymentError::JsonError(_)
+            | DeploymentError::YamlError(_)
+            | DeploymentError::TomlSerError(_)
+            | DeploymentError::TomlDeError(_) => ErrorCode::Serialization,
+        }
+    }
+}
+
+impl From<DeploymentError> for IpcError {
+    fn from(error: DeploymentError) -> Self {
+        Self::new(error.code(), error.to_string())
+    }
+}
@@ -0,0 +1,119 @@
+//! `.gitignore` management for project-level deployments
+//!
+//! Keeps deployed files listed in a managed block of the project's `.gitignore`.
+
+use std::fs;
+use std::path::Path;
+
+use super::error::{DeploymentError, DeploymentResult};
+
+const BLOCK_START: &str = "# AgentsToolkit";
+const BLOCK_END: &str = "# End AgentsToolkit";
+
+/// Add deployed files under `project_root` to its `.gitignore`
+///
+/// Returns the entries that were newly added. Files outside the project are skipped.
+pub fn update_gitignore(project_root: &Path, deployed_files: &[String]) -> DeploymentResult<Vec<String>> {
+    let gitignore_path = project_root.join(".gitignore");
+    let existing = if gitignore_path.exists() {
+        fs::read_to_string(&gitignore_path).map_err(|e| {
+            DeploymentError::fs_error(&gitignore_path, format!("Failed to read .gitignore: {}", e))
+        })?
+    } else {
+        String::new()
+    };
+
+    let mut lines: Vec<String> = existing.lines().map(String::from).collect();
+
+    let mut new_entries: Vec<String> = Vec::new();
+    for file in deployed_files {
+        let relative = match Path::new(file).strip_prefix(project_root) {
+            Ok(rel) => rel.to_string_lossy().replace('\\', "/"),
+            Err(_) => continue,
+        };
+        let entry = format!("/{}", relative);
+
+        let already_listed = lines
+            .iter()
+            .chain(new_entries.iter())
+            .any(|line| line.trim() == entry);
+        if !already_listed {
+            new_entries.push(entry);
+        }
+    }
+
+    if new_entries.is_empty() {
+        return Ok(new_entries);
+    }
+
+    match lines.iter().position(|line| line.trim() == BLOCK_END) {
+        Some(end) => {
+            lines.splice(end..end, new_entries.iter().cloned());
+        }
+        None => {
+            if lines.last().map(|l| !l.trim().is_empty()).unwrap_or(false) {
+                lines.push(String::new());
+            }
+            lines.push(BLOCK_START.to_string());
+            lines.extend(new_entries.iter().cloned());
+            lines.push(BLOCK_END.to_string());
+        }
+    }
+
+    let mut content = lines.join("\n");
+    content.push('\n');
+    fs::write(&gitignore_path, content).map_err(|e| {
+        DeploymentError::fs_error(&gitignore_path, format!("Failed to write .gitignore: {}", e))
+    })?;
+
+    Ok(new_entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_update_gitignore_is_idempotent() {
+        let temp = tempdir().unwrap();
+        let repo = temp.path();
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        fs::write(repo.join(".gitignore"), "target/\n").unwrap();
+
+        let deployed = vec![
+            repo.join(".cursor").join("rules.md").to_string_lossy().to_string(),
+            "/somewhere/else/AGENTS.md".to_string(),
+        ];
+
+        let added = update_gitignore(repo, &deployed).unwrap();
+        assert_eq!(added, vec!["/.cursor/rules.md"]);
+
+        let added = update_gitignore(repo, &deployed).unwrap();
+        assert!(added.is_empty());
+
+        let content = fs::read_to_string(repo.join(".gitignore")).unwrap();
+        assert_eq!(content.matches("/.cursor/rules.md").count(), 1);
+        assert!(content.starts_with("target/\n"));
+        assert!(content.contains(BLOCK_START));
+        assert!(!content.contains("AGENTS.md"));
+    }
+
+    #[test]
+    fn test_update_gitignore_creates_file_and_extends_block() {
+        let temp = tempdir().unwrap();
+        let repo = temp.path();
+
+        let claude = repo.join(".claude").join("CLAUDE.md").to_string_lossy().to_string();
+        update_gitignore(repo, &[claude]).unwrap();
+
+        let cursor = repo.join(".cursor").join("rules.md").to_string_lossy().to_string();
+        update_gitignore(repo, &[cursor]).unwrap();
+
+        let content = fs::read_to_string(repo.join(".gitignore")).unwrap();
+        assert_eq!(
+            content,
+            "# AgentsToolkit\n/.claude/CLAUDE.md\n/.cursor/rules.md\n# End AgentsToolkit\n"
+        );
+    }
+}
@@ -0,0 +1,108 @@
+//! Cross-process deployment lock
+//!
+//! Every deployer writes the shared `~/.agentsmd/AGENTS.md`, so deployments hold an
+//! OS advisory lock on a lock file for their write-and-link phase to keep batch or
+//! parallel deploys from interleaving. The OS drops the lock when its holder exits,
+//! so a crashed deploy never leaves the lock behind.
+
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{Seek, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::error::{DeploymentError, DeploymentResult};
+
+const LOCK_FILE_NAME: &str = ".deploy.lock";
+const POLL_INTERVAL: Duration = Duration::from_millis(25);
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Held deployment lock; released when dropped
+#[derive(Debug)]
+pub struct DeploymentLock {
+    file: File,
+    path: PathBuf,
+}
+
+impl DeploymentLock {
+    /// Acquire the lock in `agentsmd_home`, waiting up to the default timeout
+    pub fn acquire(agentsmd_home: &Path) -> DeploymentResult<Self> {
+        Self::acquire_with_timeout(agentsmd_home, DEFAULT_TIMEOUT)
+    }
+
+    /// Acquire the lock in `agentsmd_home`, waiting up to `timeout`
+    pub fn acquire_with_timeout(agentsmd_home: &Path, timeout: Duration) -> DeploymentResult<Self> {
+        fs::create_dir_all(agentsmd_home).map_err(|e| {
+            DeploymentError::fs_error(agentsmd_home, format!("Failed to create directory: {}", e))
+        })?;
+
+        let path = agentsmd_home.join(LOCK_FILE_NAME);
+        // The lock file itself is left in place; only the OS lock on it matters
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(|e| DeploymentError::fs_error(&path, format!("Failed to open lock file: {}", e)))?;
+        let started = Instant::now();
+
+        loop {
+            match file.try_lock() {
+                Ok(()) => {
+                    // Holder pid, for diagnosing a deploy that holds the lock too long
+                    let _ = file.set_len(0);
+                    let _ = file.rewind();
+                    let _ = writeln!(file, "{}", std::process::id());
+                    return Ok(Self { file, path });
+                }
+                Err(TryLockError::WouldBlock) => {
+                    if started.elapsed() >= timeout {
+                        return Err(DeploymentError::LockTimeout(path));
+                    }
+                    thread::sleep(POLL_INTERVAL);
+                }
+                Err(TryLockError::Error(e)) => {
+                    return Err(DeploymentError::fs_error(
+                        &path,
+                        format!("Failed to lock file: {}", e),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+impl Drop for DeploymentLock {
+    fn drop(&mut self) {
+        if let Err(e) = self.file.unlock() {
+            log::warn!("Failed to release deployment lock {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_lock_is_exclusive_until_dropped() {
+        let temp = tempdir().unwrap();
+
+        let lock = DeploymentLock::acquire(temp.path()).unwrap();
+        let second = DeploymentLock::acquire_with_timeout(temp.path(), Duration::from_millis(50));
+        assert!(matches!(second, Err(DeploymentError::LockTimeout(_))));
+
+        drop(lock);
+        assert!(DeploymentLock::acquire_with_timeout(temp.path(), Duration::from_millis(50)).is_ok());
+    }
+
+    #[test]
+    fn test_leftover_lock_file_does_not_block() {
+        let temp = tempdir().unwrap();
+        fs::write(temp.path().join(LOCK_FILE_NAME), "12345\n").unwrap();
+
+        let lock = DeploymentLock::acquire_with_timeout(temp.path(), Duration::from_millis(50));
+        assert!(lock.is_ok());
+    }
+}
@@ -60,7 +60,7 @@ pub struct DeploymentLogger {
 impl DeploymentLogger {
     /// Create a new deployment logger
     pub fn new() -> DeploymentResult<Self> {
-        let agentsmd_home = fs_manager::get_agentsmd_home();
+        let agentsmd_home = fs_manager::get_agentsmd_home()?;
         let log_dir = agentsmd_home.join("logs");
         fs::create_dir_all(&log_dir).ok();
 
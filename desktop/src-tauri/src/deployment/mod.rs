@@ -9,30 +9,38 @@ pub mod command_validator;
 pub mod converters;
 pub mod deployer;
 pub mod error;
+pub mod gitignore;
+pub mod lock;
 pub mod logger;
 pub mod project;
 pub mod registry;
 pub mod state;
 pub mod validator;
+pub mod verify;
 
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use crate::budget;
 use crate::fs_manager;
-use crate::ipc;
 use crate::command_registry;
 use crate::out_reference_manager;
-use crate::types::RulePack;
+use crate::types::{AgentsMdOptions, GenerateResult, LoadedPack, RulePack};
+use crate::util;
 use crate::deployment::validator::DeploymentValidator;
+use serde::{Deserialize, Serialize};
 use serde_json;
 
 pub use deployer::{
-    AgentDeployer, AgentStatus, BudgetUsage, DeploymentConfig, DeploymentOutput,
-    PreparedDeployment, TargetLevel, ValidationReport,
+    AgentDeployer, AgentStatus, BudgetUsage, DeploymentConfig, DeploymentOptions, DeploymentOutput,
+    DeploymentPhase, DeploymentProgress, DeploymentStatusInfo, OutReferenceResult, PreparedDeployment, ProgressSink,
+    RestoredFile, RollbackPlan, TargetLevel, ValidationReport,
 };
-pub use error::{DeploymentError, DeploymentResult};
+pub use error::{DeploymentError, DeploymentResult, ErrorCode, IpcError};
 pub use registry::DeployerRegistry;
-pub use state::{BackupManager, DeploymentState, StateManager};
+pub use state::{BackupEntry, BackupManager, DeploymentHistoryPage, DeploymentState, FailedDeployment, StateManager};
 
 /// Main deployment manager that orchestrates all deployment operations
 pub struct DeploymentManager {
@@ -53,11 +61,20 @@ impl DeploymentManager {
         })
     }
 
-    fn merge_with_command_validation(
+    /// Add the checks driven by the config itself to a deployer's validation report
+    ///
+    /// Covers the opt-in installed-agent check and validation of the selected commands.
+    fn merge_with_config_validation(
         &self,
         mut validation: ValidationReport,
         config: &DeploymentConfig,
     ) -> DeploymentResult<ValidationReport> {
+        if config.require_agent_installed {
+            let installed = DeploymentValidator::validate_agent_installed(&config.agent_id)?;
+            validation.errors.extend(installed.errors);
+            validation.valid = validation.valid && installed.valid;
+        }
+
         if config.custom_command_ids.is_empty() {
             return Ok(validation);
         }
@@ -65,6 +82,7 @@ impl DeploymentManager {
         let command_validation = DeploymentValidator::validate_commands_for_agent(
             &config.custom_command_ids,
             &config.agent_id,
+            config.inline_out_references,
         )?;
 
         validation.warnings.extend(command_validation.warnings);
@@ -74,12 +92,124 @@ impl DeploymentManager {
         Ok(validation)
     }
 
+    /// Log a failed deployment phase and keep it in the agent's failure history
+    ///
+    /// A failure to record the attempt is only logged, so it never hides the original error.
+    fn log_failure(
+        &self,
+        agent_id: &str,
+        phase: logger::DeploymentOperation,
+        errors: Vec<String>,
+    ) -> DeploymentResult<()> {
+        let failure = FailedDeployment::new(agent_id.to_string(), phase.clone(), errors.join("; "));
+        if let Err(e) = self.state_manager.record_failure(failure) {
+            log::warn!("Failed to record failed deployment for {}: {}", agent_id, e);
+        }
+        self.logger.log_failure(agent_id, phase, errors, None)
+    }
+
+    /// Error for a failed validation, keeping the numbers when only the budget failed
+    fn validation_error(agent_id: &str, validation: &ValidationReport) -> DeploymentError {
+        let budget = &validation.budget_usage;
+        match budget.max_chars {
+            Some(limit) if validation.only_over_budget() => DeploymentError::CharacterLimitExceeded {
+                current: budget.current_chars,
+                limit,
+                agent_id: agent_id.to_string(),
+                suggestion: validation.suggestion.clone(),
+            },
+            _ => DeploymentError::ValidationFailed(validation.errors.join("; ")),
+        }
+    }
+
+    /// Reject project-level deployments to agents without project-level support
+    fn ensure_target_level_supported(
+        deployer: &dyn AgentDeployer,
+        config: &DeploymentConfig,
+    ) -> DeploymentResult<()> {
+        if config.target_level == TargetLevel::Project && !deployer.supports_project_level() {
+            return Err(DeploymentError::UnsupportedTargetLevel {
+                agent_id: config.agent_id.clone(),
+                level: "project".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Prepare a deployment, inlining out-references into AGENTS.md when requested
+    ///
+    /// Inlining only applies to agents without out-reference support; the appended
+    /// content counts toward the AGENTS.md budget checked during validation.
+    fn prepare_deployment(
+        deployer: &dyn AgentDeployer,
+        config: &DeploymentConfig,
+    ) -> DeploymentResult<PreparedDeployment> {
+        let mut prepared = deployer.prepare(config)?;
+
+        let supports_out_references = deployer
+            .agent_definition()
+            .character_limits
+            .supports_out_references;
+        if config.inline_out_references && !supports_out_references {
+            let resolved = collect_out_references_for_selection(
+                &config.custom_command_ids,
+                &config.pack_ids,
+            )?;
+            if !resolved.is_empty() {
+                let (section, warnings) = render_inlined_out_references(&resolved);
+                for warning in &warnings {
+                    log::warn!("{}", warning);
+                }
+                prepared.append_to_agents_md(&section);
+                prepared.warnings.extend(warnings);
+            }
+        }
+
+        Ok(prepared)
+    }
+
+    /// Register (or replace) the deployer for an agent
+    pub fn register_deployer(&mut self, deployer: Arc<dyn AgentDeployer>) {
+        self.registry.register(deployer);
+    }
+
     /// Deploy to a specific agent
     pub fn deploy(&self, config: &DeploymentConfig) -> DeploymentResult<DeploymentOutput> {
+        self.deploy_with_progress(config, |_| {})
+    }
+
+    /// Deploy to a specific agent, reporting each phase to `on_progress`
+    ///
+    /// The AGENTS.md, command and out-reference events come from the deployer as it
+    /// writes each file.
+    pub fn deploy_with_progress<F>(
+        &self,
+        config: &DeploymentConfig,
+        mut on_progress: F,
+    ) -> DeploymentResult<DeploymentOutput>
+    where
+        F: FnMut(DeploymentProgress),
+    {
+        let mut step = 0;
+        let mut total = 0;
+        let mut report = |phase: DeploymentPhase, total: usize, item: Option<String>| {
+            step += 1;
+            on_progress(DeploymentProgress {
+                agent_id: config.agent_id.clone(),
+                phase,
+                current: step,
+                total: total.max(step),
+                item,
+            });
+        };
+
+        report(DeploymentPhase::Prepare, total, None);
+
         let deployer = self
             .registry
-            .get_deployer(&config.agent_id)
+            .get_deployer_for(config)
             .ok_or_else(|| DeploymentError::agent_not_found(&config.agent_id))?;
+        Self::ensure_target_level_supported(deployer.as_ref(), config)?;
 
         // Log the start of deployment
         self.logger.log_success(
@@ -89,56 +219,66 @@ impl DeploymentManager {
         )?;
 
         // Prepare deployment
-        let prepared = match deployer.prepare(config) {
+        let mut prepared = match Self::prepare_deployment(deployer.as_ref(), config) {
             Ok(p) => p,
             Err(e) => {
-                self.logger.log_failure(
+                self.log_failure(
                     &config.agent_id,
                     logger::DeploymentOperation::Prepare,
                     vec![e.to_string()],
-                    None,
                 )?;
                 return Err(e);
             }
         };
 
+        // Leave hand-edited command files alone unless the caller forces an overwrite
+        let previous = self.state_manager.get_agent_state(&config.agent_id)?;
+        let (preserved_hashes, edit_warnings) = match &previous {
+            Some(previous) if !config.force_overwrite => preserve_edited_commands(&mut prepared, previous),
+            _ => (BTreeMap::new(), Vec::new()),
+        };
+
+        // Files the last deployment placed can be replaced even when they're copies
+        if let Some(previous) = &previous {
+            prepared.previous_files = previous.files_created.iter().cloned().collect();
+        }
+
+        // prepare, validate, backup, AGENTS.md, each command and out-reference, done
+        total = 5 + prepared.commands.len() + prepared.out_references.len();
+        report(DeploymentPhase::Validate, total, None);
+
         // Validate deployment
         let validation = match deployer.validate(&prepared) {
             Ok(v) => v,
             Err(e) => {
-                self.logger.log_failure(
+                self.log_failure(
                     &config.agent_id,
                     logger::DeploymentOperation::Validate,
                     vec![e.to_string()],
-                    None,
                 )?;
                 return Err(e);
             }
         };
 
-        let validation = match self.merge_with_command_validation(validation, config) {
+        let validation = match self.merge_with_config_validation(validation, config) {
             Ok(v) => v,
             Err(e) => {
-                self.logger.log_failure(
+                self.log_failure(
                     &config.agent_id,
                     logger::DeploymentOperation::Validate,
                     vec![e.to_string()],
-                    None,
                 )?;
                 return Err(e);
             }
         };
 
         if !validation.valid {
-            self.logger.log_failure(
+            self.log_failure(
                 &config.agent_id,
                 logger::DeploymentOperation::Validate,
                 validation.errors.clone(),
-                None,
             )?;
-            return Err(DeploymentError::ValidationFailed(
-                validation.errors.join("; "),
-            ));
+            return Err(Self::validation_error(&config.agent_id, &validation));
         }
 
         self.logger.log_success(
@@ -151,6 +291,11 @@ impl DeploymentManager {
             )),
         )?;
 
+        report(DeploymentPhase::Backup, total, None);
+
+        // Hold the shared AGENTS.md lock from backup through write-and-link
+        let deploy_lock = lock::DeploymentLock::acquire(&fs_manager::get_agentsmd_home()?)?;
+
         // Create backup of existing files
         let files_to_backup: Vec<PathBuf> = prepared
             .target_paths
@@ -162,35 +307,91 @@ impl DeploymentManager {
         let backup_path = self.backup_manager.create_backup(&config.agent_id, &files_to_backup)?;
 
         // Execute deployment
-        let result = match deployer.deploy(prepared.clone(), config) {
+        let mut result = match deployer.deploy(prepared.clone(), config, &mut |phase, item| {
+            report(phase, total, item)
+        }) {
             Ok(r) => r,
             Err(e) => {
                 // Attempt rollback on failure
                 if let Some(ref backup) = backup_path {
                     let _ = self.backup_manager.restore_backup(backup, &files_to_backup);
                 }
-                self.logger.log_failure(
+                self.log_failure(
                     &config.agent_id,
                     logger::DeploymentOperation::Deploy,
                     vec![e.to_string()],
-                    None,
                 )?;
                 return Err(e);
             }
         };
 
+        // Read back what was written before anything else can touch it
+        if config.verify_after_deploy {
+            let mismatches = verify::verify_deployed_files(&prepared, &result.deployed_files);
+            if !mismatches.is_empty() {
+                let created = DeploymentState::new(
+                    config.agent_id.clone(),
+                    result.method.clone(),
+                    config.target_level.clone(),
+                )
+                .with_files(result.deployed_files.clone())
+                .with_dirs(result.created_dirs.clone());
+                let _ = deployer.rollback(&created);
+                remove_created_dirs(&created.dirs_created);
+                if let Some(ref backup) = backup_path {
+                    let _ = self.backup_manager.restore_backup(backup, &files_to_backup);
+                }
+                self.log_failure(
+                    &config.agent_id,
+                    logger::DeploymentOperation::Deploy,
+                    mismatches.clone(),
+                )?;
+                return Err(DeploymentError::VerificationFailed(mismatches.join("; ")));
+            }
+        }
+
+        drop(deploy_lock);
+
+        result.warnings.extend(prepared.warnings.iter().cloned());
+        result.warnings.extend(edit_warnings);
+
+        // Keep project-level deployed files out of git when requested
+        if config.update_gitignore && config.target_level == TargetLevel::Project {
+            let project_root = config
+                .project_path
+                .as_ref()
+                .map(PathBuf::from)
+                .or_else(project::ProjectDetector::detect_project_root);
+            match project_root {
+                Some(root) => {
+                    if let Err(e) = gitignore::update_gitignore(&root, &result.deployed_files) {
+                        result.warnings.push(format!("Could not update .gitignore: {}", e));
+                    }
+                }
+                None => result
+                    .warnings
+                    .push("Could not update .gitignore: project root not found".to_string()),
+            }
+        }
+
         // Record deployment state
+        let preserved_files: Vec<String> = preserved_hashes.keys().cloned().collect();
+        let mut file_hashes = deployed_command_hashes(&prepared, &result.deployed_files);
+        file_hashes.extend(preserved_hashes);
         let state = DeploymentState::new(
             config.agent_id.clone(),
             result.method.clone(),
-            match config.target_level {
-                TargetLevel::User => "user".to_string(),
-                TargetLevel::Project => "project".to_string(),
-            },
+            config.target_level.clone(),
         )
         .with_packs(config.pack_ids.clone())
         .with_commands(config.custom_command_ids.clone())
-        .with_files(result.deployed_files.clone());
+        .with_files(result.deployed_files.clone())
+        .with_preserved(preserved_files)
+        .with_modified(result.modified_files.clone())
+        .with_dirs(result.created_dirs.clone())
+        .with_file_hashes(file_hashes)
+        .with_content_hash(util::content_hash(prepared.agents_md_content.as_bytes()))
+        .with_options(config.options());
 
         let state = if let Some(backup) = backup_path {
             state.with_backup(backup.to_string_lossy().to_string())
@@ -216,17 +417,95 @@ impl DeploymentManager {
             )),
         )?;
 
+        report(DeploymentPhase::Done, total, None);
+
         Ok(result)
     }
 
     /// Rollback the last deployment for an agent
     pub fn rollback(&self, agent_id: &str, timestamp: Option<String>) -> DeploymentResult<()> {
+        let (deployer, state) = self.rollback_deployer(agent_id, timestamp)?;
+
+        // Perform rollback
+        deployer.rollback(&state)?;
+        remove_created_dirs(&state.dirs_created);
+
+        // If there's a backup, restore it
+        if let Some(backup_path) = &state.backup_path {
+            let backup = PathBuf::from(backup_path);
+            self.backup_manager.restore_backup(&backup, &state.restorable_files())?;
+        }
+
+        // Remove the deployment from state
+        self.state_manager.remove_latest_deployment(agent_id)?;
+
+        self.logger.log_success(
+            agent_id,
+            logger::DeploymentOperation::Rollback,
+            Some("Rollback completed successfully".to_string()),
+        )?;
+
+        Ok(())
+    }
+
+    /// List what `rollback` would remove and restore, without changing anything
+    pub fn preview_rollback(&self, agent_id: &str, timestamp: Option<String>) -> DeploymentResult<RollbackPlan> {
+        let (_, state) = self.rollback_deployer(agent_id, timestamp)?;
+
+        let files_to_remove = state
+            .files_created
+            .iter()
+            .filter(|f| fs::symlink_metadata(f).is_ok())
+            .cloned()
+            .collect();
+
+        let files_to_restore = match &state.backup_path {
+            Some(backup_path) => {
+                self.backup_manager
+                    .plan_restore(Path::new(backup_path), &state.restorable_files())?
+                    .into_iter()
+                    .map(|(backup, original)| RestoredFile {
+                        path: original.to_string_lossy().to_string(),
+                        backup_path: backup.to_string_lossy().to_string(),
+                    })
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+
+        Ok(RollbackPlan {
+            agent_id: agent_id.to_string(),
+            timestamp: state.timestamp,
+            files_to_remove,
+            dirs_to_remove: state.dirs_created.clone(),
+            files_to_restore,
+            backup_path: state.backup_path.clone(),
+        })
+    }
+
+    /// The deployment a rollback applies to, with the deployer that made it
+    ///
+    /// The deployer is chosen from the options recorded with the deployment, so a
+    /// deployment made as skills is rolled back by the skills deployer.
+    fn rollback_deployer(
+        &self,
+        agent_id: &str,
+        timestamp: Option<String>,
+    ) -> DeploymentResult<(Arc<dyn AgentDeployer>, DeploymentState)> {
+        if !self.registry.has_deployer(agent_id) {
+            return Err(DeploymentError::agent_not_found(agent_id));
+        }
+
+        let state = self.rollback_target(agent_id, timestamp)?;
         let deployer = self
             .registry
-            .get_deployer(agent_id)
+            .get_deployer_for(&DeploymentConfig::from_state(&state))
             .ok_or_else(|| DeploymentError::agent_not_found(agent_id))?;
+        Ok((deployer, state))
+    }
 
-        // Get the deployment state to rollback
+    /// The recorded deployment a rollback applies to: the one at `timestamp`, or the latest
+    fn rollback_target(&self, agent_id: &str, timestamp: Option<String>) -> DeploymentResult<DeploymentState> {
         let state = match timestamp {
             Some(ts) => {
                 // Parse timestamp and find specific deployment
@@ -255,31 +534,70 @@ impl DeploymentManager {
             }
         };
 
-        // Perform rollback
-        deployer.rollback(&state)?;
+        Ok(state)
+    }
 
-        // If there's a backup, restore it
-        if let Some(backup_path) = &state.backup_path {
-            let backup = PathBuf::from(backup_path);
-            let original_paths: Vec<PathBuf> = state
-                .files_created
-                .iter()
-                .map(|f| PathBuf::from(f))
-                .collect();
+    /// Move an agent's latest deployment to another target level
+    ///
+    /// Redeploys the recorded packs and commands at `new_level`, then removes the old
+    /// level's files that the new deployment didn't reuse (such as the shared AGENTS.md).
+    pub fn retarget(
+        &self,
+        agent_id: &str,
+        new_level: TargetLevel,
+        project_path: Option<String>,
+    ) -> DeploymentResult<DeploymentOutput> {
+        let previous = self.latest_state(agent_id)?;
+
+        let config = DeploymentConfig {
+            target_level: new_level,
+            project_path,
+            ..DeploymentConfig::from_state(&previous)
+        };
+        let deployer = self
+            .registry
+            .get_deployer_for(&config)
+            .ok_or_else(|| DeploymentError::agent_not_found(agent_id))?;
 
-            self.backup_manager.restore_backup(&backup, &original_paths)?;
-        }
+        // Deploy first so a failure leaves the old level in place
+        let result = self.deploy(&config)?;
 
-        // Remove the deployment from state
-        self.state_manager.remove_latest_deployment(agent_id)?;
+        let stale_files: Vec<String> = previous
+            .files_created
+            .iter()
+            .filter(|f| !result.deployed_files.contains(f))
+            .cloned()
+            .collect();
+        let stale = previous.clone().with_files(stale_files);
+        deployer.rollback(&stale)?;
+        remove_created_dirs(&stale.dirs_created);
 
         self.logger.log_success(
             agent_id,
-            logger::DeploymentOperation::Rollback,
-            Some("Rollback completed successfully".to_string()),
+            logger::DeploymentOperation::Deploy,
+            Some(format!(
+                "Retargeted from {}-level, removed {} files",
+                previous.target_level.as_str(),
+                stale.files_created.len()
+            )),
         )?;
 
-        Ok(())
+        Ok(result)
+    }
+
+    /// Redeploy an agent's latest recorded packs and commands to the same target
+    ///
+    /// Picks up edits made to packs or commands since that deployment.
+    pub fn redeploy_current(&self, agent_id: &str) -> DeploymentResult<DeploymentOutput> {
+        let previous = self.latest_state(agent_id)?;
+        self.deploy(&DeploymentConfig::from_state(&previous))
+    }
+
+    /// Latest recorded deployment for an agent, or an error if it was never deployed
+    fn latest_state(&self, agent_id: &str) -> DeploymentResult<DeploymentState> {
+        self.state_manager.get_agent_state(agent_id)?.ok_or_else(|| {
+            DeploymentError::StateError(format!("No deployment found for agent {}", agent_id))
+        })
     }
 
     /// Get deployment status for an agent
@@ -292,31 +610,72 @@ impl DeploymentManager {
         deployer.get_status()
     }
 
+    /// Get deployment status for an agent, including whether its config directory is a symlink
+    pub fn get_status_info(&self, agent_id: &str) -> DeploymentResult<DeploymentStatusInfo> {
+        let status = self.get_status(agent_id)?;
+        Ok(DeploymentStatusInfo {
+            status,
+            agent_dir: fs_manager::inspect_agent_dir(agent_id).ok(),
+        })
+    }
+
     /// Get deployment history for an agent
     pub fn get_history(&self, agent_id: &str) -> DeploymentResult<Vec<DeploymentState>> {
         self.state_manager.get_agent_history(agent_id)
     }
 
+    /// Collapse repeated identical deployments in an agent's history
+    pub fn compact_history(&self, agent_id: &str) -> DeploymentResult<usize> {
+        self.state_manager.compact_history(agent_id)
+    }
+
+    /// Get the most recent deployments across all agents, newest first
+    pub fn recent_deployments(&self, limit: usize) -> DeploymentResult<Vec<DeploymentState>> {
+        self.state_manager.recent_deployments(limit)
+    }
+
+    /// Agents with at least one recorded deployment
+    pub fn deployed_agents(&self) -> DeploymentResult<Vec<String>> {
+        self.state_manager.deployed_agents()
+    }
+
+    /// Get failed deployment attempts for an agent
+    pub fn get_failures(&self, agent_id: &str) -> DeploymentResult<Vec<FailedDeployment>> {
+        self.state_manager.get_agent_failures(agent_id)
+    }
+
+    /// Get a page of deployment history for an agent, newest first
+    pub fn get_history_page(
+        &self,
+        agent_id: &str,
+        offset: usize,
+        limit: usize,
+    ) -> DeploymentResult<DeploymentHistoryPage> {
+        self.state_manager.get_agent_history_page(agent_id, offset, limit)
+    }
+
     /// Validate a deployment without executing it
     pub fn validate_deployment(&self, config: &DeploymentConfig) -> DeploymentResult<ValidationReport> {
         let deployer = self
             .registry
-            .get_deployer(&config.agent_id)
+            .get_deployer_for(config)
             .ok_or_else(|| DeploymentError::agent_not_found(&config.agent_id))?;
+        Self::ensure_target_level_supported(deployer.as_ref(), config)?;
 
-        let prepared = deployer.prepare(config)?;
+        let prepared = Self::prepare_deployment(deployer.as_ref(), config)?;
         let validation = deployer.validate(&prepared)?;
-        self.merge_with_command_validation(validation, config)
+        self.merge_with_config_validation(validation, config)
     }
 
     /// Preview a deployment without executing it
     pub fn preview_deployment(&self, config: &DeploymentConfig) -> DeploymentResult<PreparedDeployment> {
         let deployer = self
             .registry
-            .get_deployer(&config.agent_id)
+            .get_deployer_for(config)
             .ok_or_else(|| DeploymentError::agent_not_found(&config.agent_id))?;
+        Self::ensure_target_level_supported(deployer.as_ref(), config)?;
 
-        let prepared = deployer.prepare(config)?;
+        let prepared = Self::prepare_deployment(deployer.as_ref(), config)?;
 
         // Also validate to include any warnings
         let _ = deployer.validate(&prepared)?;
@@ -324,23 +683,373 @@ impl DeploymentManager {
         Ok(prepared)
     }
 
+    /// Build the exact content each target path would receive, without writing anything
+    ///
+    /// Covers the shared AGENTS.md, every command, out-reference, and config file the
+    /// deployer prepared. Targets that only link to one of those (such as CLAUDE.md)
+    /// and directory targets are left out.
+    pub fn materialize(&self, config: &DeploymentConfig) -> DeploymentResult<HashMap<PathBuf, String>> {
+        let prepared = self.preview_deployment(config)?;
+        let agents_md = fs_manager::get_agentsmd_home()?.join("AGENTS.md");
+
+        let mut materialized = HashMap::new();
+        for target in &prepared.target_paths {
+            if let Some(content) = materialized_content(&prepared, target, &agents_md) {
+                materialized.insert(target.clone(), content.to_string());
+            }
+        }
+
+        Ok(materialized)
+    }
+
     /// Get all available agent IDs
     pub fn available_agents(&self) -> Vec<String> {
         self.registry.agent_ids()
     }
+
+    /// Check every registered agent against a deployment's shape
+    ///
+    /// `config.agent_id` is ignored. Agents are excluded when they can't deploy at the
+    /// target level, can't take the out-references the selection pulls in, or reject
+    /// one of the selected commands.
+    pub fn deployable_agents_for(&self, config: &DeploymentConfig) -> DeploymentResult<Vec<DeployableAgent>> {
+        let needs_out_references =
+            !collect_out_references_for_selection(&config.custom_command_ids, &config.pack_ids)?.is_empty();
+        let commands = config
+            .custom_command_ids
+            .iter()
+            .map(|id| command_registry::get_command_by_id(id).map_err(DeploymentError::ConfigurationError))
+            .collect::<DeploymentResult<Vec<_>>>()?;
+
+        let mut agent_ids = self.registry.agent_ids();
+        agent_ids.sort();
+
+        let mut agents = Vec::new();
+        for agent_id in agent_ids {
+            let Some(deployer) = self.registry.get_deployer(&agent_id) else {
+                continue;
+            };
+            let mut definition = deployer.agent_definition().clone();
+            if config.inline_out_references {
+                definition.character_limits.supports_out_references = true;
+            }
+
+            let reason = if config.target_level == TargetLevel::Project && !deployer.supports_project_level() {
+                Some("no project-level support".to_string())
+            } else if config.target_level == TargetLevel::User && !deployer.supports_user_level() {
+                Some("no user-level support".to_string())
+            } else if needs_out_references && !definition.character_limits.supports_out_references {
+                Some("no out-reference support".to_string())
+            } else {
+                command_validator::validate_command_set(&commands, &definition)
+                    .errors
+                    .into_iter()
+                    .next()
+            };
+
+            agents.push(DeployableAgent {
+                agent_id,
+                deployable: reason.is_none(),
+                reason,
+            });
+        }
+
+        Ok(agents)
+    }
+}
+
+/// Whether an agent can take a given deployment, and why not when it can't
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DeployableAgent {
+    pub agent_id: String,
+    pub deployable: bool,
+    pub reason: Option<String>,
+}
+
+/// Prepared command whose relative path a deployed file ends with, preferring the longest
+fn command_for_file<'a>(prepared: &'a PreparedDeployment, file: &Path) -> Option<(&'a String, &'a String)> {
+    prepared
+        .commands
+        .iter()
+        .filter(|(rel_path, _)| file.ends_with(rel_path.as_str()))
+        .max_by_key(|(rel_path, _)| Path::new(rel_path.as_str()).components().count())
+}
+
+/// Hash the content of every deployed command file, keyed by its path
+fn deployed_command_hashes(prepared: &PreparedDeployment, deployed_files: &[String]) -> BTreeMap<String, String> {
+    deployed_files
+        .iter()
+        .filter_map(|file| {
+            command_for_file(prepared, Path::new(file))
+                .map(|(_, content)| (file.clone(), util::content_hash(content.as_bytes())))
+        })
+        .collect()
+}
+
+/// Drop prepared commands whose deployed file was edited since the last deployment
+///
+/// A file counts as edited when its content no longer matches the hash recorded in
+/// `previous`. Returns the recorded hashes of the kept files, so later deployments go
+/// on preserving them, along with one warning per file.
+fn preserve_edited_commands(
+    prepared: &mut PreparedDeployment,
+    previous: &DeploymentState,
+) -> (BTreeMap<String, String>, Vec<String>) {
+    let mut preserved = BTreeMap::new();
+    let mut warnings = Vec::new();
+
+    for (file, hash) in &previous.file_hashes {
+        let current = match fs::read(file) {
+            Ok(current) => current,
+            Err(_) => continue,
+        };
+        if util::content_hash(&current) == *hash {
+            continue;
+        }
+
+        let name = match command_for_file(prepared, Path::new(file)) {
+            Some((name, _)) => name.clone(),
+            None => continue,
+        };
+        prepared.commands.remove(&name);
+        preserved.insert(file.clone(), hash.clone());
+        warnings.push(format!(
+            "{} was edited since the last deployment and was not overwritten; use force overwrite to replace it",
+            file
+        ));
+    }
+
+    (preserved, warnings)
+}
+
+/// Content prepared for a target path, matched by the longest relative path it ends with
+fn materialized_content<'a>(
+    prepared: &'a PreparedDeployment,
+    target: &Path,
+    agents_md: &Path,
+) -> Option<&'a str> {
+    if target == agents_md {
+        return Some(&prepared.agents_md_content);
+    }
+
+    prepared
+        .commands
+        .iter()
+        .chain(&prepared.out_references)
+        .chain(&prepared.config_files)
+        .filter(|(rel_path, _)| target.ends_with(rel_path.as_str()))
+        .max_by_key(|(rel_path, _)| Path::new(rel_path.as_str()).components().count())
+        .map(|(_, content)| content.as_str())
+}
+
+/// Warning returned by deployers when a commands-only deployment skipped AGENTS.md
+pub const COMMANDS_ONLY_WARNING: &str =
+    "No rule packs selected: AGENTS.md was not updated, only commands were deployed";
+
+/// Generate AGENTS.md for a pack selection
+///
+/// Failures are reported in the result rather than returned, so the UI can show them
+/// alongside an empty budget.
+pub fn generate_agents_md(pack_ids: &[String], options: AgentsMdOptions) -> GenerateResult {
+    let AgentsMdOptions {
+        include_metadata,
+        inline_content,
+        rewrite_links,
+        include_toc,
+        header,
+    } = options;
+    let include_metadata = include_metadata.unwrap_or(true);
+    // Imports have no sections in the file itself, so a TOC only applies to inlined packs
+    let include_toc = include_toc && inline_content;
+
+    let result = (|| -> Result<GenerateResult, String> {
+        let mut lines: Vec<String> = Vec::new();
+        let reference_name = Path::new(&header.reference_link)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| header.reference_link.clone());
+        lines.push(format!("# {}", header.title));
+        lines.push("".into());
+        lines.push("Non-negotiable rules for all AI agents. Violations constitute workflow failures.".into());
+        lines.push("".into());
+        lines.push(format!("**Version:** {} (Modular Rule Packs)  ", header.version));
+        lines.push(format!(
+            "**Reference:** Command examples at [{}]({}).",
+            reference_name, header.reference_link
+        ));
+        lines.push("".into());
+        lines.push("---".into());
+        lines.push("".into());
+        lines.push("## Active Rule Packs".into());
+        lines.push("".into());
+
+        let mut packs: Vec<LoadedPack> = Vec::new();
+        for id in pack_ids.iter() {
+            let pack = fs_manager::load_pack_full(id)?;
+            packs.push(pack);
+        }
+
+        for pack in packs.iter() {
+            lines.push(format!(
+                "- **{}** (`rule-packs/{}/`) — {}",
+                pack.name, pack.id, pack.description
+            ));
+        }
+
+        lines.push("".into());
+        lines.push("---".into());
+        lines.push("".into());
+
+        if include_toc {
+            // Entries are filled in once the document is final, see `insert_toc`
+            lines.push("## Table of Contents".into());
+            lines.push("".into());
+        }
+
+        if inline_content {
+            for pack in packs.iter() {
+                lines.push(format!("<!-- Pack: {} v{} -->", pack.id, pack.version));
+                lines.push(pack.content.clone());
+                lines.push("".into());
+            }
+        } else {
+            lines.push("<!-- BEGIN PACK IMPORTS -->".into());
+            lines.push("".into());
+            for pack in packs.iter() {
+                for file in pack.files.iter() {
+                    lines.push(format!("@rule-packs/{}/{}", pack.id, file));
+                }
+                lines.push("".into());
+            }
+            lines.push("<!-- END PACK IMPORTS -->".into());
+            lines.push("".into());
+        }
+
+        lines.push("---".into());
+        lines.push("".into());
+
+        let mut budget = budget::calculate(pack_ids, None)?;
+        if include_metadata {
+            lines.push("## Configuration".into());
+            lines.push("".into());
+            lines.push("**Character Budget:**".into());
+            for item in budget.pack_breakdown.iter() {
+                if let Some(pack) = packs.iter().find(|p| p.id == item.pack_id) {
+                    lines.push(format!(
+                        "- {}: ~{} words (~{} chars)",
+                        pack.name, item.words, item.chars
+                    ));
+                } else {
+                    lines.push(format!(
+                        "- {}: ~{} words (~{} chars)",
+                        item.pack_id, item.words, item.chars
+                    ));
+                }
+            }
+            let total_words: u64 = budget.pack_breakdown.iter().map(|p| p.words).sum();
+            lines.push(format!(
+                "- **Total:** ~{} words (~{} chars)",
+                total_words, budget.total_chars
+            ));
+        }
+
+        let mut content = lines.join("\n");
+        if rewrite_links {
+            // Links relative to ~/.agentsmd/ dangle once AGENTS.md is linked into an agent's directory
+            let agentsmd_home = fs_manager::get_agentsmd_home().map_err(|e| e.to_string())?;
+            content = converters::rewrite_relative_links(&content, &agentsmd_home);
+        }
+
+        // Report the budget of the file as emitted, not of its raw parts
+        let mut content = converters::normalize(&content);
+        if include_toc {
+            content = insert_toc(&content, &packs);
+        }
+        budget.total_chars = budget::char_count(&content);
+
+        Ok(GenerateResult {
+            success: true,
+            content,
+            budget,
+            error: None,
+        })
+    })();
+
+    result.unwrap_or_else(|err| GenerateResult {
+        success: false,
+        content: String::new(),
+        budget: budget::empty_budget_info(),
+        error: Some(err),
+    })
+}
+
+/// Fill in the table of contents of a generated AGENTS.md with inline packs
+///
+/// Anchors come from a single slugger run over every heading of the finished document,
+/// so a pack heading repeating an earlier one links to its numbered anchor. Each pack
+/// links to the first heading after its `<!-- Pack: -->` marker; a pack without
+/// headings is listed unlinked.
+fn insert_toc(content: &str, packs: &[LoadedPack]) -> String {
+    fn heading_texts(entries: &[converters::OutlineEntry], texts: &mut Vec<String>) {
+        for entry in entries {
+            texts.push(entry.text.clone());
+            heading_texts(&entry.children, texts);
+        }
+    }
+    let heading_count = |text: &str| {
+        let mut texts = Vec::new();
+        heading_texts(&converters::outline(text), &mut texts);
+        texts.len()
+    };
+
+    let mut headings = Vec::new();
+    heading_texts(&converters::outline(content), &mut headings);
+    let mut slugger = util::HeadingSlugger::new();
+    let slugs: Vec<String> = headings.iter().map(|heading| slugger.slug(heading)).collect();
+
+    let mut entries = String::new();
+    let mut search_from = 0;
+    for pack in packs {
+        let marker = format!("<!-- Pack: {} v{} -->", pack.id, pack.version);
+        let Some(found) = content[search_from..].find(&marker) else {
+            continue;
+        };
+        let marker_at = search_from + found;
+        search_from = marker_at + marker.len();
+
+        let first = heading_count(&content[..marker_at]);
+        if heading_count(&pack.content) > 0 && first < headings.len() {
+            entries.push_str(&format!("- [{}](#{})\n", headings[first], slugs[first]));
+        } else {
+            entries.push_str(&format!("- {}\n", pack.name));
+        }
+    }
+
+    let toc_heading = "## Table of Contents\n\n";
+    match content.find(toc_heading) {
+        Some(at) => {
+            let insert_at = at + toc_heading.len();
+            format!("{}{}\n{}", &content[..insert_at], entries, &content[insert_at..])
+        }
+        None => content.to_string(),
+    }
 }
 
 /// Helper function to generate AGENTS.md content from pack IDs
+///
+/// This is the content deployers place, so relative links are rewritten to resolve from
+/// the agent directories AGENTS.md is linked into.
 pub fn generate_agents_md_content(
     pack_ids: &[String],
     inline_content: bool,
 ) -> DeploymentResult<String> {
-    let result = ipc::generate_agents_md(
-        pack_ids.to_vec(),
-        Some(true),  // include_metadata
-        Some(inline_content),
-    )
-    .map_err(|e| DeploymentError::ConfigurationError(e))?;
+    let options = AgentsMdOptions {
+        inline_content,
+        rewrite_links: true,
+        ..Default::default()
+    };
+    let result = generate_agents_md(pack_ids, options);
 
     if !result.success {
         return Err(DeploymentError::ConfigurationError(
@@ -360,10 +1069,24 @@ pub struct ResolvedOutReference {
 }
 
 /// Collect out-references required by the selected commands and packs
+///
+/// A path that matches more than one reference resolves to the first of them;
+/// `preview_out_references` lists the others so the ambiguity can be fixed.
 pub fn collect_out_references_for_selection(
     command_ids: &[String],
     pack_ids: &[String],
 ) -> DeploymentResult<Vec<ResolvedOutReference>> {
+    Ok(resolve_selection(command_ids, pack_ids)?
+        .into_iter()
+        .map(|(resolved, _)| resolved)
+        .collect())
+}
+
+/// Resolve a selection's out-references, each with the other references its path matched
+fn resolve_selection(
+    command_ids: &[String],
+    pack_ids: &[String],
+) -> DeploymentResult<Vec<(ResolvedOutReference, Vec<String>)>> {
     let mut requested_paths: Vec<String> = Vec::new();
 
     // Commands
@@ -395,32 +1118,306 @@ pub fn collect_out_references_for_selection(
 
     let available_refs = out_reference_manager::list_out_references()
         .map_err(DeploymentError::ConfigurationError)?;
-    let base_dir = out_reference_manager::get_out_references_dir();
-    let mut resolved: Vec<ResolvedOutReference> = Vec::new();
+    let base_dir = out_reference_manager::get_out_references_dir()
+        .map_err(DeploymentError::ConfigurationError)?;
+    let mut resolved: Vec<(ResolvedOutReference, Vec<String>)> = Vec::new();
 
     for path in requested_paths {
-        if let Some(meta) = available_refs
-            .iter()
-            .find(|r| path.contains(&r.file_path) || r.file_path.contains(&path))
-        {
-            let content = out_reference_manager::read_out_reference_content(meta.id.clone())
-                .map_err(DeploymentError::ConfigurationError)?;
-            resolved.push(ResolvedOutReference {
-                file_path: meta.file_path.clone(),
-                source_path: base_dir.join(&meta.file_path),
-                content,
-            });
-        } else {
+        // An exact path wins; otherwise the first reference whose path segments match
+        let candidates: Vec<_> = match available_refs.iter().find(|r| r.file_path == path) {
+            Some(exact) => vec![exact],
+            None => available_refs
+                .iter()
+                .filter(|r| out_reference_manager::reference_matches(&r.file_path, &path))
+                .collect(),
+        };
+        let Some((meta, others)) = candidates.split_first() else {
             return Err(DeploymentError::ConfigurationError(format!(
                 "Out-reference not found for path: {}",
                 path
             )));
+        };
+        let others: Vec<String> = others.iter().map(|r| r.file_path.clone()).collect();
+        if !others.is_empty() {
+            log::warn!(
+                "Out-reference path {} is ambiguous; using {} over {}",
+                path,
+                meta.file_path,
+                others.join(", ")
+            );
+        }
+
+        // Different spellings of a path can resolve to the same reference
+        if let Some((_, ambiguous)) = resolved.iter_mut().find(|(r, _)| r.file_path == meta.file_path) {
+            for other in others {
+                if !ambiguous.contains(&other) {
+                    ambiguous.push(other);
+                }
+            }
+            continue;
         }
+        let content = out_reference_manager::read_out_reference_content(meta.id.clone())
+            .map_err(DeploymentError::ConfigurationError)?;
+        resolved.push((
+            ResolvedOutReference {
+                file_path: meta.file_path.clone(),
+                source_path: base_dir.join(&meta.file_path),
+                content,
+            },
+            others,
+        ));
     }
 
     Ok(resolved)
 }
 
+/// An out-reference a selection would deploy, as shown before deploying
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct OutReferencePreview {
+    pub file_path: String,
+    pub source_path: PathBuf,
+    pub char_count: u64,
+    /// Other references the requested path also matched; the deployment uses this one
+    #[serde(default)]
+    pub ambiguous_matches: Vec<String>,
+}
+
+/// List the out-references the selected commands and packs would pull in
+///
+/// Resolves exactly as a deployment would, so missing paths fail the same way and
+/// ambiguous ones are listed with the references they could also mean; nothing is
+/// written.
+pub fn preview_out_references(
+    command_ids: &[String],
+    pack_ids: &[String],
+) -> DeploymentResult<Vec<OutReferencePreview>> {
+    Ok(resolve_selection(command_ids, pack_ids)?
+        .into_iter()
+        .map(|(resolved, ambiguous_matches)| OutReferencePreview {
+            char_count: budget::char_count(&resolved.content),
+            file_path: resolved.file_path,
+            source_path: resolved.source_path,
+            ambiguous_matches,
+        })
+        .collect())
+}
+
+/// Render resolved out-references as a labeled AGENTS.md section
+///
+/// `resolved` comes from `collect_out_references_for_selection`, which already includes
+/// each reference once. Returns the section along with a warning for each group of
+/// references whose links to each other form a cycle.
+pub fn render_inlined_out_references(resolved: &[ResolvedOutReference]) -> (String, Vec<String>) {
+    let mut section = String::from("\n\n---\n\n## Inlined References\n");
+    for reference in resolved {
+        section.push_str(&format!(
+            "\n### {}\n\n{}\n",
+            reference.file_path,
+            reference.content.trim_end()
+        ));
+    }
+
+    let warnings = out_reference_link_cycles(resolved)
+        .into_iter()
+        .map(|cycle| {
+            format!(
+                "Circular out-reference links between {}; each reference is inlined once",
+                cycle.join(", ")
+            )
+        })
+        .collect();
+
+    (section, warnings)
+}
+
+/// Groups of out-references whose links to each other form a cycle
+///
+/// Each strongly connected group is reported once, however many cycles run through it,
+/// with its members in input order.
+fn out_reference_link_cycles(references: &[ResolvedOutReference]) -> Vec<Vec<String>> {
+    let links: Vec<Vec<usize>> = references
+        .iter()
+        .map(|reference| {
+            let targets: Vec<String> = out_reference_manager::markdown_links(&reference.content)
+                .into_iter()
+                .filter_map(|target| target.split('#').next().map(str::to_string))
+                .filter(|target| !target.is_empty())
+                .collect();
+            references
+                .iter()
+                .enumerate()
+                .filter(|(_, other)| {
+                    targets
+                        .iter()
+                        .any(|target| out_reference_manager::reference_matches(&other.file_path, target))
+                })
+                .map(|(idx, _)| idx)
+                .collect()
+        })
+        .collect();
+
+    let mut cycles: Vec<Vec<usize>> = strongly_connected_components(&links)
+        .into_iter()
+        .filter(|component| component.len() > 1 || links[component[0]].contains(&component[0]))
+        .collect();
+    for cycle in &mut cycles {
+        cycle.sort_unstable();
+    }
+    cycles.sort();
+
+    cycles
+        .into_iter()
+        .map(|cycle| cycle.into_iter().map(|idx| references[idx].file_path.clone()).collect())
+        .collect()
+}
+
+/// Strongly connected components of a graph given as adjacency lists (Tarjan's algorithm)
+fn strongly_connected_components(links: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    struct Tarjan<'a> {
+        links: &'a [Vec<usize>],
+        index: Vec<Option<usize>>,
+        low: Vec<usize>,
+        on_stack: Vec<bool>,
+        stack: Vec<usize>,
+        next_index: usize,
+        components: Vec<Vec<usize>>,
+    }
+
+    impl Tarjan<'_> {
+        fn visit(&mut self, node: usize) {
+            self.index[node] = Some(self.next_index);
+            self.low[node] = self.next_index;
+            self.next_index += 1;
+            self.stack.push(node);
+            self.on_stack[node] = true;
+
+            let links = self.links;
+            for &next in &links[node] {
+                match self.index[next] {
+                    None => {
+                        self.visit(next);
+                        self.low[node] = self.low[node].min(self.low[next]);
+                    }
+                    Some(index) if self.on_stack[next] => self.low[node] = self.low[node].min(index),
+                    Some(_) => {}
+                }
+            }
+
+            // `node` is the root of a component: everything above it on the stack belongs to it
+            if self.index[node] == Some(self.low[node]) {
+                let mut component = Vec::new();
+                while let Some(member) = self.stack.pop() {
+                    self.on_stack[member] = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                self.components.push(component);
+            }
+        }
+    }
+
+    let mut tarjan = Tarjan {
+        links,
+        index: vec![None; links.len()],
+        low: vec![0; links.len()],
+        on_stack: vec![false; links.len()],
+        stack: Vec::new(),
+        next_index: 0,
+        components: Vec::new(),
+    };
+    for node in 0..links.len() {
+        if tarjan.index[node].is_none() {
+            tarjan.visit(node);
+        }
+    }
+    tarjan.components
+}
+
+/// Refresh out-references that were deployed to an agent as copies
+///
+/// Symlinked out-references already track their source and are left alone. Returns
+/// the deployed paths that were rewritten.
+pub fn resync_out_references(agent_id: &str) -> DeploymentResult<Vec<String>> {
+    let state = match StateManager::new()?.get_agent_state(agent_id)? {
+        Some(state) => state,
+        None => return Ok(Vec::new()),
+    };
+
+    let references = out_reference_manager::list_out_references()
+        .map_err(DeploymentError::ConfigurationError)?;
+    let base_dir = out_reference_manager::get_out_references_dir()
+        .map_err(DeploymentError::ConfigurationError)?;
+    let mut resynced = Vec::new();
+
+    for file in &state.files_created {
+        let deployed_path = PathBuf::from(file);
+        if deployed_path.is_symlink() || !deployed_path.is_file() {
+            continue;
+        }
+
+        let reference = match references
+            .iter()
+            .find(|r| deployed_path.ends_with(&r.file_path))
+        {
+            Some(reference) => reference,
+            None => continue,
+        };
+
+        let source_path = base_dir.join(&reference.file_path);
+        let source = fs::read(&source_path).map_err(|e| {
+            DeploymentError::fs_error(&source_path, format!("Failed to read out-reference: {}", e))
+        })?;
+        let deployed = fs::read(&deployed_path).map_err(|e| {
+            DeploymentError::fs_error(&deployed_path, format!("Failed to read deployed copy: {}", e))
+        })?;
+
+        if source != deployed {
+            fs::write(&deployed_path, &source).map_err(|e| {
+                DeploymentError::fs_error(&deployed_path, format!("Failed to update deployed copy: {}", e))
+            })?;
+            resynced.push(file.clone());
+        }
+    }
+
+    Ok(resynced)
+}
+
+/// Create the missing directories between `base` and the parent of `path`
+///
+/// Returns the directories that did not exist before, outermost first, so they
+/// can be recorded in the deployment state and removed on rollback.
+pub fn create_parent_dirs(base: &Path, path: &Path) -> DeploymentResult<Vec<PathBuf>> {
+    let parent = match path.parent() {
+        Some(parent) => parent,
+        None => return Ok(Vec::new()),
+    };
+
+    let mut created: Vec<PathBuf> = parent
+        .ancestors()
+        .take_while(|dir| *dir != base && dir.starts_with(base))
+        .filter(|dir| !dir.exists())
+        .map(Path::to_path_buf)
+        .collect();
+    created.reverse();
+
+    fs::create_dir_all(parent).map_err(|e| {
+        DeploymentError::fs_error(parent, format!("Failed to create directory: {}", e))
+    })?;
+    Ok(created)
+}
+
+/// Remove directories recorded as created by a deployment, deepest first
+///
+/// Directories that still hold files (added by the user or another deployment) are kept.
+fn remove_created_dirs(dirs: &[String]) {
+    for dir in dirs.iter().rev() {
+        let _ = fs::remove_dir(dir);
+    }
+}
+
 /// Shared base deployer implementation for common functionality
 pub struct BaseDeployer {
     agent: crate::types::AgentDefinition,
@@ -443,6 +1440,17 @@ impl BaseDeployer {
         self.agent.character_limits.max_chars
     }
 
+    /// Rules filename declared by the agent definition, or `default` when unset
+    pub fn rules_filename(&self, default: &str) -> String {
+        self.agent
+            .rules_filename
+            .as_deref()
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .unwrap_or(default)
+            .to_string()
+    }
+
     /// Generate AGENTS.md content for this agent
     pub fn generate_agents_md(&self, pack_ids: &[String], inline: bool) -> DeploymentResult<String> {
         generate_agents_md_content(pack_ids, inline)
@@ -454,6 +1462,62 @@ impl BaseDeployer {
             .map_err(|e| DeploymentError::ConfigurationError(e.to_string()))
     }
 
+    /// Get every config path the agent declares (expands ~)
+    pub fn get_config_paths(&self) -> DeploymentResult<Vec<PathBuf>> {
+        fs_manager::expand_agent_config_paths(&self.agent)
+            .map_err(|e| DeploymentError::ConfigurationError(e.to_string()))
+    }
+
+    /// Link each prepared out-reference from the agentsmd home into `out_ref_dir`
+    ///
+    /// Fails the deployment on the first reference that can't be placed. Returns the paths
+    /// placed and one result per reference.
+    pub fn deploy_out_references(
+        &self,
+        prepared: &PreparedDeployment,
+        out_ref_dir: &Path,
+        config: &DeploymentConfig,
+        warnings: &mut Vec<String>,
+        progress: &mut ProgressSink<'_>,
+    ) -> DeploymentResult<(Vec<String>, Vec<OutReferenceResult>)> {
+        let source_dir =
+            out_reference_manager::get_out_references_dir().map_err(DeploymentError::ConfigurationError)?;
+        let mut deployed = Vec::new();
+        let mut results = Vec::new();
+
+        for rel_path in prepared.out_references.keys() {
+            let source_path = source_dir.join(rel_path);
+            let dest_path = out_ref_dir.join(rel_path);
+            if let Some(parent) = dest_path.parent() {
+                fs::create_dir_all(parent).map_err(|e| {
+                    DeploymentError::fs_error(parent, format!("Failed to create directory: {}", e))
+                })?;
+            }
+
+            let (method, warning) = crate::symlink::create_link(
+                dest_path.clone(),
+                source_path,
+                config.force_overwrite,
+                config.link_strategy,
+                prepared.deployed_before(&dest_path),
+            )
+            .map_err(|e| {
+                DeploymentError::fs_error(&dest_path, format!("Failed to deploy out-reference {}: {}", rel_path, e))
+            })?;
+
+            deployed.push(dest_path.to_string_lossy().to_string());
+            warnings.extend(warning.clone());
+            results.push(OutReferenceResult {
+                file_path: rel_path.clone(),
+                status: method,
+                warning,
+            });
+            progress(DeploymentPhase::OutReference, Some(rel_path.clone()));
+        }
+
+        Ok((deployed, results))
+    }
+
     /// Check if agent is installed
     pub fn is_installed(&self) -> bool {
         if let Ok(path) = self.get_config_path() {
@@ -3,9 +3,10 @@
 //! Handles detection of project roots and project-level configuration paths.
 
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use super::error::{DeploymentError, DeploymentResult};
+use crate::fs_manager;
 
 /// Detects project roots and provides project-level paths
 pub struct ProjectDetector;
@@ -13,45 +14,21 @@ pub struct ProjectDetector;
 impl ProjectDetector {
     /// Detect the project root by walking up from the current directory
     /// 
-    /// Looks for common project indicators like .git, package.json, Cargo.toml, etc.
+    /// Looks for project markers like .git, package.json, Cargo.toml, etc.
     pub fn detect_project_root() -> Option<PathBuf> {
         let current_dir = env::current_dir().ok()?;
         Self::detect_project_root_from(&current_dir)
     }
 
-    /// Detect project root from a specific starting directory
-    pub fn detect_project_root_from(start_dir: &PathBuf) -> Option<PathBuf> {
-        let mut current = start_dir.clone();
-
-        loop {
-            // Check for .git directory (most common indicator)
-            if current.join(".git").exists() {
-                return Some(current);
-            }
-
-            // Check for other project indicators
-            if current.join("package.json").exists()
-                || current.join("Cargo.toml").exists()
-                || current.join("pyproject.toml").exists()
-                || current.join("go.mod").exists()
-                || current.join(".agentsmd").exists()
-            {
-                return Some(current);
-            }
-
-            // Move up to parent directory
-            if let Some(parent) = current.parent() {
-                if parent == current {
-                    // Reached filesystem root
-                    break;
-                }
-                current = parent.to_path_buf();
-            } else {
-                break;
-            }
-        }
-
-        None
+    /// Detect the project root by walking up from `start`
+    ///
+    /// The nearest directory containing any of the configured project markers wins.
+    pub fn detect_project_root_from(start: &Path) -> Option<PathBuf> {
+        let markers = Self::project_markers(Self::root_markers());
+        start
+            .ancestors()
+            .find(|dir| Self::has_marker(dir, &markers))
+            .map(Path::to_path_buf)
     }
 
     /// Get the project-level config path for a specific agent
@@ -85,7 +62,12 @@ impl ProjectDetector {
         )
     }
 
-    /// Get all project indicators (files/directories that indicate a project root)
+    /// Default markers walked up to when detecting the project root
+    pub fn root_markers() -> &'static [&'static str] {
+        &[".git", "package.json", "Cargo.toml", "pyproject.toml", "go.mod", ".agentsmd"]
+    }
+
+    /// Default project indicators (files/directories that indicate a project root)
     pub fn project_indicators() -> &'static [&'static str] {
         &[
             ".git",
@@ -103,15 +85,30 @@ impl ProjectDetector {
         ]
     }
 
+    /// Project markers from `projectMarkers` in settings.json, or `defaults`
+    pub fn project_markers(defaults: &[&str]) -> Vec<String> {
+        let configured = match fs_manager::read_settings() {
+            Ok(settings) => settings.project_markers,
+            Err(e) => {
+                log::warn!("Ignoring unreadable settings.json: {}", e);
+                None
+            }
+        };
+
+        configured.unwrap_or_else(|| defaults.iter().map(|marker| marker.to_string()).collect())
+    }
+
     /// Validate that a path is a valid project root
     pub fn is_valid_project_root(path: &PathBuf) -> bool {
         if !path.exists() || !path.is_dir() {
             return false;
         }
 
-        Self::project_indicators()
-            .iter()
-            .any(|indicator| path.join(indicator).exists())
+        Self::has_marker(path, &Self::project_markers(Self::project_indicators()))
+    }
+
+    fn has_marker(dir: &Path, markers: &[String]) -> bool {
+        markers.iter().any(|marker| dir.join(marker).exists())
     }
 }
 
@@ -188,7 +185,7 @@ mod tests {
         let temp = tempdir().unwrap();
         fs::create_dir(temp.path().join(".git")).unwrap();
 
-        let root = ProjectDetector::detect_project_root_from(&temp.path().to_path_buf());
+        let root = ProjectDetector::detect_project_root_from(temp.path());
         assert!(root.is_some());
         assert_eq!(root.unwrap(), temp.path());
     }
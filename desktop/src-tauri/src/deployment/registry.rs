@@ -17,7 +17,7 @@ use super::agents::{
     placeholder::PlaceholderDeployer,
     warp::WarpDeployer,
 };
-use super::deployer::AgentDeployer;
+use super::deployer::{AgentDeployer, DeploymentConfig};
 use super::error::{DeploymentError, DeploymentResult};
 use crate::fs_manager;
 use crate::types::AgentDefinition;
@@ -25,12 +25,15 @@ use crate::types::AgentDefinition;
 /// Registry of all available agent deployers
 pub struct DeployerRegistry {
     deployers: HashMap<String, Arc<dyn AgentDeployer>>,
+    /// Deployers used instead when a deployment asks for commands as skills
+    skill_deployers: HashMap<String, Arc<dyn AgentDeployer>>,
 }
 
 impl DeployerRegistry {
     /// Create a new registry and initialize all deployers
     pub fn new() -> DeploymentResult<Self> {
         let mut deployers: HashMap<String, Arc<dyn AgentDeployer>> = HashMap::new();
+        let mut skill_deployers: HashMap<String, Arc<dyn AgentDeployer>> = HashMap::new();
 
         // Load agent registry
         let agents = fs_manager::load_agent_registry()
@@ -38,11 +41,22 @@ impl DeployerRegistry {
 
         // Create deployers for each agent
         for agent in agents {
+            if let Some(deployer) = Self::create_skills_deployer_for_agent(&agent) {
+                skill_deployers.insert(deployer.agent_id().to_string(), deployer);
+            }
             let deployer: Arc<dyn AgentDeployer> = Self::create_deployer_for_agent(agent)?;
             deployers.insert(deployer.agent_id().to_string(), deployer);
         }
 
-        Ok(Self { deployers })
+        Ok(Self { deployers, skill_deployers })
+    }
+
+    /// Create the skills-mode deployer for agents that can deploy commands as skills
+    fn create_skills_deployer_for_agent(agent: &AgentDefinition) -> Option<Arc<dyn AgentDeployer>> {
+        match agent.id.to_lowercase().as_str() {
+            "claude" => Some(Arc::new(ClaudeDeployer::new(agent.clone()).with_skills(true))),
+            _ => None,
+        }
     }
 
     /// Create the appropriate deployer for an agent
@@ -71,11 +85,30 @@ impl DeployerRegistry {
         Ok(deployer)
     }
 
+    /// Register (or replace) a deployer under its agent ID
+    pub fn register(&mut self, deployer: Arc<dyn AgentDeployer>) {
+        let agent_id = deployer.agent_id().to_lowercase();
+        self.skill_deployers.remove(&agent_id);
+        self.deployers.insert(agent_id, deployer);
+    }
+
     /// Get a deployer for a specific agent ID
     pub fn get_deployer(&self, agent_id: &str) -> Option<Arc<dyn AgentDeployer>> {
         self.deployers.get(&agent_id.to_lowercase()).cloned()
     }
 
+    /// Get the deployer for a deployment, honouring its request for skills
+    ///
+    /// Agents without a skills mode get their usual deployer.
+    pub fn get_deployer_for(&self, config: &DeploymentConfig) -> Option<Arc<dyn AgentDeployer>> {
+        let agent_id = config.agent_id.to_lowercase();
+        let skills = config
+            .emit_skills
+            .then(|| self.skill_deployers.get(&agent_id).cloned())
+            .flatten();
+        skills.or_else(|| self.get_deployer(&agent_id))
+    }
+
     /// Get all registered agent IDs
     pub fn agent_ids(&self) -> Vec<String> {
         self.deployers.keys().cloned().collect()
@@ -101,6 +134,7 @@ impl Default for DeployerRegistry {
     fn default() -> Self {
         Self::new().unwrap_or_else(|_| Self {
             deployers: HashMap::new(),
+            skill_deployers: HashMap::new(),
         })
     }
 }
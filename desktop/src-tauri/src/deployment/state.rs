@@ -4,12 +4,22 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use super::deployer::{DeploymentOptions, TargetLevel};
 use super::error::{DeploymentError, DeploymentResult};
+use super::logger::DeploymentOperation;
 use crate::fs_manager;
+use crate::util;
+
+/// Number of deployments kept per agent unless the store says otherwise
+pub const DEFAULT_HISTORY_RETENTION: usize = 10;
+
+fn default_history_retention() -> usize {
+    DEFAULT_HISTORY_RETENTION
+}
 
 /// State of a single deployment
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,28 +35,52 @@ pub struct DeploymentState {
     pub deployed_commands: Vec<String>,
     /// Files that were created during deployment
     pub files_created: Vec<String>,
+    /// Hand-edited files left in place instead of being redeployed
+    #[serde(default)]
+    pub files_preserved: Vec<String>,
+    /// Existing files the deployment updated in place, restored rather than removed
+    #[serde(default)]
+    pub files_modified: Vec<String>,
+    /// Directories created to hold those files, outermost first
+    #[serde(default)]
+    pub dirs_created: Vec<String>,
     /// Path to backup directory (if any)
     pub backup_path: Option<String>,
     /// The deployment method used
     pub method: String,
     /// Whether this was a user-level or project-level deployment
-    pub target_level: String,
+    pub target_level: TargetLevel,
     /// Project path (for project-level deployments)
     pub project_path: Option<String>,
+    /// Content hash of each deployed command file, keyed by path
+    #[serde(default)]
+    pub file_hashes: BTreeMap<String, String>,
+    /// Content hash of the generated AGENTS.md
+    #[serde(default)]
+    pub content_hash: Option<String>,
+    /// Options the deployment ran with, repeated by redeploys
+    #[serde(default)]
+    pub options: DeploymentOptions,
 }
 
 impl DeploymentState {
-    pub fn new(agent_id: String, method: String, target_level: String) -> Self {
+    pub fn new(agent_id: String, method: String, target_level: TargetLevel) -> Self {
         Self {
             agent_id,
             timestamp: Utc::now(),
             deployed_packs: Vec::new(),
             deployed_commands: Vec::new(),
             files_created: Vec::new(),
+            files_preserved: Vec::new(),
+            files_modified: Vec::new(),
+            dirs_created: Vec::new(),
             backup_path: None,
             method,
             target_level,
             project_path: None,
+            file_hashes: BTreeMap::new(),
+            content_hash: None,
+            options: DeploymentOptions::default(),
         }
     }
 
@@ -65,6 +99,21 @@ impl DeploymentState {
         self
     }
 
+    pub fn with_preserved(mut self, files: Vec<String>) -> Self {
+        self.files_preserved = files;
+        self
+    }
+
+    pub fn with_modified(mut self, files: Vec<String>) -> Self {
+        self.files_modified = files;
+        self
+    }
+
+    pub fn with_dirs(mut self, dirs: Vec<String>) -> Self {
+        self.dirs_created = dirs;
+        self
+    }
+
     pub fn with_backup(mut self, backup_path: String) -> Self {
         self.backup_path = Some(backup_path);
         self
@@ -74,6 +123,62 @@ impl DeploymentState {
         self.project_path = Some(project_path);
         self
     }
+
+    pub fn with_file_hashes(mut self, file_hashes: BTreeMap<String, String>) -> Self {
+        self.file_hashes = file_hashes;
+        self
+    }
+
+    pub fn with_content_hash(mut self, content_hash: String) -> Self {
+        self.content_hash = Some(content_hash);
+        self
+    }
+
+    pub fn with_options(mut self, options: DeploymentOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Files a rollback puts back from the backup: those created and those modified
+    pub fn restorable_files(&self) -> Vec<PathBuf> {
+        self.files_created
+            .iter()
+            .chain(&self.files_modified)
+            .map(PathBuf::from)
+            .collect()
+    }
+
+    /// Whether two states deployed the same selection with the same content to the same place
+    fn same_deployment(&self, other: &DeploymentState) -> bool {
+        self.deployed_packs == other.deployed_packs
+            && self.deployed_commands == other.deployed_commands
+            && self.content_hash == other.content_hash
+            && self.target_level == other.target_level
+            && self.project_path == other.project_path
+            && self.method == other.method
+    }
+}
+
+/// A deployment attempt that failed before it was recorded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FailedDeployment {
+    pub agent_id: String,
+    pub timestamp: DateTime<Utc>,
+    /// Phase the deployment failed in
+    pub phase: DeploymentOperation,
+    pub error: String,
+}
+
+impl FailedDeployment {
+    pub fn new(agent_id: String, phase: DeploymentOperation, error: String) -> Self {
+        Self {
+            agent_id,
+            timestamp: Utc::now(),
+            phase,
+            error,
+        }
+    }
 }
 
 /// Overall deployment state containing all deployments
@@ -84,6 +189,16 @@ pub struct DeploymentStateStore {
     pub version: String,
     /// All deployment states keyed by agent ID
     pub deployments: HashMap<String, Vec<DeploymentState>>,
+    /// Maximum number of deployments kept per agent
+    #[serde(default = "default_history_retention")]
+    pub history_retention: usize,
+    /// Failed deployment attempts keyed by agent ID
+    #[serde(default)]
+    pub failures: HashMap<String, Vec<FailedDeployment>>,
+    /// Config paths the toolkit filled with a copy when an agent link fell back to copying,
+    /// keyed by agent ID
+    #[serde(default)]
+    pub link_copies: HashMap<String, Vec<String>>,
 }
 
 impl Default for DeploymentStateStore {
@@ -91,10 +206,24 @@ impl Default for DeploymentStateStore {
         Self {
             version: "1.0".to_string(),
             deployments: HashMap::new(),
+            history_retention: DEFAULT_HISTORY_RETENTION,
+            failures: HashMap::new(),
+            link_copies: HashMap::new(),
         }
     }
 }
 
+/// One page of an agent's deployment history, newest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeploymentHistoryPage {
+    pub entries: Vec<DeploymentState>,
+    /// Total number of recorded deployments for the agent
+    pub total: u64,
+    pub offset: u64,
+    pub limit: u64,
+}
+
 /// Manages deployment state persistence
 pub struct StateManager {
     state_path: PathBuf,
@@ -103,7 +232,7 @@ pub struct StateManager {
 impl StateManager {
     /// Create a new state manager
     pub fn new() -> DeploymentResult<Self> {
-        let agentsmd_home = fs_manager::get_agentsmd_home();
+        let agentsmd_home = fs_manager::get_agentsmd_home()?;
         let state_path = agentsmd_home.join("deployment-state.json");
         Ok(Self { state_path })
     }
@@ -149,14 +278,72 @@ impl StateManager {
         let agent_states = store.deployments.entry(state.agent_id.clone()).or_insert_with(Vec::new);
         agent_states.push(state);
 
-        // Keep only the last 10 deployments per agent
-        if agent_states.len() > 10 {
-            agent_states.drain(0..agent_states.len() - 10);
+        // Keep only the most recent deployments per agent
+        let retention = store.history_retention.max(1);
+        if agent_states.len() > retention {
+            agent_states.drain(0..agent_states.len() - retention);
+        }
+
+        self.save_state(&store)
+    }
+
+    /// Record a failed deployment attempt, trimmed to the same retention as history
+    pub fn record_failure(&self, failure: FailedDeployment) -> DeploymentResult<()> {
+        let mut store = self.load_state()?;
+
+        let retention = store.history_retention.max(1);
+        let failures = store.failures.entry(failure.agent_id.clone()).or_default();
+        failures.push(failure);
+        if failures.len() > retention {
+            failures.drain(0..failures.len() - retention);
         }
 
         self.save_state(&store)
     }
 
+    /// Get failed deployment attempts for a specific agent, oldest first
+    pub fn get_agent_failures(&self, agent_id: &str) -> DeploymentResult<Vec<FailedDeployment>> {
+        let store = self.load_state()?;
+        Ok(store.failures.get(agent_id).cloned().unwrap_or_default())
+    }
+
+    /// Record that an agent link at `path` was placed as a copy
+    pub fn record_link_copy(&self, agent_id: &str, path: &str) -> DeploymentResult<()> {
+        let mut store = self.load_state()?;
+        let copies = store.link_copies.entry(agent_id.to_string()).or_default();
+        if !copies.iter().any(|p| p == path) {
+            copies.push(path.to_string());
+        }
+        self.save_state(&store)
+    }
+
+    /// Forget a copied agent link once it has been removed
+    pub fn forget_link_copy(&self, agent_id: &str, path: &str) -> DeploymentResult<()> {
+        let mut store = self.load_state()?;
+        if let Some(copies) = store.link_copies.get_mut(agent_id) {
+            copies.retain(|p| p != path);
+            if copies.is_empty() {
+                store.link_copies.remove(agent_id);
+            }
+        }
+        self.save_state(&store)
+    }
+
+    /// Agent link paths recorded as copies for an agent
+    pub fn get_link_copies(&self, agent_id: &str) -> DeploymentResult<Vec<String>> {
+        let store = self.load_state()?;
+        Ok(store.link_copies.get(agent_id).cloned().unwrap_or_default())
+    }
+
+    /// Set how many deployments are kept per agent
+    ///
+    /// Lowering the retention trims existing history on the next recorded deployment.
+    pub fn set_history_retention(&self, retention: usize) -> DeploymentResult<()> {
+        let mut store = self.load_state()?;
+        store.history_retention = retention.max(1);
+        self.save_state(&store)
+    }
+
     /// Get deployment state for a specific agent
     pub fn get_agent_state(&self, agent_id: &str) -> DeploymentResult<Option<DeploymentState>> {
         let store = self.load_state()?;
@@ -176,6 +363,48 @@ impl StateManager {
             .unwrap_or_default())
     }
 
+    /// Get a page of deployment history for an agent, newest first
+    pub fn get_agent_history_page(
+        &self,
+        agent_id: &str,
+        offset: usize,
+        limit: usize,
+    ) -> DeploymentResult<DeploymentHistoryPage> {
+        let history = self.get_agent_history(agent_id)?;
+        let total = history.len();
+        let entries = history.into_iter().rev().skip(offset).take(limit).collect();
+
+        Ok(DeploymentHistoryPage {
+            entries,
+            total: total as u64,
+            offset: offset as u64,
+            limit: limit as u64,
+        })
+    }
+
+    /// Get the most recent deployments across all agents, newest first
+    pub fn recent_deployments(&self, limit: usize) -> DeploymentResult<Vec<DeploymentState>> {
+        let store = self.load_state()?;
+        let mut deployments: Vec<DeploymentState> =
+            store.deployments.into_values().flatten().collect();
+        deployments.sort_by_key(|s| std::cmp::Reverse(s.timestamp));
+        deployments.truncate(limit);
+        Ok(deployments)
+    }
+
+    /// Agents with at least one recorded deployment, sorted by id
+    pub fn deployed_agents(&self) -> DeploymentResult<Vec<String>> {
+        let store = self.load_state()?;
+        let mut agents: Vec<String> = store
+            .deployments
+            .into_iter()
+            .filter(|(_, states)| !states.is_empty())
+            .map(|(agent_id, _)| agent_id)
+            .collect();
+        agents.sort();
+        Ok(agents)
+    }
+
     /// Get deployment by timestamp
     pub fn get_deployment_by_timestamp(
         &self,
@@ -195,9 +424,75 @@ impl StateManager {
     pub fn clear_agent_state(&self, agent_id: &str) -> DeploymentResult<()> {
         let mut store = self.load_state()?;
         store.deployments.remove(agent_id);
+        store.failures.remove(agent_id);
         self.save_state(&store)
     }
 
+    /// Replace a renamed command id in every recorded deployment
+    ///
+    /// Returns how many states were updated.
+    pub fn rename_command(&self, old_id: &str, new_id: &str) -> DeploymentResult<usize> {
+        let mut store = self.load_state()?;
+
+        let mut updated = 0;
+        for state in store.deployments.values_mut().flatten() {
+            let mut renamed = false;
+            for command in state.deployed_commands.iter_mut().filter(|c| *c == old_id) {
+                *command = new_id.to_string();
+                renamed = true;
+            }
+            if renamed {
+                updated += 1;
+            }
+        }
+
+        if updated > 0 {
+            self.save_state(&store)?;
+        }
+        Ok(updated)
+    }
+
+    /// Collapse runs of identical consecutive deployments into the latest of each run
+    ///
+    /// The collapsed state keeps the created directories of the first run and the oldest
+    /// backup of the run that is still on disk, so rolling it back restores as close to
+    /// what was there before the run started as possible; backups already pruned are
+    /// dropped rather than kept as dangling paths. Returns how many states were removed.
+    pub fn compact_history(&self, agent_id: &str) -> DeploymentResult<usize> {
+        let mut store = self.load_state()?;
+
+        let Some(states) = store.deployments.get_mut(agent_id) else {
+            return Ok(0);
+        };
+
+        let before = states.len();
+        let mut compacted: Vec<DeploymentState> = Vec::with_capacity(before);
+        for state in states.drain(..) {
+            match compacted.last_mut() {
+                Some(previous) if previous.same_deployment(&state) => {
+                    let backup_path = [previous.backup_path.take(), state.backup_path.clone()]
+                        .into_iter()
+                        .flatten()
+                        .find(|backup| Path::new(backup).exists());
+                    let dirs_created = std::mem::take(&mut previous.dirs_created);
+                    *previous = DeploymentState {
+                        backup_path,
+                        dirs_created,
+                        ..state
+                    };
+                }
+                _ => compacted.push(state),
+            }
+        }
+        *states = compacted;
+
+        let removed = before - states.len();
+        if removed > 0 {
+            self.save_state(&store)?;
+        }
+        Ok(removed)
+    }
+
     /// Remove the latest deployment for an agent
     pub fn remove_latest_deployment(&self, agent_id: &str) -> DeploymentResult<Option<DeploymentState>> {
         let mut store = self.load_state()?;
@@ -212,6 +507,37 @@ impl StateManager {
     }
 }
 
+/// Manifest file written into every backup directory
+const BACKUP_MANIFEST: &str = ".manifest.json";
+
+/// Content hashes of the files in a backup, keyed by path relative to the backup directory
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupManifest {
+    pub files: BTreeMap<String, BackupManifestEntry>,
+    /// Original location of each top-level backup entry, keyed by its name in the backup
+    #[serde(default)]
+    pub originals: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupManifestEntry {
+    pub hash: String,
+    pub size: u64,
+    /// Hard-linked from the previous backup because the content hadn't changed
+    pub reused: bool,
+}
+
+/// A backup directory for an agent, as listed for restoring
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupEntry {
+    pub path: PathBuf,
+    pub timestamp: DateTime<Utc>,
+    pub file_count: u64,
+}
+
 /// Manages backup creation and restoration
 pub struct BackupManager {
     backup_root: PathBuf,
@@ -220,7 +546,7 @@ pub struct BackupManager {
 impl BackupManager {
     /// Create a new backup manager
     pub fn new() -> DeploymentResult<Self> {
-        let agentsmd_home = fs_manager::get_agentsmd_home();
+        let agentsmd_home = fs_manager::get_agentsmd_home()?;
         let backup_root = agentsmd_home.join("backups");
         Ok(Self { backup_root })
     }
@@ -245,44 +571,89 @@ impl BackupManager {
             return Ok(None);
         }
 
-        let timestamp = Utc::now().format("%Y%m%d_%H%M%S");
-        let backup_dir = self.backup_root.join(agent_id).join(timestamp.to_string());
+        let previous = self.latest_backup(agent_id);
+
+        // Deploys within the same second get their own directory
+        let agent_dir = self.backup_root.join(agent_id);
+        let timestamp = Utc::now().format("%Y%m%d_%H%M%S").to_string();
+        let mut backup_dir = agent_dir.join(&timestamp);
+        let mut suffix = 1;
+        while backup_dir.exists() {
+            backup_dir = agent_dir.join(format!("{}_{}", timestamp, suffix));
+            suffix += 1;
+        }
 
         fs::create_dir_all(&backup_dir).map_err(|e| {
             DeploymentError::BackupFailed(format!("Failed to create backup directory: {}", e))
         })?;
 
+        let mut backup = IncrementalBackup {
+            dir: &backup_dir,
+            previous: previous.as_ref().map(|(dir, manifest)| (dir.as_path(), manifest)),
+            manifest: BackupManifest::default(),
+        };
         for file in existing_files {
             let relative = file
                 .file_name()
                 .map(|n| n.to_string_lossy().to_string())
                 .unwrap_or_else(|| "unknown".to_string());
-
-            let backup_path = backup_dir.join(&relative);
-
-            if file.is_dir() {
-                copy_dir_all(file, &backup_path)?;
-            } else {
-                fs::copy(file, &backup_path).map_err(|e| {
-                    DeploymentError::BackupFailed(format!("Failed to backup {}: {}", relative, e))
-                })?;
-            }
+            backup.add(file, &relative)?;
+            backup
+                .manifest
+                .originals
+                .insert(relative, file.to_string_lossy().to_string());
         }
 
+        let manifest = serde_json::to_string_pretty(&backup.manifest)
+            .map_err(|e| DeploymentError::BackupFailed(format!("Failed to serialize backup manifest: {}", e)))?;
+        fs::write(backup_dir.join(BACKUP_MANIFEST), manifest)
+            .map_err(|e| DeploymentError::BackupFailed(format!("Failed to write backup manifest: {}", e)))?;
+
         // Clean up old backups (keep last 5)
         self.cleanup_old_backups(agent_id, 5)?;
 
         Ok(Some(backup_dir))
     }
 
-    /// Restore files from a backup
-    pub fn restore_backup(&self, backup_path: &PathBuf, original_paths: &[PathBuf]) -> DeploymentResult<()> {
+    /// Read the manifest of a backup directory
+    pub fn read_manifest(backup_path: &Path) -> DeploymentResult<BackupManifest> {
+        let content = fs::read_to_string(backup_path.join(BACKUP_MANIFEST))
+            .map_err(|e| DeploymentError::BackupFailed(format!("Failed to read backup manifest: {}", e)))?;
+        serde_json::from_str(&content)
+            .map_err(|e| DeploymentError::BackupFailed(format!("Failed to parse backup manifest: {}", e)))
+    }
+
+    /// Most recent backup for an agent that has a readable manifest
+    fn latest_backup(&self, agent_id: &str) -> Option<(PathBuf, BackupManifest)> {
+        let mut backups: Vec<PathBuf> = fs::read_dir(self.backup_root.join(agent_id))
+            .ok()?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.is_dir())
+            .collect();
+        backups.sort_by_key(|p| backup_order(p));
+
+        backups
+            .into_iter()
+            .rev()
+            .find_map(|dir| Self::read_manifest(&dir).ok().map(|manifest| (dir, manifest)))
+    }
+
+    /// Pair each backed-up entry with the original path it would be restored to
+    ///
+    /// Entries are matched by file name; backed-up files with no matching original are skipped.
+    pub fn plan_restore(
+        &self,
+        backup_path: &Path,
+        original_paths: &[PathBuf],
+    ) -> DeploymentResult<Vec<(PathBuf, PathBuf)>> {
         if !backup_path.exists() {
             return Err(DeploymentError::RollbackFailed(
                 "Backup directory does not exist".to_string(),
             ));
         }
 
+        let mut restores = Vec::new();
         for entry in fs::read_dir(backup_path).map_err(|e| {
             DeploymentError::RollbackFailed(format!("Failed to read backup directory: {}", e))
         })? {
@@ -291,57 +662,100 @@ impl BackupManager {
             })?;
 
             let backup_file = entry.path();
-            let file_name = backup_file
-                .file_name()
-                .map(|n| n.to_string_lossy().to_string())
-                .unwrap_or_default();
+            let file_name = backup_file.file_name().unwrap_or_default();
 
             // Find the original path for this file
-            if let Some(original) = original_paths.iter().find(|p| {
-                p.file_name()
-                    .map(|n| n.to_string_lossy().to_string())
-                    .unwrap_or_default()
-                    == file_name
-            }) {
-                // Remove current file/dir
-                if original.exists() {
-                    if original.is_dir() {
-                        fs::remove_dir_all(original).map_err(|e| {
-                            DeploymentError::RollbackFailed(format!(
-                                "Failed to remove {}: {}",
-                                original.display(),
-                                e
-                            ))
-                        })?;
-                    } else {
-                        fs::remove_file(original).map_err(|e| {
-                            DeploymentError::RollbackFailed(format!(
-                                "Failed to remove {}: {}",
-                                original.display(),
-                                e
-                            ))
-                        })?;
-                    }
-                }
-
-                // Restore from backup
-                if backup_file.is_dir() {
-                    copy_dir_all(&backup_file, original)?;
-                } else {
-                    fs::copy(&backup_file, original).map_err(|e| {
-                        DeploymentError::RollbackFailed(format!(
-                            "Failed to restore {}: {}",
-                            file_name,
-                            e
-                        ))
-                    })?;
-                }
+            if let Some(original) = original_paths
+                .iter()
+                .find(|p| p.file_name() == Some(file_name))
+            {
+                restores.push((backup_file.clone(), original.clone()));
             }
         }
 
+        restores.sort();
+        Ok(restores)
+    }
+
+    /// Restore files from a backup
+    pub fn restore_backup(&self, backup_path: &Path, original_paths: &[PathBuf]) -> DeploymentResult<()> {
+        for (backup_file, original) in self.plan_restore(backup_path, original_paths)? {
+            restore_entry(&backup_file, &original)?;
+        }
+
         Ok(())
     }
 
+    /// List an agent's backups, newest first
+    pub fn list_backups(&self, agent_id: &str) -> DeploymentResult<Vec<BackupEntry>> {
+        let agent_dir = self.backup_root.join(agent_id);
+        if !agent_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut backups = Vec::new();
+        for entry in fs::read_dir(&agent_dir).map_err(|e| {
+            DeploymentError::BackupFailed(format!("Failed to read backup directory: {}", e))
+        })? {
+            let path = match entry {
+                Ok(entry) if entry.path().is_dir() => entry.path(),
+                _ => continue,
+            };
+            let Some(timestamp) = backup_timestamp(&path) else {
+                continue;
+            };
+            let file_count = count_files(&path);
+            backups.push(BackupEntry {
+                path,
+                timestamp,
+                file_count,
+            });
+        }
+
+        backups.sort_by_key(|b| std::cmp::Reverse(backup_order(&b.path)));
+        Ok(backups)
+    }
+
+    /// Restore an agent's backup directory to the locations it was taken from
+    ///
+    /// `backup_path` must be one of the agent's backups as returned by `list_backups`.
+    /// Returns the restored paths.
+    pub fn restore_named_backup(&self, agent_id: &str, backup_path: &Path) -> DeploymentResult<Vec<PathBuf>> {
+        let agent_dir = self.backup_root.join(agent_id);
+        let is_agent_backup = match (backup_path.canonicalize(), agent_dir.canonicalize()) {
+            (Ok(backup), Ok(agent_dir)) => backup.parent() == Some(agent_dir.as_path()),
+            _ => false,
+        };
+        if !is_agent_backup {
+            return Err(DeploymentError::RollbackFailed(format!(
+                "{} is not a backup of {}",
+                backup_path.display(),
+                agent_id
+            )));
+        }
+
+        let manifest = Self::read_manifest(backup_path)?;
+        if manifest.originals.is_empty() {
+            return Err(DeploymentError::RollbackFailed(format!(
+                "Backup {} does not record where its files came from; roll back by deployment instead",
+                backup_path.display()
+            )));
+        }
+
+        let mut restored = Vec::new();
+        for (name, original) in &manifest.originals {
+            let backup_file = backup_path.join(name);
+            if !backup_file.exists() {
+                continue;
+            }
+            let original = PathBuf::from(original);
+            restore_entry(&backup_file, &original)?;
+            restored.push(original);
+        }
+
+        Ok(restored)
+    }
+
     /// Clean up old backups, keeping only the most recent ones
     fn cleanup_old_backups(&self, agent_id: &str, keep_count: usize) -> DeploymentResult<()> {
         let agent_backup_dir = self.backup_root.join(agent_id);
@@ -358,8 +772,7 @@ impl BackupManager {
             .filter(|e| e.path().is_dir())
             .collect();
 
-        // Sort by name (which includes timestamp)
-        backups.sort_by_key(|e| e.path());
+        backups.sort_by_key(|e| backup_order(&e.path()));
 
         // Remove oldest backups if we have too many
         if backups.len() > keep_count {
@@ -378,8 +791,145 @@ impl BackupManager {
     }
 }
 
+/// A backup being written, reusing unchanged files from the previous one
+struct IncrementalBackup<'a> {
+    dir: &'a Path,
+    previous: Option<(&'a Path, &'a BackupManifest)>,
+    manifest: BackupManifest,
+}
+
+impl IncrementalBackup<'_> {
+    /// Back up a file or directory tree to `relative` within the backup directory
+    fn add(&mut self, source: &Path, relative: &str) -> DeploymentResult<()> {
+        if source.is_dir() {
+            let entries = fs::read_dir(source).map_err(|e| {
+                DeploymentError::BackupFailed(format!("Failed to read directory {}: {}", source.display(), e))
+            })?;
+            fs::create_dir_all(self.dir.join(relative)).map_err(|e| {
+                DeploymentError::BackupFailed(format!("Failed to create directory {}: {}", relative, e))
+            })?;
+            for entry in entries {
+                let entry = entry.map_err(|e| {
+                    DeploymentError::BackupFailed(format!("Failed to read entry: {}", e))
+                })?;
+                let child = format!("{}/{}", relative, entry.file_name().to_string_lossy());
+                self.add(&entry.path(), &child)?;
+            }
+            return Ok(());
+        }
+
+        let content = fs::read(source).map_err(|e| {
+            DeploymentError::BackupFailed(format!("Failed to backup {}: {}", relative, e))
+        })?;
+        let hash = util::content_hash(&content);
+        let size = content.len() as u64;
+        let backup_path = self.dir.join(relative);
+
+        // Link to the previous backup's copy when the content is unchanged; the hash only
+        // narrows the candidates, so the bytes are compared before trusting a match
+        let unchanged = self.previous.and_then(|(dir, manifest)| {
+            manifest
+                .files
+                .get(relative)
+                .filter(|entry| entry.hash == hash && entry.size == size)
+                .map(|_| dir.join(relative))
+                .filter(|previous| fs::read(previous).map(|bytes| bytes == content).unwrap_or(false))
+        });
+        let reused = unchanged
+            .map(|previous| fs::hard_link(previous, &backup_path).is_ok())
+            .unwrap_or(false);
+
+        if !reused {
+            fs::write(&backup_path, &content).map_err(|e| {
+                DeploymentError::BackupFailed(format!("Failed to backup {}: {}", relative, e))
+            })?;
+        }
+
+        self.manifest
+            .files
+            .insert(relative.to_string(), BackupManifestEntry { hash, size, reused });
+        Ok(())
+    }
+}
+
+/// Replace `original` with the backed-up file or directory
+fn restore_entry(backup_file: &Path, original: &Path) -> DeploymentResult<()> {
+    // Remove current file/dir
+    if original.exists() {
+        if original.is_dir() {
+            fs::remove_dir_all(original).map_err(|e| {
+                DeploymentError::RollbackFailed(format!(
+                    "Failed to remove {}: {}",
+                    original.display(),
+                    e
+                ))
+            })?;
+        } else {
+            fs::remove_file(original).map_err(|e| {
+                DeploymentError::RollbackFailed(format!(
+                    "Failed to remove {}: {}",
+                    original.display(),
+                    e
+                ))
+            })?;
+        }
+    }
+
+    // Restore from backup
+    if backup_file.is_dir() {
+        copy_dir_all(backup_file, original)?;
+    } else {
+        fs::copy(backup_file, original).map_err(|e| {
+            DeploymentError::RollbackFailed(format!(
+                "Failed to restore {}: {}",
+                backup_file.file_name().unwrap_or_default().to_string_lossy(),
+                e
+            ))
+        })?;
+    }
+
+    Ok(())
+}
+
+/// When a backup was taken, from its `%Y%m%d_%H%M%S[_n]` directory name
+fn backup_timestamp(backup_dir: &Path) -> Option<DateTime<Utc>> {
+    let name = backup_dir.file_name()?.to_string_lossy();
+    let stamp = name.get(..15)?;
+    chrono::NaiveDateTime::parse_from_str(stamp, "%Y%m%d_%H%M%S")
+        .ok()
+        .map(|naive| naive.and_utc())
+}
+
+/// Sort key putting an agent's backup directories in the order they were taken
+///
+/// Directories are named by timestamp, with `_n` appended for later backups within the
+/// same second, so `_10` must sort after `_9` rather than by name. Unrecognised names
+/// sort first.
+fn backup_order(backup_dir: &Path) -> Option<(DateTime<Utc>, u64)> {
+    let timestamp = backup_timestamp(backup_dir)?;
+    let name = backup_dir.file_name()?.to_string_lossy();
+    let suffix = match name.get(15..)? {
+        "" => 0,
+        rest => rest.strip_prefix('_')?.parse().ok()?,
+    };
+    Some((timestamp, suffix))
+}
+
+/// Number of backed-up files under a backup directory, not counting its manifest
+fn count_files(dir: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|path| path.file_name().map(|n| n != BACKUP_MANIFEST).unwrap_or(true))
+        .map(|path| if path.is_dir() { count_files(&path) } else { 1 })
+        .sum()
+}
+
 /// Recursively copy a directory
-fn copy_dir_all(src: &PathBuf, dst: &PathBuf) -> DeploymentResult<()> {
+fn copy_dir_all(src: &Path, dst: &Path) -> DeploymentResult<()> {
     fs::create_dir_all(dst).map_err(|e| {
         DeploymentError::BackupFailed(format!("Failed to create directory {}: {}", dst.display(), e))
     })?;
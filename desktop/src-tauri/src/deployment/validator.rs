@@ -7,6 +7,7 @@ use super::command_validator;
 use super::converters::FileFormat;
 use super::deployer::{BudgetUsage, ValidationReport};
 use super::error::{DeploymentError, DeploymentResult};
+use crate::budget;
 use crate::command_registry;
 use crate::fs_manager;
 
@@ -18,8 +19,9 @@ impl DeploymentValidator {
     pub fn validate_character_budget(
         content: &str,
         limit: Option<u64>,
+        warn_threshold_pct: u64,
     ) -> ValidationResult {
-        let current = content.len() as u64;
+        let current = budget::char_count(content);
 
         match limit {
             Some(max) => {
@@ -29,12 +31,12 @@ impl DeploymentValidator {
                 let mut warnings = Vec::new();
                 let mut errors = Vec::new();
 
-                if !within_limit {
-                    errors.push(format!(
-                        "Content exceeds character limit: {} / {} ({:.1}%)",
-                        current, max, percentage
-                    ));
-                } else if percentage > 80.0 {
+                let limit_error = (!within_limit).then(|| {
+                    format!("Content exceeds character limit: {} / {} ({:.1}%)", current, max, percentage)
+                });
+                if let Some(error) = &limit_error {
+                    errors.push(error.clone());
+                } else if percentage > warn_threshold_pct as f64 {
                     warnings.push(format!(
                         "Content uses {:.1}% of character limit ({} / {})",
                         percentage, current, max
@@ -50,6 +52,7 @@ impl DeploymentValidator {
                         max_chars: Some(max),
                         percentage: Some(percentage),
                         within_limit,
+                        limit_error,
                     },
                 }
             }
@@ -83,8 +86,8 @@ impl DeploymentValidator {
                     Err(e) => vec![format!("Invalid TOML: {}", e)],
                 }
             }
-            FileFormat::Markdown => {
-                // Markdown is always valid
+            FileFormat::Markdown | FileFormat::Text => {
+                // Markdown and plain text are always valid
                 Vec::new()
             }
         };
@@ -93,7 +96,7 @@ impl DeploymentValidator {
             valid: errors.is_empty(),
             errors,
             warnings: Vec::new(),
-            budget: BudgetUsage::unlimited(content.len() as u64),
+            budget: BudgetUsage::unlimited(budget::char_count(content)),
         }
     }
 
@@ -111,7 +114,7 @@ impl DeploymentValidator {
             valid: has_frontmatter,
             errors,
             warnings: Vec::new(),
-            budget: BudgetUsage::unlimited(content.len() as u64),
+            budget: BudgetUsage::unlimited(budget::char_count(content)),
         }
     }
 
@@ -167,7 +170,7 @@ impl DeploymentValidator {
             valid: errors.is_empty(),
             errors,
             warnings,
-            budget: BudgetUsage::unlimited(command_content.len() as u64),
+            budget: BudgetUsage::unlimited(budget::char_count(command_content)),
         }
     }
 
@@ -177,13 +180,19 @@ impl DeploymentValidator {
             .map_err(|e| DeploymentError::ConfigurationError(e.to_string()))?;
 
         // Check if the parent directory exists (agent installation)
-        let agent_installed = config_path.parent().map(|p| p.exists()).unwrap_or(false);
+        let install_dir = config_path.parent();
+        let agent_installed = install_dir.map(|p| p.exists()).unwrap_or(false);
 
         let (errors, warnings) = if agent_installed {
             (Vec::new(), Vec::new())
         } else {
+            let location = install_dir.unwrap_or(&config_path);
             (
-                vec![format!("Agent '{}' does not appear to be installed", agent_id)],
+                vec![format!(
+                    "Agent '{}' is not installed ({} does not exist)",
+                    agent_id,
+                    location.display()
+                )],
                 Vec::new(),
             )
         };
@@ -202,8 +211,10 @@ impl DeploymentValidator {
     pub fn validate_commands_for_agent(
         command_ids: &[String],
         agent_id: &str,
+        inline_out_references: bool,
     ) -> DeploymentResult<ValidationResult> {
-        let result = command_validator::validate_commands_for_agent(command_ids, agent_id)?;
+        let result =
+            command_validator::validate_commands_for_agent(command_ids, agent_id, inline_out_references)?;
         
         Ok(ValidationResult {
             valid: result.valid,
@@ -218,8 +229,9 @@ impl DeploymentValidator {
         agents_md_chars: u64,
         command_chars: u64,
         limit: Option<u64>,
+        warn_threshold_pct: u64,
     ) -> ValidationResult {
-        Self::validate_full_budget(agents_md_chars, command_chars, 0, limit)
+        Self::validate_full_budget(agents_md_chars, command_chars, 0, limit, warn_threshold_pct)
     }
 
     /// Validate full deployment budget including out-references
@@ -228,6 +240,7 @@ impl DeploymentValidator {
         command_chars: u64,
         out_reference_chars: u64,
         limit: Option<u64>,
+        warn_threshold_pct: u64,
     ) -> ValidationResult {
         let total = agents_md_chars + command_chars + out_reference_chars;
 
@@ -251,12 +264,15 @@ impl DeploymentValidator {
                     )
                 };
 
-                if !within_limit {
-                    errors.push(format!(
+                let limit_error = (!within_limit).then(|| {
+                    format!(
                         "Combined content exceeds character limit: {} / {} ({:.1}%){}",
                         total, max, percentage, breakdown
-                    ));
-                } else if percentage > 80.0 {
+                    )
+                });
+                if let Some(error) = &limit_error {
+                    errors.push(error.clone());
+                } else if percentage > warn_threshold_pct as f64 {
                     warnings.push(format!(
                         "Combined content uses {:.1}% of character limit ({} / {}){}",
                         percentage, total, max, breakdown
@@ -272,6 +288,7 @@ impl DeploymentValidator {
                         max_chars: Some(max),
                         percentage: Some(percentage),
                         within_limit,
+                        limit_error,
                     },
                 }
             }
@@ -344,6 +361,7 @@ impl ValidationResult {
             errors: self.errors,
             warnings: self.warnings,
             budget_usage: self.budget,
+            suggestion: None,
         }
     }
 }
@@ -382,25 +400,55 @@ mod tests {
 
     #[test]
     fn test_validate_character_budget_within_limit() {
-        let result = DeploymentValidator::validate_character_budget("Hello", Some(100));
+        let result = DeploymentValidator::validate_character_budget("Hello", Some(100), 80);
         assert!(result.valid);
         assert!(result.errors.is_empty());
     }
 
     #[test]
     fn test_validate_character_budget_over_limit() {
-        let result = DeploymentValidator::validate_character_budget("Hello World", Some(5));
+        let result = DeploymentValidator::validate_character_budget("Hello World", Some(5), 80);
         assert!(!result.valid);
         assert!(!result.errors.is_empty());
+
+        // Only a report failing on the budget alone counts as over budget
+        let mut report = result.into_report();
+        assert!(report.only_over_budget());
+        report.errors.push("Command 'x' is missing frontmatter".to_string());
+        assert!(!report.only_over_budget());
+        report.errors.remove(0);
+        assert!(!report.only_over_budget());
     }
 
     #[test]
     fn test_validate_character_budget_warning() {
         // Create a string that's 85% of limit
         let content = "x".repeat(85);
-        let result = DeploymentValidator::validate_character_budget(&content, Some(100));
+        let result = DeploymentValidator::validate_character_budget(&content, Some(100), 80);
+        assert!(result.valid);
+        assert!(!result.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_validate_character_budget_custom_threshold() {
+        let sixty_pct = "x".repeat(60);
+        let result = DeploymentValidator::validate_character_budget(&sixty_pct, Some(100), 50);
         assert!(result.valid);
         assert!(!result.warnings.is_empty());
+
+        let forty_pct = "x".repeat(40);
+        let result = DeploymentValidator::validate_character_budget(&forty_pct, Some(100), 50);
+        assert!(result.valid);
+        assert!(result.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_validate_character_budget_counts_characters_not_bytes() {
+        // 11 characters, 33 bytes
+        let content = "テストを書いてから実装";
+        let result = DeploymentValidator::validate_character_budget(content, Some(20), 80);
+        assert!(result.valid);
+        assert_eq!(result.budget.current_chars, 11);
     }
 
     #[test]
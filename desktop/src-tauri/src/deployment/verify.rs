@@ -0,0 +1,123 @@
+//! Post-deploy verification
+//!
+//! Reads deployed files back (following symlinks) and compares them with what was
+//! prepared, catching silent write failures on copy fallbacks and network drives.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::deployer::PreparedDeployment;
+use crate::fs_manager;
+
+/// Compare deployed files against the prepared content
+///
+/// Commands, out-references, and anything resolving to the shared AGENTS.md are
+/// checked; other files (merged configs, generated instructions) are skipped.
+/// Returns one message per mismatch.
+pub fn verify_deployed_files(prepared: &PreparedDeployment, deployed_files: &[String]) -> Vec<String> {
+    // Without a resolvable home there's no shared AGENTS.md to compare against
+    let agents_md = fs_manager::get_agentsmd_home()
+        .ok()
+        .map(|home| home.join("AGENTS.md"))
+        .map(|path| path.canonicalize().unwrap_or(path));
+
+    let mut mismatches = Vec::new();
+    for file in deployed_files {
+        let path = PathBuf::from(file);
+        let expected = match expected_content(prepared, &path, agents_md.as_deref()) {
+            Some(expected) => expected,
+            None => continue,
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(actual) if actual == expected => {}
+            Ok(actual) => mismatches.push(format!(
+                "{} does not match the prepared content ({} bytes written, {} expected)",
+                path.display(),
+                actual.len(),
+                expected.len()
+            )),
+            Err(e) => mismatches.push(format!("{} could not be read back: {}", path.display(), e)),
+        }
+    }
+
+    mismatches
+}
+
+fn expected_content<'a>(
+    prepared: &'a PreparedDeployment,
+    path: &Path,
+    agents_md: Option<&Path>,
+) -> Option<&'a str> {
+    if agents_md.is_some() && path.canonicalize().ok().as_deref() == agents_md {
+        return Some(&prepared.agents_md_content);
+    }
+
+    // Commands and out-references are keyed by their path under the output directory
+    // (`git/status.md`), so the longest key the deployed path ends with is its source
+    prepared
+        .commands
+        .iter()
+        .chain(&prepared.out_references)
+        .filter(|(rel_path, _)| path.ends_with(rel_path.as_str()))
+        .max_by_key(|(rel_path, _)| Path::new(rel_path.as_str()).components().count())
+        .map(|(_, content)| content.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_verify_reports_mismatched_command() {
+        let temp = tempdir().unwrap();
+        let mut prepared = PreparedDeployment::new("# AGENTS".to_string());
+        prepared.add_command("status.md".to_string(), "Show status".to_string());
+        prepared.add_out_reference("templates/pr.md".to_string(), "# PR".to_string());
+
+        let command = temp.path().join("status.md");
+        let out_ref = temp.path().join("templates").join("pr.md");
+        let unrelated = temp.path().join("settings.json");
+        fs::create_dir_all(out_ref.parent().unwrap()).unwrap();
+        fs::write(&command, "Show status").unwrap();
+        fs::write(&out_ref, "# PR").unwrap();
+        fs::write(&unrelated, "{}").unwrap();
+
+        let files: Vec<String> = [&command, &out_ref, &unrelated]
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+        assert!(verify_deployed_files(&prepared, &files).is_empty());
+
+        fs::write(&command, "Show stat").unwrap();
+        let mismatches = verify_deployed_files(&prepared, &files);
+        assert_eq!(mismatches.len(), 1);
+        assert!(mismatches[0].contains("status.md"));
+    }
+
+    #[test]
+    fn test_verify_matches_nested_command_by_relative_path() {
+        let temp = tempdir().unwrap();
+        let mut prepared = PreparedDeployment::new("# AGENTS".to_string());
+        prepared.add_command("status.md".to_string(), "Show status".to_string());
+        prepared.add_command("git/status.md".to_string(), "Show git status".to_string());
+
+        let top_level = temp.path().join("status.md");
+        let nested = temp.path().join("git").join("status.md");
+        fs::create_dir_all(nested.parent().unwrap()).unwrap();
+        fs::write(&top_level, "Show status").unwrap();
+        fs::write(&nested, "Show git status").unwrap();
+
+        let files: Vec<String> = [&top_level, &nested]
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+        assert!(verify_deployed_files(&prepared, &files).is_empty());
+
+        fs::write(&nested, "Show status").unwrap();
+        let mismatches = verify_deployed_files(&prepared, &files);
+        assert_eq!(mismatches.len(), 1);
+        assert!(mismatches[0].contains("git"));
+    }
+}
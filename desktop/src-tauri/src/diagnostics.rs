@@ -0,0 +1,86 @@
+//! Toolkit Diagnostics
+//!
+//! Aggregates the individual health checks (out-reference validation,
+//! rule pack validation, duplicate pack ids, and command script resolution)
+//! into a single report.
+
+use crate::command_registry;
+use crate::fs_manager;
+use crate::out_reference_manager;
+use crate::packs::{self, DuplicatePackId};
+use crate::types::{BrokenLink, CommandMetadata, PackValidationError};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingCommandScript {
+    pub command_id: String,
+    pub script_path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolkitHealthReport {
+    pub healthy: bool,
+    pub broken_out_reference_count: u64,
+    pub pack_error_count: u64,
+    pub pack_warning_count: u64,
+    pub missing_script_count: u64,
+    pub broken_out_references: Vec<BrokenLink>,
+    pub pack_errors: Vec<PackValidationError>,
+    pub pack_warnings: Vec<PackValidationError>,
+    pub duplicate_pack_ids: Vec<DuplicatePackId>,
+    pub missing_scripts: Vec<MissingCommandScript>,
+}
+
+/// Build a health report covering out-references, rule packs, and commands
+pub fn full_report() -> Result<ToolkitHealthReport, String> {
+    let out_ref_report = out_reference_manager::validate_out_references()?;
+
+    let mut pack_errors = Vec::new();
+    let mut pack_warnings = Vec::new();
+    let pack_ids = fs_manager::list_rule_packs()
+        .map_err(|e| format!("Failed to list packs: {}", e))?;
+    for pack_id in pack_ids {
+        let result = fs_manager::validate_pack(&pack_id)?;
+        pack_errors.extend(result.errors);
+        pack_warnings.extend(result.warnings);
+    }
+    let duplicate_pack_ids = packs::find_duplicate_ids()?;
+
+    let commands = command_registry::load_commands()?;
+    let agentsmd_home = fs_manager::get_agentsmd_home().map_err(|e| e.to_string())?;
+    let missing_scripts = find_missing_scripts(&commands, &agentsmd_home);
+
+    let healthy = out_ref_report.broken_links.is_empty()
+        && pack_errors.is_empty()
+        && duplicate_pack_ids.is_empty()
+        && missing_scripts.is_empty();
+
+    Ok(ToolkitHealthReport {
+        healthy,
+        broken_out_reference_count: out_ref_report.broken_links.len() as u64,
+        pack_error_count: pack_errors.len() as u64,
+        pack_warning_count: pack_warnings.len() as u64,
+        missing_script_count: missing_scripts.len() as u64,
+        broken_out_references: out_ref_report.broken_links,
+        pack_errors,
+        pack_warnings,
+        duplicate_pack_ids,
+        missing_scripts,
+    })
+}
+
+/// Find commands whose declared script does not exist on disk
+fn find_missing_scripts(commands: &[CommandMetadata], agentsmd_home: &Path) -> Vec<MissingCommandScript> {
+    commands
+        .iter()
+        .filter(|cmd| !cmd.script_path.is_empty())
+        .filter(|cmd| !command_registry::resolve_script_path(&cmd.script_path, agentsmd_home).exists())
+        .map(|cmd| MissingCommandScript {
+            command_id: cmd.id.clone(),
+            script_path: cmd.script_path.clone(),
+        })
+        .collect()
+}
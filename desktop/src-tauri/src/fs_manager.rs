@@ -1,14 +1,26 @@
+use crate::deployment::{DeploymentError, DeploymentResult};
 use crate::types::*;
-use dirs::home_dir;
-use std::collections::HashMap;
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
 use thiserror::Error;
 use serde_json;
 
 const AGENT_REGISTRY_JSON: &str =
     include_str!("../../../dist/core/agent-registry.bundled.json");
 
+/// Largest pack or out-reference file read into memory unless configured otherwise
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+static MAX_FILE_SIZE: AtomicU64 = AtomicU64::new(DEFAULT_MAX_FILE_SIZE);
+
+/// How the user's home directory is looked up; replaceable to simulate a missing home
+static HOME_PROVIDER: RwLock<fn() -> Option<PathBuf>> = RwLock::new(dirs::home_dir);
+
 #[derive(Error, Debug)]
 pub enum FsError {
     #[error("IO error: {0}")]
@@ -19,35 +31,94 @@ pub enum FsError {
     InvalidPath(String),
     #[error("JSON parse error: {0}")]
     JsonParse(#[from] serde_json::Error),
+    #[error("File too large: {path} is {size} bytes (limit {limit} bytes)")]
+    TooLarge { path: String, size: u64, limit: u64 },
+    #[error(transparent)]
+    Deployment(#[from] DeploymentError),
 }
 
 pub type Result<T> = std::result::Result<T, FsError>;
 
 /// Get the ~/.agentsmd/ directory path
-pub fn get_agentsmd_home() -> PathBuf {
+///
+/// `AGENTSMD_HOME` wins when set; otherwise fails with `HomeDirUnavailable` when the
+/// home directory can't be resolved.
+pub fn get_agentsmd_home() -> DeploymentResult<PathBuf> {
     if let Ok(env_path) = std::env::var("AGENTSMD_HOME") {
-        return PathBuf::from(env_path);
+        return Ok(PathBuf::from(env_path));
+    }
+
+    Ok(home_dir()?.join(".agentsmd"))
+}
+
+/// Resolve the user's home directory
+///
+/// Fails with `HomeDirUnavailable` rather than falling back to the working directory,
+/// so deployers never write into whatever directory the app was started from.
+pub fn home_dir() -> DeploymentResult<PathBuf> {
+    let provider = *HOME_PROVIDER.read().unwrap_or_else(|e| e.into_inner());
+    provider().ok_or(DeploymentError::HomeDirUnavailable)
+}
+
+/// Replace how the home directory is looked up (`dirs::home_dir` by default)
+pub fn set_home_provider(provider: fn() -> Option<PathBuf>) {
+    *HOME_PROVIDER.write().unwrap_or_else(|e| e.into_inner()) = provider;
+}
+
+/// Current size cap for pack and out-reference reads, in bytes
+pub fn max_file_size() -> u64 {
+    MAX_FILE_SIZE.load(Ordering::Relaxed)
+}
+
+/// Change the size cap for pack and out-reference reads
+pub fn set_max_file_size(bytes: u64) {
+    MAX_FILE_SIZE.store(bytes, Ordering::Relaxed);
+}
+
+/// Read a text file, refusing files larger than `max_file_size()`
+///
+/// The size is checked from metadata first so oversized files are never loaded.
+pub fn read_file_guarded(path: &Path) -> Result<String> {
+    let size = fs::metadata(path)?.len();
+    let limit = max_file_size();
+    if size > limit {
+        return Err(FsError::TooLarge {
+            path: path.display().to_string(),
+            size,
+            limit,
+        });
     }
 
-    let home = home_dir().expect("Could not find home directory");
-    home.join(".agentsmd")
+    Ok(fs::read_to_string(path)?)
 }
 
 /// Get the rule-packs directory (~/.agentsmd/rule-packs or AGENTSMD_HOME/rule-packs)
-pub fn get_rule_packs_dir() -> PathBuf {
-    get_agentsmd_home().join("rule-packs")
+pub fn get_rule_packs_dir() -> DeploymentResult<PathBuf> {
+    Ok(get_agentsmd_home()?.join("rule-packs"))
 }
 
 /// Ensure ~/.agentsmd/ directory exists
 pub fn ensure_agentsmd_dir() -> Result<PathBuf> {
-    let path = get_agentsmd_home();
+    let path = get_agentsmd_home()?;
     fs::create_dir_all(&path)?;
     Ok(path)
 }
 
+/// Read user settings from ~/.agentsmd/settings.json, defaulting when it doesn't exist
+pub fn read_settings() -> Result<Settings> {
+    let path = get_agentsmd_home()?.join("settings.json");
+    if !path.exists() {
+        return Ok(Settings::default());
+    }
+
+    let content = fs::read_to_string(&path)?;
+    let settings = serde_json::from_str(&content)?;
+    Ok(settings)
+}
+
 /// Read AGENTS.md content from ~/.agentsmd/AGENTS.md
 pub fn read_agents_md() -> Result<String> {
-    let agentsmd_home = get_agentsmd_home();
+    let agentsmd_home = get_agentsmd_home()?;
     let agents_md_path = agentsmd_home.join("AGENTS.md");
     
     if !agents_md_path.exists() {
@@ -71,7 +142,7 @@ pub fn write_agents_md(content: String) -> Result<()> {
 
 /// List available rule packs in rule-packs/ directory
 pub fn list_rule_packs() -> Result<Vec<String>> {
-    let packs_dir = get_rule_packs_dir();
+    let packs_dir = get_rule_packs_dir()?;
     
     if !packs_dir.exists() {
         return Ok(Vec::new());
@@ -98,7 +169,7 @@ pub fn list_rule_packs() -> Result<Vec<String>> {
 
 /// Read pack.json for a given pack
 pub fn read_pack_json(pack_id: String) -> Result<String> {
-    let pack_json_path = get_rule_packs_dir().join(&pack_id).join("pack.json");
+    let pack_json_path = get_rule_packs_dir()?.join(&pack_id).join("pack.json");
     
     if !pack_json_path.exists() {
         return Err(FsError::NotFound(format!("Pack not found: {}", pack_id)));
@@ -110,7 +181,7 @@ pub fn read_pack_json(pack_id: String) -> Result<String> {
 
 /// Read all pack markdown files and concatenate
 pub fn read_pack_content(pack_id: String) -> Result<String> {
-    let pack_dir = get_rule_packs_dir().join(&pack_id);
+    let pack_dir = get_rule_packs_dir()?.join(&pack_id);
     
     // First read pack.json to get file list
     let pack_json_content = read_pack_json(pack_id.clone())?;
@@ -122,20 +193,20 @@ pub fn read_pack_content(pack_id: String) -> Result<String> {
         if !file_path.exists() {
             return Err(FsError::NotFound(format!("Pack file not found: {}", file)));
         }
-        let content = fs::read_to_string(&file_path)?;
+        let content = read_file_guarded(&file_path)?;
         contents.push(content);
     }
     
     Ok(contents.join("\n\n---\n\n"))
 }
 
-fn pack_out_ref_overrides_path() -> PathBuf {
-    get_rule_packs_dir().join("out-references.json")
+fn pack_out_ref_overrides_path() -> DeploymentResult<PathBuf> {
+    Ok(get_rule_packs_dir()?.join("out-references.json"))
 }
 
 /// Load persisted out-reference overrides for rule packs
 pub fn read_pack_out_ref_overrides() -> Result<HashMap<String, Vec<String>>> {
-    let path = pack_out_ref_overrides_path();
+    let path = pack_out_ref_overrides_path()?;
     if !path.exists() {
         return Ok(HashMap::new());
     }
@@ -147,7 +218,7 @@ pub fn read_pack_out_ref_overrides() -> Result<HashMap<String, Vec<String>>> {
 
 /// Persist out-reference overrides for rule packs
 pub fn write_pack_out_ref_overrides(map: &HashMap<String, Vec<String>>) -> Result<()> {
-    let path = pack_out_ref_overrides_path();
+    let path = pack_out_ref_overrides_path()?;
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)?;
     }
@@ -157,7 +228,318 @@ pub fn write_pack_out_ref_overrides(map: &HashMap<String, Vec<String>>) -> Resul
     Ok(())
 }
 
-/// Get agent's config directory path (expands ~)
+/// `path` rewritten to `new_path` when it refers to the out-reference at `old_path`
+///
+/// Matches `old_path` itself or a path ending in `/old_path` (e.g. `out-references/templates/old.md`),
+/// keeping whatever prefix it had.
+pub fn migrate_out_ref_path(path: &str, old_path: &str, new_path: &str) -> Option<String> {
+    let prefix = path.strip_suffix(old_path)?;
+    if prefix.is_empty() || prefix.ends_with('/') {
+        Some(format!("{}{}", prefix, new_path))
+    } else {
+        None
+    }
+}
+
+/// Rewrite every entry of `paths` that refers to `old_path`; returns whether any changed
+fn migrate_out_ref_paths(paths: &mut [String], old_path: &str, new_path: &str) -> bool {
+    let mut changed = false;
+    for path in paths.iter_mut() {
+        if let Some(migrated) = migrate_out_ref_path(path, old_path, new_path) {
+            *path = migrated;
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Point pack out-reference overrides at an out-reference's new path after it moves
+///
+/// Entries equal to `old_path`, or ending in `/old_path` (e.g. `out-references/templates/old.md`),
+/// have that part replaced with `new_path`. Returns the ids of the packs that changed, sorted.
+pub fn migrate_pack_out_ref_overrides(old_path: &str, new_path: &str) -> Result<Vec<String>> {
+    let mut overrides = read_pack_out_ref_overrides()?;
+
+    let mut changed = Vec::new();
+    for (pack_id, paths) in overrides.iter_mut() {
+        if migrate_out_ref_paths(paths, old_path, new_path) {
+            changed.push(pack_id.clone());
+        }
+    }
+
+    if !changed.is_empty() {
+        write_pack_out_ref_overrides(&overrides)?;
+    }
+    changed.sort();
+    Ok(changed)
+}
+
+/// Point the `outReferences` listed in each pack's `pack.json` at an out-reference's new path
+///
+/// Matches entries like `migrate_pack_out_ref_overrides`. Returns the ids of the packs that
+/// changed, sorted.
+pub fn migrate_pack_json_out_references(old_path: &str, new_path: &str) -> Result<Vec<String>> {
+    let packs_dir = get_rule_packs_dir()?;
+
+    let mut changed = Vec::new();
+    for pack_id in list_rule_packs()? {
+        let pack_json_path = packs_dir.join(&pack_id).join("pack.json");
+        let mut pack: RulePack = match serde_json::from_str(&fs::read_to_string(&pack_json_path)?) {
+            Ok(pack) => pack,
+            Err(e) => {
+                log::warn!("Skipping out-reference migration for pack {}: {}", pack_id, e);
+                continue;
+            }
+        };
+        if migrate_out_ref_paths(&mut pack.out_references, old_path, new_path) {
+            fs::write(&pack_json_path, serde_json::to_string_pretty(&pack)? + "\n")?;
+            changed.push(pack_id);
+        }
+    }
+
+    changed.sort();
+    Ok(changed)
+}
+
+/// Load a pack's metadata, with its out-references replaced by any saved override
+pub fn load_pack(pack_id: &str) -> std::result::Result<RulePack, String> {
+    let json_str = read_pack_json(pack_id.to_string())
+        .map_err(|e| format!("Failed to load pack {}: {}", pack_id, e))?;
+
+    let mut pack: RulePack = serde_json::from_str::<RulePack>(&json_str)
+        .map_err(|e| format!("Failed to parse pack.json: {}", e))?;
+
+    if let Ok(overrides) = read_pack_out_ref_overrides() {
+        if let Some(refs) = overrides.get(&pack.id) {
+            pack.out_references = refs.clone();
+        }
+    }
+
+    Ok(pack)
+}
+
+/// Load a pack with its concatenated content and measured size
+pub fn load_pack_full(pack_id: &str) -> std::result::Result<LoadedPack, String> {
+    let pack = load_pack(pack_id)?;
+    let content = read_pack_content(pack_id.to_string())
+        .map_err(|e| format!("Failed to load pack content: {}", e))?;
+
+    let actual_word_count = content.split_whitespace().count() as u64;
+    let actual_character_count = crate::budget::char_count(&content);
+    let pack_path = get_rule_packs_dir().map_err(|e| e.to_string())?.join(pack_id);
+
+    Ok(LoadedPack {
+        id: pack.id,
+        name: pack.name,
+        version: pack.version,
+        description: pack.description,
+        dependencies: pack.dependencies,
+        target_agents: pack.target_agents,
+        files: pack.files,
+        out_references: pack.out_references,
+        metadata: pack.metadata,
+        path: pack_path.to_string_lossy().to_string(),
+        content,
+        actual_word_count,
+        actual_character_count,
+    })
+}
+
+/// Order a pack after its dependencies, reporting (rather than failing on) cycles and version
+/// mismatches
+pub fn resolve_dependencies(pack_id: &str) -> std::result::Result<DependencyResolution, String> {
+    // Simplified dependency resolution
+    let mut order = Vec::new();
+    let mut visited = HashSet::new();
+
+    fn resolve_recursive(
+        id: String,
+        visited: &mut HashSet<String>,
+        order: &mut Vec<String>,
+        path: &mut Vec<String>,
+    ) -> std::result::Result<(), String> {
+        if visited.contains(&id) {
+            return Err(format!("Circular dependency detected: {}", path.join(" -> ")));
+        }
+
+        visited.insert(id.clone());
+        path.push(id.clone());
+
+        let json_str = read_pack_json(id.clone())
+            .map_err(|e| format!("Failed to load pack: {}", e))?;
+        let pack: RulePack = serde_json::from_str(&json_str)
+            .map_err(|e| format!("Failed to parse pack: {}", e))?;
+
+        for dep in &pack.dependencies {
+            if let Some(mismatch) = dependency_version_mismatch(&id, dep) {
+                return Err(mismatch);
+            }
+            if !order.contains(&dep.id) {
+                resolve_recursive(dep.id.clone(), visited, order, path)?;
+            }
+        }
+
+        if !order.contains(&id) {
+            order.push(id.clone());
+        }
+
+        path.pop();
+        visited.remove(&id);
+        Ok(())
+    }
+
+    let mut path = Vec::new();
+    match resolve_recursive(pack_id.to_string(), &mut visited, &mut order, &mut path) {
+        Ok(_) => Ok(DependencyResolution {
+            order,
+            success: true,
+            error: None,
+            circular_path: None,
+        }),
+        Err(e) => Ok(DependencyResolution {
+            order: Vec::new(),
+            success: false,
+            error: Some(e),
+            circular_path: Some(path),
+        }),
+    }
+}
+
+/// Parse a pack version, accepting short forms like `1` or `1.0`
+fn parse_pack_version(version: &str) -> Option<semver::Version> {
+    let version = version.trim().trim_start_matches('v');
+    let core_len = version.find(['-', '+']).unwrap_or(version.len());
+    let padding = 2usize.saturating_sub(version[..core_len].matches('.').count());
+    let padded = format!("{}{}{}", &version[..core_len], ".0".repeat(padding), &version[core_len..]);
+    semver::Version::parse(&padded).ok()
+}
+
+/// Describe why an installed dependency doesn't satisfy `dep`'s version requirement
+///
+/// Returns `None` when there is no requirement, it is met, or the dependency is
+/// missing (reported separately).
+fn dependency_version_mismatch(pack_id: &str, dep: &Dependency) -> Option<String> {
+    let requirement = dep.version_req.as_deref()?;
+    let req = match semver::VersionReq::parse(requirement) {
+        Ok(req) => req,
+        Err(e) => {
+            return Some(format!(
+                "Pack {} has an invalid version requirement for {} ({}): {}",
+                pack_id, dep.id, requirement, e
+            ))
+        }
+    };
+
+    let json_str = read_pack_json(dep.id.clone()).ok()?;
+    let resolved: RulePack = serde_json::from_str(&json_str).ok()?;
+    match parse_pack_version(&resolved.version) {
+        Some(version) if req.matches(&version) => None,
+        Some(_) => Some(format!(
+            "Pack {} requires {} {}, but version {} is installed",
+            pack_id, dep.id, requirement, resolved.version
+        )),
+        None => Some(format!(
+            "Pack {} requires {} {}, but its version {} is not valid semver",
+            pack_id, dep.id, requirement, resolved.version
+        )),
+    }
+}
+
+/// Check a pack's files, dependencies and out-reference overrides
+pub fn validate_pack(pack_id: &str) -> std::result::Result<PackValidationResult, String> {
+    // Basic validation - check if pack exists and is parseable
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    let mut files = Vec::new();
+    
+    match load_pack(pack_id) {
+        Ok(pack) => {
+            // Check files exist and have content; an empty file becomes a dangling import
+            let pack_dir = get_rule_packs_dir().map_err(|e| e.to_string())?.join(pack_id);
+            
+            for file in &pack.files {
+                let file_path = pack_dir.join(file);
+                let exists = file_path.exists();
+                let content = read_file_guarded(&file_path);
+                let non_empty = content
+                    .as_ref()
+                    .map(|content| !content.trim().is_empty())
+                    .unwrap_or(false);
+                if !exists {
+                    errors.push(PackValidationError {
+                        pack_id: pack_id.to_string(),
+                        message: format!("File not found: {}", file),
+                        severity: "error".to_string(),
+                        file: Some(file.clone()),
+                    });
+                } else if let Err(e @ FsError::TooLarge { .. }) = &content {
+                    errors.push(PackValidationError {
+                        pack_id: pack_id.to_string(),
+                        message: e.to_string(),
+                        severity: "error".to_string(),
+                        file: Some(file.clone()),
+                    });
+                } else if !non_empty {
+                    warnings.push(PackValidationError {
+                        pack_id: pack_id.to_string(),
+                        message: format!("Empty file: {} contributes no content to AGENTS.md", file),
+                        severity: "warning".to_string(),
+                        file: Some(file.clone()),
+                    });
+                }
+                files.push(PackFileStatus {
+                    file: file.clone(),
+                    exists,
+                    non_empty,
+                });
+            }
+            
+            // Check dependencies exist and satisfy their version requirements
+            for dep in &pack.dependencies {
+                let message = if read_pack_json(dep.id.clone()).is_err() {
+                    format!("Dependency not found: {}", dep.id)
+                } else if let Some(mismatch) = dependency_version_mismatch(pack_id, dep) {
+                    mismatch
+                } else {
+                    continue;
+                };
+                errors.push(PackValidationError {
+                    pack_id: pack_id.to_string(),
+                    message,
+                    severity: "error".to_string(),
+                    file: None,
+                });
+            }
+
+            // Check out-reference overrides resolve to tracked references
+            for message in crate::out_reference_manager::validate_pack_out_ref_overrides(pack_id)? {
+                warnings.push(PackValidationError {
+                    pack_id: pack_id.to_string(),
+                    message,
+                    severity: "warning".to_string(),
+                    file: None,
+                });
+            }
+        }
+        Err(e) => {
+            errors.push(PackValidationError {
+                pack_id: pack_id.to_string(),
+                message: e,
+                severity: "error".to_string(),
+                file: None,
+            });
+        }
+    }
+    
+    Ok(PackValidationResult {
+        valid: errors.is_empty(),
+        errors,
+        warnings,
+        files,
+    })
+}
+
+/// Get agent's config directory path (expands ~ and environment variables)
 pub fn get_agent_config_path(agent_id: String) -> Result<PathBuf> {
     let agents = load_agent_registry()?;
     let agent = agents
@@ -173,6 +555,51 @@ pub fn get_agent_config_path(agent_id: String) -> Result<PathBuf> {
     expand_path(config_path)
 }
 
+/// Inspect the directory holding an agent's config, noting whether it is a symlink
+///
+/// A config directory symlinked to a synced location (e.g. `~/.cursor` into a
+/// cloud drive) can change how links created inside it behave.
+pub fn inspect_agent_dir(agent_id: &str) -> Result<AgentDirInfo> {
+    let config_path = get_agent_config_path(agent_id.to_string())?;
+    let dir = config_path.parent().map(Path::to_path_buf).unwrap_or(config_path);
+
+    let is_symlink = fs::symlink_metadata(&dir)
+        .map(|m| m.file_type().is_symlink())
+        .unwrap_or(false);
+    let resolved_target = if is_symlink {
+        fs::canonicalize(&dir).ok().map(|p| p.to_string_lossy().to_string())
+    } else {
+        None
+    };
+
+    Ok(AgentDirInfo {
+        path: dir.to_string_lossy().to_string(),
+        exists: dir.exists(),
+        is_symlink,
+        resolved_target,
+    })
+}
+
+/// Get all of an agent's config paths (expands ~ and environment variables)
+pub fn get_agent_config_paths(agent_id: String) -> Result<Vec<PathBuf>> {
+    let agents = load_agent_registry()?;
+    let agent = agents
+        .into_iter()
+        .find(|a| a.id == agent_id)
+        .ok_or_else(|| FsError::NotFound(format!("Agent not found: {}", agent_id)))?;
+
+    expand_agent_config_paths(&agent)
+}
+
+/// Expand every config path declared by an agent
+pub fn expand_agent_config_paths(agent: &AgentDefinition) -> Result<Vec<PathBuf>> {
+    if agent.config_paths.is_empty() {
+        return Err(FsError::InvalidPath(format!("No config paths defined for agent {}", agent.id)));
+    }
+
+    agent.config_paths.iter().map(|p| expand_path(p)).collect()
+}
+
 /// Check if a path exists
 pub fn check_path_exists(path: String) -> Result<bool> {
     let path_buf = Path::new(&path);
@@ -180,22 +607,142 @@ pub fn check_path_exists(path: String) -> Result<bool> {
 }
 
 /// Load agent registry from bundled JSON export
+///
+/// Malformed agent entries are skipped and logged rather than failing the whole registry.
 pub fn load_agent_registry() -> Result<Vec<AgentDefinition>> {
-    serde_json::from_str(AGENT_REGISTRY_JSON).map_err(FsError::JsonParse)
+    let (agents, warnings) = parse_agent_registry(AGENT_REGISTRY_JSON)?;
+    for warning in warnings {
+        log::warn!("{}", warning);
+    }
+    Ok(agents)
+}
+
+/// Parse registry JSON, returning valid agents and a warning per skipped entry
+pub fn parse_agent_registry(json: &str) -> Result<(Vec<AgentDefinition>, Vec<String>)> {
+    let entries: Vec<serde_json::Value> = serde_json::from_str(json)?;
+
+    let mut agents = Vec::new();
+    let mut warnings = Vec::new();
+    for (index, entry) in entries.into_iter().enumerate() {
+        let label = entry
+            .get("id")
+            .and_then(|id| id.as_str())
+            .map(|id| id.to_string())
+            .unwrap_or_else(|| format!("#{}", index));
+
+        match serde_json::from_value::<AgentDefinition>(entry) {
+            Ok(agent) => agents.push(agent),
+            Err(e) => warnings.push(format!("Skipping malformed agent registry entry {}: {}", label, e)),
+        }
+    }
+
+    Ok((agents, warnings))
+}
+
+/// Deployment strategies the deployers know how to apply
+const KNOWN_DEPLOYMENT_STRATEGIES: &[&str] = &["symlink", "copy", "inline", "api"];
+
+/// Check the bundled agent registry as a whole
+pub fn validate_agent_registry() -> Result<AgentRegistryReport> {
+    check_agent_registry(AGENT_REGISTRY_JSON)
+}
+
+/// Check registry JSON for duplicate IDs, malformed entries, unknown deployment
+/// strategies, and config paths claimed by more than one agent
+///
+/// Only fails when the JSON isn't an array; every other problem goes in the report.
+/// Unknown strategies are warnings, since deployers treat them as non-file-based.
+pub fn check_agent_registry(json: &str) -> Result<AgentRegistryReport> {
+    let (agents, malformed) = parse_agent_registry(json)?;
+    let mut errors = malformed;
+    let mut warnings = Vec::new();
+
+    let mut ids: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut path_owners: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for agent in &agents {
+        *ids.entry(agent.id.as_str()).or_default() += 1;
+        if agent.id.trim().is_empty() {
+            errors.push(format!("Agent \"{}\" has an empty ID", agent.name));
+        }
+        if agent.name.trim().is_empty() {
+            errors.push(format!("[{}] Agent name cannot be empty", agent.id));
+        }
+        if agent.config_paths.is_empty() {
+            errors.push(format!("[{}] Agent must have at least one config path", agent.id));
+        }
+        if !KNOWN_DEPLOYMENT_STRATEGIES.contains(&agent.deployment_strategy.as_str()) {
+            warnings.push(format!(
+                "[{}] Unknown deployment strategy '{}' (expected one of: {})",
+                agent.id,
+                agent.deployment_strategy,
+                KNOWN_DEPLOYMENT_STRATEGIES.join(", ")
+            ));
+        }
+
+        for path in &agent.config_paths {
+            let normalized = path.trim().trim_end_matches(['/', '\\']);
+            let owners = path_owners.entry(normalized).or_default();
+            if !owners.contains(&agent.id.as_str()) {
+                owners.push(&agent.id);
+            }
+        }
+    }
+
+    for (id, count) in ids {
+        if count > 1 && !id.trim().is_empty() {
+            errors.push(format!("Duplicate agent ID: {} ({} entries)", id, count));
+        }
+    }
+    for (path, owners) in path_owners {
+        if owners.len() > 1 {
+            errors.push(format!("Config path {} is shared by agents: {}", path, owners.join(", ")));
+        }
+    }
+
+    Ok(AgentRegistryReport {
+        valid: errors.is_empty(),
+        agent_count: agents.len(),
+        errors,
+        warnings,
+    })
+}
+
+/// Expand `$VAR`, `${VAR}`, and `%VAR%` environment variables in a path
+///
+/// Both syntaxes are expanded on every platform, since the agent registry is shared
+/// across them. Variables that aren't set are left as written.
+pub fn expand_env_vars(path: &str) -> String {
+    static ENV_VAR: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}|\$([A-Za-z_][A-Za-z0-9_]*)|%([A-Za-z_][A-Za-z0-9_()]*)%")
+            .unwrap()
+    });
+
+    ENV_VAR
+        .replace_all(path, |caps: &Captures| {
+            let name = caps
+                .get(1)
+                .or_else(|| caps.get(2))
+                .or_else(|| caps.get(3))
+                .map(|m| m.as_str())
+                .unwrap_or_default();
+            std::env::var(name).unwrap_or_else(|_| caps[0].to_string())
+        })
+        .into_owned()
 }
 
 fn expand_path(path: &str) -> Result<PathBuf> {
-    let trimmed = path.trim();
+    let expanded = expand_env_vars(path);
+    let trimmed = expanded.trim();
 
     if let Some(stripped) = trimmed.strip_prefix("~/") {
         let home = home_dir()
-            .ok_or_else(|| FsError::InvalidPath("Could not find home directory".to_string()))?;
+            .map_err(|_| FsError::InvalidPath("Could not find home directory".to_string()))?;
         return Ok(home.join(stripped));
     }
 
     if trimmed == "~" {
         let home = home_dir()
-            .ok_or_else(|| FsError::InvalidPath("Could not find home directory".to_string()))?;
+            .map_err(|_| FsError::InvalidPath("Could not find home directory".to_string()))?;
         return Ok(home);
     }
 
@@ -204,7 +751,101 @@ fn expand_path(path: &str) -> Result<PathBuf> {
         Ok(path_buf)
     } else {
         let home = home_dir()
-            .ok_or_else(|| FsError::InvalidPath("Could not find home directory".to_string()))?;
+            .map_err(|_| FsError::InvalidPath("Could not find home directory".to_string()))?;
         Ok(home.join(path_buf))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_agent_registry_skips_malformed_entries() {
+        let json = r#"[
+            {
+                "id": "cursor",
+                "name": "Cursor",
+                "configPaths": ["~/.cursor/commands"],
+                "agentsMdSupport": "native",
+                "commandFormat": "slash",
+                "characterLimits": { "maxChars": 1000000, "supportsOutReferences": true },
+                "deploymentStrategy": "symlink",
+                "buildOutput": "cursor/commands",
+                "fileFormat": "markdown"
+            },
+            { "id": "broken", "name": "Broken Agent" }
+        ]"#;
+
+        let (agents, warnings) = parse_agent_registry(json).unwrap();
+
+        assert_eq!(agents.len(), 1);
+        assert_eq!(agents[0].id, "cursor");
+        assert_eq!(agents[0].character_limits.warn_threshold_pct, DEFAULT_WARN_THRESHOLD_PCT);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("broken"));
+    }
+
+    #[test]
+    fn test_check_agent_registry_reports_shared_config_path() {
+        let agent = |id: &str, path: &str, strategy: &str| {
+            serde_json::json!({
+                "id": id,
+                "name": id,
+                "configPaths": [path],
+                "agentsMdSupport": "native",
+                "commandFormat": "slash",
+                "characterLimits": { "maxChars": null, "supportsOutReferences": true },
+                "deploymentStrategy": strategy,
+                "buildOutput": id,
+                "fileFormat": "markdown"
+            })
+        };
+        let json = serde_json::json!([
+            agent("cursor", "~/.cursor/commands", "symlink"),
+            agent("fork", "~/.cursor/commands/", "copy"),
+            agent("claude", "~/.claude/commands", "teleport"),
+            agent("claude", "~/.claude/skills", "symlink"),
+        ])
+        .to_string();
+
+        let report = check_agent_registry(&json).unwrap();
+        assert!(!report.valid);
+        assert_eq!(report.agent_count, 4);
+        assert!(report
+            .errors
+            .contains(&"Config path ~/.cursor/commands is shared by agents: cursor, fork".to_string()));
+        assert!(report.errors.contains(&"Duplicate agent ID: claude (2 entries)".to_string()));
+        assert_eq!(report.errors.len(), 2);
+        assert_eq!(report.warnings.len(), 1);
+        assert!(report.warnings[0].starts_with("[claude] Unknown deployment strategy 'teleport'"));
+
+        assert!(validate_agent_registry().unwrap().valid);
+    }
+
+    #[test]
+    fn test_parse_agent_registry_rejects_non_array() {
+        assert!(parse_agent_registry("{\"agents\": []}").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_expand_path_expands_unix_env_vars() {
+        let home = PathBuf::from(std::env::var("HOME").unwrap());
+
+        assert_eq!(expand_path("$HOME/x").unwrap(), home.join("x"));
+        assert_eq!(expand_path("${HOME}/x").unwrap(), home.join("x"));
+        assert_eq!(
+            expand_env_vars("/opt/$AGENTSTOOLKIT_UNSET_VAR/x"),
+            "/opt/$AGENTSTOOLKIT_UNSET_VAR/x"
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_expand_path_expands_windows_env_vars() {
+        let profile = PathBuf::from(std::env::var("USERPROFILE").unwrap());
+
+        assert_eq!(expand_path("%USERPROFILE%\\x").unwrap(), profile.join("x"));
+    }
+}
@@ -1,16 +1,23 @@
+use crate::budget;
 use crate::command_registry;
 use crate::deployment::{
-    self, AgentStatus, DeploymentConfig, DeploymentManager, DeploymentOutput,
-    PreparedDeployment, ValidationReport,
+    self, BackupEntry, BackupManager, DeployableAgent, DeploymentConfig,
+    DeploymentManager, DeploymentOutput, DeploymentProgress, DeploymentStatusInfo, ErrorCode,
+    IpcError, PreparedDeployment, RollbackPlan, TargetLevel, ValidationReport,
 };
-use crate::deployment::state::DeploymentState;
+use crate::deployment::command_loader;
+use crate::deployment::converters;
+use crate::deployment::state::{DeploymentHistoryPage, DeploymentState, FailedDeployment, StateManager};
 use crate::fs_manager;
+use crate::packs;
 use crate::symlink::{self, SymlinkError};
 use crate::types::*;
-use std::collections::HashSet;
+use crate::util;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
+use tauri::Emitter;
 use once_cell::sync::Lazy;
 
 // Global deployment manager instance
@@ -18,172 +25,10 @@ static DEPLOYMENT_MANAGER: Lazy<Mutex<Option<DeploymentManager>>> = Lazy::new(||
     Mutex::new(DeploymentManager::new().ok())
 });
 
-fn get_deployment_manager() -> Result<std::sync::MutexGuard<'static, Option<DeploymentManager>>, String> {
-    DEPLOYMENT_MANAGER.lock().map_err(|e| format!("Failed to acquire lock: {}", e))
-}
-
-fn load_pack_full_internal(pack_id: &str) -> Result<LoadedPack, String> {
-    let pack = load_pack(pack_id.to_string())?;
-    let content = fs_manager::read_pack_content(pack_id.to_string())
-        .map_err(|e| format!("Failed to load pack content: {}", e))?;
-
-    let actual_word_count = content.split_whitespace().count() as u64;
-    let actual_character_count = content.len() as u64;
-    let pack_path = fs_manager::get_rule_packs_dir().join(pack_id);
-
-    Ok(LoadedPack {
-        id: pack.id,
-        name: pack.name,
-        version: pack.version,
-        description: pack.description,
-        dependencies: pack.dependencies,
-        target_agents: pack.target_agents,
-        files: pack.files,
-        out_references: pack.out_references,
-        metadata: pack.metadata,
-        path: pack_path.to_string_lossy().to_string(),
-        content,
-        actual_word_count,
-        actual_character_count,
-    })
-}
-
-fn resolve_dependencies_internal(pack_id: String) -> Result<DependencyResolution, String> {
-    // Simplified dependency resolution
-    let mut order = Vec::new();
-    let mut visited = HashSet::new();
-
-    fn resolve_recursive(
-        id: String,
-        visited: &mut HashSet<String>,
-        order: &mut Vec<String>,
-        path: &mut Vec<String>,
-    ) -> Result<(), String> {
-        if visited.contains(&id) {
-            return Err(format!("Circular dependency detected: {}", path.join(" -> ")));
-        }
-
-        visited.insert(id.clone());
-        path.push(id.clone());
-
-        let json_str = fs_manager::read_pack_json(id.clone())
-            .map_err(|e| format!("Failed to load pack: {}", e))?;
-        let pack: RulePack = serde_json::from_str(&json_str)
-            .map_err(|e| format!("Failed to parse pack: {}", e))?;
-
-        for dep_id in &pack.dependencies {
-            if !order.contains(dep_id) {
-                resolve_recursive(dep_id.clone(), visited, order, path)?;
-            }
-        }
-
-        if !order.contains(&id) {
-            order.push(id.clone());
-        }
-
-        path.pop();
-        visited.remove(&id);
-        Ok(())
-    }
-
-    let mut path = Vec::new();
-    match resolve_recursive(pack_id, &mut visited, &mut order, &mut path) {
-        Ok(_) => Ok(DependencyResolution {
-            order,
-            success: true,
-            error: None,
-            circular_path: None,
-        }),
-        Err(e) => Ok(DependencyResolution {
-            order: Vec::new(),
-            success: false,
-            error: Some(e),
-            circular_path: Some(path),
-        }),
-    }
-}
-
-fn empty_budget_info() -> BudgetInfo {
-    BudgetInfo {
-        total_chars: 0,
-        max_chars: None,
-        percentage: None,
-        within_limit: true,
-        pack_breakdown: Vec::new(),
-    }
-}
-
-fn get_agent_char_limit(agent_id: &str) -> Option<u64> {
-    if let Ok(agents) = fs_manager::load_agent_registry() {
-        if let Some(agent) = agents
-            .iter()
-            .find(|a| a.id.eq_ignore_ascii_case(agent_id))
-        {
-            if let Some(max) = agent.character_limits.max_chars {
-                return Some(max);
-            }
-        }
-    }
-
-    match agent_id.to_lowercase().as_str() {
-        "cursor" => Some(1_000_000),
-        "claude" => Some(200_000),
-        "copilot" => Some(8_000),
-        "gemini" => Some(1_000_000),
-        "codex" => Some(50_000),
-        _ => None,
-    }
-}
-
-fn calculate_budget_internal(
-    pack_ids: &[String],
-    agent_id: Option<String>,
-) -> Result<BudgetInfo, String> {
-    let mut pack_breakdown: Vec<PackBudgetItem> = Vec::new();
-    let mut total_chars: u64 = 0;
-    let mut total_words: u64 = 0;
-    let mut seen: HashSet<String> = HashSet::new();
-
-    for pack_id in pack_ids {
-        let resolution = resolve_dependencies_internal(pack_id.clone())?;
-        if !resolution.success {
-            return Err(resolution.error.unwrap_or_else(|| "Failed to resolve dependencies".into()));
-        }
-
-        for id in resolution.order {
-            if seen.insert(id.clone()) {
-                let pack = load_pack_full_internal(&id)?;
-                total_chars += pack.actual_character_count;
-                total_words += pack.actual_word_count;
-                pack_breakdown.push(PackBudgetItem {
-                    pack_id: id,
-                    chars: pack.actual_character_count,
-                    words: pack.actual_word_count,
-                    percentage_of_total: 0,
-                });
-            }
-        }
-    }
-
-    for item in pack_breakdown.iter_mut() {
-        if total_chars > 0 {
-            item.percentage_of_total = ((item.chars as f64 / total_chars as f64) * 100.0).round() as u64;
-        }
-    }
-
-    let max_chars = agent_id
-        .as_ref()
-        .and_then(|id| get_agent_char_limit(id));
-    let percentage = max_chars.map(|max| ((total_chars as f64 / max as f64) * 100.0).round() as u64);
-    let within_limit = max_chars.map(|max| total_chars <= max).unwrap_or(true);
-
-    Ok(BudgetInfo {
-        total_chars,
-        max_chars,
-        percentage,
-        within_limit,
-        pack_breakdown,
-    })
+fn get_deployment_manager() -> Result<std::sync::MutexGuard<'static, Option<DeploymentManager>>, IpcError> {
+    DEPLOYMENT_MANAGER
+        .lock()
+        .map_err(|e| IpcError::new(ErrorCode::Internal, format!("Failed to acquire lock: {}", e)))
 }
 
 /// Get all agents from the registry
@@ -200,20 +45,55 @@ pub fn get_agent_by_id(id: String) -> Result<Option<AgentDefinition>, String> {
     Ok(agents.into_iter().find(|agent| agent.id == id))
 }
 
+/// Command formats the deployers know how to produce
+const KNOWN_COMMAND_FORMATS: &[&str] = &["slash", "prompts-prefix", "cli", "workflow", "inline"];
+
 /// Validate an agent definition
 #[tauri::command]
-pub fn validate_agent(agent: AgentDefinition) -> Result<(), String> {
-    // Basic validation
+pub fn validate_agent(agent: AgentDefinition) -> AgentValidationResult {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+
     if agent.id.is_empty() {
-        return Err("Agent ID cannot be empty".to_string());
+        errors.push("Agent ID cannot be empty".to_string());
     }
     if agent.name.is_empty() {
-        return Err("Agent name cannot be empty".to_string());
+        errors.push("Agent name cannot be empty".to_string());
     }
     if agent.config_paths.is_empty() {
-        return Err("Agent must have at least one config path".to_string());
+        errors.push("Agent must have at least one config path".to_string());
+    }
+
+    let mut seen_paths = HashSet::new();
+    for path in &agent.config_paths {
+        if !seen_paths.insert(path.trim()) {
+            errors.push(format!("Duplicate config path: {}", path));
+        }
+    }
+
+    if !KNOWN_COMMAND_FORMATS.contains(&agent.command_format.as_str()) {
+        warnings.push(format!(
+            "Unrecognized command format '{}' (expected one of: {})",
+            agent.command_format,
+            KNOWN_COMMAND_FORMATS.join(", ")
+        ));
+    }
+
+    if agent.character_limits.max_chars == Some(0) {
+        errors.push("Character limit maxChars must be positive".to_string());
+    }
+
+    AgentValidationResult {
+        valid: errors.is_empty(),
+        errors,
+        warnings,
     }
-    Ok(())
+}
+
+/// Validate the whole agent registry for duplicate IDs, shared config paths, and unknown strategies
+#[tauri::command]
+pub fn validate_agent_registry() -> Result<AgentRegistryReport, String> {
+    fs_manager::validate_agent_registry().map_err(|e| format!("Failed to validate agent registry: {}", e))
 }
 
 /// List all available rule packs
@@ -225,6 +105,7 @@ pub fn list_available_packs() -> Result<Vec<RulePack>, String> {
         .unwrap_or_default();
     
     let mut packs = Vec::new();
+    let mut declared_ids = Vec::new();
     for pack_id in pack_ids {
         match fs_manager::read_pack_json(pack_id.clone()) {
             Ok(json_str) => {
@@ -233,6 +114,7 @@ pub fn list_available_packs() -> Result<Vec<RulePack>, String> {
                         if let Some(refs) = overrides.get(&pack.id) {
                             pack.out_references = refs.clone();
                         }
+                        declared_ids.push((pack_id.clone(), pack.id.clone()));
                         packs.push(pack)
                     }
                     Err(e) => {
@@ -245,26 +127,28 @@ pub fn list_available_packs() -> Result<Vec<RulePack>, String> {
             }
         }
     }
+
+    for duplicate in packs::duplicate_ids(declared_ids) {
+        log::warn!(
+            "Pack id '{}' is declared by multiple directories: {}",
+            duplicate.pack_id,
+            duplicate.directories.join(", ")
+        );
+    }
     
     Ok(packs)
 }
 
+/// Find pack ids declared by more than one pack directory
+#[tauri::command]
+pub fn find_duplicate_pack_ids() -> Result<Vec<packs::DuplicatePackId>, String> {
+    packs::find_duplicate_ids()
+}
+
 /// Load a pack's metadata
 #[tauri::command]
 pub fn load_pack(pack_id: String) -> Result<RulePack, String> {
-    let json_str = fs_manager::read_pack_json(pack_id.clone())
-        .map_err(|e| format!("Failed to load pack {}: {}", pack_id, e))?;
-
-    let mut pack: RulePack = serde_json::from_str::<RulePack>(&json_str)
-        .map_err(|e| format!("Failed to parse pack.json: {}", e))?;
-
-    if let Ok(overrides) = fs_manager::read_pack_out_ref_overrides() {
-        if let Some(refs) = overrides.get(&pack.id) {
-            pack.out_references = refs.clone();
-        }
-    }
-
-    Ok(pack)
+    fs_manager::load_pack(&pack_id)
 }
 
 /// Update out-references linked to a pack and return updated metadata
@@ -284,66 +168,35 @@ pub fn update_pack_out_references(
     fs_manager::write_pack_out_ref_overrides(&overrides)
         .map_err(|e| e.to_string())?;
 
+    for warning in out_reference_manager::validate_pack_out_ref_overrides(&pack_id)? {
+        log::warn!("[{}] {}", pack_id, warning);
+    }
+
     load_pack(pack_id)
 }
 
 /// Load a pack with full content
 #[tauri::command]
 pub fn load_pack_full(pack_id: String) -> Result<LoadedPack, String> {
-    load_pack_full_internal(&pack_id)
+    fs_manager::load_pack_full(&pack_id)
+}
+
+/// Get the JSON Schema for `pack.json`
+#[tauri::command]
+pub fn get_rulepack_schema() -> serde_json::Value {
+    crate::types::rulepack_schema()
 }
 
 /// Validate a pack
 #[tauri::command]
 pub fn validate_pack(pack_id: String) -> Result<PackValidationResult, String> {
-    // Basic validation - check if pack exists and is parseable
-    let mut errors = Vec::new();
-    let mut warnings = Vec::new();
-    
-    match load_pack(pack_id.clone()) {
-        Ok(pack) => {
-            // Check files exist
-            let pack_dir = fs_manager::get_rule_packs_dir().join(&pack_id);
-            
-            for file in &pack.files {
-                let file_path = pack_dir.join(file);
-                if !file_path.exists() {
-                    errors.push(PackValidationError {
-                        pack_id: pack_id.clone(),
-                        message: format!("File not found: {}", file),
-                        severity: "error".to_string(),
-                        file: Some(file.clone()),
-                    });
-                }
-            }
-            
-            // Check dependencies exist
-            for dep_id in &pack.dependencies {
-                if fs_manager::read_pack_json(dep_id.clone()).is_err() {
-                    errors.push(PackValidationError {
-                        pack_id: pack_id.clone(),
-                        message: format!("Dependency not found: {}", dep_id),
-                        severity: "error".to_string(),
-                        file: None,
-                    });
-                }
-            }
-        }
-        Err(e) => {
-            errors.push(PackValidationError {
-                pack_id: pack_id.clone(),
-                message: e,
-                severity: "error".to_string(),
-                file: None,
-            });
-        }
-    }
-    
-    Ok(PackValidationResult {
-        valid: errors.is_empty(),
-        errors,
-        warnings,
-    })
+    fs_manager::validate_pack(&pack_id)
+}
+
+/// Compare two packs' content and report their overlap
+#[tauri::command]
+pub fn diff_packs(pack_a: String, pack_b: String) -> Result<packs::PackDiff, String> {
+    packs::diff(&pack_a, &pack_b)
 }
 
 /// Resolve dependencies for a pack
@@ -351,12 +204,60 @@ pub fn validate_pack(pack_id: String) -> Result<PackValidationResult, String> {
 pub fn resolve_dependencies(pack_id: String) -> Result<DependencyResolution, String> {
     // Validate the pack exists before resolving
     load_pack(pack_id.clone())?;
-    resolve_dependencies_internal(pack_id)
+    fs_manager::resolve_dependencies(&pack_id)
+}
+
+/// Resolve a pack selection to the effective set that gets deployed
+///
+/// Packs are deduplicated and ordered so dependencies come before the packs that
+/// need them. Packs added only as dependencies name the pack that required them.
+#[tauri::command]
+pub fn resolve_selection(pack_ids: Vec<String>) -> Result<Vec<ResolvedPack>, String> {
+    let mut resolved: Vec<ResolvedPack> = Vec::new();
+
+    for pack_id in &pack_ids {
+        let resolution = fs_manager::resolve_dependencies(pack_id)?;
+        if !resolution.success {
+            return Err(resolution.error.unwrap_or_else(|| "Failed to resolve dependencies".into()));
+        }
+
+        for id in resolution.order {
+            if resolved.iter().any(|p| p.pack_id == id) {
+                continue;
+            }
+            resolved.push(ResolvedPack {
+                selected: pack_ids.contains(&id),
+                pack_id: id,
+                pulled_in_by: None,
+            });
+        }
+    }
+
+    // Dependents always follow their dependencies; credit the first one that lists it
+    for idx in 0..resolved.len() {
+        if resolved[idx].selected {
+            continue;
+        }
+        let dependency = resolved[idx].pack_id.clone();
+        let mut pulled_in_by = None;
+        for candidate in &resolved[idx + 1..] {
+            let pack = load_pack(candidate.pack_id.clone())?;
+            if pack.dependencies.iter().any(|dep| dep.id == dependency) {
+                pulled_in_by = Some(candidate.pack_id.clone());
+                break;
+            }
+        }
+        resolved[idx].pulled_in_by = pulled_in_by;
+    }
+
+    Ok(resolved)
 }
 
 #[tauri::command]
 pub fn load_pack_file(pack_id: String, file: String) -> Result<String, String> {
-    let pack_dir = fs_manager::get_rule_packs_dir().join(&pack_id);
+    let pack_dir = fs_manager::get_rule_packs_dir()
+        .map_err(|e| e.to_string())?
+        .join(&pack_id);
     let file_path = pack_dir.join(&file);
 
     if !file_path.starts_with(&pack_dir) {
@@ -367,12 +268,83 @@ pub fn load_pack_file(pack_id: String, file: String) -> Result<String, String> {
         return Err(format!("File not found: {}", file));
     }
 
-    fs::read_to_string(&file_path).map_err(|e| format!("Failed to read file: {}", e))
+    fs_manager::read_file_guarded(&file_path).map_err(|e| format!("Failed to read file: {}", e))
 }
 
 #[tauri::command]
 pub fn calculate_budget(pack_ids: Vec<String>, agent_id: Option<String>) -> Result<BudgetInfo, String> {
-    calculate_budget_internal(&pack_ids, agent_id)
+    budget::calculate(&pack_ids, agent_id)
+}
+
+/// Budget of a deployment broken down by pack, command, and out-reference
+#[tauri::command]
+pub fn calculate_full_budget(
+    pack_ids: Vec<String>,
+    command_ids: Vec<String>,
+    agent_id: Option<String>,
+) -> Result<budget::FullBudgetInfo, String> {
+    budget::full_budget(&pack_ids, &command_ids, agent_id)
+}
+
+/// Estimate the budget a candidate pack would add to a composition
+#[tauri::command]
+pub fn pack_marginal_cost(
+    current_pack_ids: Vec<String>,
+    candidate_pack_id: String,
+    agent_id: Option<String>,
+) -> Result<budget::MarginalCost, String> {
+    budget::marginal_cost(&current_pack_ids, &candidate_pack_id, agent_id)
+}
+
+/// Greedily fit candidate packs, in order, within an agent's limit
+#[tauri::command]
+pub fn fit_packs(candidate_pack_ids: Vec<String>, agent_id: String) -> Result<budget::PackFit, String> {
+    budget::fit_packs(&candidate_pack_ids, &agent_id)
+}
+
+/// Budget of a composition (packs plus commands) for each of several agents
+#[tauri::command]
+pub fn budget_for_agents(
+    pack_ids: Vec<String>,
+    command_ids: Vec<String>,
+    agent_ids: Vec<String>,
+) -> Result<HashMap<String, BudgetInfo>, String> {
+    budget::report_for_agents(&pack_ids, &command_ids, &agent_ids)
+}
+
+/// Validate several packs at once, returning each pack's result sorted by pack id
+///
+/// Each pack is validated on a scoped worker thread; packs are split into one chunk per
+/// available core so a large selection doesn't spawn a thread per pack. Sorting keeps the
+/// merged errors and warnings stable however the selection was ordered.
+fn validate_packs_parallel(
+    pack_ids: &[String],
+) -> Result<Vec<(String, PackValidationResult)>, String> {
+    let validate = |id: &String| validate_pack(id.clone()).map(|result| (id.clone(), result));
+    let mut results = if pack_ids.len() < 2 {
+        pack_ids.iter().map(validate).collect::<Result<Vec<_>, _>>()?
+    } else {
+        let workers = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        let chunk_size = pack_ids.len().div_ceil(workers);
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = pack_ids
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || chunk.iter().map(validate).collect::<Vec<_>>()))
+                .collect();
+            let mut results = Vec::with_capacity(pack_ids.len());
+            for handle in handles {
+                let chunk = handle
+                    .join()
+                    .map_err(|_| "Pack validation worker panicked".to_string())?;
+                for result in chunk {
+                    results.push(result?);
+                }
+            }
+            Ok::<_, String>(results)
+        })?
+    };
+    results.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(results)
 }
 
 #[tauri::command]
@@ -383,8 +355,7 @@ pub fn validate_composition(
     let mut errors: Vec<String> = Vec::new();
     let mut warnings: Vec<String> = Vec::new();
 
-    for pack_id in &pack_ids {
-        let validation = validate_pack(pack_id.clone())?;
+    for (_, validation) in validate_packs_parallel(&pack_ids)? {
         for err in validation.errors {
             errors.push(format!("[{}] {}", err.pack_id, err.message));
         }
@@ -394,7 +365,7 @@ pub fn validate_composition(
     }
 
     if errors.is_empty() {
-        let budget = calculate_budget_internal(&pack_ids, agent_id.clone())?;
+        let budget = budget::calculate(&pack_ids, agent_id.clone())?;
         if let Some(agent) = agent_id {
             if !budget.within_limit {
                 let limit = budget
@@ -413,7 +384,7 @@ pub fn validate_composition(
                     percent_display
                 ));
             } else if let Some(percent) = budget.percentage {
-                if percent > 80 {
+                if percent > budget::agent_warn_threshold(&agent) {
                     warnings.push(format!(
                         "Composition uses {}% of {} character limit",
                         percent, agent
@@ -433,107 +404,70 @@ pub fn validate_composition(
 #[tauri::command]
 pub fn generate_agents_md(
     pack_ids: Vec<String>,
-    include_metadata: Option<bool>,
-    inline_content: Option<bool>,
+    options: Option<AgentsMdOptions>,
 ) -> Result<GenerateResult, String> {
-    let include_metadata = include_metadata.unwrap_or(true);
-    let inline_content = inline_content.unwrap_or(false);
-
-    let result = (|| -> Result<GenerateResult, String> {
-        let mut lines: Vec<String> = Vec::new();
-        lines.push("# AGENTS.md — Mandatory Agent Behavior & Workflow Standards".into());
-        lines.push("".into());
-        lines.push("Non-negotiable rules for all AI agents. Violations constitute workflow failures.".into());
-        lines.push("".into());
-        lines.push("**Version:** 2.0.0 (Modular Rule Packs)  ".into());
-        lines.push("**Reference:** Command examples at [AGENTS_REFERENCE.md](docs/AGENTS_REFERENCE.md).".into());
-        lines.push("".into());
-        lines.push("---".into());
-        lines.push("".into());
-        lines.push("## Active Rule Packs".into());
-        lines.push("".into());
-
-        let mut packs: Vec<LoadedPack> = Vec::new();
-        for id in pack_ids.iter() {
-            let pack = load_pack_full_internal(id)?;
-            packs.push(pack);
-        }
+    Ok(deployment::generate_agents_md(&pack_ids, options.unwrap_or_default()))
+}
 
-        for pack in packs.iter() {
-            lines.push(format!(
-                "- **{}** (`rule-packs/{}/`) — {}",
-                pack.name, pack.id, pack.description
-            ));
-        }
+/// Generate AGENTS.md and write it to `dest_path` without deploying it to an agent
+///
+/// Returns the budget of the written file, measured against `agent_id`'s limit when given.
+#[tauri::command]
+pub fn export_agents_md(
+    pack_ids: Vec<String>,
+    dest_path: String,
+    inline_content: Option<bool>,
+    agent_id: Option<String>,
+) -> Result<BudgetInfo, String> {
+    let dest = PathBuf::from(&dest_path);
+    if dest.is_dir() {
+        return Err(format!("Export destination is a directory: {}", dest_path));
+    }
+    let parent = match dest.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    if !parent.is_dir() {
+        return Err(format!("Export directory does not exist: {}", parent.display()));
+    }
+    let max_chars = match agent_id.as_deref() {
+        Some(agent_id) => budget::agent_char_limit(&find_registry_agent(agent_id)?.id),
+        None => None,
+    };
 
-        lines.push("".into());
-        lines.push("---".into());
-        lines.push("".into());
+    let options = AgentsMdOptions {
+        inline_content: inline_content.unwrap_or(false),
+        ..Default::default()
+    };
+    let generated = generate_agents_md(pack_ids, Some(options))?;
+    if !generated.success {
+        return Err(generated.error.unwrap_or_else(|| "Failed to generate AGENTS.md".to_string()));
+    }
 
-        if inline_content {
-            for pack in packs.iter() {
-                lines.push(format!("<!-- Pack: {} v{} -->", pack.id, pack.version));
-                lines.push(pack.content.clone());
-                lines.push("".into());
-            }
-        } else {
-            lines.push("<!-- BEGIN PACK IMPORTS -->".into());
-            lines.push("".into());
-            for pack in packs.iter() {
-                for file in pack.files.iter() {
-                    lines.push(format!("@rule-packs/{}/{}", pack.id, file));
-                }
-                lines.push("".into());
-            }
-            lines.push("<!-- END PACK IMPORTS -->".into());
-            lines.push("".into());
-        }
+    util::write_atomic(&dest, generated.content.as_bytes())
+        .map_err(|e| format!("Failed to write {}: {}", dest_path, e))?;
 
-        lines.push("---".into());
-        lines.push("".into());
-
-        let budget = calculate_budget_internal(&pack_ids, None)?;
-        if include_metadata {
-            lines.push("## Configuration".into());
-            lines.push("".into());
-            lines.push("**Character Budget:**".into());
-            for item in budget.pack_breakdown.iter() {
-                if let Some(pack) = packs.iter().find(|p| p.id == item.pack_id) {
-                    lines.push(format!(
-                        "- {}: ~{} words (~{} chars)",
-                        pack.name, item.words, item.chars
-                    ));
-                } else {
-                    lines.push(format!(
-                        "- {}: ~{} words (~{} chars)",
-                        item.pack_id, item.words, item.chars
-                    ));
-                }
-            }
-            let total_words: u64 = budget.pack_breakdown.iter().map(|p| p.words).sum();
-            lines.push(format!(
-                "- **Total:** ~{} words (~{} chars)",
-                total_words, budget.total_chars
-            ));
-        }
+    let mut budget = generated.budget;
+    budget.max_chars = max_chars;
+    (budget.percentage, budget.within_limit) = budget::limit_usage(budget.total_chars, max_chars);
+    Ok(budget)
+}
 
-        Ok(GenerateResult {
-            success: true,
-            content: lines.join("\n"),
-            budget,
-            error: None,
-        })
-    })();
-
-    match result {
-        Ok(ok) => Ok(ok),
-        Err(err) => Ok(GenerateResult {
-            success: false,
-            content: String::new(),
-            budget: empty_budget_info(),
-            error: Some(err),
-        }),
-    }
+/// Get a headings-only outline of the AGENTS.md generated for the given packs
+#[tauri::command]
+pub fn get_agents_md_outline(
+    pack_ids: Vec<String>,
+    inline_content: Option<bool>,
+) -> Result<Vec<converters::OutlineEntry>, String> {
+    let content = deployment::generate_agents_md_content(&pack_ids, inline_content.unwrap_or(false))
+        .map_err(|e| e.to_string())?;
+    Ok(converters::outline(&content))
+}
+
+/// Split an inline AGENTS.md back into per-pack markdown for editing
+#[tauri::command]
+pub fn split_agents_md(content: String) -> Result<Vec<converters::PackSection>, String> {
+    converters::split_agents_md(&content).map_err(|e| e.to_string())
 }
 
 /// Read AGENTS.md content
@@ -553,9 +487,25 @@ pub fn write_agents_md(content: String) -> Result<(), String> {
 /// Get ~/.agentsmd/ path
 #[tauri::command]
 pub fn get_agentsmd_home() -> Result<String, String> {
-    Ok(fs_manager::get_agentsmd_home()
-        .to_string_lossy()
-        .to_string())
+    fs_manager::get_agentsmd_home()
+        .map(|home| home.to_string_lossy().to_string())
+        .map_err(|e| e.to_string())
+}
+
+/// Get the largest pack or out-reference file that will be read, in bytes
+#[tauri::command]
+pub fn get_max_file_size() -> u64 {
+    fs_manager::max_file_size()
+}
+
+/// Change the largest pack or out-reference file that will be read, in bytes
+#[tauri::command]
+pub fn set_max_file_size(bytes: u64) -> Result<(), String> {
+    if bytes == 0 {
+        return Err("Maximum file size must be greater than zero".to_string());
+    }
+    fs_manager::set_max_file_size(bytes);
+    Ok(())
 }
 
 /// Check if an agent is installed (has config directory)
@@ -567,26 +517,61 @@ pub fn check_agent_installed(agent_id: String) -> Result<bool, String> {
     Ok(config_path.exists())
 }
 
-/// Create agent link (symlink/junction/hardlink/copy)
+/// Create agent links (symlink/junction/hardlink/copy) for each config path
 #[tauri::command]
-pub fn create_agent_link(agent_id: String, force: bool) -> Result<(String, Option<String>), String> {
-    let agents = fs_manager::load_agent_registry()
-        .map_err(|e| format!("Failed to load agents: {}", e))?;
-    let agent = agents
-        .into_iter()
-        .find(|a| a.id == agent_id)
-        .ok_or_else(|| format!("Agent not found: {}", agent_id))?;
+pub fn create_agent_link(
+    agent_id: String,
+    force: bool,
+    link_strategy: Option<LinkStrategy>,
+) -> Result<Vec<AgentLinkResult>, String> {
+    let agent = find_registry_agent(&agent_id)?;
+    link_agent_config_paths(&agent, force, link_strategy.unwrap_or_default())
+}
 
-    // Resolve the target path (agent config path)
-    let link_path = fs_manager::get_agent_config_path(agent.id.clone())
-        .map_err(|e| format!("Failed to get agent config path: {}", e))?;
+/// Link every config path of `agent` to AGENTS.md (file paths) or `~/.agentsmd/` (directories)
+///
+/// Each path is linked independently; failures are reported in that path's result.
+/// Paths that end up as copies are recorded in deployment state so
+/// `unlink_agent_config_paths` can remove them later.
+pub fn link_agent_config_paths(
+    agent: &AgentDefinition,
+    force: bool,
+    strategy: LinkStrategy,
+) -> Result<Vec<AgentLinkResult>, String> {
+    let link_paths = fs_manager::expand_agent_config_paths(agent)
+        .map_err(|e| format!("Failed to get agent config paths: {}", e))?;
+
+    let mut results = Vec::new();
+    for (raw_config_path, link_path) in agent.config_paths.iter().zip(link_paths) {
+        let path = link_path.to_string_lossy().to_string();
+        let linked = link_path_to_agentsmd(&agent.id, raw_config_path, link_path, force, strategy);
+        results.push(match linked {
+            Ok((method, warning)) => AgentLinkResult {
+                path,
+                method: Some(method),
+                warning,
+                error: None,
+            },
+            Err(error) => AgentLinkResult {
+                path,
+                method: None,
+                warning: None,
+                error: Some(error),
+            },
+        });
+    }
 
+    Ok(results)
+}
+
+fn link_path_to_agentsmd(
+    agent_id: &str,
+    raw_config_path: &str,
+    link_path: PathBuf,
+    force: bool,
+    strategy: LinkStrategy,
+) -> Result<(String, Option<String>), String> {
     // Decide whether the agent expects a file or directory target
-    let raw_config_path = agent
-        .config_paths
-        .get(0)
-        .cloned()
-        .unwrap_or_default();
     let expects_file = link_path
         .metadata()
         .map(|m| m.is_file())
@@ -610,9 +595,33 @@ pub fn create_agent_link(agent_id: String, force: bool) -> Result<(String, Optio
             .map_err(|e| format!("Failed to create ~/.agentsmd/: {}", e))?
     };
 
+    // A copy placed by an earlier call is ours to replace
+    let link_str = link_path.to_string_lossy().to_string();
+    let state = StateManager::new().map_err(|e| e.to_string())?;
+    let replace_own = state
+        .get_link_copies(agent_id)
+        .map(|copies| copies.contains(&link_str))
+        .unwrap_or(false);
+
     // Create link: link_path points to source_path
-    match symlink::create_link(link_path, source_path, force) {
-        Ok((method, warning)) => {
+    match symlink::create_link(link_path, source_path, force, strategy, replace_own) {
+        Ok((method, mut warning)) => {
+            // Copies are real files and directories; remember they're ours to delete
+            let recorded = if matches!(method, LinkMethod::Copy) {
+                state.record_link_copy(agent_id, &link_str)
+            } else if replace_own {
+                state.forget_link_copy(agent_id, &link_str)
+            } else {
+                Ok(())
+            };
+            if let Err(e) = recorded {
+                let note = format!("Could not record {} in deployment state: {}", link_str, e);
+                warning = Some(match warning {
+                    Some(existing) => format!("{}\n{}", existing, note),
+                    None => note,
+                });
+            }
+
             let method_str = match method {
                 LinkMethod::Symlink => "symlink",
                 LinkMethod::Junction => "junction",
@@ -626,14 +635,54 @@ pub fn create_agent_link(agent_id: String, force: bool) -> Result<(String, Optio
     }
 }
 
-/// Remove agent link
+/// Remove agent links for each config path
 #[tauri::command]
 pub fn remove_agent_link(agent_id: String) -> Result<(), String> {
-    let link_path = fs_manager::get_agent_config_path(agent_id)
-        .map_err(|e| format!("Failed to get agent config path: {}", e))?;
-    
-    symlink::remove_link(link_path)
-        .map_err(|e| format!("Failed to remove link: {}", e))
+    let agent = find_registry_agent(&agent_id)?;
+    unlink_agent_config_paths(&agent)
+}
+
+/// Remove the links at every config path of `agent`
+pub fn unlink_agent_config_paths(agent: &AgentDefinition) -> Result<(), String> {
+    let link_paths = fs_manager::expand_agent_config_paths(agent)
+        .map_err(|e| format!("Failed to get agent config paths: {}", e))?;
+
+    // Only directories recorded in deployment state may be deleted recursively
+    let state = StateManager::new().map_err(|e| e.to_string())?;
+    let history = state.get_agent_history(&agent.id).unwrap_or_default();
+    let copies = state.get_link_copies(&agent.id).unwrap_or_default();
+
+    let mut errors = Vec::new();
+    for link_path in link_paths {
+        let link_str = link_path.to_string_lossy().to_string();
+        let copied = copies.contains(&link_str);
+        let created_by_toolkit =
+            copied || history.iter().any(|state| state.files_created.contains(&link_str));
+
+        match symlink::remove_link(link_path, created_by_toolkit) {
+            Ok(()) if copied => {
+                if let Err(e) = state.forget_link_copy(&agent.id, &link_str) {
+                    errors.push(format!("Removed {} but could not update deployment state: {}", link_str, e));
+                }
+            }
+            Ok(()) => {}
+            Err(e) => errors.push(format!("Failed to remove link {}: {}", link_str, e)),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+fn find_registry_agent(agent_id: &str) -> Result<AgentDefinition, String> {
+    fs_manager::load_agent_registry()
+        .map_err(|e| format!("Failed to load agents: {}", e))?
+        .into_iter()
+        .find(|a| a.id == agent_id)
+        .ok_or_else(|| format!("Agent not found: {}", agent_id))
 }
 
 /// Check symlink support
@@ -646,69 +695,231 @@ pub fn check_symlink_support() -> Result<(bool, String), String> {
 // Deployment Commands
 // ============================================================================
 
-/// Deploy to a specific agent
+/// Deploy to a specific agent, emitting `deployment://progress` events as it runs
 #[tauri::command]
-pub fn deploy_to_agent(agent_id: String, config: DeploymentConfig) -> Result<DeploymentOutput, String> {
+pub fn deploy_to_agent(
+    app: tauri::AppHandle,
+    agent_id: String,
+    config: DeploymentConfig,
+) -> Result<DeploymentOutput, IpcError> {
+    deploy_with_progress(&config, |progress| {
+        if let Err(e) = app.emit("deployment://progress", progress) {
+            log::warn!("Failed to emit deployment progress: {}", e);
+        }
+    })
+}
+
+/// Deploy through the shared deployment manager, reporting each phase to `on_progress`
+pub fn deploy_with_progress<F>(config: &DeploymentConfig, on_progress: F) -> Result<DeploymentOutput, IpcError>
+where
+    F: FnMut(DeploymentProgress),
+{
     let guard = get_deployment_manager()?;
-    let manager = guard.as_ref().ok_or("Deployment manager not initialized")?;
-    
-    manager.deploy(&config).map_err(|e| e.to_string())
+    let manager = guard.as_ref().ok_or_else(IpcError::not_initialized)?;
+
+    manager.deploy_with_progress(config, on_progress).map_err(IpcError::from)
 }
 
 /// Validate a deployment without executing it
 #[tauri::command]
-pub fn validate_deployment(agent_id: String, config: DeploymentConfig) -> Result<ValidationReport, String> {
+pub fn validate_deployment(agent_id: String, config: DeploymentConfig) -> Result<ValidationReport, IpcError> {
     let guard = get_deployment_manager()?;
-    let manager = guard.as_ref().ok_or("Deployment manager not initialized")?;
+    let manager = guard.as_ref().ok_or_else(IpcError::not_initialized)?;
     
-    manager.validate_deployment(&config).map_err(|e| e.to_string())
+    manager.validate_deployment(&config).map_err(IpcError::from)
 }
 
 /// Rollback a deployment
 #[tauri::command]
-pub fn rollback_deployment(agent_id: String, timestamp: Option<String>) -> Result<(), String> {
+pub fn rollback_deployment(agent_id: String, timestamp: Option<String>) -> Result<(), IpcError> {
     let guard = get_deployment_manager()?;
-    let manager = guard.as_ref().ok_or("Deployment manager not initialized")?;
+    let manager = guard.as_ref().ok_or_else(IpcError::not_initialized)?;
     
-    manager.rollback(&agent_id, timestamp).map_err(|e| e.to_string())
+    manager.rollback(&agent_id, timestamp).map_err(IpcError::from)
 }
 
-/// Get deployment status for an agent
+/// List an agent's backup directories, newest first
 #[tauri::command]
-pub fn get_deployment_status(agent_id: String) -> Result<AgentStatus, String> {
+pub fn list_backups(agent_id: String) -> Result<Vec<BackupEntry>, IpcError> {
+    BackupManager::new()?.list_backups(&agent_id).map_err(IpcError::from)
+}
+
+/// Restore one of an agent's backup directories to where its files came from
+#[tauri::command]
+pub fn restore_backup(agent_id: String, backup_path: String) -> Result<Vec<PathBuf>, IpcError> {
+    BackupManager::new()?
+        .restore_named_backup(&agent_id, Path::new(&backup_path))
+        .map_err(IpcError::from)
+}
+
+/// List what rolling back a deployment would remove and restore
+#[tauri::command]
+pub fn preview_rollback(agent_id: String, timestamp: Option<String>) -> Result<RollbackPlan, IpcError> {
     let guard = get_deployment_manager()?;
-    let manager = guard.as_ref().ok_or("Deployment manager not initialized")?;
+    let manager = guard.as_ref().ok_or_else(IpcError::not_initialized)?;
+
+    manager.preview_rollback(&agent_id, timestamp).map_err(IpcError::from)
+}
+
+/// Get deployment status for an agent, including whether its config directory is a symlink
+#[tauri::command]
+pub fn get_deployment_status(agent_id: String) -> Result<DeploymentStatusInfo, IpcError> {
+    let guard = get_deployment_manager()?;
+    let manager = guard.as_ref().ok_or_else(IpcError::not_initialized)?;
     
-    manager.get_status(&agent_id).map_err(|e| e.to_string())
+    manager.get_status_info(&agent_id).map_err(IpcError::from)
 }
 
 /// Get deployment history for an agent
 #[tauri::command]
-pub fn get_deployment_history(agent_id: String) -> Result<Vec<DeploymentState>, String> {
+pub fn get_deployment_history(agent_id: String) -> Result<Vec<DeploymentState>, IpcError> {
     let guard = get_deployment_manager()?;
-    let manager = guard.as_ref().ok_or("Deployment manager not initialized")?;
+    let manager = guard.as_ref().ok_or_else(IpcError::not_initialized)?;
     
-    manager.get_history(&agent_id).map_err(|e| e.to_string())
+    manager.get_history(&agent_id).map_err(IpcError::from)
+}
+
+/// Collapse repeated identical deployments in an agent's history; returns how many were removed
+#[tauri::command]
+pub fn compact_deployment_history(agent_id: String) -> Result<usize, IpcError> {
+    let guard = get_deployment_manager()?;
+    let manager = guard.as_ref().ok_or_else(IpcError::not_initialized)?;
+
+    manager.compact_history(&agent_id).map_err(IpcError::from)
+}
+
+/// Get the most recent deployments across all agents, newest first
+#[tauri::command]
+pub fn get_recent_deployments(limit: usize) -> Result<Vec<DeploymentState>, IpcError> {
+    let guard = get_deployment_manager()?;
+    let manager = guard.as_ref().ok_or_else(IpcError::not_initialized)?;
+
+    manager.recent_deployments(limit).map_err(IpcError::from)
+}
+
+/// List agents that currently have a deployment recorded
+#[tauri::command]
+pub fn list_deployed_agents() -> Result<Vec<String>, IpcError> {
+    let guard = get_deployment_manager()?;
+    let manager = guard.as_ref().ok_or_else(IpcError::not_initialized)?;
+
+    manager.deployed_agents().map_err(IpcError::from)
+}
+
+/// Get a page of deployment history for an agent, newest first
+#[tauri::command]
+pub fn get_deployment_history_page(
+    agent_id: String,
+    offset: usize,
+    limit: usize,
+) -> Result<DeploymentHistoryPage, IpcError> {
+    let guard = get_deployment_manager()?;
+    let manager = guard.as_ref().ok_or_else(IpcError::not_initialized)?;
+
+    manager
+        .get_history_page(&agent_id, offset, limit)
+        .map_err(IpcError::from)
+}
+
+/// Get failed deployment attempts for an agent
+#[tauri::command]
+pub fn get_deployment_failures(agent_id: String) -> Result<Vec<FailedDeployment>, IpcError> {
+    let guard = get_deployment_manager()?;
+    let manager = guard.as_ref().ok_or_else(IpcError::not_initialized)?;
+
+    manager.get_failures(&agent_id).map_err(IpcError::from)
+}
+
+/// Move an agent's latest deployment between user and project level
+#[tauri::command]
+pub fn retarget_deployment(
+    agent_id: String,
+    target_level: TargetLevel,
+    project_path: Option<String>,
+) -> Result<DeploymentOutput, IpcError> {
+    let guard = get_deployment_manager()?;
+    let manager = guard.as_ref().ok_or_else(IpcError::not_initialized)?;
+
+    manager
+        .retarget(&agent_id, target_level, project_path)
+        .map_err(IpcError::from)
+}
+
+/// Redeploy an agent's latest recorded packs and commands
+#[tauri::command]
+pub fn redeploy_current(agent_id: String) -> Result<DeploymentOutput, IpcError> {
+    let guard = get_deployment_manager()?;
+    let manager = guard.as_ref().ok_or_else(IpcError::not_initialized)?;
+
+    manager.redeploy_current(&agent_id).map_err(IpcError::from)
 }
 
 /// Preview a deployment without executing it
 #[tauri::command]
-pub fn preview_deployment(agent_id: String, config: DeploymentConfig) -> Result<PreparedDeployment, String> {
+pub fn preview_deployment(agent_id: String, config: DeploymentConfig) -> Result<PreparedDeployment, IpcError> {
     let guard = get_deployment_manager()?;
-    let manager = guard.as_ref().ok_or("Deployment manager not initialized")?;
+    let manager = guard.as_ref().ok_or_else(IpcError::not_initialized)?;
     
-    manager.preview_deployment(&config).map_err(|e| e.to_string())
+    manager.preview_deployment(&config).map_err(IpcError::from)
+}
+
+/// Show the exact content each target path would receive, without deploying
+#[tauri::command]
+pub fn materialize_deployment(config: DeploymentConfig) -> Result<HashMap<PathBuf, String>, IpcError> {
+    let guard = get_deployment_manager()?;
+    let manager = guard.as_ref().ok_or_else(IpcError::not_initialized)?;
+
+    manager.materialize(&config).map_err(IpcError::from)
+}
+
+/// List the out-references a selection of commands and packs would deploy
+#[tauri::command]
+pub fn preview_out_references(
+    command_ids: Vec<String>,
+    pack_ids: Vec<String>,
+) -> Result<Vec<deployment::OutReferencePreview>, IpcError> {
+    deployment::preview_out_references(&command_ids, &pack_ids).map_err(IpcError::from)
+}
+
+/// Re-copy current out-reference content to an agent's copy-deployed paths
+#[tauri::command]
+pub fn resync_out_references(agent_id: String) -> Result<Vec<String>, IpcError> {
+    deployment::resync_out_references(&agent_id).map_err(IpcError::from)
+}
+
+/// Preview the converted file a command would deploy as for an agent
+#[tauri::command]
+pub fn preview_command_for_agent(
+    command_id: String,
+    agent_id: String,
+) -> Result<command_loader::CommandPreview, IpcError> {
+    command_loader::preview_command_for_agent(&command_id, &agent_id).map_err(IpcError::from)
+}
+
+/// Preview and check the Warp workflow a command would deploy as
+#[tauri::command]
+pub fn preview_warp_workflow(command_id: String) -> Result<command_loader::WarpWorkflowPreview, IpcError> {
+    command_loader::preview_warp_workflow(&command_id).map_err(IpcError::from)
 }
 
 /// Get all available agents for deployment
 #[tauri::command]
-pub fn get_deployable_agents() -> Result<Vec<String>, String> {
+pub fn get_deployable_agents() -> Result<Vec<String>, IpcError> {
     let guard = get_deployment_manager()?;
-    let manager = guard.as_ref().ok_or("Deployment manager not initialized")?;
+    let manager = guard.as_ref().ok_or_else(IpcError::not_initialized)?;
     
     Ok(manager.available_agents())
 }
 
+/// Get every agent with whether it supports the given deployment config
+#[tauri::command]
+pub fn get_deployable_agents_for(config: DeploymentConfig) -> Result<Vec<DeployableAgent>, IpcError> {
+    let guard = get_deployment_manager()?;
+    let manager = guard.as_ref().ok_or_else(IpcError::not_initialized)?;
+
+    manager.deployable_agents_for(&config).map_err(IpcError::from)
+}
+
 // ============================================================================
 // Command Registry Commands
 // ============================================================================
@@ -737,6 +948,30 @@ pub fn get_commands_by_category(category: String) -> Result<Vec<CommandMetadata>
     command_registry::get_commands_by_category(&category)
 }
 
+/// List the distinct command categories in use
+#[tauri::command]
+pub fn list_command_categories() -> Result<Vec<String>, String> {
+    command_registry::list_command_categories()
+}
+
+/// Check a command's markdown structure
+#[tauri::command]
+pub fn lint_command(command_id: String) -> Result<Vec<CommandLint>, String> {
+    command_registry::lint_command(&command_id)
+}
+
+/// Check that a command's template section is well-formed markdown
+#[tauri::command]
+pub fn validate_command_template(command_id: String) -> Result<Vec<String>, String> {
+    command_registry::validate_template(&command_id)
+}
+
+/// List one page of commands matching a filter
+#[tauri::command]
+pub fn list_commands(filter: CommandFilter, offset: usize, limit: usize) -> Result<CommandPage, String> {
+    command_registry::list_commands(&filter, offset, limit)
+}
+
 /// Load raw command content (markdown)
 #[tauri::command]
 pub fn load_command_content(command_id: String) -> Result<String, String> {
@@ -753,6 +988,19 @@ pub fn update_command_out_references(
     command_registry::get_command_by_id(&command_id)
 }
 
+/// Reset a command to its content-derived out-references and return refreshed metadata
+#[tauri::command]
+pub fn clear_command_out_references(command_id: String) -> Result<CommandMetadata, String> {
+    command_registry::clear_command_out_references(&command_id)?;
+    command_registry::get_command_by_id(&command_id)
+}
+
+/// Rename a command and return its metadata under the new id
+#[tauri::command]
+pub fn rename_command(old_id: String, new_id: String) -> Result<CommandMetadata, String> {
+    command_registry::rename_command(&old_id, &new_id)
+}
+
 /// Validate command compatibility with a specific agent
 #[tauri::command]
 pub fn validate_command_for_agent(
@@ -762,6 +1010,12 @@ pub fn validate_command_for_agent(
     command_registry::validate_command_for_agent(&command_id, &agent_id)
 }
 
+/// Check a command against every registered agent
+#[tauri::command]
+pub fn command_compatibility_matrix(command_id: String) -> Result<Vec<AgentCommandCompatibility>, String> {
+    command_registry::command_compatibility_matrix(&command_id)
+}
+
 /// Calculate budget for a set of commands
 #[tauri::command]
 pub fn calculate_command_budget(command_ids: Vec<String>) -> Result<CommandBudgetInfo, String> {
@@ -780,7 +1034,7 @@ pub fn refresh_commands() -> Result<(), String> {
 // ============================================================================
 
 use crate::out_reference_manager;
-use crate::types::{OutReference, OutReferenceValidationReport, ReferenceLink};
+use crate::types::{FormatValidation, OutReference, OutReferenceValidationReport, ReferenceLink};
 
 /// List all out-references
 #[tauri::command]
@@ -788,12 +1042,27 @@ pub fn list_out_references() -> Result<Vec<OutReference>, String> {
     out_reference_manager::list_out_references()
 }
 
+/// List out-references filtered by format and tags (all tags must match)
+#[tauri::command]
+pub fn list_out_references_filtered(
+    format: Option<String>,
+    tags: Option<Vec<String>>,
+) -> Result<Vec<OutReference>, String> {
+    out_reference_manager::list_out_references_filtered(format, tags)
+}
+
 /// Get a single out-reference by ID
 #[tauri::command]
 pub fn get_out_reference(id: String) -> Result<OutReference, String> {
     out_reference_manager::get_out_reference(id)
 }
 
+/// Get a single out-reference by its linked relative path
+#[tauri::command]
+pub fn get_out_reference_by_path(path: String) -> Result<OutReference, String> {
+    out_reference_manager::get_by_path(&path)
+}
+
 /// Create a new out-reference
 #[tauri::command]
 pub fn create_out_reference(
@@ -824,6 +1093,18 @@ pub fn update_out_reference_metadata(
     out_reference_manager::update_out_reference_metadata(id, name, description, tags)
 }
 
+/// Import a file a command links to into the out-references store
+#[tauri::command]
+pub fn import_command_out_reference(command_id: String, ref_path: String) -> Result<OutReference, String> {
+    out_reference_manager::create_from_command_reference(&command_id, &ref_path)
+}
+
+/// Rename an out-reference's file, repointing pack overrides at the new path
+#[tauri::command]
+pub fn rename_out_reference(id: String, new_file_name: String) -> Result<OutReference, String> {
+    out_reference_manager::rename_out_reference(id, new_file_name)
+}
+
 /// Delete an out-reference
 #[tauri::command]
 pub fn delete_out_reference(id: String) -> Result<(), String> {
@@ -848,12 +1129,46 @@ pub fn validate_out_references() -> Result<OutReferenceValidationReport, String>
     out_reference_manager::validate_out_references()
 }
 
+/// Check that an out-reference's content parses as its declared format
+#[tauri::command]
+pub fn validate_out_reference_format(id: String) -> Result<FormatValidation, String> {
+    out_reference_manager::validate_format(id)
+}
+
+/// Delete untracked files from the out-references directory (or just list them with `dry_run`)
+#[tauri::command]
+pub fn purge_orphaned_out_references(dry_run: Option<bool>) -> Result<Vec<String>, String> {
+    if dry_run.unwrap_or(false) {
+        out_reference_manager::preview_orphan_purge()
+    } else {
+        out_reference_manager::purge_orphans()
+    }
+}
+
+/// Suggest unused out-references and untracked files to clean up
+#[tauri::command]
+pub fn suggest_out_reference_cleanup() -> Result<Vec<out_reference_manager::CleanupSuggestion>, String> {
+    out_reference_manager::suggest_cleanup()
+}
+
+/// Delete selected out-reference cleanup suggestions
+#[tauri::command]
+pub fn cleanup_unused_out_references(ids: Vec<String>) -> Result<Vec<String>, String> {
+    out_reference_manager::cleanup_unused(&ids)
+}
+
 /// Find what references a specific out-reference
 #[tauri::command]
 pub fn find_references_to(id: String) -> Result<Vec<ReferenceLink>, String> {
     out_reference_manager::find_references_to(id)
 }
 
+/// Find what commands/packs reference the out-reference at a linked path
+#[tauri::command]
+pub fn find_references_to_path(path: String) -> Result<Vec<ReferenceLink>, String> {
+    out_reference_manager::find_references_to_path(path)
+}
+
 /// Export out-references to a JSON bundle
 #[tauri::command]
 pub fn export_out_references(ids: Vec<String>) -> Result<String, String> {
@@ -866,8 +1181,32 @@ pub fn import_out_references(bundle: String) -> Result<Vec<OutReference>, String
     out_reference_manager::import_out_references(bundle)
 }
 
+/// Export out-references to a zip archive
+#[tauri::command]
+pub fn export_out_references_zip(ids: Vec<String>, dest_path: String) -> Result<(), String> {
+    out_reference_manager::export_out_references_zip(ids, Path::new(&dest_path))
+}
+
+/// Import out-references from a zip archive
+#[tauri::command]
+pub fn import_out_references_zip(source_path: String) -> Result<Vec<OutReference>, String> {
+    out_reference_manager::import_out_references_zip(Path::new(&source_path))
+}
+
 /// Get out-reference statistics
 #[tauri::command]
 pub fn get_out_reference_stats() -> Result<out_reference_manager::OutReferenceStats, String> {
     out_reference_manager::get_out_reference_stats()
 }
+
+// ============================================================================
+// Diagnostics Commands
+// ============================================================================
+
+use crate::diagnostics;
+
+/// Get an aggregated health report for the whole toolkit
+#[tauri::command]
+pub fn get_toolkit_health() -> Result<diagnostics::ToolkitHealthReport, String> {
+    diagnostics::full_report()
+}
@@ -1,7 +1,13 @@
+pub mod budget;
 pub mod command_registry;
 pub mod deployment;
+pub mod diagnostics;
 pub mod fs_manager;
 pub mod ipc;
 pub mod out_reference_manager;
+pub mod packs;
 pub mod symlink;
 pub mod types;
+pub mod util;
+#[cfg(feature = "watch")]
+pub mod watcher;
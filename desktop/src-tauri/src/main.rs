@@ -1,13 +1,19 @@
 // Prevents additional console window on Windows in release builds
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod budget;
 mod command_registry;
 mod deployment;
+mod diagnostics;
 mod fs_manager;
 mod ipc;
 mod out_reference_manager;
+mod packs;
 mod symlink;
 mod types;
+mod util;
+#[cfg(feature = "watch")]
+mod watcher;
 
 use ipc::*;
 
@@ -18,25 +24,63 @@ fn main() {
     tauri::Builder::default()
         .setup(|app| {
             log::info!("Tauri app initialized");
+
+            #[cfg(feature = "watch")]
+            {
+                use tauri::{Emitter, Manager};
+
+                let handle = app.handle().clone();
+                let started = fs_manager::get_agentsmd_home()
+                    .map_err(|e| e.to_string())
+                    .and_then(|agentsmd_home| {
+                        watcher::watch_library(&agentsmd_home, watcher::DEFAULT_DEBOUNCE, move |change| {
+                            if let Err(e) = handle.emit(watcher::LIBRARY_CHANGED_EVENT, change) {
+                                log::warn!("Failed to emit library change: {}", e);
+                            }
+                        })
+                    });
+                match started {
+                    Ok(Some(library_watcher)) => {
+                        app.manage(library_watcher);
+                    }
+                    Ok(None) => {}
+                    Err(e) => log::warn!("Library watcher not started: {}", e),
+                }
+            }
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             get_all_agents,
             get_agent_by_id,
             validate_agent,
+            validate_agent_registry,
             list_available_packs,
             load_pack,
             load_pack_full,
             load_pack_file,
             update_pack_out_references,
             validate_pack,
+            get_rulepack_schema,
+            find_duplicate_pack_ids,
+            diff_packs,
             resolve_dependencies,
+            resolve_selection,
             calculate_budget,
+            calculate_full_budget,
+            pack_marginal_cost,
+            fit_packs,
+            budget_for_agents,
             validate_composition,
             generate_agents_md,
+            export_agents_md,
+            get_agents_md_outline,
+            split_agents_md,
             read_agents_md,
             write_agents_md,
             get_agentsmd_home,
+            get_max_file_size,
+            set_max_file_size,
             check_agent_installed,
             create_agent_link,
             remove_agent_link,
@@ -45,34 +89,70 @@ fn main() {
             deploy_to_agent,
             validate_deployment,
             rollback_deployment,
+            preview_rollback,
+            list_backups,
+            restore_backup,
             get_deployment_status,
             get_deployment_history,
+            get_deployment_history_page,
+            compact_deployment_history,
+            get_recent_deployments,
+            list_deployed_agents,
+            get_deployment_failures,
+            retarget_deployment,
+            redeploy_current,
             preview_deployment,
+            materialize_deployment,
+            preview_command_for_agent,
+            preview_warp_workflow,
+            resync_out_references,
+            preview_out_references,
             get_deployable_agents,
+            get_deployable_agents_for,
             // Command registry commands
             list_available_commands,
             get_command_by_id,
             get_commands_for_agent,
             get_commands_by_category,
+            list_command_categories,
+            list_commands,
+            lint_command,
+            validate_command_template,
             load_command_content,
             update_command_out_references,
+            clear_command_out_references,
+            rename_command,
             validate_command_for_agent,
+            command_compatibility_matrix,
             calculate_command_budget,
             refresh_commands,
             // Out-reference commands
             list_out_references,
+            list_out_references_filtered,
             get_out_reference,
+            get_out_reference_by_path,
             create_out_reference,
             update_out_reference,
             update_out_reference_metadata,
+            rename_out_reference,
+            import_command_out_reference,
             delete_out_reference,
             read_out_reference_content,
             write_out_reference_content,
             validate_out_references,
+            validate_out_reference_format,
+            purge_orphaned_out_references,
+            suggest_out_reference_cleanup,
+            cleanup_unused_out_references,
             find_references_to,
+            find_references_to_path,
             export_out_references,
             import_out_references,
+            export_out_references_zip,
+            import_out_references_zip,
             get_out_reference_stats,
+            // Diagnostics commands
+            get_toolkit_health,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
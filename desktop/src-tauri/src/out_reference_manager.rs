@@ -3,21 +3,26 @@
 //! Provides CRUD operations for managing out-references - external files
 //! that can be referenced by commands and rule packs.
 
+use crate::budget;
 use crate::command_registry;
 use crate::fs_manager;
 use crate::types::{
-    BrokenLink, OutReference, OutReferenceCategory, OutReferenceValidationReport, ReferenceLink,
-    FileFormat, RulePack,
+    BrokenLink, FormatValidation, OutReference, OutReferenceCategory, OutReferenceValidationReport,
+    ReferenceLink, FileFormat, RulePack,
 };
+use crate::util;
 use once_cell::sync::Lazy;
 use regex::Regex;
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
 use serde_json;
 use uuid::Uuid;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
 
 /// Metadata index file structure
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -27,13 +32,49 @@ pub struct OutReferenceMetadata {
 }
 
 /// Get the out-references directory path
-pub fn get_out_references_dir() -> PathBuf {
-    fs_manager::get_agentsmd_home().join("out-references")
+pub fn get_out_references_dir() -> Result<PathBuf, String> {
+    let home = fs_manager::get_agentsmd_home().map_err(|e| e.to_string())?;
+    Ok(home.join("out-references"))
+}
+
+/// Resolve a reference's `file_path` to its location under the out-references directory
+///
+/// Absolute paths and `..` components are rejected, and the nearest existing ancestor is
+/// canonicalized so a symlinked subdirectory can't lead outside the directory either.
+fn resolve_contained_path(file_path: &str) -> Result<PathBuf, String> {
+    let base_dir = get_out_references_dir()?;
+    let escapes = || format!("Out-reference path escapes the out-references directory: {}", file_path);
+
+    let relative = Path::new(file_path);
+    let lexically_contained = relative
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    if !lexically_contained {
+        return Err(escapes());
+    }
+
+    let full_path = base_dir.join(relative);
+    let existing = full_path
+        .ancestors()
+        .find(|p| p.starts_with(&base_dir) && p.exists());
+    if let Some(existing) = existing {
+        let canonical_base = base_dir
+            .canonicalize()
+            .map_err(|e| format!("Failed to resolve {}: {}", base_dir.display(), e))?;
+        let resolved = existing
+            .canonicalize()
+            .map_err(|e| format!("Failed to resolve {}: {}", existing.display(), e))?;
+        if !resolved.starts_with(&canonical_base) {
+            return Err(escapes());
+        }
+    }
+
+    Ok(full_path)
 }
 
 /// Ensure the out-references directory structure exists
 pub fn ensure_out_references_dir() -> Result<PathBuf, String> {
-    let base_dir = get_out_references_dir();
+    let base_dir = get_out_references_dir()?;
 
     // Create category subdirectories
     let categories = ["templates", "examples", "schemas"];
@@ -63,7 +104,7 @@ pub fn ensure_out_references_dir() -> Result<PathBuf, String> {
 
 /// Load metadata from disk
 fn load_metadata() -> Result<OutReferenceMetadata, String> {
-    let metadata_path = get_out_references_dir().join("metadata.json");
+    let metadata_path = get_out_references_dir()?.join("metadata.json");
 
     if !metadata_path.exists() {
         let mut metadata = OutReferenceMetadata {
@@ -77,12 +118,32 @@ fn load_metadata() -> Result<OutReferenceMetadata, String> {
     let content = fs::read_to_string(&metadata_path)
         .map_err(|e| format!("Failed to read metadata.json: {}", e))?;
 
-    let mut metadata: OutReferenceMetadata =
-        serde_json::from_str(&content).map_err(|e| format!("Failed to parse metadata.json: {}", e))?;
+    let mut metadata: OutReferenceMetadata = match serde_json::from_str(&content) {
+        Ok(metadata) => metadata,
+        Err(e) => recover_metadata(&metadata_path, &e.to_string())?,
+    };
     populate_linked_from(&mut metadata)?;
     Ok(metadata)
 }
 
+/// Move a corrupt metadata.json aside and rebuild the index from the filesystem
+fn recover_metadata(metadata_path: &Path, parse_error: &str) -> Result<OutReferenceMetadata, String> {
+    let backup_path = metadata_path.with_extension("json.bak");
+    log::warn!(
+        "metadata.json is corrupt ({}); backing it up to {} and rebuilding",
+        parse_error,
+        backup_path.display()
+    );
+    fs::rename(metadata_path, &backup_path)
+        .map_err(|e| format!("Failed to back up corrupt metadata.json: {}", e))?;
+
+    update_metadata_index()?;
+
+    let content = fs::read_to_string(metadata_path)
+        .map_err(|e| format!("Failed to read rebuilt metadata.json: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse rebuilt metadata.json: {}", e))
+}
+
 /// Normalize a reference path for comparison
 fn normalize_reference_path(path: &str) -> String {
     path.trim_start_matches("../")
@@ -94,33 +155,55 @@ fn normalize_reference_path(path: &str) -> String {
         .to_string()
 }
 
+/// Split a normalized reference path into its non-empty segments
+fn path_segments(path: &str) -> Vec<&str> {
+    path.split(['/', '\\'])
+        .filter(|s| !s.is_empty() && *s != ".")
+        .collect()
+}
+
 /// Check if two reference paths refer to the same target
-fn reference_matches(meta_path: &str, ref_path: &str) -> bool {
+///
+/// Paths match when the shorter one's segments are a trailing run of the longer one's,
+/// so `templates/pr.md` matches `out-references/templates/pr.md` but not `templates/xpr.md`.
+pub(crate) fn reference_matches(meta_path: &str, ref_path: &str) -> bool {
     let meta_norm = normalize_reference_path(meta_path);
     let ref_norm = normalize_reference_path(ref_path);
+    let meta_segments = path_segments(&meta_norm);
+    let ref_segments = path_segments(&ref_norm);
+
+    if meta_segments.is_empty() || ref_segments.is_empty() {
+        return false;
+    }
 
-    ref_norm.contains(&meta_norm) || meta_norm.contains(&ref_norm)
+    meta_segments.ends_with(&ref_segments) || ref_segments.ends_with(&meta_segments)
 }
 
-/// Parse out-reference style links from markdown content
-fn parse_out_reference_links(content: &str) -> Vec<String> {
+/// Targets of every `[text](target)` link in markdown content, in order
+pub(crate) fn markdown_links(content: &str) -> Vec<&str> {
     static LINK_PATTERN: Lazy<Regex> =
         Lazy::new(|| Regex::new(r"\[([^\]]+)\]\(([^)]+)\)").unwrap());
 
+    LINK_PATTERN
+        .captures_iter(content)
+        .filter_map(|caps| caps.get(2))
+        .map(|link_path| link_path.as_str())
+        .collect()
+}
+
+/// Parse out-reference style links from markdown content
+fn parse_out_reference_links(content: &str) -> Vec<String> {
     let mut references = Vec::new();
 
-    for caps in LINK_PATTERN.captures_iter(content) {
-        if let Some(link_path) = caps.get(2) {
-            let path = link_path.as_str();
-            if path.contains("rule-packs/")
-                || path.contains("docs/")
-                || path.contains("templates/")
-                || path.contains("out-references/")
-            {
-                let normalized = normalize_reference_path(path);
-                if !references.contains(&normalized) {
-                    references.push(normalized);
-                }
+    for path in markdown_links(content) {
+        if path.contains("rule-packs/")
+            || path.contains("docs/")
+            || path.contains("templates/")
+            || path.contains("out-references/")
+        {
+            let normalized = normalize_reference_path(path);
+            if !references.contains(&normalized) {
+                references.push(normalized);
             }
         }
     }
@@ -214,7 +297,7 @@ fn populate_linked_from(metadata: &mut OutReferenceMetadata) -> Result<(), Strin
 /// Save metadata to disk
 fn save_metadata(metadata: &OutReferenceMetadata) -> Result<(), String> {
     ensure_out_references_dir()?;
-    let metadata_path = get_out_references_dir().join("metadata.json");
+    let metadata_path = get_out_references_dir()?.join("metadata.json");
 
     let json = serde_json::to_string_pretty(metadata)
         .map_err(|e| format!("Failed to serialize metadata: {}", e))?;
@@ -229,6 +312,32 @@ pub fn list_out_references() -> Result<Vec<OutReference>, String> {
     Ok(metadata.references)
 }
 
+/// List out-references matching a format and carrying every given tag
+pub fn list_out_references_filtered(
+    format: Option<String>,
+    tags: Option<Vec<String>>,
+) -> Result<Vec<OutReference>, String> {
+    let format = format.as_deref().map(parse_format).transpose()?;
+    let references = list_out_references()?;
+    Ok(filter_references(references, format, &tags.unwrap_or_default()))
+}
+
+/// Keep references with the given format (if any) that have all of `tags`
+fn filter_references(
+    references: Vec<OutReference>,
+    format: Option<FileFormat>,
+    tags: &[String],
+) -> Vec<OutReference> {
+    references
+        .into_iter()
+        .filter(|r| format.is_none() || format == Some(r.format))
+        .filter(|r| {
+            tags.iter()
+                .all(|tag| r.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+        })
+        .collect()
+}
+
 /// Get a single out-reference by ID
 pub fn get_out_reference(id: String) -> Result<OutReference, String> {
     let metadata = load_metadata()?;
@@ -239,6 +348,42 @@ pub fn get_out_reference(id: String) -> Result<OutReference, String> {
         .ok_or_else(|| format!("Out-reference not found: {}", id))
 }
 
+/// Get a single out-reference by the relative path commands and packs use to link it
+pub fn get_by_path(path: &str) -> Result<OutReference, String> {
+    let metadata = load_metadata()?;
+    find_by_path(metadata.references, path)
+}
+
+/// Resolve a path to exactly one reference, preferring an exact normalized match
+fn find_by_path(references: Vec<OutReference>, path: &str) -> Result<OutReference, String> {
+    let wanted = normalize_reference_path(path);
+    let mut matches: Vec<OutReference> = references
+        .into_iter()
+        .filter(|r| reference_matches(&r.file_path, path))
+        .collect();
+
+    if let Some(idx) = matches
+        .iter()
+        .position(|r| normalize_reference_path(&r.file_path) == wanted)
+    {
+        return Ok(matches.swap_remove(idx));
+    }
+
+    match matches.len() {
+        0 => Err(format!("Out-reference not found for path: {}", path)),
+        1 => Ok(matches.remove(0)),
+        _ => Err(format!(
+            "Ambiguous out-reference path {}: matches {}",
+            path,
+            matches
+                .iter()
+                .map(|r| r.file_path.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
 /// Create a new out-reference
 pub fn create_out_reference(
     name: String,
@@ -261,7 +406,7 @@ pub fn create_out_reference(
     let file_path = format!("{}/{}", category, file_name);
 
     // Calculate counts
-    let character_count = content.len() as u64;
+    let character_count = budget::char_count(&content);
     let word_count = content.split_whitespace().count() as u64;
 
     let out_ref = OutReference {
@@ -279,8 +424,11 @@ pub fn create_out_reference(
         updated_at: now,
     };
 
-    // Write the file content
-    let full_path = get_out_references_dir().join(&file_path);
+    // Write the file content, refusing to overwrite a reference whose name sanitizes the same
+    let full_path = resolve_contained_path(&file_path)?;
+    if full_path.exists() {
+        return Err(format!("An out-reference file already exists at {}", file_path));
+    }
     if let Some(parent) = full_path.parent() {
         fs::create_dir_all(parent)
             .map_err(|e| format!("Failed to create directory: {}", e))?;
@@ -308,12 +456,12 @@ pub fn update_out_reference(id: String, content: String) -> Result<(), String> {
 
     // Update file content
     let file_path = &metadata.references[ref_idx].file_path;
-    let full_path = get_out_references_dir().join(file_path);
+    let full_path = resolve_contained_path(file_path)?;
     fs::write(&full_path, &content)
         .map_err(|e| format!("Failed to write file: {}", e))?;
 
     // Update metadata
-    metadata.references[ref_idx].character_count = content.len() as u64;
+    metadata.references[ref_idx].character_count = budget::char_count(&content);
     metadata.references[ref_idx].word_count = content.split_whitespace().count() as u64;
     metadata.references[ref_idx].updated_at = Utc::now().to_rfc3339();
 
@@ -352,6 +500,60 @@ pub fn update_out_reference_metadata(
     Ok(updated)
 }
 
+/// Rename an out-reference's file within its directory
+///
+/// Pack overrides, `pack.json` out-references, command overrides, and command links
+/// pointing at the old path are rewritten to the new one.
+pub fn rename_out_reference(id: String, new_file_name: String) -> Result<OutReference, String> {
+    let new_file_name = new_file_name.trim();
+    if new_file_name.is_empty() || new_file_name.contains(['/', '\\']) {
+        return Err(format!("Invalid out-reference file name: {}", new_file_name));
+    }
+
+    let mut metadata = load_metadata()?;
+    let ref_idx = metadata
+        .references
+        .iter()
+        .position(|r| r.id == id)
+        .ok_or_else(|| format!("Out-reference not found: {}", id))?;
+
+    let old_path = metadata.references[ref_idx].file_path.clone();
+    let new_path = match old_path.rsplit_once('/') {
+        Some((dir, _)) => format!("{}/{}", dir, new_file_name),
+        None => new_file_name.to_string(),
+    };
+    if new_path == old_path {
+        return Ok(metadata.references[ref_idx].clone());
+    }
+
+    let old_full = resolve_contained_path(&old_path)?;
+    let new_full = resolve_contained_path(&new_path)?;
+    if new_full.exists() {
+        return Err(format!("An out-reference file already exists at {}", new_path));
+    }
+    fs::rename(&old_full, &new_full).map_err(|e| format!("Failed to rename file: {}", e))?;
+
+    metadata.references[ref_idx].file_path = new_path.clone();
+    metadata.references[ref_idx].updated_at = Utc::now().to_rfc3339();
+    let renamed = metadata.references[ref_idx].clone();
+    if let Err(e) = save_metadata(&metadata) {
+        // Keep the file where the unchanged metadata says it is
+        if let Err(undo) = fs::rename(&new_full, &old_full) {
+            log::warn!("Failed to restore {} after rename: {}", old_path, undo);
+        }
+        return Err(e);
+    }
+
+    fs_manager::migrate_pack_out_ref_overrides(&old_path, &new_path)
+        .map_err(|e| format!("Failed to update pack out-reference overrides: {}", e))?;
+    fs_manager::migrate_pack_json_out_references(&old_path, &new_path)
+        .map_err(|e| format!("Failed to update pack out-references: {}", e))?;
+    command_registry::migrate_command_out_references(&old_path, &new_path)
+        .map_err(|e| format!("Failed to update command out-references: {}", e))?;
+
+    Ok(renamed)
+}
+
 /// Delete an out-reference
 pub fn delete_out_reference(id: String) -> Result<(), String> {
     let mut metadata = load_metadata()?;
@@ -364,7 +566,7 @@ pub fn delete_out_reference(id: String) -> Result<(), String> {
 
     // Delete the file
     let file_path = &metadata.references[ref_idx].file_path;
-    let full_path = get_out_references_dir().join(file_path);
+    let full_path = resolve_contained_path(file_path)?;
     if full_path.exists() {
         fs::remove_file(&full_path)
             .map_err(|e| format!("Failed to delete file: {}", e))?;
@@ -380,9 +582,9 @@ pub fn delete_out_reference(id: String) -> Result<(), String> {
 /// Read the content of an out-reference
 pub fn read_out_reference_content(id: String) -> Result<String, String> {
     let out_ref = get_out_reference(id)?;
-    let full_path = get_out_references_dir().join(&out_ref.file_path);
+    let full_path = resolve_contained_path(&out_ref.file_path)?;
 
-    fs::read_to_string(&full_path)
+    fs_manager::read_file_guarded(&full_path)
         .map_err(|e| format!("Failed to read file: {}", e))
 }
 
@@ -391,18 +593,45 @@ pub fn write_out_reference_content(id: String, content: String) -> Result<(), St
     update_out_reference(id, content)
 }
 
+/// Check that an out-reference's content parses as its declared format
+///
+/// JSON, YAML, and TOML are parsed; Markdown and plain text are always valid.
+pub fn validate_format(id: String) -> Result<FormatValidation, String> {
+    let out_ref = get_out_reference(id.clone())?;
+    let content = read_out_reference_content(id)?;
+    Ok(check_format(&out_ref, &content))
+}
+
+fn check_format(out_ref: &OutReference, content: &str) -> FormatValidation {
+    let error = match out_ref.format {
+        FileFormat::Json => serde_json::from_str::<serde_json::Value>(content).err().map(|e| e.to_string()),
+        FileFormat::Yaml => serde_yaml::from_str::<serde_yaml::Value>(content).err().map(|e| e.to_string()),
+        FileFormat::Toml => toml::from_str::<toml::Table>(content).err().map(|e| e.to_string()),
+        FileFormat::Markdown | FileFormat::Text => None,
+    };
+
+    FormatValidation {
+        id: out_ref.id.clone(),
+        file_path: out_ref.file_path.clone(),
+        format: out_ref.format,
+        valid: error.is_none(),
+        error,
+    }
+}
+
 /// Validate all out-references
 pub fn validate_out_references() -> Result<OutReferenceValidationReport, String> {
     let metadata = load_metadata()?;
-    let base_dir = get_out_references_dir();
+    let base_dir = get_out_references_dir()?;
     let commands = command_registry::load_commands().unwrap_or_default();
     let pack_references = collect_pack_references().unwrap_or_default();
 
     let mut broken_links: Vec<BrokenLink> = Vec::new();
     let mut unused_references: Vec<String> = Vec::new();
     let mut orphaned_files: Vec<String> = Vec::new();
+    let mut format_errors: Vec<FormatValidation> = Vec::new();
 
-    // Check each reference exists on disk
+    // Check each reference exists on disk and parses as its declared format
     for out_ref in &metadata.references {
         let file_path = base_dir.join(&out_ref.file_path);
         if !file_path.exists() {
@@ -412,6 +641,11 @@ pub fn validate_out_references() -> Result<OutReferenceValidationReport, String>
                 target_path: out_ref.file_path.clone(),
                 reason: "File does not exist".to_string(),
             });
+        } else if let Ok(content) = fs_manager::read_file_guarded(&file_path) {
+            let validation = check_format(out_ref, &content);
+            if !validation.valid {
+                format_errors.push(validation);
+            }
         }
     }
 
@@ -495,16 +729,164 @@ pub fn validate_out_references() -> Result<OutReferenceValidationReport, String>
         }
     }
 
-    let valid = broken_links.is_empty();
+    let valid = broken_links.is_empty() && format_errors.is_empty();
 
     Ok(OutReferenceValidationReport {
         valid,
         broken_links,
         unused_references,
         orphaned_files,
+        format_errors,
     })
 }
 
+/// List the orphaned files `purge_orphans` would delete, without deleting them
+pub fn preview_orphan_purge() -> Result<Vec<String>, String> {
+    Ok(validate_out_references()?.orphaned_files)
+}
+
+/// Delete files in the out-references directory that aren't tracked in metadata
+///
+/// Refuses to run when metadata.json is missing or had to be rebuilt, since every
+/// file would then look untracked. Returns the removed paths, relative to the
+/// out-references directory.
+pub fn purge_orphans() -> Result<Vec<String>, String> {
+    ensure_metadata_intact()?;
+    let orphans = preview_orphan_purge()?;
+    for relative_path in &orphans {
+        let full_path = resolve_contained_path(relative_path)?;
+        fs::remove_file(&full_path)
+            .map_err(|e| format!("Failed to delete {}: {}", relative_path, e))?;
+    }
+    Ok(orphans)
+}
+
+/// Check that metadata.json exists, parses, and was never rebuilt after corruption
+fn ensure_metadata_intact() -> Result<(), String> {
+    let metadata_path = get_out_references_dir()?.join("metadata.json");
+    if !metadata_path.exists() {
+        return Err("Refusing to purge: metadata.json does not exist".to_string());
+    }
+
+    let content = fs::read_to_string(&metadata_path)
+        .map_err(|e| format!("Failed to read metadata.json: {}", e))?;
+    if serde_json::from_str::<OutReferenceMetadata>(&content).is_err() {
+        return Err("Refusing to purge: metadata.json is corrupt".to_string());
+    }
+
+    let backup_path = metadata_path.with_extension("json.bak");
+    if backup_path.exists() {
+        return Err(format!(
+            "Refusing to purge: metadata.json was rebuilt after corruption; review {} first",
+            backup_path.display()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Something in the out-references directory that can likely be removed
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupSuggestion {
+    /// Reference id, or the relative path for an untracked file
+    pub id: String,
+    pub reason: String,
+}
+
+/// Suggest out-references and files to clean up, most clearly unneeded first
+///
+/// Orphaned files come first since nothing tracks them at all, followed by tracked
+/// references no command or pack links to, largest first.
+pub fn suggest_cleanup() -> Result<Vec<CleanupSuggestion>, String> {
+    let report = validate_out_references()?;
+    let metadata = load_metadata()?;
+
+    let mut suggestions: Vec<CleanupSuggestion> = report
+        .orphaned_files
+        .into_iter()
+        .map(|path| CleanupSuggestion {
+            id: path,
+            reason: "File is not tracked in out-reference metadata".to_string(),
+        })
+        .collect();
+
+    let mut unused: Vec<&OutReference> = metadata
+        .references
+        .iter()
+        .filter(|r| report.unused_references.contains(&r.id))
+        .collect();
+    unused.sort_by(|a, b| {
+        b.character_count
+            .cmp(&a.character_count)
+            .then_with(|| a.file_path.cmp(&b.file_path))
+    });
+    suggestions.extend(unused.into_iter().map(|r| CleanupSuggestion {
+        id: r.id.clone(),
+        reason: format!("{} is not linked from any command or pack", r.file_path),
+    }));
+
+    Ok(suggestions)
+}
+
+/// Delete the selected cleanup suggestions
+///
+/// Every id must still be suggested by `suggest_cleanup`, so a reference that became
+/// linked again is never deleted. Returns the deleted ids.
+pub fn cleanup_unused(ids: &[String]) -> Result<Vec<String>, String> {
+    let suggestions = suggest_cleanup()?;
+    if let Some(id) = ids.iter().find(|id| !suggestions.iter().any(|s| &s.id == *id)) {
+        return Err(format!("Out-reference is not a cleanup candidate: {}", id));
+    }
+
+    let tracked: Vec<String> = load_metadata()?.references.into_iter().map(|r| r.id).collect();
+    let mut deleted = Vec::new();
+    for id in ids {
+        if deleted.contains(id) {
+            continue;
+        }
+        if tracked.contains(id) {
+            delete_out_reference(id.clone())?;
+        } else {
+            let full_path = resolve_contained_path(id)?;
+            fs::remove_file(&full_path).map_err(|e| format!("Failed to delete {}: {}", id, e))?;
+        }
+        deleted.push(id.clone());
+    }
+
+    Ok(deleted)
+}
+
+/// Check a pack's out-reference overrides against tracked references
+///
+/// Returns one warning per override path that doesn't resolve to a tracked out-reference.
+pub fn validate_pack_out_ref_overrides(pack_id: &str) -> Result<Vec<String>, String> {
+    let overrides = fs_manager::read_pack_out_ref_overrides()
+        .map_err(|e| format!("Failed to read pack out-reference overrides: {}", e))?;
+    let paths = match overrides.get(pack_id) {
+        Some(paths) => paths,
+        None => return Ok(Vec::new()),
+    };
+
+    let metadata = load_metadata()?;
+    Ok(paths
+        .iter()
+        .filter(|path| {
+            !metadata
+                .references
+                .iter()
+                .any(|r| reference_matches(&r.file_path, path))
+        })
+        .map(|path| format!("Out-reference override not found: {}", path))
+        .collect())
+}
+
+/// Find what commands/packs reference the out-reference at a linked path
+pub fn find_references_to_path(path: String) -> Result<Vec<ReferenceLink>, String> {
+    let out_ref = get_by_path(&path)?;
+    find_references_to(out_ref.id)
+}
+
 /// Find what commands/packs reference a specific out-reference
 pub fn find_references_to(id: String) -> Result<Vec<ReferenceLink>, String> {
     let out_ref = get_out_reference(id)?;
@@ -554,7 +936,7 @@ pub fn find_references_to(id: String) -> Result<Vec<ReferenceLink>, String> {
 
 /// Rebuild metadata index from filesystem
 pub fn update_metadata_index() -> Result<(), String> {
-    let base_dir = get_out_references_dir();
+    let base_dir = get_out_references_dir()?;
     let mut references: Vec<OutReference> = Vec::new();
 
     let categories = ["templates", "examples", "schemas"];
@@ -579,7 +961,7 @@ pub fn update_metadata_index() -> Result<(), String> {
 
                 // Read content for counts
                 let content = fs::read_to_string(&path).unwrap_or_default();
-                let character_count = content.len() as u64;
+                let character_count = budget::char_count(&content);
                 let word_count = content.split_whitespace().count() as u64;
 
                 let now = Utc::now().to_rfc3339();
@@ -634,9 +1016,15 @@ pub fn import_out_references(bundle: String) -> Result<Vec<OutReference>, String
     let imports: Vec<(OutReference, String)> = serde_json::from_str(&bundle)
         .map_err(|e| format!("Failed to parse import bundle: {}", e))?;
 
+    // Check every path up front so a bad entry doesn't leave a partial import
+    let targets = imports
+        .iter()
+        .map(|(out_ref, _)| resolve_contained_path(&out_ref.file_path))
+        .collect::<Result<Vec<_>, _>>()?;
+
     let mut created: Vec<OutReference> = Vec::new();
 
-    for (mut out_ref, content) in imports {
+    for ((mut out_ref, content), full_path) in imports.into_iter().zip(targets) {
         // Generate new ID to avoid conflicts
         out_ref.id = Uuid::new_v4().to_string();
         let now = Utc::now().to_rfc3339();
@@ -644,7 +1032,6 @@ pub fn import_out_references(bundle: String) -> Result<Vec<OutReference>, String
         out_ref.updated_at = now;
 
         // Write the file
-        let full_path = get_out_references_dir().join(&out_ref.file_path);
         if let Some(parent) = full_path.parent() {
             fs::create_dir_all(parent)
                 .map_err(|e| format!("Failed to create directory: {}", e))?;
@@ -663,6 +1050,174 @@ pub fn import_out_references(bundle: String) -> Result<Vec<OutReference>, String
     Ok(created)
 }
 
+/// Track a file a command links to but the out-references store doesn't have yet
+///
+/// `ref_path` must be one of the command's out-references. The source is looked up next to
+/// the command, then under `~/.agentsmd/`, its `docs/` folder, and each rule pack. Paths that
+/// don't start with a category are stored under `templates/`, keeping the reference's own
+/// segments so the command's link resolves to the new entry.
+pub fn create_from_command_reference(command_id: &str, ref_path: &str) -> Result<OutReference, String> {
+    let command = command_registry::get_command_by_id(command_id)?;
+    if !command.out_references.iter().any(|r| reference_matches(r, ref_path)) {
+        return Err(format!("Command {} does not reference {}", command.id, ref_path));
+    }
+
+    let mut metadata = load_metadata()?;
+    if metadata.references.iter().any(|r| reference_matches(&r.file_path, ref_path)) {
+        return Err(format!("Out-reference is already tracked: {}", ref_path));
+    }
+
+    let relative = normalize_reference_path(ref_path);
+    let segments = path_segments(&relative);
+    if segments.is_empty() || segments.contains(&"..") {
+        return Err(format!("Cannot import out-reference outside the agentsmd tree: {}", ref_path));
+    }
+    let relative = segments.join("/");
+
+    let home = fs_manager::get_agentsmd_home().map_err(|e| e.to_string())?;
+    let mut search_roots = vec![command_registry::get_commands_directory()?, home.clone(), home.join("docs")];
+    if let Ok(pack_ids) = fs_manager::list_rule_packs() {
+        let packs_dir = fs_manager::get_rule_packs_dir().map_err(|e| e.to_string())?;
+        search_roots.extend(pack_ids.iter().map(|id| packs_dir.join(id)));
+    }
+    let source = search_roots
+        .iter()
+        .map(|root| root.join(&relative))
+        .find(|candidate| candidate.is_file())
+        .ok_or_else(|| format!("No source file found for out-reference {}", ref_path))?;
+    let content = fs_manager::read_file_guarded(&source)
+        .map_err(|e| format!("Failed to read {}: {}", source.display(), e))?;
+
+    let (category, file_path) = match parse_category(segments[0]) {
+        Ok(category) if segments.len() > 1 => (category, relative.clone()),
+        _ => (OutReferenceCategory::Templates, format!("templates/{}", relative)),
+    };
+    let full_path = resolve_contained_path(&file_path)?;
+    if full_path.exists() {
+        return Err(format!("An out-reference file already exists at {}", file_path));
+    }
+    if let Some(parent) = full_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+    fs::write(&full_path, &content).map_err(|e| format!("Failed to write file: {}", e))?;
+
+    let now = Utc::now().to_rfc3339();
+    let out_ref = OutReference {
+        id: Uuid::new_v4().to_string(),
+        name: source
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| relative.clone()),
+        description: format!("Imported from command {}", command.id),
+        category,
+        file_path,
+        format: detect_format_from_extension(&source),
+        tags: Vec::new(),
+        linked_from: Vec::new(),
+        character_count: budget::char_count(&content),
+        word_count: content.split_whitespace().count() as u64,
+        created_at: now.clone(),
+        updated_at: now,
+    };
+
+    metadata.references.push(out_ref.clone());
+    save_metadata(&metadata)?;
+    Ok(out_ref)
+}
+
+/// Name of the metadata entry inside an exported zip archive
+const ZIP_METADATA_ENTRY: &str = "metadata.json";
+
+/// Export out-references to a zip archive at `dest_path`
+///
+/// Files are stored under their `file_path` and copied straight from disk, so large
+/// and binary references are supported. The references' metadata is written to a
+/// `metadata.json` entry at the root of the archive.
+pub fn export_out_references_zip(ids: Vec<String>, dest_path: &Path) -> Result<(), String> {
+    let references = ids
+        .into_iter()
+        .map(get_out_reference)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let file = fs::File::create(dest_path)
+        .map_err(|e| format!("Failed to create {}: {}", dest_path.display(), e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for out_ref in &references {
+        let mut source = fs::File::open(resolve_contained_path(&out_ref.file_path)?)
+            .map_err(|e| format!("Failed to read file {}: {}", out_ref.file_path, e))?;
+        zip.start_file(out_ref.file_path.replace('\\', "/"), options)
+            .map_err(|e| format!("Failed to add {} to archive: {}", out_ref.file_path, e))?;
+        io::copy(&mut source, &mut zip)
+            .map_err(|e| format!("Failed to add {} to archive: {}", out_ref.file_path, e))?;
+    }
+
+    let metadata = serde_json::to_string_pretty(&references)
+        .map_err(|e| format!("Failed to serialize export: {}", e))?;
+    zip.start_file(ZIP_METADATA_ENTRY, options)
+        .map_err(|e| format!("Failed to add metadata to archive: {}", e))?;
+    zip.write_all(metadata.as_bytes())
+        .map_err(|e| format!("Failed to add metadata to archive: {}", e))?;
+
+    zip.finish()
+        .map_err(|e| format!("Failed to finish archive: {}", e))?;
+    Ok(())
+}
+
+/// Import out-references from a zip archive created by `export_out_references_zip`
+pub fn import_out_references_zip(source_path: &Path) -> Result<Vec<OutReference>, String> {
+    let file = fs::File::open(source_path)
+        .map_err(|e| format!("Failed to open {}: {}", source_path.display(), e))?;
+    let mut archive = ZipArchive::new(file)
+        .map_err(|e| format!("Failed to read archive: {}", e))?;
+
+    let imports: Vec<OutReference> = {
+        let entry = archive
+            .by_name(ZIP_METADATA_ENTRY)
+            .map_err(|e| format!("Archive has no {}: {}", ZIP_METADATA_ENTRY, e))?;
+        serde_json::from_reader(entry)
+            .map_err(|e| format!("Failed to parse archive metadata: {}", e))?
+    };
+
+    // Check every path up front so a bad entry doesn't leave a partial import
+    let targets = imports
+        .iter()
+        .map(|out_ref| resolve_contained_path(&out_ref.file_path))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut created: Vec<OutReference> = Vec::new();
+
+    for (mut out_ref, full_path) in imports.into_iter().zip(targets) {
+        let entry_name = out_ref.file_path.replace('\\', "/");
+
+        // Generate new ID to avoid conflicts
+        out_ref.id = Uuid::new_v4().to_string();
+        let now = Utc::now().to_rfc3339();
+        out_ref.created_at = now.clone();
+        out_ref.updated_at = now;
+
+        let mut entry = archive
+            .by_name(&entry_name)
+            .map_err(|e| format!("Archive is missing {}: {}", entry_name, e))?;
+        if let Some(parent) = full_path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create directory: {}", e))?;
+        }
+        let mut dest = fs::File::create(&full_path)
+            .map_err(|e| format!("Failed to write file: {}", e))?;
+        io::copy(&mut entry, &mut dest)
+            .map_err(|e| format!("Failed to write file: {}", e))?;
+
+        let mut metadata = load_metadata()?;
+        metadata.references.push(out_ref.clone());
+        save_metadata(&metadata)?;
+
+        created.push(out_ref);
+    }
+
+    Ok(created)
+}
+
 /// Get statistics about out-references
 pub fn get_out_reference_stats() -> Result<OutReferenceStats, String> {
     let metadata = load_metadata()?;
@@ -723,38 +1278,140 @@ fn parse_category(category: &str) -> Result<OutReferenceCategory, String> {
 }
 
 fn parse_format(format: &str) -> Result<FileFormat, String> {
-    match format.to_lowercase().as_str() {
-        "markdown" | "md" => Ok(FileFormat::Markdown),
-        "json" => Ok(FileFormat::Json),
-        "yaml" | "yml" => Ok(FileFormat::Yaml),
-        "text" | "txt" => Ok(FileFormat::Text),
-        _ => Err(format!("Invalid format: {}", format)),
-    }
+    FileFormat::parse(format).ok_or_else(|| format!("Invalid format: {}", format))
 }
 
 fn generate_file_name(name: &str, format: &FileFormat) -> String {
-    let base = name
-        .to_lowercase()
-        .replace(' ', "-")
-        .chars()
-        .filter(|c| c.is_alphanumeric() || *c == '-')
-        .collect::<String>();
-
-    let extension = match format {
-        FileFormat::Markdown => "md",
-        FileFormat::Json => "json",
-        FileFormat::Yaml => "yaml",
-        FileFormat::Text => "txt",
-    };
+    format!("{}.{}", util::safe_file_stem(&name.to_lowercase()), format.extension())
+}
 
-    format!("{}.{}", base, extension)
+fn detect_format_from_extension(path: &Path) -> FileFormat {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .and_then(FileFormat::from_extension)
+        .unwrap_or(FileFormat::Text)
 }
 
-fn detect_format_from_extension(path: &PathBuf) -> FileFormat {
-    match path.extension().and_then(|e| e.to_str()) {
-        Some("md") | Some("markdown") => FileFormat::Markdown,
-        Some("json") => FileFormat::Json,
-        Some("yaml") | Some("yml") => FileFormat::Yaml,
-        _ => FileFormat::Text,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn out_ref(id: &str, file_path: &str) -> OutReference {
+        OutReference {
+            id: id.to_string(),
+            name: id.to_string(),
+            description: String::new(),
+            category: OutReferenceCategory::Templates,
+            file_path: file_path.to_string(),
+            format: FileFormat::Markdown,
+            tags: Vec::new(),
+            linked_from: Vec::new(),
+            character_count: 0,
+            word_count: 0,
+            created_at: String::new(),
+            updated_at: String::new(),
+        }
+    }
+
+    fn sample_refs() -> Vec<OutReference> {
+        vec![
+            out_ref("pr-template", "templates/pr.md"),
+            out_ref("pr-example", "examples/pr.md"),
+            out_ref("issue-template", "templates/issue.md"),
+        ]
+    }
+
+    fn tagged(id: &str, format: FileFormat, tags: &[&str]) -> OutReference {
+        OutReference {
+            format,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            ..out_ref(id, &format!("templates/{}.{}", id, format.extension()))
+        }
+    }
+
+    fn tagged_refs() -> Vec<OutReference> {
+        vec![
+            tagged("pr", FileFormat::Markdown, &["git", "review"]),
+            tagged("issue", FileFormat::Markdown, &["git"]),
+            tagged("ci", FileFormat::Yaml, &["git", "ci"]),
+        ]
+    }
+
+    fn ids(references: Vec<OutReference>) -> Vec<String> {
+        references.into_iter().map(|r| r.id).collect()
+    }
+
+    #[test]
+    fn test_filter_references_by_format() {
+        let found = filter_references(tagged_refs(), parse_format("markdown").ok(), &[]);
+        assert_eq!(ids(found), vec!["pr", "issue"]);
+    }
+
+    #[test]
+    fn test_filter_references_by_tag() {
+        let found = filter_references(tagged_refs(), None, &["ci".to_string()]);
+        assert_eq!(ids(found), vec!["ci"]);
+
+        let found = filter_references(tagged_refs(), None, &["git".to_string()]);
+        assert_eq!(ids(found), vec!["pr", "issue", "ci"]);
+    }
+
+    #[test]
+    fn test_filter_references_requires_all_tags() {
+        let tags = vec!["git".to_string(), "review".to_string()];
+        assert_eq!(ids(filter_references(tagged_refs(), None, &tags)), vec!["pr"]);
+
+        let found = filter_references(tagged_refs(), Some(FileFormat::Yaml), &tags);
+        assert!(found.is_empty());
+    }
+
+    #[test]
+    fn test_find_by_path_exact() {
+        let found = find_by_path(sample_refs(), "templates/pr.md").unwrap();
+        assert_eq!(found.id, "pr-template");
+    }
+
+    #[test]
+    fn test_find_by_path_prefixed() {
+        let found = find_by_path(sample_refs(), "~/.agentsmd/out-references/templates/issue.md").unwrap();
+        assert_eq!(found.id, "issue-template");
+
+        let found = find_by_path(sample_refs(), "../out-references/examples/pr.md").unwrap();
+        assert_eq!(found.id, "pr-example");
+    }
+
+    #[test]
+    fn test_find_by_path_ambiguous_and_missing() {
+        let err = find_by_path(sample_refs(), "pr.md").unwrap_err();
+        assert!(err.contains("Ambiguous"));
+        assert!(err.contains("templates/pr.md") && err.contains("examples/pr.md"));
+
+        assert!(find_by_path(sample_refs(), "templates/r.md").is_err());
+    }
+
+    #[test]
+    fn test_reference_matches_whole_segments() {
+        assert!(reference_matches("templates/pr.md", "out-references/templates/pr.md"));
+        assert!(reference_matches("templates/pr.md", "pr.md"));
+        assert!(!reference_matches("templates/pr.md", "r.md"));
+        assert!(!reference_matches("templates/pr.md", ""));
+    }
+
+    #[test]
+    fn test_format_detection_agrees_with_parsing() {
+        for ext in ["md", "markdown", "toml", "json", "yaml", "yml", "txt"] {
+            let path = PathBuf::from(format!("templates/sample.{}", ext));
+            assert_eq!(
+                Some(detect_format_from_extension(&path)),
+                parse_format(ext).ok(),
+                "extension {}",
+                ext
+            );
+        }
+
+        let toml = parse_format("toml").unwrap();
+        assert_eq!(toml, FileFormat::Toml);
+        assert_eq!(generate_file_name("Lint Config", &toml), "lint-config.toml");
+        assert_eq!(detect_format_from_extension(Path::new("notes.log")), FileFormat::Text);
     }
 }
@@ -0,0 +1,373 @@
+//! Rule pack comparison and consistency checks
+//!
+//! Compares the resolved content of two rule packs to show how much they overlap,
+//! and detects pack directories that declare the same id.
+
+use crate::fs_manager;
+use crate::types::RulePack;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+/// Lines of unchanged context shown around each diff hunk
+const CONTEXT_LINES: usize = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackDiff {
+    pub pack_a: String,
+    pub pack_b: String,
+    /// Unified diff of pack A's content against pack B's
+    pub unified_diff: String,
+    /// Line-based similarity between 0.0 (nothing shared) and 1.0 (identical)
+    pub similarity: f64,
+    pub shared_lines: u64,
+    pub lines_only_in_a: u64,
+    pub lines_only_in_b: u64,
+}
+
+/// A pack id declared by more than one pack directory
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicatePackId {
+    pub pack_id: String,
+    pub directories: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DiffOp {
+    Equal(usize, usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+/// Find pack ids declared by more than one directory under `rule-packs/`
+///
+/// Directories whose `pack.json` can't be read or parsed are skipped.
+pub fn find_duplicate_ids() -> Result<Vec<DuplicatePackId>, String> {
+    let dirs = fs_manager::list_rule_packs().map_err(|e| format!("Failed to list packs: {}", e))?;
+
+    let declared = dirs.into_iter().filter_map(|dir| {
+        let json = fs_manager::read_pack_json(dir.clone()).ok()?;
+        let pack: RulePack = serde_json::from_str(&json).ok()?;
+        Some((dir, pack.id))
+    });
+
+    Ok(duplicate_ids(declared))
+}
+
+/// Group `(directory, declared id)` pairs and keep the ids claimed more than once
+pub fn duplicate_ids(declared: impl IntoIterator<Item = (String, String)>) -> Vec<DuplicatePackId> {
+    let mut by_id: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (dir, id) in declared {
+        by_id.entry(id).or_default().push(dir);
+    }
+
+    by_id
+        .into_iter()
+        .filter(|(_, dirs)| dirs.len() > 1)
+        .map(|(pack_id, mut directories)| {
+            directories.sort();
+            DuplicatePackId { pack_id, directories }
+        })
+        .collect()
+}
+
+/// Compare the resolved content of two packs
+pub fn diff(pack_a: &str, pack_b: &str) -> Result<PackDiff, String> {
+    let content_a = fs_manager::read_pack_content(pack_a.to_string())
+        .map_err(|e| format!("Failed to load pack content for {}: {}", pack_a, e))?;
+    let content_b = fs_manager::read_pack_content(pack_b.to_string())
+        .map_err(|e| format!("Failed to load pack content for {}: {}", pack_b, e))?;
+
+    Ok(diff_content(pack_a, &content_a, pack_b, &content_b))
+}
+
+/// Compare two pieces of pack content
+pub fn diff_content(pack_a: &str, content_a: &str, pack_b: &str, content_b: &str) -> PackDiff {
+    let lines_a: Vec<&str> = content_a.lines().collect();
+    let lines_b: Vec<&str> = content_b.lines().collect();
+    let ops = diff_lines(&lines_a, &lines_b);
+
+    let shared = ops.iter().filter(|op| matches!(op, DiffOp::Equal(..))).count();
+    let total = lines_a.len() + lines_b.len();
+    let similarity = if total == 0 {
+        1.0
+    } else {
+        (2 * shared) as f64 / total as f64
+    };
+
+    PackDiff {
+        pack_a: pack_a.to_string(),
+        pack_b: pack_b.to_string(),
+        unified_diff: render_unified(pack_a, &lines_a, pack_b, &lines_b, &ops),
+        similarity,
+        shared_lines: shared as u64,
+        lines_only_in_a: (lines_a.len() - shared) as u64,
+        lines_only_in_b: (lines_b.len() - shared) as u64,
+    }
+}
+
+/// Line diff using Myers' O(ND) algorithm in linear space
+///
+/// Lines that never occur on the other side can't be shared, so they're set aside
+/// before the search; wholly rewritten packs then cost next to nothing to compare.
+fn diff_lines(a: &[&str], b: &[&str]) -> Vec<DiffOp> {
+    let in_a: HashSet<&str> = a.iter().copied().collect();
+    let in_b: HashSet<&str> = b.iter().copied().collect();
+    let a_idx: Vec<usize> = (0..a.len()).filter(|&i| in_b.contains(a[i])).collect();
+    let b_idx: Vec<usize> = (0..b.len()).filter(|&j| in_a.contains(b[j])).collect();
+    let a_kept: Vec<&str> = a_idx.iter().map(|&i| a[i]).collect();
+    let b_kept: Vec<&str> = b_idx.iter().map(|&j| b[j]).collect();
+
+    let mut matches = Vec::new();
+    common_lines(&a_kept, &b_kept, 0, 0, &mut matches);
+
+    let mut ops = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+    for (ki, kj) in matches {
+        let (ai, bj) = (a_idx[ki], b_idx[kj]);
+        ops.extend((i..ai).map(DiffOp::Delete));
+        ops.extend((j..bj).map(DiffOp::Insert));
+        ops.push(DiffOp::Equal(ai, bj));
+        i = ai + 1;
+        j = bj + 1;
+    }
+    ops.extend((i..a.len()).map(DiffOp::Delete));
+    ops.extend((j..b.len()).map(DiffOp::Insert));
+    ops
+}
+
+/// Append the `(i, j)` pairs of a longest common subsequence of `a` and `b`, in order
+///
+/// `a0` and `b0` are the offsets of the slices within the full sequences.
+fn common_lines(a: &[&str], b: &[&str], a0: usize, b0: usize, out: &mut Vec<(usize, usize)>) {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    out.extend((0..prefix).map(|k| (a0 + k, b0 + k)));
+    let (a, b) = (&a[prefix..], &b[prefix..]);
+    let (a0, b0) = (a0 + prefix, b0 + prefix);
+
+    let suffix = a.iter().rev().zip(b.iter().rev()).take_while(|(x, y)| x == y).count();
+    let (a, b) = (&a[..a.len() - suffix], &b[..b.len() - suffix]);
+
+    if !a.is_empty() && !b.is_empty() {
+        let (x, y, u, v) = middle_snake(a, b);
+        common_lines(&a[..x], &b[..y], a0, b0, out);
+        out.extend((0..u - x).map(|k| (a0 + x + k, b0 + y + k)));
+        common_lines(&a[u..], &b[v..], a0 + u, b0 + v, out);
+    }
+
+    out.extend((0..suffix).map(|k| (a0 + a.len() + k, b0 + b.len() + k)));
+}
+
+/// Middle snake of a shortest edit script between `a` and `b`, as `(x, y, u, v)`
+///
+/// Searches forward from the start and backward from the end until the two paths
+/// meet; the snake runs diagonally from `(x, y)` to `(u, v)`. Both inputs must be
+/// non-empty.
+fn middle_snake(a: &[&str], b: &[&str]) -> (usize, usize, usize, usize) {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let max = (n + m + 1) / 2;
+    let offset = max + 1;
+    let mut forward = vec![0isize; 2 * offset as usize + 1];
+    let mut backward = vec![0isize; 2 * offset as usize + 1];
+    let at = |k: isize| (k + offset) as usize;
+
+    for d in 0..=max {
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && forward[at(k - 1)] < forward[at(k + 1)]) {
+                forward[at(k + 1)]
+            } else {
+                forward[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            let (x0, y0) = (x, y);
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[at(k)] = x;
+
+            let back_k = delta - k;
+            if odd && back_k.abs() < d && x + backward[at(back_k)] >= n {
+                return (x0 as usize, y0 as usize, x as usize, y as usize);
+            }
+        }
+
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && backward[at(k - 1)] < backward[at(k + 1)]) {
+                backward[at(k + 1)]
+            } else {
+                backward[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            let (x0, y0) = (x, y);
+            while x < n && y < m && a[(n - 1 - x) as usize] == b[(m - 1 - y) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[at(k)] = x;
+
+            let forward_k = delta - k;
+            if !odd && forward_k.abs() <= d && x + forward[at(forward_k)] >= n {
+                return ((n - x) as usize, (m - y) as usize, (n - x0) as usize, (m - y0) as usize);
+            }
+        }
+    }
+
+    unreachable!("forward and backward searches meet within (n + m + 1) / 2 steps")
+}
+
+/// Render diff operations as a unified diff with context hunks
+fn render_unified(label_a: &str, a: &[&str], label_b: &str, b: &[&str], ops: &[DiffOp]) -> String {
+    let changes: Vec<usize> = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, op)| !matches!(op, DiffOp::Equal(..)))
+        .map(|(idx, _)| idx)
+        .collect();
+
+    if changes.is_empty() {
+        return String::new();
+    }
+
+    // Group changed ops into hunks, merging ones whose context overlaps
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for idx in changes {
+        let start = idx.saturating_sub(CONTEXT_LINES);
+        let end = (idx + CONTEXT_LINES + 1).min(ops.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut out = format!("--- a/{}\n+++ b/{}\n", label_a, label_b);
+    let (mut scanned, mut a_start, mut b_start) = (0, 0, 0);
+    for (start, end) in hunks {
+        let hunk = &ops[start..end];
+        let (a_skipped, b_skipped) = line_counts(&ops[scanned..start]);
+        a_start += a_skipped;
+        b_start += b_skipped;
+        scanned = start;
+        let (a_len, b_len) = line_counts(hunk);
+
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            a_start + 1,
+            a_len,
+            b_start + 1,
+            b_len
+        ));
+        for op in hunk {
+            match *op {
+                DiffOp::Equal(i, _) => out.push_str(&format!(" {}\n", a[i])),
+                DiffOp::Delete(i) => out.push_str(&format!("-{}\n", a[i])),
+                DiffOp::Insert(j) => out.push_str(&format!("+{}\n", b[j])),
+            }
+        }
+    }
+
+    out
+}
+
+/// Number of lines `ops` covers in each side
+fn line_counts(ops: &[DiffOp]) -> (usize, usize) {
+    let a_len = ops.iter().filter(|op| !matches!(op, DiffOp::Insert(_))).count();
+    let b_len = ops.iter().filter(|op| !matches!(op, DiffOp::Delete(_))).count();
+    (a_len, b_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GIT_RULES: &str = "# Git Workflow\n\n- Branch per issue\n- Small commits\n- Rebase before merge\n- Reference issues\n";
+    const TEST_RULES: &str = "# Testing\n\nWrite tests first.\nKeep suites fast.\nMock the network.\n";
+
+    #[test]
+    fn test_diff_identical_packs() {
+        let result = diff_content("git", GIT_RULES, "git", GIT_RULES);
+        assert_eq!(result.similarity, 1.0);
+        assert_eq!(result.lines_only_in_a, 0);
+        assert!(result.unified_diff.is_empty());
+    }
+
+    #[test]
+    fn test_diff_similarity_ordering() {
+        let tweaked = GIT_RULES.replace("Small commits", "Atomic commits");
+        let close = diff_content("git", GIT_RULES, "git-v2", &tweaked);
+        let distant = diff_content("git", GIT_RULES, "testing", TEST_RULES);
+
+        assert!(close.similarity < 1.0);
+        assert!(close.similarity > distant.similarity);
+        assert!(distant.similarity < 0.3);
+
+        assert!(close.unified_diff.starts_with("--- a/git\n+++ b/git-v2\n"));
+        assert!(close.unified_diff.contains("-- Small commits\n"));
+        assert!(close.unified_diff.contains("+- Atomic commits\n"));
+    }
+
+    #[test]
+    fn test_diff_large_packs() {
+        let original: Vec<String> = (0..50_000).map(|i| format!("- rule {}", i)).collect();
+        let mut edited = original.clone();
+        for i in (0..edited.len()).step_by(1000) {
+            edited[i] = format!("- revised rule {}", i);
+        }
+        let (a, b) = (original.join("\n"), edited.join("\n"));
+
+        let close = diff_content("big", &a, "big-v2", &b);
+        assert_eq!(close.shared_lines, 49_950);
+        assert_eq!(close.lines_only_in_a, 50);
+        assert_eq!(close.lines_only_in_b, 50);
+        assert!(close.unified_diff.contains("@@ -2998,7 +2998,7 @@\n"));
+
+        let rewritten: Vec<String> = (0..50_000).map(|i| format!("- other rule {}", i)).collect();
+        let distant = diff_content("big", &a, "other", &rewritten.join("\n"));
+        assert_eq!(distant.shared_lines, 0);
+        assert_eq!(distant.similarity, 0.0);
+    }
+
+    #[test]
+    fn test_diff_finds_longest_common_subsequence() {
+        fn lcs_len(a: &[&str], b: &[&str]) -> usize {
+            let mut row = vec![0usize; b.len() + 1];
+            for x in a {
+                let mut diag = 0;
+                for (j, y) in b.iter().enumerate() {
+                    let above = row[j + 1];
+                    row[j + 1] = if x == y { diag + 1 } else { above.max(row[j]) };
+                    diag = above;
+                }
+            }
+            row[b.len()]
+        }
+
+        let cases = [
+            ("abcabba", "cbabac"),
+            ("abcdef", "fedcba"),
+            ("aaab", "baaa"),
+            ("xaxbxc", "abc"),
+            ("abc", "xyz"),
+            ("", "abc"),
+        ];
+        for (a, b) in cases {
+            let a: Vec<&str> = a.split("").filter(|s| !s.is_empty()).collect();
+            let b: Vec<&str> = b.split("").filter(|s| !s.is_empty()).collect();
+            let ops = diff_lines(&a, &b);
+
+            let shared: Vec<(usize, usize)> = ops
+                .iter()
+                .filter_map(|op| match *op {
+                    DiffOp::Equal(i, j) => Some((i, j)),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(shared.len(), lcs_len(&a, &b), "{:?} vs {:?}", a, b);
+            assert!(shared.iter().all(|&(i, j)| a[i] == b[j]));
+            assert_eq!(line_counts(&ops), (a.len(), b.len()));
+        }
+    }
+}
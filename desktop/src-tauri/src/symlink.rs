@@ -1,4 +1,5 @@
-use crate::types::LinkMethod;
+use crate::fs_manager;
+use crate::types::{LinkMethod, LinkStrategy};
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
@@ -20,8 +21,14 @@ pub enum SymlinkError {
     LinkExists(PathBuf),
     #[error("Refusing to overwrite existing non-link path: {0}")]
     WouldOverwrite(PathBuf),
+    #[error("Could not create symlink at {path}: {reason}")]
+    SymlinkRequired { path: PathBuf, reason: String },
+    #[error("Parent directory {path} is a symlink to {target}, which does not exist")]
+    BrokenParentLink { path: PathBuf, target: PathBuf },
     #[error("All linking methods failed")]
     AllMethodsFailed,
+    #[error("Refusing to delete real directory not created by the toolkit: {0}")]
+    RefusingToDeleteRealDir(PathBuf),
 }
 
 pub type Result<T> = std::result::Result<T, SymlinkError>;
@@ -115,10 +122,18 @@ fn copy_dir_all(src: &Path, dst: &Path) -> io::Result<()> {
 }
 
 /// Create a link using the best available method with fallback chain
+///
+/// `strategy` can skip the chain: `ForceCopy` copies straight away and `ForceSymlink`
+/// fails instead of falling back to another method. `replace_own` marks `link_path` as
+/// placed by an earlier deployment, so a copy left there is replaced rather than refused.
+/// When a parent directory of `link_path` is itself a symlink, the returned warning
+/// names where the link lands.
 pub fn create_link(
     link_path: PathBuf,
     target_path: PathBuf,
     force: bool,
+    strategy: LinkStrategy,
+    replace_own: bool,
 ) -> Result<(LinkMethod, Option<String>)> {
     // Ensure paths are absolute
     let link_path = if link_path.is_absolute() {
@@ -128,7 +143,54 @@ pub fn create_link(
             .map_err(|e| SymlinkError::Io(e))?
             .join(link_path)
     };
-    
+
+    let parent_warning = symlinked_parent_warning(&link_path)?;
+    let (method, warning) = place_link(link_path, target_path, force, strategy, replace_own)?;
+    let warning = match (parent_warning, warning) {
+        (Some(parent), Some(method_warning)) => Some(format!("{}\n{}", parent, method_warning)),
+        (parent, method_warning) => parent.or(method_warning),
+    };
+    Ok((method, warning))
+}
+
+/// Warn when an ancestor directory of `link_path` is a symlink
+///
+/// Files then land wherever that symlink points, which may be a relocated or read-only
+/// directory. Symlinked ancestors of the home directory are system layout and ignored.
+fn symlinked_parent_warning(link_path: &Path) -> Result<Option<String>> {
+    let home = fs_manager::home_dir().ok();
+    let ancestor = link_path
+        .ancestors()
+        .skip(1)
+        .filter(|dir| home.as_ref().is_none_or(|home| !home.starts_with(dir)))
+        .find(|dir| dir.is_symlink());
+    let Some(ancestor) = ancestor else {
+        return Ok(None);
+    };
+
+    let resolved = fs::canonicalize(ancestor).map_err(|_| SymlinkError::BrokenParentLink {
+        path: ancestor.to_path_buf(),
+        target: fs::read_link(ancestor).unwrap_or_default(),
+    })?;
+    let relative = link_path.strip_prefix(ancestor).unwrap_or(link_path);
+
+    Ok(Some(format!(
+        "⚠️  {} is a symlink to {}; {} will be created at {}",
+        ancestor.display(),
+        resolved.display(),
+        link_path.display(),
+        resolved.join(relative).display()
+    )))
+}
+
+/// Place the link itself, falling back through the available methods
+fn place_link(
+    link_path: PathBuf,
+    target_path: PathBuf,
+    force: bool,
+    strategy: LinkStrategy,
+    replace_own: bool,
+) -> Result<(LinkMethod, Option<String>)> {
     let target_path = if target_path.is_absolute() {
         target_path
     } else {
@@ -151,21 +213,21 @@ pub fn create_link(
     // Check existing path behavior
     if link_path.exists() {
         if paths_point_to_same(&link_path, &target_path) {
-            return Ok((LinkMethod::Existing, None));
-        }
-
-        if !force {
+            if strategy != LinkStrategy::ForceCopy || !link_path.is_symlink() {
+                return Ok((LinkMethod::Existing, None));
+            }
+            // Our own link to the same target; swap it for a copy
+            fs::remove_file(&link_path)?;
+        } else if replace_own {
+            // A copy (or link) placed by an earlier deployment
+            remove_link(link_path.clone(), true)?;
+        } else if !force {
             return Err(SymlinkError::LinkExists(link_path));
-        }
-
-        // Only remove if it's a link-like path; avoid deleting real data
-        if link_path.is_symlink() {
+        } else if link_path.is_symlink() {
+            // Only remove if it's a link-like path; avoid deleting real data
             fs::remove_file(&link_path)?;
-        } else if link_path.is_file() {
-            // A real file unrelated to target; do not delete
-            return Err(SymlinkError::WouldOverwrite(link_path));
         } else {
-            // Directory/junction or other non-link path
+            // A real file or directory unrelated to target; do not delete
             return Err(SymlinkError::WouldOverwrite(link_path));
         }
     }
@@ -175,9 +237,19 @@ pub fn create_link(
         fs::create_dir_all(parent)?;
     }
     
+    if strategy == LinkStrategy::ForceCopy {
+        return copy_as_fallback(&link_path, &target_path).map(|method| (method, None));
+    }
+
     // Try symlink first
     match create_symlink(&link_path, &target_path) {
         Ok(method) => return Ok((method, None)),
+        Err(e) if strategy == LinkStrategy::ForceSymlink => {
+            return Err(SymlinkError::SymlinkRequired {
+                path: link_path,
+                reason: e.to_string(),
+            });
+        }
         Err(_) => {} // Fall through to next method
     }
     
@@ -214,14 +286,32 @@ pub fn create_link(
     }
 }
 
+/// Check whether a directory path is a junction (Windows only)
+#[cfg(target_os = "windows")]
+fn is_junction(path: &Path) -> bool {
+    junction::exists(path).unwrap_or(false)
+}
+
+#[cfg(not(target_os = "windows"))]
+fn is_junction(_path: &Path) -> bool {
+    false
+}
+
 /// Remove a link (symlink, junction, hard link, or copied file/dir)
-pub fn remove_link(link_path: PathBuf) -> Result<()> {
+///
+/// Real directories are only removed when `created_by_toolkit` is set, so a
+/// user's own folder at the link path is never deleted recursively.
+pub fn remove_link(link_path: PathBuf, created_by_toolkit: bool) -> Result<()> {
     if !link_path.exists() && !link_path.is_symlink() {
         return Ok(()); // Already gone
     }
     
     if link_path.is_dir() && !link_path.is_symlink() {
-        // It's a real directory or junction
+        if !created_by_toolkit && !is_junction(&link_path) {
+            return Err(SymlinkError::RefusingToDeleteRealDir(link_path));
+        }
+
+        // It's a junction or a directory copied by the toolkit
         #[cfg(target_os = "windows")]
         {
             // Try rmdir first (for junctions), fall back to remove_dir_all
@@ -238,6 +328,9 @@ pub fn remove_link(link_path: PathBuf) -> Result<()> {
         {
             fs::remove_dir_all(&link_path)?;
         }
+    } else if cfg!(target_os = "windows") && link_path.is_symlink() && link_path.is_dir() {
+        // Directory symlinks on Windows are removed as directories
+        fs::remove_dir(&link_path)?;
     } else {
         // Symlink, hard link, or file
         fs::remove_file(&link_path)?;
@@ -334,3 +427,130 @@ fn paths_point_to_same(a: &Path, b: &Path) -> bool {
 
     false
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_remove_link_refuses_real_directory() {
+        let temp = tempdir().unwrap();
+        let user_dir = temp.path().join("commands");
+        fs::create_dir_all(&user_dir).unwrap();
+        fs::write(user_dir.join("my-command.md"), "user data").unwrap();
+
+        let result = remove_link(user_dir.clone(), false);
+
+        assert!(matches!(result, Err(SymlinkError::RefusingToDeleteRealDir(_))));
+        assert!(user_dir.join("my-command.md").exists());
+    }
+
+    #[test]
+    fn test_remove_link_removes_toolkit_copy() {
+        let temp = tempdir().unwrap();
+        let copied_dir = temp.path().join("commands");
+        fs::create_dir_all(&copied_dir).unwrap();
+        fs::write(copied_dir.join("status.md"), "copied").unwrap();
+
+        remove_link(copied_dir.clone(), true).unwrap();
+
+        assert!(!copied_dir.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_remove_link_removes_directory_symlink() {
+        let temp = tempdir().unwrap();
+        let target = temp.path().join("target");
+        fs::create_dir_all(&target).unwrap();
+        fs::write(target.join("keep.md"), "source").unwrap();
+        let link = temp.path().join("link");
+        symlink(&target, &link).unwrap();
+
+        remove_link(link.clone(), false).unwrap();
+
+        assert!(!link.is_symlink());
+        assert!(target.join("keep.md").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_create_link_under_symlinked_parent_warns() {
+        let temp = tempdir().unwrap();
+        let source = temp.path().join("AGENTS.md");
+        fs::write(&source, "# Rules").unwrap();
+        let real_dir = temp.path().join("relocated");
+        fs::create_dir_all(&real_dir).unwrap();
+        let alias = temp.path().join("agent-config");
+        symlink(&real_dir, &alias).unwrap();
+
+        let link = alias.join("rules").join("AGENTS.md");
+        let (method, warning) = create_link(link.clone(), source.clone(), false, LinkStrategy::Auto, false).unwrap();
+
+        assert!(matches!(method, LinkMethod::Symlink));
+        let warning = warning.expect("symlinked parent should be reported");
+        let real_link = real_dir.canonicalize().unwrap().join("rules").join("AGENTS.md");
+        assert!(warning.contains(&format!("{} is a symlink to", alias.display())), "{}", warning);
+        assert!(warning.contains(&real_link.display().to_string()), "{}", warning);
+        assert_eq!(fs::read_to_string(&real_link).unwrap(), "# Rules");
+        assert_eq!(fs::read_to_string(&link).unwrap(), "# Rules");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_create_link_under_dangling_parent_fails_clearly() {
+        let temp = tempdir().unwrap();
+        let source = temp.path().join("AGENTS.md");
+        fs::write(&source, "# Rules").unwrap();
+        let alias = temp.path().join("agent-config");
+        symlink(temp.path().join("moved-away"), &alias).unwrap();
+
+        let result = create_link(alias.join("AGENTS.md"), source, false, LinkStrategy::Auto, false);
+        assert!(matches!(result, Err(SymlinkError::BrokenParentLink { .. })));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_create_link_beyond_max_path() {
+        let temp = tempdir().unwrap();
+        let source_dir = temp.path().join("source");
+        fs::create_dir_all(source_dir.join("nested")).unwrap();
+        fs::write(source_dir.join("nested").join("status.md"), "# Status").unwrap();
+        let source_file = source_dir.join("AGENTS.md");
+        fs::write(&source_file, "# Rules").unwrap();
+
+        let mut deep = temp.path().to_path_buf();
+        while deep.as_os_str().len() <= 260 {
+            deep.push("a-deeply-nested-command-folder");
+        }
+
+        // Whichever method the chain settles on, the result must be usable
+        let link = deep.join("AGENTS.md");
+        create_link(link.clone(), source_file.clone(), false, LinkStrategy::Auto, false).unwrap();
+        assert_eq!(fs::read_to_string(&link).unwrap(), "# Rules");
+        remove_link(link.clone(), true).unwrap();
+        assert!(!link.exists());
+
+        let dir_link = deep.join("commands");
+        create_link(dir_link.clone(), source_dir.clone(), false, LinkStrategy::Auto, false).unwrap();
+        assert_eq!(fs::read_to_string(dir_link.join("nested").join("status.md")).unwrap(), "# Status");
+        remove_link(dir_link.clone(), true).unwrap();
+        assert!(!dir_link.exists());
+
+        // The fallbacks themselves
+        let junction = deep.join("junction");
+        assert!(matches!(create_junction(&junction, &source_dir).unwrap(), LinkMethod::Junction));
+        assert_eq!(fs::read_to_string(junction.join("AGENTS.md")).unwrap(), "# Rules");
+        remove_link(junction.clone(), false).unwrap();
+        assert!(!junction.exists());
+        assert!(source_file.exists());
+
+        let copy = deep.join("copy");
+        assert!(matches!(copy_as_fallback(&copy, &source_dir).unwrap(), LinkMethod::Copy));
+        assert_eq!(fs::read_to_string(copy.join("nested").join("status.md")).unwrap(), "# Status");
+        remove_link(copy.clone(), true).unwrap();
+        assert!(!copy.exists());
+        assert!(source_file.exists());
+    }
+}
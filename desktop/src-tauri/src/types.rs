@@ -1,5 +1,8 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+pub use crate::deployment::converters::FileFormat;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AgentDefinition {
@@ -15,6 +18,29 @@ pub struct AgentDefinition {
     pub requires_frontmatter: Option<bool>,
     pub sandbox_script_path: Option<String>,
     pub notes: Option<String>,
+    /// File the agent reads its rules from (e.g. `CLAUDE.md`); deployers fall back to their own default
+    #[serde(default)]
+    pub rules_filename: Option<String>,
+}
+
+/// Problems found in an agent definition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentValidationResult {
+    pub valid: bool,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Problems found across the whole agent registry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentRegistryReport {
+    pub valid: bool,
+    /// Entries that parsed into agent definitions
+    pub agent_count: usize,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,16 +48,26 @@ pub struct AgentDefinition {
 pub struct CharacterLimits {
     pub max_chars: Option<u64>,
     pub supports_out_references: bool,
+    /// Percentage of `max_chars` at which budget warnings start
+    #[serde(default = "default_warn_threshold_pct")]
+    pub warn_threshold_pct: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Default budget warning threshold (percent of the character limit)
+pub const DEFAULT_WARN_THRESHOLD_PCT: u64 = 80;
+
+fn default_warn_threshold_pct() -> u64 {
+    DEFAULT_WARN_THRESHOLD_PCT
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct RulePack {
     pub id: String,
     pub name: String,
     pub version: String,
     pub description: String,
-    pub dependencies: Vec<String>,
+    pub dependencies: Vec<Dependency>,
     pub target_agents: Vec<String>,
     pub files: Vec<String>,
     #[serde(default)]
@@ -39,7 +75,50 @@ pub struct RulePack {
     pub metadata: PackMetadata,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A pack dependency with an optional semver requirement on its version
+///
+/// Written in `pack.json` as a bare pack id or as `{ "id": "git", "versionReq": ">=2.0" }`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(from = "DependencySpec", into = "DependencySpec")]
+pub struct Dependency {
+    pub id: String,
+    pub version_req: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum DependencySpec {
+    Id(String),
+    #[serde(rename_all = "camelCase")]
+    Constrained {
+        id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        version_req: Option<String>,
+    },
+}
+
+impl From<DependencySpec> for Dependency {
+    fn from(spec: DependencySpec) -> Self {
+        match spec {
+            DependencySpec::Id(id) => Dependency { id, version_req: None },
+            DependencySpec::Constrained { id, version_req } => Dependency { id, version_req },
+        }
+    }
+}
+
+impl From<Dependency> for DependencySpec {
+    fn from(dep: Dependency) -> Self {
+        match dep.version_req {
+            None => DependencySpec::Id(dep.id),
+            Some(req) => DependencySpec::Constrained {
+                id: dep.id,
+                version_req: Some(req),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PackMetadata {
     pub word_count: u64,
@@ -48,6 +127,11 @@ pub struct PackMetadata {
     pub tags: Vec<String>,
 }
 
+/// JSON Schema describing `pack.json`, for validating packs while authoring them
+pub fn rulepack_schema() -> serde_json::Value {
+    serde_json::to_value(schemars::schema_for!(RulePack)).unwrap_or_default()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LoadedPack {
@@ -55,7 +139,7 @@ pub struct LoadedPack {
     pub name: String,
     pub version: String,
     pub description: String,
-    pub dependencies: Vec<String>,
+    pub dependencies: Vec<Dependency>,
     pub target_agents: Vec<String>,
     pub files: Vec<String>,
     #[serde(default)]
@@ -81,6 +165,19 @@ pub struct PackValidationResult {
     pub valid: bool,
     pub errors: Vec<PackValidationError>,
     pub warnings: Vec<PackValidationError>,
+    /// Status of each file listed in `pack.json`, in listed order
+    #[serde(default)]
+    pub files: Vec<PackFileStatus>,
+}
+
+/// Whether a file listed by a pack exists and contributes content
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PackFileStatus {
+    pub file: String,
+    pub exists: bool,
+    /// The file has content beyond whitespace
+    pub non_empty: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -92,6 +189,17 @@ pub struct DependencyResolution {
     pub circular_path: Option<Vec<String>>,
 }
 
+/// A pack in the effective, dependency-resolved set for a selection
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolvedPack {
+    pub pack_id: String,
+    /// Whether the user selected this pack directly
+    pub selected: bool,
+    /// Pack whose dependency list added this one, for packs not selected directly
+    pub pulled_in_by: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PackBudgetItem {
@@ -119,6 +227,41 @@ pub struct ValidationResult {
     pub warnings: Vec<String>,
 }
 
+/// Title and version block at the top of a generated AGENTS.md
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AgentsMdHeader {
+    pub title: String,
+    pub version: String,
+    /// Path of the command reference document linked under the version
+    pub reference_link: String,
+}
+
+impl Default for AgentsMdHeader {
+    fn default() -> Self {
+        Self {
+            title: "AGENTS.md — Mandatory Agent Behavior & Workflow Standards".to_string(),
+            version: "2.0.0".to_string(),
+            reference_link: "docs/AGENTS_REFERENCE.md".to_string(),
+        }
+    }
+}
+
+/// How AGENTS.md is generated from a pack selection
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AgentsMdOptions {
+    /// Append the pack and budget metadata comment; on unless set to false
+    pub include_metadata: Option<bool>,
+    /// Inline each pack's content instead of importing its files
+    pub inline_content: bool,
+    /// Rewrite relative links to absolute `~/.agentsmd/` paths
+    pub rewrite_links: bool,
+    /// Add a table of contents linking each inlined pack section
+    pub include_toc: bool,
+    pub header: AgentsMdHeader,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GenerateResult {
@@ -128,7 +271,19 @@ pub struct GenerateResult {
     pub error: Option<String>,
 }
 
+/// Outcome of linking one of an agent's config paths
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentLinkResult {
+    pub path: String,
+    /// Link method used, when the link was created
+    pub method: Option<String>,
+    pub warning: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum LinkMethod {
     Symlink,
     Junction,
@@ -137,6 +292,79 @@ pub enum LinkMethod {
     Existing,
 }
 
+impl LinkMethod {
+    /// Lowercase name, as reported in deployment results
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LinkMethod::Symlink => "symlink",
+            LinkMethod::Junction => "junction",
+            LinkMethod::Hardlink => "hardlink",
+            LinkMethod::Copy => "copy",
+            LinkMethod::Existing => "existing",
+        }
+    }
+}
+
+/// User settings read from `~/.agentsmd/settings.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Settings {
+    /// Files or directories that mark a project root, replacing the defaults when set
+    #[serde(default, alias = "project_markers")]
+    pub project_markers: Option<Vec<String>>,
+}
+
+/// What an agent's config directory is on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentDirInfo {
+    /// The directory path, after expanding `~` and environment variables
+    pub path: String,
+    /// Whether anything exists at the path (following symlinks)
+    pub exists: bool,
+    /// Whether the path itself is a symlink
+    pub is_symlink: bool,
+    /// Where a symlinked directory ultimately points
+    pub resolved_target: Option<String>,
+}
+
+/// Which linking methods deployments may use
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LinkStrategy {
+    /// Symlink, falling back to junction, hard link, then copy
+    #[default]
+    Auto,
+    /// Symlink only; fail instead of falling back
+    ForceSymlink,
+    /// Always copy, e.g. for synced folders where links break
+    ForceCopy,
+}
+
+impl LinkStrategy {
+    /// Method reported by deployers that placed links with `methods`
+    ///
+    /// The furthest fallback wins, so a single copied file makes the deployment a copy.
+    /// Links already in place don't count; when nothing new was placed the strategy's
+    /// preferred method is reported.
+    pub fn deployment_method(&self, methods: &[LinkMethod]) -> &'static str {
+        let rank = |method: &LinkMethod| match method {
+            LinkMethod::Symlink => 0,
+            LinkMethod::Junction => 1,
+            LinkMethod::Hardlink => 2,
+            LinkMethod::Copy => 3,
+            LinkMethod::Existing => -1,
+        };
+        match methods.iter().filter(|m| rank(m) >= 0).max_by_key(|m| rank(m)) {
+            Some(method) => method.as_str(),
+            None => match self {
+                LinkStrategy::ForceCopy => "copy",
+                LinkStrategy::Auto | LinkStrategy::ForceSymlink => "symlink",
+            },
+        }
+    }
+}
+
 // ============================================================================
 // Command Types
 // ============================================================================
@@ -156,6 +384,22 @@ pub struct CommandMetadata {
     pub character_count: u64,
     pub word_count: u64,
     pub source_path: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Folder a nested command lives in (`git` for `git/status`)
+    #[serde(default)]
+    pub subfolder: Option<String>,
+}
+
+/// A structural problem found in a command's markdown
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandLint {
+    /// Lint rule identifier (e.g. `missing-description`)
+    pub rule: String,
+    pub message: String,
+    /// 1-based line number, when the problem is tied to a line
+    pub line: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -165,6 +409,36 @@ pub struct CommandCompatibilityResult {
     pub reason: Option<String>,
 }
 
+/// Server-side filter for listing commands; unset fields match everything
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CommandFilter {
+    pub category: Option<String>,
+    pub agent_id: Option<String>,
+    #[serde(rename = "requiresGitHub")]
+    pub requires_github: Option<bool>,
+}
+
+/// One page of filtered commands, sorted by id
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandPage {
+    pub commands: Vec<CommandMetadata>,
+    /// Number of commands matching the filter across all pages
+    pub total: u64,
+    pub offset: u64,
+    pub limit: u64,
+}
+
+/// Compatibility of a command with one registered agent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentCommandCompatibility {
+    pub agent_id: String,
+    pub compatible: bool,
+    pub reason: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CommandBudgetItem {
@@ -225,15 +499,6 @@ pub enum OutReferenceCategory {
     Schemas,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum FileFormat {
-    Markdown,
-    Json,
-    Yaml,
-    Text,
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct OutReferenceValidationReport {
@@ -241,6 +506,21 @@ pub struct OutReferenceValidationReport {
     pub broken_links: Vec<BrokenLink>,
     pub unused_references: Vec<String>,
     pub orphaned_files: Vec<String>,
+    /// References whose content doesn't parse as their declared format
+    #[serde(default)]
+    pub format_errors: Vec<FormatValidation>,
+}
+
+/// Result of parsing an out-reference's content as its declared format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatValidation {
+    pub id: String,
+    pub file_path: String,
+    pub format: FileFormat,
+    pub valid: bool,
+    /// Parser error, when the content is invalid
+    pub error: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,154 @@
+//! Shared helpers
+//!
+//! Small utilities used across markdown generation, out-reference and command file handling.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Turn a display name into a non-empty, filesystem-safe file stem
+///
+/// Whitespace becomes `-`, anything other than alphanumerics, `-` and `_` is dropped,
+/// and repeated or edge dashes are trimmed. Names with nothing usable left (all
+/// symbols or emoji) fall back to a short stable hash of the original name.
+pub fn safe_file_stem(name: &str) -> String {
+    let mut stem = String::new();
+    for c in name.trim().chars() {
+        let c = if c.is_whitespace() { '-' } else { c };
+        if !(c.is_alphanumeric() || c == '-' || c == '_') {
+            continue;
+        }
+        if c == '-' && (stem.is_empty() || stem.ends_with('-')) {
+            continue;
+        }
+        stem.push(c);
+    }
+
+    let stem = stem.trim_end_matches('-');
+    if stem.is_empty() {
+        format!("{:08x}", short_hash(name))
+    } else {
+        stem.to_string()
+    }
+}
+
+/// GitHub-style anchor slug for a markdown heading
+///
+/// Lowercases, turns each space into `-`, and drops punctuation other than `-` and `_`.
+/// Letters outside ASCII are kept, so `Café` links as `#café` just like on GitHub.
+pub fn slugify_heading(text: &str) -> String {
+    text.trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Hands out unique heading slugs within one document
+///
+/// Repeated headings get GitHub's counters: `foo`, `foo-1`, `foo-2`.
+#[derive(Debug, Default)]
+pub struct HeadingSlugger {
+    seen: HashMap<String, usize>,
+}
+
+impl HeadingSlugger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Slug for the next heading, numbered if an earlier heading already used it
+    pub fn slug(&mut self, text: &str) -> String {
+        let base = slugify_heading(text);
+        let mut slug = base.clone();
+        while let Some(count) = self.seen.get_mut(&slug) {
+            *count += 1;
+            slug = format!("{}-{}", base, count);
+        }
+        self.seen.insert(slug.clone(), 0);
+        slug
+    }
+}
+
+/// 64-bit FNV-1a hash of file content as hex, stable across builds and platforms
+pub fn content_hash(bytes: &[u8]) -> String {
+    let hash = bytes.iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// Write a file by renaming a fully written sibling temp file over it
+///
+/// Readers see either the old content or the new, never a partial write.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let temp_path = path.with_file_name(format!(
+        ".{}.tmp-{}",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+
+    let result = fs::write(&temp_path, contents).and_then(|_| fs::rename(&temp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// 32-bit FNV-1a hash, stable across builds and platforms
+fn short_hash(value: &str) -> u32 {
+    value.bytes().fold(0x811c_9dc5, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slugify_heading_matches_github() {
+        assert_eq!(slugify_heading("Git Workflow"), "git-workflow");
+        assert_eq!(slugify_heading("Testing & QA"), "testing--qa");
+        assert_eq!(slugify_heading("Docs (v2.1)!"), "docs-v21");
+        assert_eq!(slugify_heading("snake_case and-dash"), "snake_case-and-dash");
+        assert_eq!(slugify_heading("Café Übersicht"), "café-übersicht");
+        assert_eq!(slugify_heading("日本語 ガイド"), "日本語-ガイド");
+        assert_eq!(slugify_heading("🚀 Launch"), "-launch");
+    }
+
+    #[test]
+    fn test_heading_slugger_numbers_duplicates() {
+        let mut slugger = HeadingSlugger::new();
+        assert_eq!(slugger.slug("Foo"), "foo");
+        assert_eq!(slugger.slug("Foo"), "foo-1");
+        assert_eq!(slugger.slug("foo"), "foo-2");
+        // A literal heading that collides with a generated one is numbered too
+        assert_eq!(slugger.slug("Foo 1"), "foo-1-1");
+        assert_eq!(slugger.slug("Bar"), "bar");
+    }
+
+    #[test]
+    fn test_safe_file_stem_cleans_names() {
+        assert_eq!(safe_file_stem("Lint Config"), "Lint-Config");
+        assert_eq!(safe_file_stem("  PR / Review  template! "), "PR-Review-template");
+        assert_eq!(safe_file_stem("commit_msg"), "commit_msg");
+    }
+
+    #[test]
+    fn test_safe_file_stem_emoji_only() {
+        let stem = safe_file_stem("🚀🔥✨");
+        assert_eq!(stem.len(), 8);
+        assert!(stem.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(stem, safe_file_stem("🚀🔥✨"));
+        assert_ne!(stem, safe_file_stem("🎉"));
+    }
+}
@@ -0,0 +1,115 @@
+//! Library file watching
+//!
+//! Watches the command sources and out-references under `~/.agentsmd` so edits made
+//! in an external editor refresh cached data and notify the frontend.
+
+use crate::command_registry;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// Event emitted to the frontend when library files change
+pub const LIBRARY_CHANGED_EVENT: &str = "library://changed";
+
+/// Quiet period used to batch bursts of editor writes into one event
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Payload of a `library://changed` event
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryChanged {
+    pub commands: bool,
+    pub out_references: bool,
+    pub paths: Vec<String>,
+}
+
+/// Running library watcher; watching stops when dropped
+pub struct LibraryWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+/// Watch `commands/src` and `out-references` under `agentsmd_home`
+///
+/// Returns `Ok(None)` when neither directory exists. Changes are batched until
+/// `debounce` passes without new events; command changes clear the command cache
+/// before `on_change` is called. Out-reference metadata is read from disk on every
+/// request, so it needs no refresh of its own.
+pub fn watch_library<F>(
+    agentsmd_home: &Path,
+    debounce: Duration,
+    on_change: F,
+) -> Result<Option<LibraryWatcher>, String>
+where
+    F: Fn(LibraryChanged) + Send + 'static,
+{
+    let commands_dir = agentsmd_home.join("commands").join("src");
+    let out_refs_dir = agentsmd_home.join("out-references");
+    let watched: Vec<PathBuf> = [commands_dir.clone(), out_refs_dir.clone()]
+        .into_iter()
+        .filter(|dir| dir.is_dir())
+        .collect();
+
+    if watched.is_empty() {
+        log::info!("No library directories to watch under {}", agentsmd_home.display());
+        return Ok(None);
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)
+        .map_err(|e| format!("Failed to create file watcher: {}", e))?;
+    for dir in &watched {
+        watcher
+            .watch(dir, RecursiveMode::Recursive)
+            .map_err(|e| format!("Failed to watch {}: {}", dir.display(), e))?;
+    }
+
+    // Match both the configured and resolved forms, since event paths may be either
+    let with_canonical = |dir: PathBuf| -> Vec<PathBuf> {
+        match dir.canonicalize() {
+            Ok(resolved) if resolved != dir => vec![dir, resolved],
+            _ => vec![dir],
+        }
+    };
+    let commands_roots = with_canonical(commands_dir);
+    let out_refs_roots = with_canonical(out_refs_dir);
+
+    thread::spawn(move || {
+        // The channel closes when the watcher is dropped, ending the loop
+        while let Ok(first) = rx.recv() {
+            let mut events = vec![first];
+            while let Ok(event) = rx.recv_timeout(debounce) {
+                events.push(event);
+            }
+
+            let mut change = LibraryChanged::default();
+            for event in events.into_iter().flatten() {
+                for path in event.paths {
+                    if commands_roots.iter().any(|root| path.starts_with(root)) {
+                        change.commands = true;
+                    } else if out_refs_roots.iter().any(|root| path.starts_with(root)) {
+                        change.out_references = true;
+                    } else {
+                        continue;
+                    }
+                    let path = path.to_string_lossy().to_string();
+                    if !change.paths.contains(&path) {
+                        change.paths.push(path);
+                    }
+                }
+            }
+
+            if !change.commands && !change.out_references {
+                continue;
+            }
+            if change.commands {
+                command_registry::clear_cache();
+            }
+            on_change(change);
+        }
+    });
+
+    Ok(Some(LibraryWatcher { _watcher: watcher }))
+}
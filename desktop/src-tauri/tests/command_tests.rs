@@ -2,13 +2,17 @@
 //!
 //! Unit tests for command loading, parsing, and validation.
 
+mod common;
+
 #[cfg(test)]
 mod tests {
     use agentstoolkit_desktop::command_registry;
     use agentstoolkit_desktop::types::*;
+    use crate::common;
 
     #[test]
     fn test_load_commands_returns_vec() {
+        let _env = common::lock_env();
         // This test verifies that load_commands returns a valid vector
         // even if the commands directory is empty
         let result = command_registry::load_commands();
@@ -17,12 +21,14 @@ mod tests {
 
     #[test]
     fn test_get_command_by_id_not_found() {
+        let _env = common::lock_env();
         let result = command_registry::get_command_by_id("nonexistent-command");
         assert!(result.is_err());
     }
 
     #[test]
     fn test_get_commands_by_category() {
+        let _env = common::lock_env();
         // Should return empty vec for unknown category
         let result = command_registry::get_commands_by_category("unknown");
         assert!(result.is_ok());
@@ -31,6 +37,7 @@ mod tests {
 
     #[test]
     fn test_validate_command_for_agent_not_found() {
+        let _env = common::lock_env();
         // Should return error for nonexistent command
         let result = command_registry::validate_command_for_agent(
             "nonexistent-command",
@@ -50,6 +57,7 @@ mod tests {
 
     #[test]
     fn test_clear_cache_succeeds() {
+        let _env = common::lock_env();
         // Should not panic
         command_registry::clear_cache();
     }
@@ -108,7 +116,9 @@ External links like [Example](https://example.com) should be ignored.
 #[cfg(test)]
 mod command_validator_tests {
     use agentstoolkit_desktop::deployment::command_validator;
-    use agentstoolkit_desktop::types::{AgentDefinition, CharacterLimits, CommandMetadata};
+    use agentstoolkit_desktop::types::{
+        AgentDefinition, CharacterLimits, CommandMetadata, DEFAULT_WARN_THRESHOLD_PCT,
+    };
 
     fn create_test_agent(max_chars: Option<u64>, supports_out_refs: bool) -> AgentDefinition {
         AgentDefinition {
@@ -120,6 +130,7 @@ mod command_validator_tests {
             character_limits: CharacterLimits {
                 max_chars,
                 supports_out_references: supports_out_refs,
+                warn_threshold_pct: DEFAULT_WARN_THRESHOLD_PCT,
             },
             deployment_strategy: "symlink".to_string(),
             build_output: "test/commands".to_string(),
@@ -127,6 +138,7 @@ mod command_validator_tests {
             requires_frontmatter: Some(false),
             sandbox_script_path: None,
             notes: None,
+            rules_filename: None,
         }
     }
 
@@ -148,6 +160,8 @@ mod command_validator_tests {
             character_count: chars,
             word_count: chars / 5,
             source_path: "commands/src/test.md".to_string(),
+            aliases: Vec::new(),
+            subfolder: None,
         }
     }
 
@@ -205,3 +219,414 @@ mod command_validator_tests {
         assert!(!result.valid);
     }
 }
+
+/// Command category tests
+///
+/// Checks that a frontmatter category outside the built-in buckets is honoured.
+#[cfg(test)]
+mod category_tests {
+    use agentstoolkit_desktop::command_registry;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_custom_frontmatter_category() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        std::env::set_var("AGENTSMD_HOME", temp.path());
+
+        common::write_command(
+            temp.path(),
+            "run-suite",
+            "---\ncategory: testing\n---\nRun the full test suite.\n",
+        );
+        common::write_command(temp.path(), "status", "Show the git status.\n");
+
+        let testing = command_registry::get_commands_by_category("testing").unwrap();
+        assert_eq!(testing.len(), 1);
+        assert_eq!(testing[0].id, "run-suite");
+        assert_eq!(testing[0].description, "Run the full test suite.");
+
+        assert_eq!(command_registry::get_commands_by_category("Testing").unwrap().len(), 1);
+
+        let categories = command_registry::list_command_categories().unwrap();
+        assert_eq!(categories, vec!["git", "testing"]);
+    }
+}
+
+/// Command compatibility matrix tests
+///
+/// Checks a command with out-references against every registered agent.
+#[cfg(test)]
+mod compatibility_tests {
+    use agentstoolkit_desktop::{command_registry, fs_manager};
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_matrix_flags_agents_without_out_reference_support() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path();
+        std::env::set_var("AGENTSMD_HOME", home);
+
+        common::write_command(
+            home,
+            "pr",
+            "Open a pull request.\n\nUse [the template](../templates/pr-template.md).\n",
+        );
+
+        let agents = fs_manager::load_agent_registry().unwrap();
+        let matrix = command_registry::command_compatibility_matrix("pr").unwrap();
+        assert_eq!(matrix.len(), agents.len());
+
+        for agent in &agents {
+            let entry = matrix.iter().find(|e| e.agent_id == agent.id).unwrap();
+            if agent.character_limits.supports_out_references {
+                assert!(entry.compatible, "{} should be compatible", agent.id);
+            } else {
+                assert!(!entry.compatible, "{} should be incompatible", agent.id);
+                assert!(entry.reason.as_deref().unwrap().contains("out-references"));
+            }
+        }
+        assert!(agents.iter().any(|a| !a.character_limits.supports_out_references));
+    }
+}
+
+/// Command lint tests
+///
+/// Lints a command file with structural problems.
+#[cfg(test)]
+mod lint_tests {
+    use agentstoolkit_desktop::command_registry;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_lint_command_flags_missing_description() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        std::env::set_var("AGENTSMD_HOME", temp.path());
+
+        common::write_command(
+            temp.path(),
+            "ship",
+            "\nRun: `python3 ~/.agentsmd/scripts/ship.py`\n\nSee [the guide](docs/guide.md\n",
+        );
+        common::write_command(temp.path(), "status", "Show the project status.\n");
+
+        let lints = command_registry::lint_command("ship").unwrap();
+        let rules: Vec<&str> = lints.iter().map(|l| l.rule.as_str()).collect();
+        assert!(rules.contains(&"missing-description"));
+        assert!(rules.contains(&"missing-script"));
+        assert!(lints
+            .iter()
+            .any(|l| l.rule == "malformed-link" && l.line == Some(4)));
+
+        assert!(command_registry::lint_command("status").unwrap().is_empty());
+    }
+}
+
+/// Command listing tests
+///
+/// Filters commands by category and pages through the result.
+#[cfg(test)]
+mod list_tests {
+    use agentstoolkit_desktop::command_registry;
+    use agentstoolkit_desktop::types::CommandFilter;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_list_commands_filters_and_pages() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path();
+        std::env::set_var("AGENTSMD_HOME", home);
+
+        for id in ["deploy", "lint", "release", "test"] {
+            common::write_command(home, id, &format!("---\ncategory: ops\n---\n\nRun {}.\n", id));
+        }
+        common::write_command(home, "notes", "---\ncategory: docs\n---\n\nWrite notes.\n");
+
+        let filter = CommandFilter {
+            category: Some("Ops".to_string()),
+            ..Default::default()
+        };
+        let page = command_registry::list_commands(&filter, 1, 2).unwrap();
+        assert_eq!(page.total, 4);
+        assert_eq!(page.offset, 1);
+        assert_eq!(page.limit, 2);
+        let ids: Vec<&str> = page.commands.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["lint", "release"]);
+
+        let last = command_registry::list_commands(&filter, 3, 2).unwrap();
+        assert_eq!(last.commands.len(), 1);
+        assert_eq!(last.commands[0].id, "test");
+
+        let github = CommandFilter {
+            requires_github: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(command_registry::list_commands(&github, 0, 10).unwrap().total, 0);
+        assert_eq!(command_registry::list_commands(&CommandFilter::default(), 0, 10).unwrap().total, 5);
+    }
+}
+
+/// Command out-reference override tests
+///
+/// Sets and clears a command's out-reference override.
+#[cfg(test)]
+mod out_ref_override_tests {
+    use agentstoolkit_desktop::command_registry;
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_clearing_override_restores_extracted_references() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path();
+        std::env::set_var("AGENTSMD_HOME", home);
+
+        common::write_command(
+            home,
+            "pr",
+            "Open a pull request.\n\nUse [the template](../templates/pr-template.md).\n",
+        );
+
+        command_registry::update_command_out_references("pr", vec!["examples/pr.md".to_string()]).unwrap();
+        let command = command_registry::get_command_by_id("pr").unwrap();
+        assert_eq!(command.out_references, vec!["examples/pr.md"]);
+
+        command_registry::clear_command_out_references("pr").unwrap();
+        let command = command_registry::get_command_by_id("pr").unwrap();
+        assert_eq!(command.out_references, vec!["templates/pr-template.md"]);
+
+        let overrides = fs::read_to_string(home.join("commands").join("out-references.json")).unwrap();
+        assert!(!overrides.contains("\"pr\""));
+    }
+}
+
+/// Command preview tests
+///
+/// Previews a command for agents with non-markdown command formats and checks
+/// the output parses in the declared format.
+#[cfg(test)]
+mod preview_tests {
+    use agentstoolkit_desktop::deployment::command_loader::preview_command_for_agent;
+    use agentstoolkit_desktop::deployment::converters::FileFormat;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_preview_command_for_toml_and_json_agents() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        std::env::set_var("AGENTSMD_HOME", temp.path());
+
+        common::write_command(
+            temp.path(),
+            "status",
+            "Show the project status.\n\nSummarize \"open\" issues and recent commits.\n",
+        );
+
+        let gemini = preview_command_for_agent("status", "gemini").unwrap();
+        assert_eq!(gemini.filename, "status.toml");
+        assert_eq!(gemini.format, FileFormat::Toml);
+        toml::from_str::<toml::Value>(&gemini.content).expect("gemini preview should be valid TOML");
+
+        let cline = preview_command_for_agent("status", "cline").unwrap();
+        assert_eq!(cline.filename, "status.json");
+        assert_eq!(cline.format, FileFormat::Json);
+        serde_json::from_str::<serde_json::Value>(&cline.content)
+            .expect("cline preview should be valid JSON");
+    }
+}
+
+/// Command rename tests
+///
+/// Renames a command and checks its override, out-reference links and deployment state follow.
+#[cfg(test)]
+mod rename_tests {
+    use agentstoolkit_desktop::deployment::{DeploymentState, StateManager, TargetLevel};
+    use agentstoolkit_desktop::{command_registry, out_reference_manager};
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_rename_command_updates_references() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path();
+        std::env::set_var("AGENTSMD_HOME", home);
+
+        let commands_dir = common::write_command(home, "status", "Show repository status.\n")
+            .parent()
+            .unwrap()
+            .to_path_buf();
+        common::write_command(home, "pr", "Open a pull request.\n");
+
+        let out_ref = out_reference_manager::create_out_reference(
+            "Status Template".to_string(),
+            "Status report layout".to_string(),
+            "templates".to_string(),
+            "# Status\n".to_string(),
+            "markdown".to_string(),
+            Vec::new(),
+        )
+        .unwrap();
+        command_registry::update_command_out_references("status", vec![out_ref.file_path.clone()]).unwrap();
+
+        assert!(command_registry::rename_command("status", "pr").is_err());
+
+        let renamed = command_registry::rename_command("status", "st").unwrap();
+        assert_eq!(renamed.id, "st");
+        assert_eq!(renamed.out_references, vec![out_ref.file_path.clone()]);
+        assert!(commands_dir.join("st.md").exists());
+        assert!(!commands_dir.join("status.md").exists());
+
+        assert_eq!(command_registry::get_command_by_id("st").unwrap().id, "st");
+        assert!(command_registry::get_command_by_id("status").is_err());
+
+        let links = out_reference_manager::find_references_to(out_ref.id.clone()).unwrap();
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].id, "st");
+        let out_ref = out_reference_manager::get_out_reference(out_ref.id).unwrap();
+        assert_eq!(out_ref.linked_from, vec!["command:st"]);
+    }
+
+    #[test]
+    fn test_rename_command_updates_deployment_state() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path();
+        std::env::set_var("AGENTSMD_HOME", home);
+
+        common::write_command(home, "status", "Show repository status.\n");
+        common::write_command(home, "pr", "Open a pull request.\n");
+
+        let state_manager = StateManager::new().unwrap();
+        for agent_id in ["claude", "cursor"] {
+            let state = DeploymentState::new(agent_id.to_string(), "copy".to_string(), TargetLevel::User)
+                .with_commands(vec!["status".to_string(), "pr".to_string()]);
+            state_manager.record_deployment(state).unwrap();
+        }
+
+        command_registry::rename_command("status", "st").unwrap();
+
+        for agent_id in ["claude", "cursor"] {
+            let state = state_manager.get_agent_state(agent_id).unwrap().unwrap();
+            assert_eq!(state.deployed_commands, vec!["st", "pr"]);
+        }
+    }
+}
+
+/// Command template validation tests
+///
+/// Validates a command whose template leaves a code fence open.
+#[cfg(test)]
+mod template_validation_tests {
+    use agentstoolkit_desktop::command_registry;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_validate_template_reports_unclosed_fence() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        std::env::set_var("AGENTSMD_HOME", temp.path());
+
+        common::write_command(
+            temp.path(),
+            "walkthrough",
+            "Write a walkthrough of the change.\n\n**Template:**\n```markdown\n## Summary\n- What changed\n",
+        );
+        common::write_command(
+            temp.path(),
+            "pr",
+            "Open a pull request.\n\n**Template:**\n```markdown\n## Summary\n```\n\n**Notes:** keep it short\n",
+        );
+
+        let issues = command_registry::validate_template("walkthrough").unwrap();
+        assert_eq!(issues, vec!["Code fence opened on template line 1 is never closed"]);
+
+        assert!(command_registry::validate_template("pr").unwrap().is_empty());
+    }
+}
+
+/// Warp workflow preview tests
+///
+/// Previews a multi-section command as a Warp workflow and parses it back.
+#[cfg(test)]
+mod warp_workflow_preview_tests {
+    use agentstoolkit_desktop::deployment::command_loader::preview_warp_workflow;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_warp_preview_summarizes_and_round_trips() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        std::env::set_var("AGENTSMD_HOME", temp.path());
+
+        common::write_command(
+            temp.path(),
+            "release",
+            "---\ndescription: Cut a release\n---\n\n# Release\n\n## Prepare\n\nBump: the version.\n\n## Publish\n\n- Tag the commit\n- Push tags\n",
+        );
+
+        let preview = preview_warp_workflow("release").unwrap();
+        assert_eq!(preview.filename, "release.yaml");
+
+        let workflow: serde_yaml::Value = serde_yaml::from_str(&preview.content).unwrap();
+        let steps = workflow["steps"].as_sequence().unwrap();
+        assert_eq!(preview.summary.step_count, steps.len() as u64);
+        assert_eq!(preview.summary.step_descriptions.len(), steps.len());
+        assert_eq!(preview.summary.name, "release");
+
+        let command = steps[0]["command"].as_str().unwrap();
+        assert!(command.contains("## Prepare\n\nBump: the version."));
+        assert!(command.contains("## Publish\n\n- Tag the commit\n- Push tags"));
+    }
+}
+
+/// Library watcher tests
+///
+/// Writes a command file under a watched AGENTSMD_HOME and waits for the change event.
+#[cfg(all(test, feature = "watch"))]
+mod library_watcher_tests {
+    use agentstoolkit_desktop::watcher;
+    use std::fs;
+    use std::sync::mpsc;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_new_command_file_fires_change_event() {
+        let temp = TempDir::new().unwrap();
+        let commands_dir = temp.path().join("commands").join("src");
+        fs::create_dir_all(&commands_dir).unwrap();
+
+        // Nothing to watch yet in an empty home
+        let empty = TempDir::new().unwrap();
+        assert!(watcher::watch_library(empty.path(), Duration::from_millis(50), |_| {})
+            .unwrap()
+            .is_none());
+
+        let (tx, rx) = mpsc::channel();
+        let _watcher = watcher::watch_library(temp.path(), Duration::from_millis(50), move |change| {
+            let _ = tx.send(change);
+        })
+        .unwrap()
+        .expect("commands directory should be watched");
+
+        fs::write(commands_dir.join("ship.md"), "Ship the current branch.\n").unwrap();
+
+        let change = rx.recv_timeout(Duration::from_secs(5)).expect("change event");
+        assert!(change.commands);
+        assert!(!change.out_references);
+        assert!(change.paths.iter().any(|p| p.ends_with("ship.md")));
+    }
+}
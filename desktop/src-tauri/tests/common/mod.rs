@@ -0,0 +1,340 @@
+//! Shared fixtures for the integration tests
+//!
+//! Each area file pulls this in with `mod common;`, so not every helper is used by every binary.
+
+#![allow(dead_code)]
+
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use agentstoolkit_desktop::command_registry;
+use agentstoolkit_desktop::deployment::{
+    AgentDeployer, AgentStatus, BudgetUsage, DeploymentConfig, DeploymentOutput, DeploymentPhase,
+    DeploymentResult, DeploymentState, PreparedDeployment, ProgressSink, ValidationReport,
+};
+use agentstoolkit_desktop::fs_manager;
+use agentstoolkit_desktop::types::{AgentDefinition, CharacterLimits, DEFAULT_WARN_THRESHOLD_PCT};
+
+static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+/// Environment variables tests point at temporary directories
+const ENV_VARS: &[&str] = &["HOME", "AGENTSMD_HOME"];
+
+/// Holds the process-wide test lock and restores shared state when dropped
+pub struct EnvGuard {
+    vars: Vec<(&'static str, Option<OsString>)>,
+    cwd: Option<PathBuf>,
+    _lock: MutexGuard<'static, ()>,
+}
+
+/// Serialize a test that changes `HOME`, `AGENTSMD_HOME`, the working directory, or other
+/// process-wide settings
+///
+/// Tests in one binary run on parallel threads but share all of these.
+pub fn lock_env() -> EnvGuard {
+    let lock = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    EnvGuard {
+        vars: ENV_VARS.iter().map(|name| (*name, std::env::var_os(name))).collect(),
+        cwd: std::env::current_dir().ok(),
+        _lock: lock,
+    }
+}
+
+impl Drop for EnvGuard {
+    fn drop(&mut self) {
+        for (name, value) in &self.vars {
+            match value {
+                Some(value) => std::env::set_var(name, value),
+                None => std::env::remove_var(name),
+            }
+        }
+        if let Some(cwd) = &self.cwd {
+            let _ = std::env::set_current_dir(cwd);
+        }
+        fs_manager::set_home_provider(dirs::home_dir);
+        fs_manager::set_max_file_size(fs_manager::DEFAULT_MAX_FILE_SIZE);
+        command_registry::clear_cache();
+    }
+}
+
+/// Rule pack written to `rule-packs/<dir>/` with its `pack.json`
+pub struct PackFixture {
+    id: String,
+    dir: String,
+    name: String,
+    version: String,
+    description: String,
+    dependencies: serde_json::Value,
+    out_references: Vec<String>,
+    /// Files listed in `pack.json`, with the content to write (`None` leaves it missing)
+    files: Vec<(String, Option<String>)>,
+}
+
+impl PackFixture {
+    /// A pack whose single `rules.md` holds `# <id>`
+    pub fn new(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            dir: id.to_string(),
+            name: id.to_string(),
+            version: "1.0.0".to_string(),
+            description: String::new(),
+            dependencies: serde_json::json!([]),
+            out_references: Vec::new(),
+            files: vec![("rules.md".to_string(), Some(format!("# {}\n", id)))],
+        }
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    pub fn with_version(mut self, version: &str) -> Self {
+        self.version = version.to_string();
+        self
+    }
+
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = description.to_string();
+        self
+    }
+
+    /// Write the pack to a directory named differently from its id
+    pub fn in_dir(mut self, dir: &str) -> Self {
+        self.dir = dir.to_string();
+        self
+    }
+
+    pub fn with_dependencies(mut self, dependencies: &[&str]) -> Self {
+        self.dependencies = serde_json::json!(dependencies);
+        self
+    }
+
+    /// Dependencies as raw JSON, for versioned `{ "id", "version" }` entries
+    pub fn with_dependencies_json(mut self, dependencies: serde_json::Value) -> Self {
+        self.dependencies = dependencies;
+        self
+    }
+
+    pub fn with_out_references(mut self, out_references: &[&str]) -> Self {
+        self.out_references = out_references.iter().map(|r| r.to_string()).collect();
+        self
+    }
+
+    /// Replace the content of `rules.md`
+    pub fn with_rules(mut self, content: impl Into<String>) -> Self {
+        self.files[0].1 = Some(content.into());
+        self
+    }
+
+    /// List another file in `pack.json` and write it
+    pub fn with_file(mut self, name: &str, content: impl Into<String>) -> Self {
+        self.files.push((name.to_string(), Some(content.into())));
+        self
+    }
+
+    /// List a file in `pack.json` without creating it
+    pub fn with_missing_file(mut self, name: &str) -> Self {
+        self.files.push((name.to_string(), None));
+        self
+    }
+
+    /// List only files that don't exist
+    pub fn without_rules(mut self) -> Self {
+        self.files.remove(0);
+        self
+    }
+
+    /// Write the pack under `home` and return its directory
+    pub fn write(&self, home: &Path) -> PathBuf {
+        let pack_dir = home.join("rule-packs").join(&self.dir);
+        fs::create_dir_all(&pack_dir).unwrap();
+        for (name, content) in &self.files {
+            if let Some(content) = content {
+                fs::write(pack_dir.join(name), content).unwrap();
+            }
+        }
+
+        let mut pack = serde_json::json!({
+            "id": self.id,
+            "name": self.name,
+            "version": self.version,
+            "description": self.description,
+            "dependencies": self.dependencies,
+            "targetAgents": [],
+            "files": self.files.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+            "metadata": { "wordCount": 0, "characterCount": 0, "category": "universal", "tags": [] }
+        });
+        if !self.out_references.is_empty() {
+            pack["outReferences"] = serde_json::json!(self.out_references);
+        }
+        fs::write(pack_dir.join("pack.json"), pack.to_string()).unwrap();
+        pack_dir
+    }
+}
+
+/// Write a pack whose only file is `rules.md` with `content`
+pub fn write_pack(home: &Path, id: &str, content: &str) -> PathBuf {
+    PackFixture::new(id).with_rules(content).write(home)
+}
+
+/// Write the `huge` pack, far larger than Copilot's character limit
+pub fn write_huge_pack(home: &Path) -> PathBuf {
+    PackFixture::new("huge")
+        .with_name("Huge")
+        .with_description("Far more than Copilot can take")
+        .with_rules("Always write tests.\n".repeat(600))
+        .write(home)
+}
+
+/// Write a command source to `commands/src/<id>.md` and drop the cached command list
+pub fn write_command(home: &Path, id: &str, content: &str) -> PathBuf {
+    let path = home.join("commands").join("src").join(format!("{}.md", id));
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, content).unwrap();
+    command_registry::clear_cache();
+    path
+}
+
+/// Metadata entry for a tracked out-reference under `templates/`
+pub fn out_reference(id: &str, name: &str, file_path: &str) -> serde_json::Value {
+    serde_json::json!({
+        "id": id,
+        "name": name,
+        "description": "",
+        "category": "templates",
+        "filePath": file_path,
+        "format": "markdown",
+        "tags": [],
+        "linkedFrom": [],
+        "characterCount": 0,
+        "wordCount": 0,
+        "createdAt": "2024-01-01T00:00:00Z",
+        "updatedAt": "2024-01-01T00:00:00Z"
+    })
+}
+
+/// Write `out-references/metadata.json` tracking `references`, without writing their files
+pub fn write_out_reference_metadata(home: &Path, references: &[serde_json::Value]) -> PathBuf {
+    let out_refs_dir = home.join("out-references");
+    fs::create_dir_all(&out_refs_dir).unwrap();
+    let metadata = serde_json::json!({ "version": "1.0.0", "references": references });
+    fs::write(out_refs_dir.join("metadata.json"), metadata.to_string()).unwrap();
+    out_refs_dir
+}
+
+/// Minimal agent definition with no character limit and no config paths
+pub fn agent(id: &str) -> AgentDefinition {
+    AgentDefinition {
+        id: id.to_string(),
+        name: id.to_string(),
+        config_paths: Vec::new(),
+        agents_md_support: "native".to_string(),
+        command_format: "slash".to_string(),
+        character_limits: CharacterLimits {
+            max_chars: None,
+            supports_out_references: false,
+            warn_threshold_pct: DEFAULT_WARN_THRESHOLD_PCT,
+        },
+        deployment_strategy: "copy".to_string(),
+        build_output: String::new(),
+        file_format: "markdown".to_string(),
+        requires_frontmatter: None,
+        sandbox_script_path: None,
+        notes: None,
+        rules_filename: None,
+    }
+}
+
+type WriteStep = dyn Fn(&PreparedDeployment) -> DeploymentResult<Vec<String>> + Send + Sync;
+
+/// Deployer whose prepared content and write step are set by the test
+pub struct MockDeployer {
+    agent: AgentDefinition,
+    agents_md: String,
+    commands: Vec<(String, String)>,
+    write: Box<WriteStep>,
+}
+
+impl MockDeployer {
+    /// Prepares `# AGENTS.md` and reports its commands as deployed without writing them
+    pub fn new(agent_id: &str) -> Self {
+        Self {
+            agent: agent(agent_id),
+            agents_md: "# AGENTS.md".to_string(),
+            commands: Vec::new(),
+            write: Box::new(|prepared| Ok(prepared.commands.keys().cloned().collect())),
+        }
+    }
+
+    pub fn with_agents_md(mut self, content: impl Into<String>) -> Self {
+        self.agents_md = content.into();
+        self
+    }
+
+    pub fn with_command(mut self, name: &str, content: &str) -> Self {
+        self.commands.push((name.to_string(), content.to_string()));
+        self
+    }
+
+    /// Replace the write step; it returns the paths it deployed
+    pub fn on_deploy(
+        mut self,
+        write: impl Fn(&PreparedDeployment) -> DeploymentResult<Vec<String>> + Send + Sync + 'static,
+    ) -> Self {
+        self.write = Box::new(write);
+        self
+    }
+}
+
+impl AgentDeployer for MockDeployer {
+    fn agent_id(&self) -> &str {
+        &self.agent.id
+    }
+
+    fn agent_definition(&self) -> &AgentDefinition {
+        &self.agent
+    }
+
+    fn prepare(&self, _config: &DeploymentConfig) -> DeploymentResult<PreparedDeployment> {
+        let mut prepared = PreparedDeployment::new(self.agents_md.clone());
+        for (name, content) in &self.commands {
+            prepared.add_command(name.clone(), content.clone());
+        }
+        Ok(prepared)
+    }
+
+    fn validate(&self, prepared: &PreparedDeployment) -> DeploymentResult<ValidationReport> {
+        Ok(ValidationReport::success(BudgetUsage::unlimited(prepared.character_count)))
+    }
+
+    fn deploy(
+        &self,
+        prepared: PreparedDeployment,
+        _config: &DeploymentConfig,
+        progress: &mut ProgressSink<'_>,
+    ) -> DeploymentResult<DeploymentOutput> {
+        let deployed = (self.write)(&prepared)?;
+        progress(DeploymentPhase::WriteAgentsMd, None);
+        let mut commands: Vec<&String> = prepared.commands.keys().collect();
+        commands.sort();
+        for name in commands {
+            progress(DeploymentPhase::Command, Some(name.clone()));
+        }
+        Ok(DeploymentOutput::success("copy", deployed))
+    }
+
+    fn rollback(&self, state: &DeploymentState) -> DeploymentResult<()> {
+        for file in &state.files_created {
+            let _ = fs::remove_file(file);
+        }
+        Ok(())
+    }
+
+    fn get_status(&self) -> DeploymentResult<AgentStatus> {
+        Ok(AgentStatus::Configured)
+    }
+}
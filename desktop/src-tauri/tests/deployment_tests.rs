@@ -2,6 +2,8 @@
 //!
 //! Tests each deployer's prepare(), validate(), deploy(), and rollback() methods.
 
+mod common;
+
 use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
@@ -357,3 +359,2085 @@ mod tests {
         assert_eq!(warnings.len(), 1);
     }
 }
+
+/// Failed deployment tests
+///
+/// Forces a validation failure and checks the attempt is kept apart from history.
+#[cfg(test)]
+mod failure_tests {
+    use agentstoolkit_desktop::deployment::logger::DeploymentOperation;
+    use agentstoolkit_desktop::deployment::{DeploymentConfig, DeploymentManager, StateManager, TargetLevel};
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_validation_failure_is_recorded() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path().join("agentsmd");
+        let repo = temp.path().join("repo");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        std::env::set_var("AGENTSMD_HOME", &home);
+
+        common::write_huge_pack(&home);
+
+        let manager = DeploymentManager::new().unwrap();
+        let config = DeploymentConfig {
+            agent_id: "copilot".to_string(),
+            pack_ids: vec!["huge".to_string()],
+            target_level: TargetLevel::Project,
+            project_path: Some(repo.to_string_lossy().to_string()),
+            verify_after_deploy: false,
+            ..Default::default()
+        };
+        assert!(manager.deploy(&config).is_err());
+
+        let failures = manager.get_failures("copilot").unwrap();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].agent_id, "copilot");
+        assert!(matches!(failures[0].phase, DeploymentOperation::Validate));
+        assert!(failures[0].error.contains("exceeds character limit"));
+
+        let state = StateManager::new().unwrap();
+        assert!(state.get_agent_history("copilot").unwrap().is_empty());
+        assert_eq!(state.get_agent_failures("copilot").unwrap().len(), 1);
+    }
+}
+
+/// Deployment history pagination tests
+///
+/// Records more deployments than the default retention and pages through them.
+#[cfg(test)]
+mod history_tests {
+    use agentstoolkit_desktop::deployment::{DeploymentHistoryPage, DeploymentState, StateManager, TargetLevel};
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_history_pages_are_newest_first() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        std::env::set_var("AGENTSMD_HOME", temp.path());
+
+        let manager = StateManager::new().unwrap();
+        manager.set_history_retention(20).unwrap();
+
+        for i in 0..15 {
+            let state = DeploymentState::new("cursor".to_string(), "symlink".to_string(), TargetLevel::User)
+                .with_packs(vec![format!("pack-{}", i)]);
+            manager.record_deployment(state).unwrap();
+        }
+
+        let packs = |page: &DeploymentHistoryPage| -> Vec<String> {
+            page.entries.iter().map(|s| s.deployed_packs[0].clone()).collect()
+        };
+
+        let first = manager.get_agent_history_page("cursor", 0, 5).unwrap();
+        assert_eq!(first.total, 15);
+        assert_eq!(packs(&first), vec!["pack-14", "pack-13", "pack-12", "pack-11", "pack-10"]);
+
+        let second = manager.get_agent_history_page("cursor", 5, 5).unwrap();
+        assert_eq!(packs(&second), vec!["pack-9", "pack-8", "pack-7", "pack-6", "pack-5"]);
+
+        let last = manager.get_agent_history_page("cursor", 12, 5).unwrap();
+        assert_eq!(packs(&last), vec!["pack-2", "pack-1", "pack-0"]);
+
+        let past_end = manager.get_agent_history_page("cursor", 20, 5).unwrap();
+        assert!(past_end.entries.is_empty());
+        assert_eq!(past_end.total, 15);
+
+        // The existing full-history query still returns everything, oldest first
+        assert_eq!(manager.get_agent_history("cursor").unwrap().len(), 15);
+    }
+}
+
+/// Deployment history compaction tests
+///
+/// Records repeated identical deployments and collapses them into the latest one, keeping
+/// a backup that still exists.
+#[cfg(test)]
+mod history_compaction_tests {
+    use agentstoolkit_desktop::deployment::{DeploymentState, StateManager, TargetLevel};
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    fn state(packs: &[&str], content_hash: &str) -> DeploymentState {
+        DeploymentState::new("cursor".to_string(), "symlink".to_string(), TargetLevel::User)
+            .with_packs(packs.iter().map(|p| p.to_string()).collect())
+            .with_content_hash(content_hash.to_string())
+    }
+
+    fn backup_dir(temp: &TempDir, name: &str) -> String {
+        let dir = temp.path().join("backups").join(name);
+        fs::create_dir_all(&dir).unwrap();
+        dir.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_compact_history_keeps_earliest_backup() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        std::env::set_var("AGENTSMD_HOME", temp.path());
+
+        let original = backup_dir(&temp, "original");
+        let redeploy = backup_dir(&temp, "redeploy");
+
+        let manager = StateManager::new().unwrap();
+        manager.record_deployment(state(&["core"], "abc").with_backup(original.clone())).unwrap();
+        manager.record_deployment(state(&["core"], "abc").with_backup(redeploy)).unwrap();
+
+        assert_eq!(manager.compact_history("cursor").unwrap(), 1);
+        let history = manager.get_agent_history("cursor").unwrap();
+        assert_eq!(history[0].backup_path, Some(original));
+    }
+
+    #[test]
+    fn test_compact_history_skips_pruned_backups() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        std::env::set_var("AGENTSMD_HOME", temp.path());
+
+        // Backup cleanup has already removed the oldest backup of the run
+        let pruned = temp.path().join("backups").join("pruned").to_string_lossy().to_string();
+        let kept = backup_dir(&temp, "kept");
+        let newest = backup_dir(&temp, "newest");
+
+        let manager = StateManager::new().unwrap();
+        manager.record_deployment(state(&["core"], "abc").with_backup(pruned.clone())).unwrap();
+        manager.record_deployment(state(&["core"], "abc").with_backup(kept.clone())).unwrap();
+        manager.record_deployment(state(&["core"], "abc").with_backup(newest)).unwrap();
+        manager.record_deployment(state(&["testing"], "def").with_backup(pruned.clone())).unwrap();
+        manager.record_deployment(state(&["testing"], "def").with_backup(pruned)).unwrap();
+
+        assert_eq!(manager.compact_history("cursor").unwrap(), 3);
+        let history = manager.get_agent_history("cursor").unwrap();
+        assert_eq!(history[0].backup_path, Some(kept));
+        // A run whose backups are all gone keeps none rather than a dangling path
+        assert_eq!(history[1].backup_path, None);
+    }
+
+    #[test]
+    fn test_compact_history_keeps_different_targets() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        std::env::set_var("AGENTSMD_HOME", temp.path());
+
+        let manager = StateManager::new().unwrap();
+        manager.record_deployment(state(&["core"], "abc")).unwrap();
+        let project = DeploymentState::new("cursor".to_string(), "symlink".to_string(), TargetLevel::Project)
+            .with_packs(vec!["core".to_string()])
+            .with_content_hash("abc".to_string())
+            .with_project("/work/app".to_string());
+        manager.record_deployment(project.clone()).unwrap();
+        manager.record_deployment(project.with_project("/work/other".to_string())).unwrap();
+        manager
+            .record_deployment(
+                DeploymentState::new("cursor".to_string(), "copy".to_string(), TargetLevel::User)
+                    .with_packs(vec!["core".to_string()])
+                    .with_content_hash("abc".to_string()),
+            )
+            .unwrap();
+
+        assert_eq!(manager.compact_history("cursor").unwrap(), 0);
+        assert_eq!(manager.get_agent_history("cursor").unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_compact_history_collapses_identical_runs() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        std::env::set_var("AGENTSMD_HOME", temp.path());
+
+        let manager = StateManager::new().unwrap();
+        for _ in 0..3 {
+            manager.record_deployment(state(&["core"], "abc")).unwrap();
+        }
+        let latest_identical = manager.get_agent_state("cursor").unwrap().unwrap().timestamp;
+        manager.record_deployment(state(&["core", "testing"], "def")).unwrap();
+
+        assert_eq!(manager.compact_history("cursor").unwrap(), 2);
+
+        let history = manager.get_agent_history("cursor").unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].deployed_packs, vec!["core"]);
+        assert_eq!(history[0].timestamp, latest_identical);
+        assert_eq!(history[1].deployed_packs, vec!["core", "testing"]);
+
+        // Already compact, and unknown agents are a no-op
+        assert_eq!(manager.compact_history("cursor").unwrap(), 0);
+        assert_eq!(manager.compact_history("claude").unwrap(), 0);
+    }
+}
+
+/// Recent deployments tests
+///
+/// Records deployments for several agents and checks the merged feed ordering.
+#[cfg(test)]
+mod recent_deployments_tests {
+    use agentstoolkit_desktop::deployment::{DeploymentState, StateManager, TargetLevel};
+    use chrono::{Duration, Utc};
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_recent_deployments_are_interleaved_newest_first() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        std::env::set_var("AGENTSMD_HOME", temp.path());
+
+        let manager = StateManager::new().unwrap();
+        let start = Utc::now() - Duration::hours(1);
+        for (minutes, agent_id) in [(0, "cursor"), (10, "claude"), (20, "cursor"), (30, "claude")] {
+            let mut state = DeploymentState::new(agent_id.to_string(), "symlink".to_string(), TargetLevel::User)
+                .with_packs(vec![format!("pack-{}", minutes)]);
+            state.timestamp = start + Duration::minutes(minutes);
+            manager.record_deployment(state).unwrap();
+        }
+
+        let recent = manager.recent_deployments(10).unwrap();
+        let feed: Vec<(&str, &str)> = recent
+            .iter()
+            .map(|s| (s.agent_id.as_str(), s.deployed_packs[0].as_str()))
+            .collect();
+        assert_eq!(
+            feed,
+            vec![
+                ("claude", "pack-30"),
+                ("cursor", "pack-20"),
+                ("claude", "pack-10"),
+                ("cursor", "pack-0"),
+            ]
+        );
+
+        let limited = manager.recent_deployments(2).unwrap();
+        assert_eq!(limited.len(), 2);
+        assert_eq!(limited[1].agent_id, "cursor");
+    }
+}
+
+/// Deployed agents tests
+///
+/// Lists the agents that have a deployment recorded.
+#[cfg(test)]
+mod deployed_agents_tests {
+    use agentstoolkit_desktop::deployment::{DeploymentState, StateManager, TargetLevel};
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_deployed_agents_lists_only_recorded_agents() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        std::env::set_var("AGENTSMD_HOME", temp.path());
+
+        let manager = StateManager::new().unwrap();
+        assert!(manager.deployed_agents().unwrap().is_empty());
+
+        for agent_id in ["cursor", "claude", "gemini"] {
+            let state = DeploymentState::new(agent_id.to_string(), "symlink".to_string(), TargetLevel::User);
+            manager.record_deployment(state).unwrap();
+        }
+        manager.remove_latest_deployment("gemini").unwrap();
+
+        assert_eq!(manager.deployed_agents().unwrap(), vec!["claude", "cursor"]);
+    }
+}
+
+/// Rollback preview tests
+///
+/// Plans the rollback of a redeployment without touching files or history.
+#[cfg(test)]
+mod rollback_preview_tests {
+    use agentstoolkit_desktop::deployment::{DeploymentConfig, DeploymentManager};
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_preview_rollback_lists_removals_and_restorations() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let user_home = temp.path().join("home");
+        let agentsmd_home = temp.path().join("agentsmd");
+        std::env::set_var("HOME", &user_home);
+        std::env::set_var("AGENTSMD_HOME", &agentsmd_home);
+
+        let manager = DeploymentManager::new().unwrap();
+        let config = DeploymentConfig {
+            agent_id: "claude".to_string(),
+            force_overwrite: true,
+            ..Default::default()
+        };
+        manager.deploy(&config).unwrap();
+        manager.deploy(&config).unwrap();
+
+        let agents_md = agentsmd_home.join("AGENTS.md").to_string_lossy().to_string();
+        let claude_md = user_home.join(".claude").join("CLAUDE.md").to_string_lossy().to_string();
+
+        let plan = manager.preview_rollback("claude", None).unwrap();
+        let history = manager.get_history("claude").unwrap();
+        let latest = history.last().unwrap();
+        assert_eq!(plan.timestamp, latest.timestamp);
+        assert_eq!(plan.backup_path, latest.backup_path);
+        assert!(plan.backup_path.is_some());
+
+        let mut removed = plan.files_to_remove.clone();
+        removed.sort();
+        let mut expected = vec![agents_md.clone(), claude_md.clone()];
+        expected.sort();
+        assert_eq!(removed, expected);
+
+        let mut restored: Vec<String> = plan.files_to_restore.iter().map(|f| f.path.clone()).collect();
+        restored.sort();
+        assert_eq!(restored, expected);
+        for file in &plan.files_to_restore {
+            assert!(fs::symlink_metadata(&file.backup_path).is_ok(), "{}", file.backup_path);
+        }
+
+        // Nothing was rolled back
+        assert_eq!(manager.get_history("claude").unwrap().len(), 2);
+        assert!(fs::symlink_metadata(&claude_md).is_ok());
+    }
+}
+
+/// Named backup restore tests
+///
+/// Lists an agent's backups in the order they were taken and restores an older one
+/// directly by its directory.
+#[cfg(test)]
+mod named_backup_tests {
+    use agentstoolkit_desktop::deployment::BackupManager;
+    use std::fs;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_restore_older_named_backup() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        std::env::set_var("AGENTSMD_HOME", temp.path().join("agentsmd"));
+
+        let rules = temp.path().join("CLAUDE.md");
+        let commands = temp.path().join("commands");
+        fs::create_dir_all(&commands).unwrap();
+        let targets = vec![rules.clone(), commands.clone()];
+        let manager = BackupManager::new().unwrap();
+
+        fs::write(&rules, "v1").unwrap();
+        fs::write(commands.join("ship.md"), "ship v1").unwrap();
+        let first = manager.create_backup("claude", &targets).unwrap().unwrap();
+
+        fs::write(&rules, "v2").unwrap();
+        fs::write(commands.join("ship.md"), "ship v2").unwrap();
+        fs::write(commands.join("test.md"), "test v2").unwrap();
+        let second = manager.create_backup("claude", &targets).unwrap().unwrap();
+
+        fs::write(&rules, "v3").unwrap();
+
+        let backups = manager.list_backups("claude").unwrap();
+        let paths: Vec<_> = backups.iter().map(|b| b.path.clone()).collect();
+        assert_eq!(paths, vec![second.clone(), first.clone()]);
+        assert_eq!(backups[0].file_count, 3);
+        assert_eq!(backups[1].file_count, 2);
+        assert!(backups[0].timestamp >= backups[1].timestamp);
+
+        let mut restored = manager.restore_named_backup("claude", &first).unwrap();
+        restored.sort();
+        assert_eq!(restored, vec![rules.clone(), commands.clone()]);
+        assert_eq!(fs::read_to_string(&rules).unwrap(), "v1");
+        assert_eq!(fs::read_to_string(commands.join("ship.md")).unwrap(), "ship v1");
+        assert!(!commands.join("test.md").exists());
+
+        // Another agent's backups can't be restored through this one
+        assert!(manager.restore_named_backup("cursor", &first).is_err());
+        assert!(manager.restore_named_backup("claude", temp.path()).is_err());
+    }
+
+    #[test]
+    fn test_backups_within_one_second_sort_by_suffix() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let agentsmd_home = temp.path().join("agentsmd");
+        std::env::set_var("AGENTSMD_HOME", &agentsmd_home);
+
+        let agent_dir = agentsmd_home.join("backups").join("claude");
+        let names: Vec<String> = std::iter::once("20260101_120000".to_string())
+            .chain((1..=10).map(|n| format!("20260101_120000_{}", n)))
+            .collect();
+        for name in &names {
+            fs::create_dir_all(agent_dir.join(name)).unwrap();
+        }
+
+        let manager = BackupManager::new().unwrap();
+        let listed: Vec<String> = manager
+            .list_backups("claude")
+            .unwrap()
+            .iter()
+            .map(|b| b.path.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        let newest_first: Vec<String> = names.iter().rev().cloned().collect();
+        assert_eq!(listed, newest_first);
+
+        // Pruning after a new backup keeps the latest same-second backups, not `_9` over `_10`
+        let rules = temp.path().join("CLAUDE.md");
+        fs::write(&rules, "rules").unwrap();
+        let newest = manager.create_backup("claude", &[rules]).unwrap().unwrap();
+        let kept: Vec<PathBuf> = manager.list_backups("claude").unwrap().into_iter().map(|b| b.path).collect();
+        let mut expected = vec![newest];
+        expected.extend(names[7..].iter().rev().map(|name| agent_dir.join(name)));
+        assert_eq!(kept, expected);
+    }
+}
+
+/// Incremental backup tests
+///
+/// Deploys repeatedly and checks unchanged files are linked from the previous backup.
+#[cfg(test)]
+mod incremental_backup_tests {
+    use agentstoolkit_desktop::deployment::state::BackupManager;
+    use agentstoolkit_desktop::deployment::{DeploymentConfig, DeploymentManager};
+    use std::fs;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_unchanged_files_are_reused_between_backups() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path().join("agentsmd");
+        std::env::set_var("HOME", temp.path().join("home"));
+        std::env::set_var("AGENTSMD_HOME", &home);
+
+        let out_refs_dir = home.join("out-references");
+        fs::create_dir_all(out_refs_dir.join("templates")).unwrap();
+        common::write_out_reference_metadata(
+            &home,
+            &[common::out_reference("pr-template", "PR Template", "templates/pr-template.md")],
+        );
+        fs::write(
+            out_refs_dir.join("templates").join("pr-template.md"),
+            "# PR Template\n\nDescribe the change.\n",
+        )
+        .unwrap();
+
+        let pack_dir = common::PackFixture::new("style")
+            .with_name("Style")
+            .with_description("House style")
+            .with_rules("# Style\n")
+            .write(&home);
+
+        common::write_command(
+            &home,
+            "ship",
+            "Ship the current branch.\n\nFill in [the template](templates/pr-template.md).\n",
+        );
+
+        let manager = DeploymentManager::new().unwrap();
+        let config = DeploymentConfig {
+            agent_id: "cursor".to_string(),
+            pack_ids: vec!["style".to_string()],
+            custom_command_ids: vec!["ship".to_string()],
+            ..Default::default()
+        };
+
+        let backup_of_latest = || -> Option<PathBuf> {
+            let history = manager.get_history("cursor").unwrap();
+            history.last().unwrap().backup_path.clone().map(PathBuf::from)
+        };
+
+        // Nothing exists yet, so the first deploy has no backup
+        manager.deploy(&config).unwrap();
+        assert!(backup_of_latest().is_none());
+
+        // Only AGENTS.md changes between deploys
+        fs::write(pack_dir.join("rules.md"), "# Style\n\nUse tabs.\n").unwrap();
+        manager.deploy(&config).unwrap();
+        let first = backup_of_latest().unwrap();
+        fs::write(pack_dir.join("rules.md"), "# Style\n\nUse spaces.\n").unwrap();
+        manager.deploy(&config).unwrap();
+        let second = backup_of_latest().unwrap();
+        assert_ne!(first, second);
+
+        let first_manifest = BackupManager::read_manifest(&first).unwrap();
+        assert!(first_manifest.files.values().all(|entry| !entry.reused));
+
+        let manifest = BackupManager::read_manifest(&second).unwrap();
+        assert!(!manifest.files["AGENTS.md"].reused);
+        let template = "out-references/templates/pr-template.md";
+        assert!(manifest.files[template].reused);
+        assert!(manifest.files["pr-template.md"].reused);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let inode = |dir: &PathBuf| fs::metadata(dir.join(template)).unwrap().ino();
+            assert_eq!(inode(&first), inode(&second));
+        }
+
+        // The second backup still holds the full set of files
+        assert_eq!(
+            fs::read_to_string(second.join(template)).unwrap(),
+            "# PR Template\n\nDescribe the change.\n"
+        );
+        assert!(fs::read_to_string(second.join("AGENTS.md")).unwrap().contains("Style"));
+
+        // A previous copy whose hash and size still match but whose bytes differ (as with a
+        // hash collision) is not linked
+        let original = fs::read_to_string(second.join(template)).unwrap();
+        fs::write(second.join(template), original.replace("change", "CHANGE")).unwrap();
+        fs::write(pack_dir.join("rules.md"), "# Style\n\nUse tabs again.\n").unwrap();
+        manager.deploy(&config).unwrap();
+        let third = backup_of_latest().unwrap();
+        let manifest = BackupManager::read_manifest(&third).unwrap();
+        assert!(!manifest.files[template].reused);
+        assert_eq!(fs::read_to_string(third.join(template)).unwrap(), original);
+    }
+}
+
+/// Redeploy tests
+///
+/// Redeploys an agent from its recorded state after a pack edit.
+#[cfg(test)]
+mod redeploy_tests {
+    use agentstoolkit_desktop::deployment::{DeploymentConfig, DeploymentManager, TargetLevel};
+    use agentstoolkit_desktop::types::LinkStrategy;
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_redeploy_current_picks_up_pack_edits() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path().join("agentsmd");
+        let repo = temp.path().join("repo");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        std::env::set_var("AGENTSMD_HOME", &home);
+
+        let pack_dir = common::PackFixture::new("testing")
+            .with_name("Testing")
+            .with_rules("# Testing\n\nWrite the test first.\n")
+            .write(&home);
+
+        let manager = DeploymentManager::new().unwrap();
+        assert!(manager.redeploy_current("copilot").is_err());
+
+        let config = DeploymentConfig {
+            agent_id: "copilot".to_string(),
+            pack_ids: vec!["testing".to_string()],
+            target_level: TargetLevel::Project,
+            project_path: Some(repo.to_string_lossy().to_string()),
+            ..Default::default()
+        };
+        manager.deploy(&config).unwrap();
+
+        fs::write(pack_dir.join("rules.md"), "# Testing\n\nRun the whole suite before pushing.\n").unwrap();
+        manager.redeploy_current("copilot").unwrap();
+
+        // Copilot inlines pack content, so the deployed file shows the edit directly
+        let deployed = fs::read_to_string(repo.join(".github").join("copilot-instructions.md")).unwrap();
+        assert!(deployed.contains("Run the whole suite before pushing."));
+        assert!(!deployed.contains("Write the test first."));
+
+        let history = manager.get_history("copilot").unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].target_level, TargetLevel::Project);
+        assert_eq!(history[1].project_path, Some(repo.to_string_lossy().to_string()));
+    }
+
+    #[test]
+    fn test_redeploy_current_repeats_deployment_options() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let user_home = temp.path().join("home");
+        std::env::set_var("HOME", &user_home);
+        std::env::set_var("AGENTSMD_HOME", temp.path().join("agentsmd"));
+
+        let manager = DeploymentManager::new().unwrap();
+        let config = DeploymentConfig {
+            agent_id: "claude".to_string(),
+            link_strategy: LinkStrategy::ForceCopy,
+            ..Default::default()
+        };
+        manager.deploy(&config).unwrap();
+        manager.redeploy_current("claude").unwrap();
+
+        let rules = user_home.join(".claude").join("CLAUDE.md");
+        assert!(fs::symlink_metadata(&rules).unwrap().file_type().is_file());
+
+        let history = manager.get_history("claude").unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].method, "copy");
+        assert_eq!(history[1].target_level, TargetLevel::User);
+        assert_eq!(history[1].options, config.options());
+    }
+}
+
+/// Deployment retarget tests
+///
+/// Moves a user-level Claude deployment into a project and checks both levels.
+#[cfg(test)]
+mod retarget_tests {
+    use agentstoolkit_desktop::deployment::{DeploymentConfig, DeploymentManager, TargetLevel};
+    use agentstoolkit_desktop::types::LinkStrategy;
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_retarget_user_deployment_to_project() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let user_home = temp.path().join("home");
+        let repo = temp.path().join("repo");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        std::env::set_var("HOME", &user_home);
+        std::env::set_var("AGENTSMD_HOME", temp.path().join("agentsmd"));
+
+        let manager = DeploymentManager::new().unwrap();
+        let config = DeploymentConfig {
+            agent_id: "claude".to_string(),
+            ..Default::default()
+        };
+        manager.deploy(&config).unwrap();
+
+        let user_link = user_home.join(".claude").join("CLAUDE.md");
+        assert!(fs::symlink_metadata(&user_link).is_ok());
+
+        manager
+            .retarget(
+                "claude",
+                TargetLevel::Project,
+                Some(repo.to_string_lossy().to_string()),
+            )
+            .unwrap();
+
+        assert!(fs::symlink_metadata(&user_link).is_err());
+        assert!(repo.join(".claude").join("CLAUDE.md").exists());
+
+        let history = manager.get_history("claude").unwrap();
+        let latest = history.last().unwrap();
+        assert_eq!(latest.target_level, TargetLevel::Project);
+    }
+
+    #[test]
+    fn test_retarget_keeps_deployment_options() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let user_home = temp.path().join("home");
+        let repo = temp.path().join("repo");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        std::env::set_var("HOME", &user_home);
+        std::env::set_var("AGENTSMD_HOME", temp.path().join("agentsmd"));
+
+        let manager = DeploymentManager::new().unwrap();
+        let config = DeploymentConfig {
+            agent_id: "claude".to_string(),
+            link_strategy: LinkStrategy::ForceCopy,
+            ..Default::default()
+        };
+        manager.deploy(&config).unwrap();
+
+        manager
+            .retarget(
+                "claude",
+                TargetLevel::Project,
+                Some(repo.to_string_lossy().to_string()),
+            )
+            .unwrap();
+
+        let project_file = repo.join(".claude").join("CLAUDE.md");
+        assert!(fs::symlink_metadata(&project_file).unwrap().file_type().is_file());
+
+        let history = manager.get_history("claude").unwrap();
+        let latest = history.last().unwrap();
+        assert_eq!(latest.method, "copy");
+        assert_eq!(latest.options.link_strategy, LinkStrategy::ForceCopy);
+    }
+}
+
+/// Deployment locking tests
+///
+/// Runs two deployments in parallel against the same AGENTSMD_HOME and checks
+/// that the shared AGENTS.md is never left interleaved.
+#[cfg(test)]
+mod lock_tests {
+    use std::fs::{self, OpenOptions};
+    use std::io::Write;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+    use agentstoolkit_desktop::deployment::lock::DeploymentLock;
+    use agentstoolkit_desktop::deployment::{DeploymentConfig, DeploymentManager};
+    use tempfile::TempDir;
+    use crate::common;
+
+    const LINES: usize = 40;
+
+    /// Writes AGENTS.md one line at a time to simulate a slow filesystem
+    fn slow_writer(id: &str, agents_md: PathBuf) -> common::MockDeployer {
+        let content: String = (0..LINES).map(|i| format!("{} line {}\n", id, i)).collect();
+        common::MockDeployer::new(id)
+            .with_agents_md(content)
+            .on_deploy(move |prepared| {
+                fs::write(&agents_md, "")?;
+                for line in prepared.agents_md_content.lines() {
+                    let mut file = OpenOptions::new().append(true).open(&agents_md)?;
+                    writeln!(file, "{}", line)?;
+                    thread::sleep(Duration::from_millis(1));
+                }
+                Ok(vec![agents_md.to_string_lossy().to_string()])
+            })
+    }
+
+    fn config(agent_id: &str) -> DeploymentConfig {
+        DeploymentConfig {
+            agent_id: agent_id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parallel_deploys_do_not_interleave_agents_md() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        std::env::set_var("AGENTSMD_HOME", temp.path());
+        let agents_md = temp.path().join("AGENTS.md");
+
+        let handles: Vec<_> = ["agent-a", "agent-b"]
+            .into_iter()
+            .map(|id| {
+                let agents_md = agents_md.clone();
+                thread::spawn(move || {
+                    let mut manager = DeploymentManager::new().unwrap();
+                    manager.register_deployer(Arc::new(slow_writer(id, agents_md)));
+                    manager.deploy(&config(id)).unwrap();
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let content = fs::read_to_string(&agents_md).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), LINES);
+
+        let owner = lines[0].split(' ').next().unwrap();
+        assert!(lines.iter().all(|line| line.starts_with(owner)));
+        assert!(DeploymentLock::acquire_with_timeout(temp.path(), Duration::from_millis(50)).is_ok());
+    }
+}
+
+/// Deployment progress event tests
+///
+/// Runs a deployment through a mock deployer and checks the reported phases.
+#[cfg(test)]
+mod progress_tests {
+    use std::fs;
+    use std::sync::Arc;
+    use agentstoolkit_desktop::deployment::{DeploymentConfig, DeploymentManager, DeploymentPhase};
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_deploy_reports_phases_in_order() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        std::env::set_var("AGENTSMD_HOME", temp.path());
+        fs::create_dir_all(temp.path()).unwrap();
+
+        let mut manager = DeploymentManager::new().unwrap();
+        manager.register_deployer(Arc::new(
+            common::MockDeployer::new("mock-agent")
+                .with_command("push.md", "Push")
+                .with_command("status.md", "Status"),
+        ));
+
+        let config = DeploymentConfig {
+            agent_id: "mock-agent".to_string(),
+            fix_frontmatter: false,
+            // The mock deployer reports files without writing them
+            verify_after_deploy: false,
+            ..Default::default()
+        };
+
+        let mut events = Vec::new();
+        let output = manager
+            .deploy_with_progress(&config, |progress| events.push(progress))
+            .unwrap();
+        assert!(output.success);
+
+        let phases: Vec<_> = events.iter().map(|e| e.phase).collect();
+        assert_eq!(
+            phases,
+            vec![
+                DeploymentPhase::Prepare,
+                DeploymentPhase::Validate,
+                DeploymentPhase::Backup,
+                DeploymentPhase::WriteAgentsMd,
+                DeploymentPhase::Command,
+                DeploymentPhase::Command,
+                DeploymentPhase::Done,
+            ]
+        );
+
+        let items: Vec<_> = events.iter().filter_map(|e| e.item.as_deref()).collect();
+        assert_eq!(items, vec!["push.md", "status.md"]);
+
+        let counters: Vec<_> = events.iter().map(|e| e.current).collect();
+        assert_eq!(counters, (1..=7).collect::<Vec<_>>());
+        assert!(events.iter().skip(1).all(|e| e.total == 7));
+    }
+
+    #[test]
+    fn test_command_events_fire_as_each_file_is_written() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let user_home = temp.path().join("home");
+        let agentsmd_home = temp.path().join("agentsmd");
+        std::env::set_var("HOME", &user_home);
+        std::env::set_var("AGENTSMD_HOME", &agentsmd_home);
+
+        common::write_command(&agentsmd_home, "push", "Push the branch.\n");
+        common::write_command(&agentsmd_home, "status", "Show status.\n");
+        let commands_dir = user_home.join(".claude").join("commands");
+        let deployed = || {
+            ["push.md", "status.md"]
+                .iter()
+                .filter(|name| commands_dir.join(name).exists())
+                .count()
+        };
+
+        let manager = DeploymentManager::new().unwrap();
+        let config = DeploymentConfig {
+            agent_id: "claude".to_string(),
+            custom_command_ids: vec!["push".to_string(), "status".to_string()],
+            ..Default::default()
+        };
+
+        let mut seen_at_command = Vec::new();
+        manager
+            .deploy_with_progress(&config, |progress| {
+                if progress.phase == DeploymentPhase::Command {
+                    seen_at_command.push(deployed());
+                }
+            })
+            .unwrap();
+
+        assert_eq!(seen_at_command, vec![1, 2]);
+    }
+}
+
+/// Post-deploy verification tests
+///
+/// Uses a deployer whose copies come out different from what was prepared and
+/// checks that the deployment is rolled back with a verification error.
+#[cfg(test)]
+mod verify_tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use agentstoolkit_desktop::deployment::{
+        DeploymentConfig, DeploymentError, DeploymentManager, StateManager,
+    };
+    use tempfile::TempDir;
+    use crate::common;
+
+    /// Copies commands into place, then the "drive" changes one of them underneath us
+    fn flaky_copy_deployer(commands_dir: PathBuf) -> common::MockDeployer {
+        common::MockDeployer::new("flaky-agent")
+            .with_command("push.md", "Push the current branch")
+            .with_command("status.md", "Show the status")
+            .on_deploy(move |prepared| {
+                fs::create_dir_all(&commands_dir)?;
+                let mut deployed = Vec::new();
+                for (name, content) in &prepared.commands {
+                    let path = commands_dir.join(name);
+                    fs::write(&path, content)?;
+                    deployed.push(path.to_string_lossy().to_string());
+                }
+
+                fs::write(commands_dir.join("status.md"), "Show the")?;
+                Ok(deployed)
+            })
+    }
+
+    #[test]
+    fn test_mismatched_copy_fails_verification_and_rolls_back() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        std::env::set_var("AGENTSMD_HOME", temp.path().join("agentsmd"));
+        let commands_dir = temp.path().join("commands");
+
+        let mut manager = DeploymentManager::new().unwrap();
+        manager.register_deployer(Arc::new(flaky_copy_deployer(commands_dir.clone())));
+
+        let mut config = DeploymentConfig {
+            agent_id: "flaky-agent".to_string(),
+            ..Default::default()
+        };
+
+        match manager.deploy(&config) {
+            Err(DeploymentError::VerificationFailed(message)) => {
+                assert!(message.contains("status.md"), "{}", message);
+                assert!(!message.contains("push.md"), "{}", message);
+            }
+            other => panic!("expected VerificationFailed, got {:?}", other),
+        }
+
+        assert!(!commands_dir.join("push.md").exists());
+        assert!(!commands_dir.join("status.md").exists());
+        assert!(StateManager::new().unwrap().get_agent_state("flaky-agent").unwrap().is_none());
+
+        // Opting out keeps the old trust-the-write behaviour
+        config.verify_after_deploy = false;
+        assert!(manager.deploy(&config).is_ok());
+    }
+}
+
+/// Deployment materialize tests
+///
+/// Builds the target-to-content map for a Claude deployment without writing it.
+#[cfg(test)]
+mod materialize_tests {
+    use agentstoolkit_desktop::deployment::{DeploymentConfig, DeploymentManager};
+    use agentstoolkit_desktop::{command_registry, out_reference_manager};
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_materialize_maps_every_target_to_content() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let user_home = temp.path().join("home");
+        let agentsmd_home = temp.path().join("agentsmd");
+        std::env::set_var("HOME", &user_home);
+        std::env::set_var("AGENTSMD_HOME", &agentsmd_home);
+
+        common::write_command(&agentsmd_home, "status", "Show repository status.\n");
+        common::write_command(&agentsmd_home, "pr", "Open a pull request.\n");
+
+        common::PackFixture::new("core")
+            .with_name("Core")
+            .with_description("Core rules")
+            .with_rules("# Core\n\nKeep changes small.\n")
+            .write(&agentsmd_home);
+
+        let out_ref = out_reference_manager::create_out_reference(
+            "PR Template".to_string(),
+            "Pull request layout".to_string(),
+            "templates".to_string(),
+            "# Pull Request\n".to_string(),
+            "markdown".to_string(),
+            Vec::new(),
+        )
+        .unwrap();
+        command_registry::update_command_out_references("pr", vec![out_ref.file_path.clone()]).unwrap();
+
+        let manager = DeploymentManager::new().unwrap();
+        let config = DeploymentConfig {
+            agent_id: "claude".to_string(),
+            pack_ids: vec!["core".to_string()],
+            custom_command_ids: vec!["status".to_string(), "pr".to_string()],
+            ..Default::default()
+        };
+        let materialized = manager.materialize(&config).unwrap();
+        let preview = manager.preview_deployment(&config).unwrap();
+
+        let claude_dir = user_home.join(".claude");
+        assert_eq!(materialized.len(), 4);
+        assert_eq!(
+            materialized[&agentsmd_home.join("AGENTS.md")],
+            preview.agents_md_content
+        );
+        assert!(materialized[&claude_dir.join("commands").join("status.md")].contains("Show repository status."));
+        assert!(materialized[&claude_dir.join("commands").join("pr.md")].contains("Open a pull request."));
+        assert_eq!(
+            materialized[&claude_dir.join("references").join(&out_ref.file_path)],
+            "# Pull Request\n"
+        );
+        assert!(!claude_dir.exists());
+    }
+}
+
+/// Commands-only deployment tests
+///
+/// Deploys a command with no packs selected and checks AGENTS.md is left alone.
+#[cfg(test)]
+mod commands_only_deploy_tests {
+    use agentstoolkit_desktop::deployment::{
+        DeploymentConfig, DeploymentManager, COMMANDS_ONLY_WARNING,
+    };
+
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_commands_only_deploy_skips_agents_md() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let user_home = temp.path().join("home");
+        let agentsmd_home = temp.path().join("agentsmd");
+        std::env::set_var("HOME", &user_home);
+        std::env::set_var("AGENTSMD_HOME", &agentsmd_home);
+
+        common::write_command(&agentsmd_home, "review", "Review the open pull request.\n");
+
+        let manager = DeploymentManager::new().unwrap();
+        let config = DeploymentConfig {
+            agent_id: "cursor".to_string(),
+            custom_command_ids: vec!["review".to_string()],
+            ..Default::default()
+        };
+        let result = manager.deploy(&config).unwrap();
+
+        let command = user_home.join(".cursor").join("commands").join("review.md");
+        assert!(fs::read_to_string(&command).unwrap().contains("Review the open pull request."));
+        assert!(!agentsmd_home.join("AGENTS.md").exists());
+        assert!(!result.deployed_files.iter().any(|f| f.ends_with("AGENTS.md")));
+        assert!(result.warnings.iter().any(|w| w == COMMANDS_ONLY_WARNING));
+    }
+}
+
+/// Claude skills deployment tests
+///
+/// Deploys a command through the manager with skills requested, checks where it lands,
+/// and rolls it back.
+#[cfg(test)]
+mod claude_skills_deploy_tests {
+    use agentstoolkit_desktop::deployment::{DeploymentConfig, DeploymentManager};
+
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_emit_skills_deploys_commands_as_skills() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let user_home = temp.path().join("home");
+        let agentsmd_home = temp.path().join("agentsmd");
+        std::env::set_var("HOME", &user_home);
+        std::env::set_var("AGENTSMD_HOME", &agentsmd_home);
+
+        common::write_command(
+            &agentsmd_home,
+            "review",
+            "---\nname: review\ndescription: Review the open pull request\n---\nReview it.\n",
+        );
+
+        let manager = DeploymentManager::new().unwrap();
+        let mut config = DeploymentConfig {
+            agent_id: "claude".to_string(),
+            custom_command_ids: vec!["review".to_string()],
+            emit_skills: true,
+            ..Default::default()
+        };
+        manager.deploy(&config).unwrap();
+
+        let claude_dir = user_home.join(".claude");
+        let skill = claude_dir.join("skills").join("review").join("SKILL.md");
+        assert!(fs::read_to_string(&skill).unwrap().contains("Review it."));
+        assert!(!claude_dir.join("commands").join("review.md").exists());
+
+        config.emit_skills = false;
+        manager.deploy(&config).unwrap();
+        assert!(claude_dir.join("commands").join("review.md").exists());
+    }
+
+    #[test]
+    fn test_rollback_of_skills_deployment_removes_skills() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let user_home = temp.path().join("home");
+        let agentsmd_home = temp.path().join("agentsmd");
+        std::env::set_var("HOME", &user_home);
+        std::env::set_var("AGENTSMD_HOME", &agentsmd_home);
+
+        common::write_command(
+            &agentsmd_home,
+            "review",
+            "---\nname: review\ndescription: Review the open pull request\n---\nReview it.\n",
+        );
+
+        let manager = DeploymentManager::new().unwrap();
+        let config = DeploymentConfig {
+            agent_id: "claude".to_string(),
+            custom_command_ids: vec!["review".to_string()],
+            emit_skills: true,
+            ..Default::default()
+        };
+        manager.deploy(&config).unwrap();
+        let skill = user_home.join(".claude").join("skills").join("review").join("SKILL.md");
+        assert!(skill.exists());
+
+        let plan = manager.preview_rollback("claude", None).unwrap();
+        assert!(plan.files_to_remove.contains(&skill.to_string_lossy().to_string()));
+
+        manager.rollback("claude", None).unwrap();
+        assert!(!skill.exists());
+    }
+}
+
+/// Nested command deployment tests
+///
+/// Deploys a command from a subfolder to Claude and rolls it back.
+#[cfg(test)]
+mod nested_command_tests {
+    use agentstoolkit_desktop::command_registry;
+    use agentstoolkit_desktop::deployment::{DeploymentConfig, DeploymentManager};
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_nested_command_deploys_into_subfolder() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let user_home = temp.path().join("home");
+        let agentsmd_home = temp.path().join("agentsmd");
+        std::env::set_var("HOME", &user_home);
+        std::env::set_var("AGENTSMD_HOME", &agentsmd_home);
+
+        common::write_command(&agentsmd_home, "git/status", "Show repository status.\n");
+
+        let command = command_registry::get_command_by_id("git/status").unwrap();
+        assert_eq!(command.subfolder.as_deref(), Some("git"));
+
+        let manager = DeploymentManager::new().unwrap();
+        let config = DeploymentConfig {
+            agent_id: "claude".to_string(),
+            custom_command_ids: vec!["git/status".to_string()],
+            ..Default::default()
+        };
+        let result = manager.deploy(&config).unwrap();
+
+        let commands_dir = user_home.join(".claude").join("commands");
+        let nested_dir = commands_dir.join("git");
+        let nested_command = nested_dir.join("status.md");
+        assert!(fs::read_to_string(&nested_command).unwrap().contains("Show repository status."));
+        assert_eq!(result.created_dirs, vec![nested_dir.to_string_lossy().to_string()]);
+
+        let history = manager.get_history("claude").unwrap();
+        assert_eq!(history.last().unwrap().dirs_created, result.created_dirs);
+
+        manager.rollback("claude", None).unwrap();
+        assert!(fs::symlink_metadata(&nested_command).is_err());
+        assert!(!nested_dir.exists());
+        assert!(commands_dir.exists());
+    }
+}
+
+/// Deployed command edit tests
+///
+/// Edits a deployed command by hand and checks a re-deploy leaves it alone.
+#[cfg(test)]
+mod command_edit_conflict_tests {
+    use agentstoolkit_desktop::deployment::{DeploymentConfig, DeploymentManager};
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_redeploy_preserves_edited_command() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let user_home = temp.path().join("home");
+        let agentsmd_home = temp.path().join("agentsmd");
+        std::env::set_var("HOME", &user_home);
+        std::env::set_var("AGENTSMD_HOME", &agentsmd_home);
+
+        common::write_command(&agentsmd_home, "review", "Review the open pull request.\n");
+
+        let manager = DeploymentManager::new().unwrap();
+        let mut config = DeploymentConfig {
+            agent_id: "claude".to_string(),
+            custom_command_ids: vec!["review".to_string()],
+            ..Default::default()
+        };
+        manager.deploy(&config).unwrap();
+
+        let deployed = user_home.join(".claude").join("commands").join("review.md");
+        let edited = "Review the open pull request, then run the linter.\n";
+        fs::write(&deployed, edited).unwrap();
+
+        let result = manager.deploy(&config).unwrap();
+        assert_eq!(fs::read_to_string(&deployed).unwrap(), edited);
+        assert!(result
+            .warnings
+            .iter()
+            .any(|w| w.contains("review.md") && w.contains("edited")));
+
+        // The edited file is tracked as preserved, so rolling back leaves it alone
+        let deployed_path = deployed.to_string_lossy().to_string();
+        let state = manager.get_history("claude").unwrap().pop().unwrap();
+        assert_eq!(state.files_preserved, vec![deployed_path.clone()]);
+        assert!(!state.files_created.contains(&deployed_path));
+
+        // A second deploy still remembers the edit
+        let result = manager.deploy(&config).unwrap();
+        assert_eq!(fs::read_to_string(&deployed).unwrap(), edited);
+        assert!(result.warnings.iter().any(|w| w.contains("review.md")));
+
+        config.force_overwrite = true;
+        manager.deploy(&config).unwrap();
+        assert!(!fs::read_to_string(&deployed).unwrap().contains("linter"));
+    }
+}
+
+/// Link strategy tests
+///
+/// Deploys Claude with each link strategy and checks the recorded method.
+#[cfg(test)]
+mod link_strategy_tests {
+    use agentstoolkit_desktop::deployment::{DeploymentConfig, DeploymentManager, StateManager};
+    use agentstoolkit_desktop::types::{LinkMethod, LinkStrategy};
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_recorded_method_follows_actual_placement() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let user_home = temp.path().join("home");
+        std::env::set_var("HOME", &user_home);
+        std::env::set_var("AGENTSMD_HOME", temp.path().join("agentsmd"));
+        let claude_md = user_home.join(".claude").join("CLAUDE.md");
+
+        let manager = DeploymentManager::new().unwrap();
+        let deploy = |strategy: LinkStrategy| {
+            let config = DeploymentConfig {
+                agent_id: "claude".to_string(),
+                link_strategy: strategy,
+                ..Default::default()
+            };
+            let result = manager.deploy(&config).unwrap();
+            let recorded = StateManager::new().unwrap().get_agent_state("claude").unwrap().unwrap();
+            assert_eq!(recorded.method, result.method);
+            result.method
+        };
+
+        assert_eq!(deploy(LinkStrategy::Auto), "symlink");
+        assert!(fs::symlink_metadata(&claude_md).unwrap().file_type().is_symlink());
+
+        // Copying over our own symlink, then linking over our own copy, needs no force
+        assert_eq!(deploy(LinkStrategy::ForceCopy), "copy");
+        assert!(!fs::symlink_metadata(&claude_md).unwrap().file_type().is_symlink());
+        assert_eq!(deploy(LinkStrategy::ForceCopy), "copy");
+        assert_eq!(deploy(LinkStrategy::Auto), "symlink");
+        assert!(fs::symlink_metadata(&claude_md).unwrap().file_type().is_symlink());
+    }
+
+    #[test]
+    fn test_user_copy_is_not_replaced_without_force() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let user_home = temp.path().join("home");
+        std::env::set_var("HOME", &user_home);
+        std::env::set_var("AGENTSMD_HOME", temp.path().join("agentsmd"));
+        let claude_md = user_home.join(".claude").join("CLAUDE.md");
+        fs::create_dir_all(claude_md.parent().unwrap()).unwrap();
+        fs::write(&claude_md, "my own rules").unwrap();
+
+        let manager = DeploymentManager::new().unwrap();
+        let config = DeploymentConfig {
+            agent_id: "claude".to_string(),
+            link_strategy: LinkStrategy::ForceCopy,
+            ..Default::default()
+        };
+        assert!(manager.deploy(&config).is_err());
+        assert_eq!(fs::read_to_string(&claude_md).unwrap(), "my own rules");
+    }
+
+    #[test]
+    fn test_deployment_method_reports_furthest_fallback() {
+        let methods = [LinkMethod::Symlink, LinkMethod::Hardlink, LinkMethod::Existing];
+        assert_eq!(LinkStrategy::Auto.deployment_method(&methods), "hardlink");
+        assert_eq!(
+            LinkStrategy::Auto.deployment_method(&[LinkMethod::Symlink, LinkMethod::Copy]),
+            "copy"
+        );
+        assert_eq!(LinkStrategy::Auto.deployment_method(&[LinkMethod::Existing]), "symlink");
+        assert_eq!(LinkStrategy::ForceCopy.deployment_method(&[]), "copy");
+    }
+}
+
+/// Budget error tests
+///
+/// Deploys an over-limit Copilot composition and checks the structured budget error.
+#[cfg(test)]
+mod budget_error_tests {
+    use agentstoolkit_desktop::deployment::{
+        DeploymentConfig, DeploymentError, DeploymentManager, TargetLevel,
+    };
+
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_over_budget_copilot_deploy_reports_numbers() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path().join("agentsmd");
+        let repo = temp.path().join("repo");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        std::env::set_var("AGENTSMD_HOME", &home);
+
+        common::write_huge_pack(&home);
+
+        let manager = DeploymentManager::new().unwrap();
+        let config = DeploymentConfig {
+            agent_id: "copilot".to_string(),
+            pack_ids: vec!["huge".to_string()],
+            target_level: TargetLevel::Project,
+            project_path: Some(repo.to_string_lossy().to_string()),
+            ..Default::default()
+        };
+
+        let expected = manager.preview_deployment(&config).unwrap().character_count;
+        match manager.deploy(&config) {
+            Err(DeploymentError::CharacterLimitExceeded { current, limit, agent_id, suggestion }) => {
+                assert_eq!(agent_id, "copilot");
+                assert_eq!(limit, 8000);
+                assert_eq!(current, expected);
+                assert!(current > limit);
+                assert!(suggestion.unwrap().contains("remove pack huge"));
+            }
+            other => panic!("expected CharacterLimitExceeded, got {:?}", other),
+        }
+        assert!(!repo.join(".github").join("copilot-instructions.md").exists());
+    }
+}
+
+/// Copilot compaction tests
+///
+/// Deploys Copilot instructions with compaction requested in the config.
+#[cfg(test)]
+mod copilot_compaction_tests {
+    use agentstoolkit_desktop::deployment::converters::{minify_markdown, CompactionStrategy};
+    use agentstoolkit_desktop::deployment::{DeploymentConfig, DeploymentManager, TargetLevel};
+
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_config_compaction_minifies_instructions() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path().join("agentsmd");
+        let repo = temp.path().join("repo");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        std::env::set_var("AGENTSMD_HOME", &home);
+
+        common::write_pack(&home, "core", "Keep    it   short.\n");
+
+        let manager = DeploymentManager::new().unwrap();
+        let mut config = DeploymentConfig {
+            agent_id: "copilot".to_string(),
+            pack_ids: vec!["core".to_string()],
+            target_level: TargetLevel::Project,
+            project_path: Some(repo.to_string_lossy().to_string()),
+            ..Default::default()
+        };
+        let generated = manager.preview_deployment(&config).unwrap().agents_md_content;
+
+        config.compaction = CompactionStrategy::Minify;
+        manager.deploy(&config).unwrap();
+
+        let deployed =
+            fs::read_to_string(repo.join(".github").join("copilot-instructions.md")).unwrap();
+        assert_eq!(deployed, minify_markdown(&generated));
+        assert!(deployed.len() < generated.len());
+    }
+}
+
+/// Target level support tests
+///
+/// Checks that project-level deployments are refused for user-level-only agents.
+#[cfg(test)]
+mod target_level_tests {
+    use agentstoolkit_desktop::deployment::{
+        DeploymentConfig, DeploymentError, DeploymentManager, TargetLevel,
+    };
+
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_project_level_deploy_to_codex_is_rejected() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        std::env::set_var("AGENTSMD_HOME", temp.path());
+
+        let manager = DeploymentManager::new().unwrap();
+        let config = DeploymentConfig {
+            agent_id: "codex".to_string(),
+            target_level: TargetLevel::Project,
+            project_path: Some(temp.path().to_string_lossy().to_string()),
+            ..Default::default()
+        };
+
+        let result = manager.deploy(&config);
+        match result {
+            Err(DeploymentError::UnsupportedTargetLevel { agent_id, level }) => {
+                assert_eq!(agent_id, "codex");
+                assert_eq!(level, "project");
+            }
+            other => panic!("expected UnsupportedTargetLevel, got {:?}", other),
+        }
+
+        assert!(matches!(
+            manager.validate_deployment(&config),
+            Err(DeploymentError::UnsupportedTargetLevel { .. })
+        ));
+    }
+}
+
+/// Deployable agent filtering tests
+///
+/// Asks which agents can take a project-level deployment and checks the exclusions.
+#[cfg(test)]
+mod deployable_agents_tests {
+    use agentstoolkit_desktop::deployment::{DeploymentConfig, DeploymentManager, TargetLevel};
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_project_level_excludes_codex() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        std::env::set_var("AGENTSMD_HOME", temp.path().join("agentsmd"));
+        std::env::set_var("HOME", temp.path());
+
+        let manager = DeploymentManager::new().unwrap();
+        let config = DeploymentConfig {
+            agent_id: String::new(),
+            target_level: TargetLevel::Project,
+            project_path: Some(temp.path().to_string_lossy().to_string()),
+            ..Default::default()
+        };
+
+        let agents = manager.deployable_agents_for(&config).unwrap();
+
+        let codex = agents.iter().find(|a| a.agent_id == "codex").unwrap();
+        assert!(!codex.deployable);
+        assert_eq!(codex.reason.as_deref(), Some("no project-level support"));
+
+        let copilot = agents.iter().find(|a| a.agent_id == "copilot").unwrap();
+        assert!(copilot.deployable);
+        assert!(copilot.reason.is_none());
+    }
+}
+
+/// Installed agent check tests
+///
+/// Checks that deployments requiring an installed agent fail when it is missing.
+#[cfg(test)]
+mod agent_installed_tests {
+    use agentstoolkit_desktop::deployment::{
+        DeploymentConfig, DeploymentError, DeploymentManager,
+    };
+
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_deploy_to_uninstalled_agent_is_refused() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path().join("home");
+        std::fs::create_dir_all(&home).unwrap();
+        std::env::set_var("HOME", &home);
+        std::env::set_var("AGENTSMD_HOME", home.join(".agentsmd"));
+
+        let manager = DeploymentManager::new().unwrap();
+        let config = DeploymentConfig {
+            agent_id: "cursor".to_string(),
+            require_agent_installed: true,
+            ..Default::default()
+        };
+
+        match manager.deploy(&config) {
+            Err(DeploymentError::ValidationFailed(message)) => {
+                assert!(message.contains("Agent 'cursor' is not installed"), "{}", message);
+            }
+            other => panic!("expected ValidationFailed, got {:?}", other),
+        }
+        assert!(!home.join(".cursor").exists());
+
+        let report = manager.validate_deployment(&config).unwrap();
+        assert!(!report.valid);
+    }
+}
+
+/// Deployment IPC error code tests
+///
+/// Checks that deployment commands report an unknown agent with a stable error code.
+#[cfg(test)]
+mod ipc_error_code_tests {
+    use agentstoolkit_desktop::deployment::{DeploymentConfig, ErrorCode};
+    use agentstoolkit_desktop::ipc;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_unknown_agent_reports_agent_not_found() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        std::env::set_var("AGENTSMD_HOME", temp.path().join(".agentsmd"));
+        std::env::set_var("HOME", temp.path());
+
+        let config = DeploymentConfig {
+            agent_id: "no-such-agent".to_string(),
+            verify_after_deploy: false,
+            ..Default::default()
+        };
+
+        // deploy_to_agent forwards to this with a progress emitter
+        let error = ipc::deploy_with_progress(&config, |_| {}).unwrap_err();
+        assert_eq!(error.code, ErrorCode::AgentNotFound);
+        assert!(error.message.contains("no-such-agent"), "{}", error.message);
+
+        let serialized = serde_json::to_value(&error).unwrap();
+        assert_eq!(serialized["code"], "agent_not_found");
+
+        let error = ipc::preview_deployment("no-such-agent".to_string(), config).unwrap_err();
+        assert_eq!(error.code, ErrorCode::AgentNotFound);
+    }
+}
+
+/// Home directory resolution tests
+///
+/// Checks that a missing home directory fails deployment instead of writing into the CWD.
+#[cfg(test)]
+mod home_dir_tests {
+    use std::path::PathBuf;
+    use agentstoolkit_desktop::deployment::agents::claude::ClaudeDeployer;
+    use agentstoolkit_desktop::deployment::{
+        AgentDeployer, DeploymentConfig, DeploymentError, DeploymentManager,
+    };
+    use agentstoolkit_desktop::{fs_manager, ipc};
+    use agentstoolkit_desktop::types::AgentDefinition;
+    use tempfile::TempDir;
+    use crate::common;
+
+    fn no_home() -> Option<PathBuf> {
+        None
+    }
+
+    #[test]
+    fn test_missing_home_is_an_error() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        std::env::set_var("AGENTSMD_HOME", temp.path().join(".agentsmd"));
+        std::env::set_current_dir(temp.path()).unwrap();
+        fs_manager::set_home_provider(no_home);
+
+        assert!(matches!(fs_manager::home_dir(), Err(DeploymentError::HomeDirUnavailable)));
+
+        let agent: AgentDefinition = serde_json::from_value(serde_json::json!({
+            "id": "claude",
+            "name": "Claude Code",
+            "configPaths": ["~/.claude/commands"],
+            "agentsMdSupport": "config",
+            "commandFormat": "slash",
+            "characterLimits": { "maxChars": 200000, "supportsOutReferences": true },
+            "deploymentStrategy": "symlink",
+            "buildOutput": "claude/commands",
+            "fileFormat": "markdown"
+        }))
+        .unwrap();
+        let config = DeploymentConfig {
+            agent_id: "claude".to_string(),
+            verify_after_deploy: false,
+            ..Default::default()
+        };
+
+        let deployer = ClaudeDeployer::new(agent);
+        assert!(matches!(deployer.prepare(&config), Err(DeploymentError::HomeDirUnavailable)));
+        assert!(matches!(deployer.get_status(), Err(DeploymentError::HomeDirUnavailable)));
+        assert!(!temp.path().join(".claude").exists());
+    }
+
+    #[test]
+    fn test_missing_agentsmd_home_is_an_error() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        std::env::remove_var("HOME");
+        std::env::remove_var("AGENTSMD_HOME");
+        std::env::set_current_dir(temp.path()).unwrap();
+        fs_manager::set_home_provider(no_home);
+
+        assert!(matches!(fs_manager::get_agentsmd_home(), Err(DeploymentError::HomeDirUnavailable)));
+        assert!(matches!(DeploymentManager::new(), Err(DeploymentError::HomeDirUnavailable)));
+        assert!(fs_manager::read_agents_md().is_err());
+        assert!(ipc::get_agentsmd_home().is_err());
+        assert!(!temp.path().join(".agentsmd").exists());
+    }
+}
+
+/// Agent rules filename tests
+///
+/// Checks that deployers target the rules filename declared in the agent definition.
+#[cfg(test)]
+mod rules_filename_tests {
+    use agentstoolkit_desktop::deployment::agents::claude::ClaudeDeployer;
+    use agentstoolkit_desktop::deployment::{AgentDeployer, DeploymentConfig};
+    use agentstoolkit_desktop::types::AgentDefinition;
+    use tempfile::TempDir;
+    use crate::common;
+
+    fn claude_agent(rules_filename: Option<&str>) -> AgentDefinition {
+        serde_json::from_value(serde_json::json!({
+            "id": "claude",
+            "name": "Claude Code",
+            "configPaths": ["~/.claude/commands"],
+            "agentsMdSupport": "config",
+            "commandFormat": "slash",
+            "characterLimits": { "maxChars": 200000, "supportsOutReferences": true },
+            "deploymentStrategy": "symlink",
+            "buildOutput": "claude/commands",
+            "fileFormat": "markdown",
+            "rulesFilename": rules_filename
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_prepare_targets_custom_rules_filename() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        std::env::set_var("HOME", temp.path());
+        std::env::set_var("AGENTSMD_HOME", temp.path().join(".agentsmd"));
+
+        let config = DeploymentConfig {
+            agent_id: "claude".to_string(),
+            verify_after_deploy: false,
+            ..Default::default()
+        };
+        let claude_dir = temp.path().join(".claude");
+
+        let custom = ClaudeDeployer::new(claude_agent(Some("CLAUDE.local.md")));
+        let prepared = custom.prepare(&config).unwrap();
+        assert!(prepared.target_paths.contains(&claude_dir.join("CLAUDE.local.md")));
+        assert!(!prepared.target_paths.contains(&claude_dir.join("CLAUDE.md")));
+
+        let default = ClaudeDeployer::new(claude_agent(None));
+        let prepared = default.prepare(&config).unwrap();
+        assert!(prepared.target_paths.contains(&claude_dir.join("CLAUDE.md")));
+    }
+}
+
+/// Antigravity workflow tests
+///
+/// Deploys a custom command in Antigravity mode and checks it lands as a global workflow.
+#[cfg(test)]
+mod antigravity_workflow_tests {
+    use agentstoolkit_desktop::deployment::{DeploymentConfig, DeploymentManager};
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_commands_linked_as_global_workflows() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path().join("agentsmd");
+        let user_home = temp.path().join("home");
+        std::env::set_var("HOME", &user_home);
+        std::env::set_var("AGENTSMD_HOME", &home);
+
+        common::write_command(&home, "ship", "Ship the current branch.\n\nRun the checks first.\n");
+
+        let manager = DeploymentManager::new().unwrap();
+        let config = DeploymentConfig {
+            agent_id: "antigravity".to_string(),
+            custom_command_ids: vec!["ship".to_string()],
+            verify_after_deploy: false,
+            ..Default::default()
+        };
+        let result = manager.deploy(&config).unwrap();
+
+        let workflow = user_home
+            .join(".gemini")
+            .join("antigravity")
+            .join("global_workflows")
+            .join("ship.md");
+        assert!(workflow.exists());
+        assert!(fs::read_to_string(&workflow).unwrap().starts_with("---\ndescription: "));
+        assert!(result
+            .deployed_files
+            .contains(&workflow.to_string_lossy().to_string()));
+        assert!(!user_home.join(".gemini").join("commands").join("ship.md").exists());
+    }
+}
+
+/// Cline config merge tests
+///
+/// Deploys over an existing Cline config and checks user keys are preserved.
+#[cfg(test)]
+mod cline_config_merge_tests {
+    use agentstoolkit_desktop::deployment::{DeploymentConfig, DeploymentManager};
+    use serde_json::Value;
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_deploy_preserves_user_config_keys() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let user_home = temp.path().join("home");
+        std::env::set_var("HOME", &user_home);
+        let home = temp.path().join("agentsmd");
+        std::env::set_var("AGENTSMD_HOME", &home);
+
+        common::write_command(&home, "ship", "Ship the current branch.\n");
+
+        let cline_dir = user_home.join(".cline");
+        fs::create_dir_all(&cline_dir).unwrap();
+        fs::write(
+            cline_dir.join("config.json"),
+            r#"{
+  "theme": "solarized",
+  "rules": "old rules",
+  "commands": [
+    { "name": "mine", "content": "User command" },
+    { "name": "ship", "content": "Old ship command" }
+  ]
+}"#,
+        )
+        .unwrap();
+
+        let manager = DeploymentManager::new().unwrap();
+        let config = DeploymentConfig {
+            agent_id: "cline".to_string(),
+            custom_command_ids: vec!["ship".to_string()],
+            verify_after_deploy: false,
+            ..Default::default()
+        };
+        manager.deploy(&config).unwrap();
+
+        let merged: Value =
+            serde_json::from_str(&fs::read_to_string(cline_dir.join("config.json")).unwrap()).unwrap();
+        assert_eq!(merged["theme"], "solarized");
+        assert_eq!(merged["agentsMdPath"], "~/.agentsmd/AGENTS.md");
+        assert_ne!(merged["rules"], "old rules");
+
+        let names: Vec<&str> = merged["commands"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|cmd| cmd["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["mine", "ship"]);
+        assert_ne!(merged["commands"][1]["content"], "Old ship command");
+    }
+
+    #[test]
+    fn test_rollback_restores_existing_config() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let user_home = temp.path().join("home");
+        std::env::set_var("HOME", &user_home);
+        std::env::set_var("AGENTSMD_HOME", temp.path().join("agentsmd"));
+
+        let config_path = user_home.join(".cline").join("config.json");
+        fs::create_dir_all(config_path.parent().unwrap()).unwrap();
+        let original = r#"{ "theme": "solarized" }"#;
+        fs::write(&config_path, original).unwrap();
+
+        let manager = DeploymentManager::new().unwrap();
+        let config = DeploymentConfig {
+            agent_id: "cline".to_string(),
+            ..Default::default()
+        };
+        let output = manager.deploy(&config).unwrap();
+        let config_file = config_path.to_string_lossy().to_string();
+        assert_eq!(output.modified_files, vec![config_file.clone()]);
+        assert!(!output.deployed_files.contains(&config_file));
+
+        let plan = manager.preview_rollback("cline", None).unwrap();
+        assert!(!plan.files_to_remove.contains(&config_file));
+        assert!(plan.files_to_restore.iter().any(|f| f.path == config_file));
+
+        manager.rollback("cline", None).unwrap();
+        assert_eq!(fs::read_to_string(&config_path).unwrap(), original);
+    }
+}
+
+/// Agent link tests
+///
+/// Links and unlinks an agent that declares more than one config path, and removes a
+/// config directory the toolkit filled with a copy.
+#[cfg(test)]
+mod agent_link_tests {
+    use agentstoolkit_desktop::deployment::state::StateManager;
+    use agentstoolkit_desktop::ipc;
+    use agentstoolkit_desktop::types::{AgentDefinition, LinkStrategy};
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_links_every_config_path() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        std::env::set_var("AGENTSMD_HOME", temp.path().join("agentsmd"));
+
+        let global_dir = temp.path().join("ide");
+        let workspace_dir = temp.path().join("workspace").join(".ide");
+        fs::create_dir_all(&global_dir).unwrap();
+        fs::create_dir_all(&workspace_dir).unwrap();
+        let global = global_dir.join("AGENTS.md");
+        let workspace = workspace_dir.join("rules.md");
+
+        let agent = AgentDefinition {
+            name: "Multi IDE".to_string(),
+            config_paths: vec![
+                global.to_string_lossy().to_string(),
+                workspace.to_string_lossy().to_string(),
+            ],
+            deployment_strategy: "symlink".to_string(),
+            ..common::agent("multi-ide")
+        };
+
+        let results = ipc::link_agent_config_paths(&agent, false, LinkStrategy::Auto).unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.error.is_none() && r.method.is_some()));
+        assert!(fs::symlink_metadata(&global).is_ok());
+        assert!(fs::symlink_metadata(&workspace).is_ok());
+
+        ipc::unlink_agent_config_paths(&agent).unwrap();
+        assert!(fs::symlink_metadata(&global).is_err());
+        assert!(fs::symlink_metadata(&workspace).is_err());
+    }
+
+    #[test]
+    fn test_unlink_removes_copied_directory_link() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        std::env::set_var("AGENTSMD_HOME", temp.path().join("agentsmd"));
+
+        let rules_dir = temp.path().join("ide").join("rules");
+        let agent = AgentDefinition {
+            name: "Copy IDE".to_string(),
+            config_paths: vec![rules_dir.to_string_lossy().to_string()],
+            ..common::agent("copy-ide")
+        };
+
+        let results = ipc::link_agent_config_paths(&agent, false, LinkStrategy::ForceCopy).unwrap();
+        assert_eq!(results[0].method.as_deref(), Some("copy"), "{:?}", results[0].error);
+        assert!(rules_dir.is_dir() && !rules_dir.is_symlink());
+        let state = StateManager::new().unwrap();
+        assert_eq!(state.get_link_copies("copy-ide").unwrap(), vec![results[0].path.clone()]);
+
+        // The copy is a real directory, but the toolkit made it
+        ipc::unlink_agent_config_paths(&agent).unwrap();
+        assert!(!rules_dir.exists());
+        assert!(state.get_link_copies("copy-ide").unwrap().is_empty());
+
+        // A real directory the toolkit didn't create is still refused
+        fs::create_dir_all(&rules_dir).unwrap();
+        fs::write(rules_dir.join("mine.md"), "user rules").unwrap();
+        assert!(ipc::unlink_agent_config_paths(&agent).is_err());
+        assert!(rules_dir.join("mine.md").exists());
+    }
+}
+
+/// Agent config directory inspection tests
+///
+/// Symlinks an agent's config directory to a "synced" location and inspects it.
+#[cfg(all(test, unix))]
+mod agent_dir_symlink_tests {
+    use agentstoolkit_desktop::fs_manager;
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_inspect_agent_dir_reports_symlink_target() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path().join("home");
+        let synced = temp.path().join("synced").join("cursor");
+        fs::create_dir_all(&home).unwrap();
+        fs::create_dir_all(&synced).unwrap();
+        std::env::set_var("HOME", &home);
+        std::env::set_var("AGENTSMD_HOME", home.join(".agentsmd"));
+
+        std::os::unix::fs::symlink(&synced, home.join(".cursor")).unwrap();
+
+        let info = fs_manager::inspect_agent_dir("cursor").unwrap();
+        assert!(info.exists);
+        assert!(info.is_symlink);
+        assert_eq!(info.path, home.join(".cursor").to_string_lossy());
+        assert_eq!(
+            info.resolved_target.as_deref(),
+            Some(fs::canonicalize(&synced).unwrap().to_string_lossy().as_ref())
+        );
+
+        // A plain directory is not flagged
+        fs::create_dir_all(home.join(".claude")).unwrap();
+        let plain = fs_manager::inspect_agent_dir("claude").unwrap();
+        assert!(plain.exists);
+        assert!(!plain.is_symlink);
+        assert!(plain.resolved_target.is_none());
+    }
+}
+
+/// Agent definition validation tests
+///
+/// Checks duplicate config paths and command formats reported by validate_agent.
+#[cfg(test)]
+mod agent_validation_tests {
+    use agentstoolkit_desktop::ipc;
+    use agentstoolkit_desktop::types::AgentDefinition;
+
+    fn agent(config_paths: &[&str], command_format: &str) -> AgentDefinition {
+        serde_json::from_value(serde_json::json!({
+            "id": "custom",
+            "name": "Custom Agent",
+            "configPaths": config_paths,
+            "agentsMdSupport": "native",
+            "commandFormat": command_format,
+            "characterLimits": { "maxChars": 10000, "supportsOutReferences": true },
+            "deploymentStrategy": "symlink",
+            "buildOutput": "custom",
+            "fileFormat": "markdown"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_duplicate_config_paths_rejected() {
+        let result = ipc::validate_agent(agent(&["~/.custom/rules.md", "~/.custom/rules.md"], "slash"));
+        assert!(!result.valid);
+        assert_eq!(result.errors, vec!["Duplicate config path: ~/.custom/rules.md"]);
+
+        let result = ipc::validate_agent(agent(&["~/.custom/rules.md", "~/.custom/other.md"], "slash"));
+        assert!(result.valid);
+        assert!(result.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_command_format_warns() {
+        let result = ipc::validate_agent(agent(&["~/.custom/rules.md"], "macro"));
+        assert!(result.valid);
+        assert_eq!(result.warnings.len(), 1);
+        assert!(result.warnings[0].contains("'macro'"));
+    }
+}
+
+/// Toolkit diagnostics tests
+///
+/// Seeds an AGENTSMD_HOME with broken content and checks the aggregated report.
+#[cfg(test)]
+mod diagnostics_tests {
+    use agentstoolkit_desktop::diagnostics;
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_full_report_aggregates_all_problems() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path();
+        std::env::set_var("AGENTSMD_HOME", home);
+
+        // Out-reference tracked in metadata but missing on disk
+        let out_refs_dir = home.join("out-references");
+        fs::create_dir_all(&out_refs_dir).unwrap();
+        common::write_out_reference_metadata(
+            home,
+            &[common::out_reference("missing-template", "Missing Template", "templates/missing-template.md")],
+        );
+
+        // Pack listing a file that does not exist
+        common::PackFixture::new("broken-pack")
+            .with_name("Broken Pack")
+            .with_description("Pack with a missing file")
+            .without_rules()
+            .with_missing_file("missing.md")
+            .write(home);
+
+        // Command pointing at a script that was never installed
+        common::write_command(
+            home,
+            "ship",
+            "Ship the current branch.\n\nRun: `python3 ~/.agentsmd/scripts/ship.py`\n",
+        );
+
+        let report = diagnostics::full_report().unwrap();
+
+        assert!(!report.healthy);
+        assert!(report
+            .broken_out_references
+            .iter()
+            .any(|link| link.source_id == "missing-template"));
+        assert_eq!(report.pack_error_count, 1);
+        assert_eq!(report.pack_errors[0].pack_id, "broken-pack");
+        assert_eq!(report.pack_errors[0].file.as_deref(), Some("missing.md"));
+        assert_eq!(report.missing_script_count, 1);
+        assert_eq!(report.missing_scripts[0].command_id, "ship");
+        assert_eq!(report.missing_scripts[0].script_path, "~/.agentsmd/scripts/ship.py");
+    }
+}
+
+/// Project marker tests
+///
+/// Detects a project root from configured markers in settings.json.
+#[cfg(test)]
+mod project_markers_tests {
+    use agentstoolkit_desktop::deployment::project::ProjectDetector;
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_custom_marker_detected_only_when_configured() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let agentsmd_home = temp.path().join("agentsmd");
+        fs::create_dir_all(&agentsmd_home).unwrap();
+        std::env::set_var("AGENTSMD_HOME", &agentsmd_home);
+
+        let project = temp.path().join("service");
+        let nested = project.join("src").join("handlers");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(project.join("pyproject.toml"), "[project]\nname = \"service\"\n").unwrap();
+
+        let settings = agentsmd_home.join("settings.json");
+        fs::write(&settings, r#"{ "projectMarkers": ["package.json"] }"#).unwrap();
+        assert_eq!(ProjectDetector::detect_project_root_from(&nested), None);
+        assert!(!ProjectDetector::is_valid_project_root(&project));
+
+        fs::write(&settings, r#"{ "projectMarkers": ["package.json", "pyproject.toml"] }"#).unwrap();
+        assert_eq!(ProjectDetector::detect_project_root_from(&nested), Some(project.clone()));
+        assert!(ProjectDetector::is_valid_project_root(&project));
+
+        // Without settings the defaults apply, which include pyproject.toml
+        fs::remove_file(&settings).unwrap();
+        assert_eq!(ProjectDetector::detect_project_root_from(&nested), Some(project));
+
+        // A Makefile marks a valid root, but isn't walked up to by default
+        let tool = temp.path().join("tool");
+        let tool_src = tool.join("src");
+        fs::create_dir_all(&tool_src).unwrap();
+        fs::write(tool.join("Makefile"), "all:\n").unwrap();
+        assert!(ProjectDetector::is_valid_project_root(&tool));
+        assert_eq!(ProjectDetector::detect_project_root_from(&tool_src), None);
+    }
+}
@@ -1,5 +1,7 @@
 //! Tests for out-reference management system
 
+mod common;
+
 #[cfg(test)]
 mod tests {
     use std::fs;
@@ -120,3 +122,954 @@ mod tests {
         // This would test export/import functionality
     }
 }
+
+/// Out-reference cleanup tests
+///
+/// Suggests an unlinked reference and an untracked file for cleanup, then deletes them.
+#[cfg(test)]
+mod cleanup_tests {
+    use agentstoolkit_desktop::out_reference_manager;
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_unlinked_reference_is_suggested_and_deleted() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        std::env::set_var("AGENTSMD_HOME", temp.path());
+
+        let created = out_reference_manager::create_out_reference(
+            "Old Template".to_string(),
+            "No longer used".to_string(),
+            "templates".to_string(),
+            "# Old template\n".to_string(),
+            "markdown".to_string(),
+            Vec::new(),
+        )
+        .unwrap();
+        let orphan = out_reference_manager::get_out_references_dir()
+            .unwrap()
+            .join("examples")
+            .join("stray.md");
+        fs::create_dir_all(orphan.parent().unwrap()).unwrap();
+        fs::write(&orphan, "stray").unwrap();
+
+        let suggestions = out_reference_manager::suggest_cleanup().unwrap();
+        let ids: Vec<&str> = suggestions.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["examples/stray.md", created.id.as_str()]);
+        assert!(suggestions[1].reason.contains("not linked"));
+
+        assert!(out_reference_manager::cleanup_unused(&["templates/missing.md".to_string()]).is_err());
+
+        let deleted = out_reference_manager::cleanup_unused(std::slice::from_ref(&created.id)).unwrap();
+        assert_eq!(deleted, vec![created.id.clone()]);
+        assert!(out_reference_manager::get_out_reference(created.id).is_err());
+        assert!(orphan.exists());
+
+        let remaining = out_reference_manager::suggest_cleanup().unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, "examples/stray.md");
+    }
+}
+
+/// Command out-reference import tests
+///
+/// Imports a doc a command links to but the out-references store doesn't track yet.
+#[cfg(test)]
+mod command_import_tests {
+    use agentstoolkit_desktop::{command_registry, out_reference_manager};
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_import_untracked_command_reference() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path();
+        std::env::set_var("AGENTSMD_HOME", home);
+
+        common::write_command(
+            home,
+            "review",
+            "Review the change using the [guide](docs/review-guide.md).\n",
+        );
+        fs::create_dir_all(home.join("docs")).unwrap();
+        fs::write(home.join("docs").join("review-guide.md"), "# Review guide\n").unwrap();
+        command_registry::clear_cache();
+
+        let report = out_reference_manager::validate_out_references().unwrap();
+        assert!(report.broken_links.iter().any(|l| l.target_path == "docs/review-guide.md"));
+
+        let imported =
+            out_reference_manager::create_from_command_reference("review", "docs/review-guide.md").unwrap();
+        assert_eq!(imported.file_path, "templates/docs/review-guide.md");
+        assert_eq!(imported.name, "review-guide");
+        assert_eq!(
+            fs::read_to_string(out_reference_manager::get_out_references_dir().unwrap().join(&imported.file_path))
+                .unwrap(),
+            "# Review guide\n"
+        );
+
+        assert_eq!(out_reference_manager::get_by_path("docs/review-guide.md").unwrap().id, imported.id);
+        let report = out_reference_manager::validate_out_references().unwrap();
+        assert!(!report.broken_links.iter().any(|l| l.target_path == "docs/review-guide.md"));
+
+        // A second import is rejected, as is a path the command never references
+        assert!(out_reference_manager::create_from_command_reference("review", "docs/review-guide.md").is_err());
+        assert!(out_reference_manager::create_from_command_reference("review", "docs/other.md").is_err());
+    }
+}
+
+/// Out-reference path containment tests
+///
+/// Checks that imports can't write outside the out-references directory.
+#[cfg(test)]
+mod containment_tests {
+    use agentstoolkit_desktop::out_reference_manager;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_import_with_traversal_path_is_rejected() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path().join("agentsmd");
+        std::env::set_var("AGENTSMD_HOME", &home);
+
+        let template = out_reference_manager::create_out_reference(
+            "PR Template".to_string(),
+            String::new(),
+            "templates".to_string(),
+            "# PR Template\n".to_string(),
+            "markdown".to_string(),
+            Vec::new(),
+        )
+        .unwrap();
+        let bundle = out_reference_manager::export_out_references(vec![template.id]).unwrap();
+
+        let mut entries: serde_json::Value = serde_json::from_str(&bundle).unwrap();
+        entries[0][0]["filePath"] = serde_json::json!("../../evil.sh");
+        entries[0][1] = serde_json::json!("#!/bin/sh\necho pwned\n");
+
+        let err = out_reference_manager::import_out_references(entries.to_string()).unwrap_err();
+        assert!(err.contains("escapes the out-references directory"), "{}", err);
+        assert!(!temp.path().join("evil.sh").exists());
+        assert!(!home.join("evil.sh").exists());
+        assert_eq!(out_reference_manager::list_out_references().unwrap().len(), 1);
+    }
+}
+
+/// Inlined out-reference cycle tests
+///
+/// Checks that references linking to each other are inlined once with one warning per cycle.
+#[cfg(test)]
+mod cycle_tests {
+    use std::path::PathBuf;
+    use agentstoolkit_desktop::deployment::{render_inlined_out_references, ResolvedOutReference};
+
+    fn reference(file_path: &str, content: &str) -> ResolvedOutReference {
+        ResolvedOutReference {
+            file_path: file_path.to_string(),
+            source_path: PathBuf::from("/refs").join(file_path),
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_mutually_linked_references_inlined_once() {
+        let resolved = vec![
+            reference("style.md", "# Style\n\nSee [naming](naming.md#rules)."),
+            reference("naming.md", "# Naming\n\nFollows the [style guide](./style.md)."),
+            reference("glossary.md", "# Glossary\n\nLinks to [style](style.md) only."),
+        ];
+
+        let (section, warnings) = render_inlined_out_references(&resolved);
+
+        assert_eq!(section.matches("### style.md").count(), 1);
+        assert_eq!(section.matches("### naming.md").count(), 1);
+        assert_eq!(warnings.len(), 1, "{:?}", warnings);
+        assert!(warnings[0].contains("between style.md, naming.md;"), "{:?}", warnings);
+    }
+
+    #[test]
+    fn test_densely_linked_references_reported_once() {
+        // Every reference links to every other: exponentially many simple cycles, one group
+        let names: Vec<String> = (0..16).map(|i| format!("templates/ref-{:02}.md", i)).collect();
+        let resolved: Vec<ResolvedOutReference> = names
+            .iter()
+            .map(|name| {
+                let links: String = names.iter().map(|other| format!("[x]({})\n", other)).collect();
+                reference(name, &links)
+            })
+            .collect();
+
+        let (_, warnings) = render_inlined_out_references(&resolved);
+
+        assert_eq!(warnings.len(), 1, "{:?}", warnings);
+        assert!(warnings[0].contains(&names.join(", ")), "{:?}", warnings);
+    }
+}
+
+/// Out-reference format tests
+///
+/// Round-trips a TOML out-reference through creation and a metadata rebuild.
+#[cfg(test)]
+mod format_tests {
+    use agentstoolkit_desktop::out_reference_manager;
+    use agentstoolkit_desktop::types::FileFormat;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_toml_out_reference_round_trip() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        std::env::set_var("AGENTSMD_HOME", temp.path());
+
+        let content = "[lint]\nstrict = true\n";
+        let created = out_reference_manager::create_out_reference(
+            "Lint Config".to_string(),
+            "Shared lint settings".to_string(),
+            "schemas".to_string(),
+            content.to_string(),
+            "toml".to_string(),
+            Vec::new(),
+        )
+        .unwrap();
+
+        assert_eq!(created.format, FileFormat::Toml);
+        assert_eq!(created.file_path, "schemas/lint-config.toml");
+        assert_eq!(
+            out_reference_manager::read_out_reference_content(created.id.clone()).unwrap(),
+            content
+        );
+
+        // Rebuilding the index re-detects the format from the extension
+        out_reference_manager::update_metadata_index().unwrap();
+        let rebuilt = out_reference_manager::get_by_path("schemas/lint-config.toml").unwrap();
+        assert_eq!(rebuilt.format, FileFormat::Toml);
+    }
+}
+
+/// Out-reference format validation tests
+///
+/// Reports JSON out-references whose content no longer parses.
+#[cfg(test)]
+mod format_validation_tests {
+    use agentstoolkit_desktop::out_reference_manager;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_invalid_json_reference_reports_format_error() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        std::env::set_var("AGENTSMD_HOME", temp.path());
+
+        let created = out_reference_manager::create_out_reference(
+            "Lint Schema".to_string(),
+            "Lint rule schema".to_string(),
+            "schemas".to_string(),
+            "{\"strict\": true}".to_string(),
+            "json".to_string(),
+            Vec::new(),
+        )
+        .unwrap();
+        assert!(out_reference_manager::validate_format(created.id.clone()).unwrap().valid);
+        assert!(out_reference_manager::validate_out_references().unwrap().format_errors.is_empty());
+
+        out_reference_manager::write_out_reference_content(
+            created.id.clone(),
+            "{\"strict\": true,}".to_string(),
+        )
+        .unwrap();
+
+        let validation = out_reference_manager::validate_format(created.id.clone()).unwrap();
+        assert!(!validation.valid);
+        assert!(validation.error.unwrap().contains("trailing comma"));
+
+        let report = out_reference_manager::validate_out_references().unwrap();
+        assert!(!report.valid);
+        assert_eq!(report.format_errors.len(), 1);
+        assert_eq!(report.format_errors[0].id, created.id);
+        assert_eq!(report.format_errors[0].file_path, "schemas/lint-schema.json");
+    }
+}
+
+/// Out-reference path lookup tests
+///
+/// Resolves a linked path to a tracked out-reference and lists what links to it.
+#[cfg(test)]
+mod path_tests {
+    use agentstoolkit_desktop::out_reference_manager;
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_find_references_to_path_lists_linking_commands() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path();
+        std::env::set_var("AGENTSMD_HOME", home);
+
+        let out_refs_dir = home.join("out-references");
+        fs::create_dir_all(out_refs_dir.join("templates")).unwrap();
+        fs::write(out_refs_dir.join("templates").join("pr.md"), "# PR\n").unwrap();
+        common::write_out_reference_metadata(
+            home,
+            &[common::out_reference("pr", "PR Template", "templates/pr.md")],
+        );
+
+        common::write_command(
+            home,
+            "pr",
+            "Open a pull request.\n\nFill in the [PR template](templates/pr.md).\n",
+        );
+        common::write_command(home, "status", "Show the status.\n");
+
+        let links = out_reference_manager::find_references_to_path("templates/pr.md".to_string()).unwrap();
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].link_type, "command");
+        assert_eq!(links[0].id, "pr");
+
+        let err = out_reference_manager::find_references_to_path("templates/missing.md".to_string())
+            .unwrap_err();
+        assert!(err.contains("templates/missing.md"));
+    }
+}
+
+/// Out-reference preview tests
+///
+/// Lists the out-references a command and pack would deploy without deploying them,
+/// and flags paths that could mean more than one reference.
+#[cfg(test)]
+mod preview_tests {
+    use agentstoolkit_desktop::deployment::{collect_out_references_for_selection, preview_out_references};
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_preview_dedupes_shared_references() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path().join("agentsmd");
+        std::env::set_var("HOME", temp.path().join("home"));
+        std::env::set_var("AGENTSMD_HOME", &home);
+
+        let out_refs_dir = home.join("out-references");
+        fs::create_dir_all(out_refs_dir.join("templates")).unwrap();
+        common::write_out_reference_metadata(
+            &home,
+            &[
+                common::out_reference("pr-template", "pr-template", "templates/pr-template.md"),
+                common::out_reference("checklist", "checklist", "templates/checklist.md"),
+            ],
+        );
+        fs::write(out_refs_dir.join("templates/pr-template.md"), "# PR\n\nDescribe it.\n").unwrap();
+        fs::write(out_refs_dir.join("templates/checklist.md"), "- [ ] Tests\n").unwrap();
+
+        common::write_command(
+            &home,
+            "ship",
+            "Ship it.\n\nFill in [the template](templates/pr-template.md).\n",
+        );
+
+        common::PackFixture::new("review")
+            .with_name("Review")
+            .with_out_references(&["templates/pr-template.md", "templates/checklist.md"])
+            .with_rules("# Review\n")
+            .write(&home);
+
+        let preview = preview_out_references(&["ship".to_string()], &["review".to_string()]).unwrap();
+        let listed: Vec<(&str, u64)> = preview
+            .iter()
+            .map(|p| (p.file_path.as_str(), p.char_count))
+            .collect();
+        assert_eq!(
+            listed,
+            vec![("templates/checklist.md", 12), ("templates/pr-template.md", 19)]
+        );
+        assert_eq!(preview[0].source_path, out_refs_dir.join("templates/checklist.md"));
+        assert!(!temp.path().join("home").exists());
+
+        let missing = preview_out_references(&[], &["review".to_string(), "nope".to_string()]);
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn test_preview_lists_ambiguous_matches() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path().join("agentsmd");
+        std::env::set_var("AGENTSMD_HOME", &home);
+
+        let out_refs_dir = home.join("out-references");
+        fs::create_dir_all(out_refs_dir.join("templates")).unwrap();
+        fs::create_dir_all(out_refs_dir.join("release")).unwrap();
+        common::write_out_reference_metadata(
+            &home,
+            &[
+                common::out_reference("checklist", "checklist", "templates/checklist.md"),
+                common::out_reference("release-checklist", "release-checklist", "release/checklist.md"),
+                common::out_reference("xpr", "xpr", "templates/xpr.md"),
+            ],
+        );
+        fs::write(out_refs_dir.join("templates/checklist.md"), "- [ ] Tests\n").unwrap();
+        fs::write(out_refs_dir.join("release/checklist.md"), "- [ ] Tag\n").unwrap();
+        fs::write(out_refs_dir.join("templates/xpr.md"), "# XPR\n").unwrap();
+
+        common::PackFixture::new("vague")
+            .with_name("Vague")
+            .with_out_references(&["checklist.md"])
+            .with_rules("# Vague\n")
+            .write(&home);
+        let preview = preview_out_references(&[], &["vague".to_string()]).unwrap();
+        assert_eq!(preview.len(), 1);
+        assert_eq!(preview[0].file_path, "templates/checklist.md");
+        assert_eq!(preview[0].ambiguous_matches, vec!["release/checklist.md".to_string()]);
+
+        // The deployment still resolves the path, to the same reference
+        let resolved = collect_out_references_for_selection(&[], &["vague".to_string()]).unwrap();
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].file_path, "templates/checklist.md");
+
+        // Matching is by whole path segments, not substrings
+        common::PackFixture::new("partial")
+            .with_name("Partial")
+            .with_out_references(&["pr.md"])
+            .with_rules("# Partial\n")
+            .write(&home);
+        let error = preview_out_references(&[], &["partial".to_string()]).unwrap_err().to_string();
+        assert!(error.contains("not found"), "{}", error);
+    }
+}
+
+/// Orphaned out-reference purge tests
+///
+/// Drops an untracked file into the out-references directory and purges it, and refuses
+/// to purge when metadata.json can't be trusted.
+#[cfg(test)]
+mod purge_tests {
+    use agentstoolkit_desktop::out_reference_manager;
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_purge_removes_only_untracked_files() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path().join("agentsmd");
+        std::env::set_var("AGENTSMD_HOME", &home);
+
+        let tracked = out_reference_manager::create_out_reference(
+            "PR Template".to_string(),
+            String::new(),
+            "templates".to_string(),
+            "# PR Template\n".to_string(),
+            "markdown".to_string(),
+            Vec::new(),
+        )
+        .unwrap();
+        let out_refs_dir = home.join("out-references");
+        let orphan = out_refs_dir.join("examples").join("stray.md");
+        fs::write(&orphan, "# Left behind\n").unwrap();
+
+        let preview = out_reference_manager::preview_orphan_purge().unwrap();
+        assert_eq!(preview, vec!["examples/stray.md"]);
+        assert!(orphan.exists());
+
+        let removed = out_reference_manager::purge_orphans().unwrap();
+        assert_eq!(removed, vec!["examples/stray.md"]);
+        assert!(!orphan.exists());
+        assert!(out_refs_dir.join(&tracked.file_path).exists());
+        assert!(out_reference_manager::validate_out_references()
+            .unwrap()
+            .orphaned_files
+            .is_empty());
+    }
+
+    #[test]
+    fn test_purge_refuses_without_metadata() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path().join("agentsmd");
+        std::env::set_var("AGENTSMD_HOME", &home);
+
+        let tracked = out_reference_manager::create_out_reference(
+            "PR Template".to_string(),
+            String::new(),
+            "templates".to_string(),
+            "# PR Template\n".to_string(),
+            "markdown".to_string(),
+            Vec::new(),
+        )
+        .unwrap();
+        let out_refs_dir = home.join("out-references");
+        fs::remove_file(out_refs_dir.join("metadata.json")).unwrap();
+
+        let error = out_reference_manager::purge_orphans().unwrap_err();
+        assert!(error.contains("metadata.json"), "{}", error);
+        assert!(out_refs_dir.join(&tracked.file_path).exists());
+
+        // A rebuilt index leaves the corrupt original behind, and purging stays refused
+        fs::write(out_refs_dir.join("metadata.json"), "{ not json").unwrap();
+        assert!(out_reference_manager::purge_orphans().is_err());
+        assert!(out_refs_dir.join(&tracked.file_path).exists());
+    }
+}
+
+/// Out-reference metadata recovery tests
+///
+/// Corrupts metadata.json and checks the index is rebuilt from the filesystem.
+#[cfg(test)]
+mod recovery_tests {
+    use agentstoolkit_desktop::out_reference_manager;
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_corrupt_metadata_is_rebuilt() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path().join("agentsmd");
+        std::env::set_var("AGENTSMD_HOME", &home);
+
+        let out_refs_dir = home.join("out-references");
+        fs::create_dir_all(out_refs_dir.join("templates")).unwrap();
+        fs::create_dir_all(out_refs_dir.join("schemas")).unwrap();
+        fs::write(out_refs_dir.join("templates").join("pr-template.md"), "# PR Template\n").unwrap();
+        fs::write(out_refs_dir.join("schemas").join("config.json"), "{}\n").unwrap();
+        fs::write(out_refs_dir.join("metadata.json"), "{ not json at all").unwrap();
+
+        let mut paths: Vec<String> = out_reference_manager::list_out_references()
+            .unwrap()
+            .into_iter()
+            .map(|r| r.file_path)
+            .collect();
+        paths.sort();
+        assert_eq!(paths, vec!["schemas/config.json", "templates/pr-template.md"]);
+
+        let backup = out_refs_dir.join("metadata.json.bak");
+        assert_eq!(fs::read_to_string(backup).unwrap(), "{ not json at all");
+        assert!(serde_json::from_str::<serde_json::Value>(
+            &fs::read_to_string(out_refs_dir.join("metadata.json")).unwrap()
+        )
+        .is_ok());
+    }
+}
+
+/// Out-reference rename tests
+///
+/// Renames a reference that a pack override points at and checks the override follows it.
+#[cfg(test)]
+mod rename_tests {
+    use agentstoolkit_desktop::{command_registry, fs_manager, out_reference_manager};
+    use std::collections::HashMap;
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_rename_rewrites_pack_overrides() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path();
+        std::env::set_var("AGENTSMD_HOME", home);
+        fs::create_dir_all(home.join("rule-packs")).unwrap();
+
+        let out_ref = out_reference_manager::create_out_reference(
+            "old".to_string(),
+            "PR template".to_string(),
+            "templates".to_string(),
+            "# PR\n".to_string(),
+            "markdown".to_string(),
+            Vec::new(),
+        )
+        .unwrap();
+        assert_eq!(out_ref.file_path, "templates/old.md");
+
+        let mut overrides = HashMap::new();
+        overrides.insert(
+            "github-hygiene".to_string(),
+            vec!["templates/old.md".to_string(), "templates/other.md".to_string()],
+        );
+        overrides.insert("testing".to_string(), vec!["out-references/templates/old.md".to_string()]);
+        fs_manager::write_pack_out_ref_overrides(&overrides).unwrap();
+
+        let renamed = out_reference_manager::rename_out_reference(out_ref.id, "new.md".to_string()).unwrap();
+        assert_eq!(renamed.file_path, "templates/new.md");
+
+        let out_dir = out_reference_manager::get_out_references_dir().unwrap();
+        assert!(!out_dir.join("templates/old.md").exists());
+        assert_eq!(fs::read_to_string(out_dir.join("templates/new.md")).unwrap(), "# PR\n");
+
+        let overrides = fs_manager::read_pack_out_ref_overrides().unwrap();
+        assert_eq!(
+            overrides["github-hygiene"],
+            vec!["templates/new.md".to_string(), "templates/other.md".to_string()]
+        );
+        assert_eq!(overrides["testing"], vec!["out-references/templates/new.md".to_string()]);
+
+        // Nothing left pointing at the old path
+        assert!(fs_manager::migrate_pack_out_ref_overrides("templates/old.md", "templates/x.md")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_rename_rewrites_pack_json_and_command_references() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path();
+        std::env::set_var("AGENTSMD_HOME", home);
+
+        let out_ref = out_reference_manager::create_out_reference(
+            "old".to_string(),
+            "PR template".to_string(),
+            "templates".to_string(),
+            "# PR\n".to_string(),
+            "markdown".to_string(),
+            Vec::new(),
+        )
+        .unwrap();
+
+        common::PackFixture::new("github-hygiene")
+            .with_out_references(&["templates/old.md", "templates/other.md"])
+            .write(home);
+        common::PackFixture::new("testing").write(home);
+        let command = common::write_command(
+            home,
+            "pr",
+            "# PR\n\nFill in the [template](../out-references/templates/old.md) and the [other](../out-references/templates/older.md).\n",
+        );
+        common::write_command(home, "status", "# Status\n\nShow status.\n");
+        command_registry::update_command_out_references("status", vec!["templates/old.md".to_string()])
+            .unwrap();
+
+        out_reference_manager::rename_out_reference(out_ref.id, "new.md".to_string()).unwrap();
+
+        let pack = fs_manager::load_pack("github-hygiene").unwrap();
+        assert_eq!(pack.out_references, vec!["templates/new.md", "templates/other.md"]);
+        assert!(fs_manager::load_pack("testing").unwrap().out_references.is_empty());
+
+        let content = fs::read_to_string(&command).unwrap();
+        assert!(content.contains("[template](../out-references/templates/new.md)"));
+        assert!(content.contains("[other](../out-references/templates/older.md)"));
+
+        let status = command_registry::get_command_by_id("status").unwrap();
+        assert_eq!(status.out_references, vec!["templates/new.md"]);
+    }
+}
+
+/// Out-reference deployment result tests
+///
+/// Deploys out-references to Claude, one of which has to fall back to a copy, and
+/// checks that one which can't be placed fails the deployment.
+#[cfg(test)]
+mod results_tests {
+    use agentstoolkit_desktop::deployment::agents::claude::ClaudeDeployer;
+    use agentstoolkit_desktop::deployment::{AgentDeployer, DeploymentConfig, DeploymentManager};
+    use agentstoolkit_desktop::fs_manager;
+    use agentstoolkit_desktop::types::LinkMethod;
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_results_report_each_out_reference() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let user_home = temp.path().join("home");
+        let home = temp.path().join("agentsmd");
+        std::env::set_var("HOME", &user_home);
+        std::env::set_var("AGENTSMD_HOME", &home);
+
+        let out_refs_dir = home.join("out-references");
+        fs::create_dir_all(out_refs_dir.join("templates")).unwrap();
+        common::write_out_reference_metadata(
+            &home,
+            &[
+                common::out_reference("pr-template", "pr-template", "templates/pr-template.md"),
+                common::out_reference("checklist", "checklist", "templates/checklist.md"),
+                common::out_reference("notes", "notes", "templates/notes.md"),
+            ],
+        );
+        fs::write(out_refs_dir.join("templates/pr-template.md"), "# PR\n\nDescribe it.\n").unwrap();
+        fs::write(out_refs_dir.join("templates/checklist.md"), "- [ ] Tests\n").unwrap();
+        fs::write(out_refs_dir.join("templates/notes.md"), "Notes\n").unwrap();
+
+        common::PackFixture::new("review")
+            .with_name("Review")
+            .with_out_references(&["templates/pr-template.md", "templates/checklist.md", "templates/notes.md"])
+            .with_rules("# Review\n")
+            .write(&home);
+
+        // A dangling link left at one destination makes symlinking fail, so it is copied
+        let references_dir = user_home.join(".claude").join("references").join("templates");
+        fs::create_dir_all(&references_dir).unwrap();
+        let stale_target = temp.path().join("stale");
+        fs::create_dir_all(&stale_target).unwrap();
+        std::os::unix::fs::symlink(stale_target.join("checklist.md"), references_dir.join("checklist.md"))
+            .unwrap();
+
+        let agent = fs_manager::load_agent_registry()
+            .unwrap()
+            .into_iter()
+            .find(|a| a.id == "claude")
+            .unwrap();
+        let deployer = ClaudeDeployer::new(agent);
+        let config = DeploymentConfig {
+            agent_id: "claude".to_string(),
+            pack_ids: vec!["review".to_string()],
+            ..Default::default()
+        };
+        let prepared = deployer.prepare(&config).unwrap();
+        let output = deployer.deploy(prepared, &config, &mut |_, _| {}).unwrap();
+
+        let mut results = output.out_reference_results.clone();
+        results.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+        assert_eq!(results.len(), 3);
+
+        assert_eq!(results[0].file_path, "templates/checklist.md");
+        assert_eq!(results[0].status, LinkMethod::Copy);
+        let warning = results[0].warning.as_deref().unwrap();
+        assert!(warning.contains("Copied files instead of creating link"));
+        assert!(output.warnings.iter().any(|w| w == warning));
+
+        assert_eq!(results[1].file_path, "templates/notes.md");
+        assert_eq!(results[1].status, LinkMethod::Symlink);
+
+        assert_eq!(results[2].file_path, "templates/pr-template.md");
+        assert_eq!(results[2].status, LinkMethod::Symlink);
+        assert!(results[2].warning.is_none());
+    }
+
+    #[test]
+    fn test_unplaceable_out_reference_fails_the_deploy() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let user_home = temp.path().join("home");
+        let home = temp.path().join("agentsmd");
+        std::env::set_var("HOME", &user_home);
+        std::env::set_var("AGENTSMD_HOME", &home);
+
+        let out_refs_dir = home.join("out-references");
+        fs::create_dir_all(out_refs_dir.join("templates")).unwrap();
+        common::write_out_reference_metadata(
+            &home,
+            &[common::out_reference("notes", "notes", "templates/notes.md")],
+        );
+        fs::write(out_refs_dir.join("templates/notes.md"), "Notes\n").unwrap();
+        common::PackFixture::new("review")
+            .with_name("Review")
+            .with_out_references(&["templates/notes.md"])
+            .with_rules("# Review\n")
+            .write(&home);
+
+        // A file the user put there themselves is left alone, and the deploy fails
+        let references_dir = user_home.join(".claude").join("references").join("templates");
+        fs::create_dir_all(&references_dir).unwrap();
+        fs::write(references_dir.join("notes.md"), "My own notes\n").unwrap();
+
+        let manager = DeploymentManager::new().unwrap();
+        let config = DeploymentConfig {
+            agent_id: "claude".to_string(),
+            pack_ids: vec!["review".to_string()],
+            ..Default::default()
+        };
+        let error = manager.deploy(&config).unwrap_err().to_string();
+        assert!(error.contains("templates/notes.md"), "{}", error);
+        assert_eq!(fs::read_to_string(references_dir.join("notes.md")).unwrap(), "My own notes\n");
+        assert!(manager.get_history("claude").unwrap().is_empty());
+    }
+}
+
+
+/// Out-reference resync tests
+///
+/// Simulates a copy-fallback deployment and checks that resync refreshes the copy.
+#[cfg(test)]
+mod resync_tests {
+    use agentstoolkit_desktop::deployment::{self, DeploymentState, StateManager, TargetLevel};
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_resync_refreshes_copied_out_references() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path().join("agentsmd");
+        std::env::set_var("AGENTSMD_HOME", &home);
+
+        let out_refs_dir = home.join("out-references");
+        fs::create_dir_all(out_refs_dir.join("templates")).unwrap();
+        common::write_out_reference_metadata(
+            &home,
+            &[common::out_reference("pr-template", "PR Template", "templates/pr-template.md")],
+        );
+        let source = out_refs_dir.join("templates").join("pr-template.md");
+        fs::write(&source, "# PR Template\n\nv1\n").unwrap();
+
+        // Copy deployment as produced on systems without symlink support
+        let deployed = temp
+            .path()
+            .join(".cursor")
+            .join("out-references")
+            .join("templates")
+            .join("pr-template.md");
+        fs::create_dir_all(deployed.parent().unwrap()).unwrap();
+        fs::copy(&source, &deployed).unwrap();
+
+        let state = DeploymentState::new("cursor".to_string(), "copy".to_string(), TargetLevel::User)
+            .with_files(vec![deployed.to_string_lossy().to_string()]);
+        StateManager::new().unwrap().record_deployment(state).unwrap();
+
+        fs::write(&source, "# PR Template\n\nv2\n").unwrap();
+
+        let resynced = deployment::resync_out_references("cursor").unwrap();
+        assert_eq!(resynced, vec![deployed.to_string_lossy().to_string()]);
+        assert_eq!(fs::read_to_string(&deployed).unwrap(), "# PR Template\n\nv2\n");
+
+        // Already in sync: nothing to do
+        assert!(deployment::resync_out_references("cursor").unwrap().is_empty());
+    }
+}
+
+/// Out-reference zip export tests
+///
+/// Exports references to a zip archive and imports them into a fresh home.
+#[cfg(test)]
+mod zip_tests {
+    use agentstoolkit_desktop::out_reference_manager;
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_zip_export_import_round_trip() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        std::env::set_var("AGENTSMD_HOME", temp.path().join("source"));
+
+        let template = out_reference_manager::create_out_reference(
+            "PR Template".to_string(),
+            "Pull request body".to_string(),
+            "templates".to_string(),
+            "# PR Template\n\n## Summary\n".to_string(),
+            "markdown".to_string(),
+            vec!["git".to_string()],
+        )
+        .unwrap();
+        let schema = out_reference_manager::create_out_reference(
+            "Config Schema".to_string(),
+            "Settings schema".to_string(),
+            "schemas".to_string(),
+            "{\"type\": \"object\"}\n".to_string(),
+            "json".to_string(),
+            Vec::new(),
+        )
+        .unwrap();
+
+        let archive = temp.path().join("refs.zip");
+        out_reference_manager::export_out_references_zip(
+            vec![template.id.clone(), schema.id.clone()],
+            &archive,
+        )
+        .unwrap();
+
+        let fresh_home = temp.path().join("fresh");
+        std::env::set_var("AGENTSMD_HOME", &fresh_home);
+        let imported = out_reference_manager::import_out_references_zip(&archive).unwrap();
+        assert_eq!(imported.len(), 2);
+
+        for original in [&template, &schema] {
+            let copy = imported.iter().find(|r| r.name == original.name).unwrap();
+            assert_ne!(copy.id, original.id);
+            assert_eq!(copy.file_path, original.file_path);
+            assert_eq!(copy.description, original.description);
+            assert_eq!(copy.format, original.format);
+            assert_eq!(copy.tags, original.tags);
+        }
+
+        let out_refs_dir = fresh_home.join("out-references");
+        assert_eq!(
+            fs::read_to_string(out_refs_dir.join(&template.file_path)).unwrap(),
+            "# PR Template\n\n## Summary\n"
+        );
+        assert_eq!(
+            fs::read_to_string(out_refs_dir.join(&schema.file_path)).unwrap(),
+            "{\"type\": \"object\"}\n"
+        );
+
+        let listed = out_reference_manager::list_out_references().unwrap();
+        assert_eq!(listed.len(), 2);
+    }
+}
+
+/// Inlined out-reference tests
+///
+/// Deploys a command with an out-reference to an agent without out-reference support.
+#[cfg(test)]
+mod inline_out_reference_tests {
+    use agentstoolkit_desktop::deployment::{DeploymentConfig, DeploymentManager};
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_out_references_inlined_for_codex() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path().join("agentsmd");
+        std::env::set_var("HOME", temp.path().join("home"));
+        std::env::set_var("AGENTSMD_HOME", &home);
+
+        let out_refs_dir = home.join("out-references");
+        fs::create_dir_all(out_refs_dir.join("templates")).unwrap();
+        common::write_out_reference_metadata(
+            &home,
+            &[common::out_reference("pr-template", "PR Template", "templates/pr-template.md")],
+        );
+        fs::write(
+            out_refs_dir.join("templates").join("pr-template.md"),
+            "# PR Template\n\nDescribe the change.\n",
+        )
+        .unwrap();
+
+        common::write_command(
+            &home,
+            "ship",
+            "Ship the current branch.\n\nFill in [the template](templates/pr-template.md).\n",
+        );
+
+        let manager = DeploymentManager::new().unwrap();
+        let mut config = DeploymentConfig {
+            agent_id: "codex".to_string(),
+            custom_command_ids: vec!["ship".to_string()],
+            ..Default::default()
+        };
+
+        // Without inlining, codex can't take the out-reference
+        assert!(!manager.validate_deployment(&config).unwrap().valid);
+
+        config.inline_out_references = true;
+        let result = manager.deploy(&config).unwrap();
+
+        let agents_md = fs::read_to_string(home.join("AGENTS.md")).unwrap();
+        assert!(agents_md.contains("## Inlined References"));
+        assert!(agents_md.contains("### templates/pr-template.md\n\n# PR Template\n\nDescribe the change."));
+
+        assert!(result
+            .deployed_files
+            .iter()
+            .all(|file| !file.contains("pr-template")));
+    }
+}
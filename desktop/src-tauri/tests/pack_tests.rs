@@ -0,0 +1,912 @@
+//! Rule pack tests
+//!
+//! Exercises pack commands against a temporary AGENTSMD_HOME.
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use agentstoolkit_desktop::ipc;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_unresolved_out_ref_override_warns_in_composition() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path();
+        std::env::set_var("AGENTSMD_HOME", home);
+
+        common::PackFixture::new("core")
+            .with_name("Core")
+            .with_description("Core rules")
+            .with_rules("# Core\n\nAlways test.\n")
+            .write(home);
+
+        let pack = ipc::update_pack_out_references(
+            "core".to_string(),
+            vec!["templates/does-not-exist.md".to_string()],
+        )
+        .unwrap();
+        assert_eq!(pack.out_references, vec!["templates/does-not-exist.md"]);
+
+        let result = ipc::validate_composition(vec!["core".to_string()], None).unwrap();
+        assert!(result.valid);
+        assert!(result
+            .warnings
+            .iter()
+            .any(|w| w.contains("[core]") && w.contains("templates/does-not-exist.md")));
+    }
+}
+
+/// Pack file lint tests
+///
+/// Validates a pack that lists an empty file alongside one with content.
+#[cfg(test)]
+mod file_lint_tests {
+    use agentstoolkit_desktop::ipc;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_empty_listed_file_is_warned() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path();
+        std::env::set_var("AGENTSMD_HOME", home);
+
+        common::PackFixture::new("testing")
+            .with_name("Testing")
+            .with_rules("# Testing\n\nWrite tests first.\n")
+            .with_file("extra.md", "\n  \n")
+            .write(home);
+
+        let result = ipc::validate_pack("testing".to_string()).unwrap();
+
+        assert!(result.valid);
+        assert_eq!(result.warnings.len(), 1);
+        assert!(result.warnings[0].message.contains("Empty file"));
+        assert_eq!(result.warnings[0].file.as_deref(), Some("extra.md"));
+
+        let statuses: Vec<(&str, bool, bool)> = result
+            .files
+            .iter()
+            .map(|f| (f.file.as_str(), f.exists, f.non_empty))
+            .collect();
+        assert_eq!(statuses, vec![("rules.md", true, true), ("extra.md", true, false)]);
+    }
+}
+
+/// Pack fit tests
+///
+/// Fits candidate packs sharing a dependency into Copilot's limit.
+#[cfg(test)]
+mod fit_tests {
+    use agentstoolkit_desktop::budget;
+    use std::path::Path;
+    use tempfile::TempDir;
+    use crate::common;
+
+    fn write_pack(home: &Path, id: &str, dependencies: &[&str], chars: usize) {
+        common::PackFixture::new(id)
+            .with_dependencies(dependencies)
+            .with_rules("x".repeat(chars))
+            .write(home);
+    }
+
+    #[test]
+    fn test_fit_packs_stops_at_overflowing_pack() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path();
+        std::env::set_var("AGENTSMD_HOME", home);
+
+        // Copilot allows 8000 chars; core is shared and only counted once
+        write_pack(home, "core", &[], 2000);
+        write_pack(home, "github-hygiene", &["core"], 3000);
+        write_pack(home, "testing", &["core"], 2000);
+        write_pack(home, "azure-devops", &["core"], 2500);
+        write_pack(home, "docs", &[], 100);
+
+        let candidates: Vec<String> = ["github-hygiene", "testing", "azure-devops", "docs"]
+            .iter()
+            .map(|id| id.to_string())
+            .collect();
+        let fit = budget::fit_packs(&candidates, "copilot").unwrap();
+
+        assert_eq!(fit.fitting, vec!["github-hygiene", "testing"]);
+        assert_eq!(fit.overflow_at.as_deref(), Some("azure-devops"));
+
+        let fit = budget::fit_packs(&candidates, "claude").unwrap();
+        assert_eq!(fit.fitting.len(), 4);
+        assert!(fit.overflow_at.is_none());
+    }
+}
+
+/// Pack selection resolution tests
+///
+/// Checks that a selection resolves to its dependencies with provenance.
+#[cfg(test)]
+mod selection_tests {
+    use agentstoolkit_desktop::ipc;
+    use agentstoolkit_desktop::types::ResolvedPack;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_selection_includes_dependencies_with_provenance() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path();
+        std::env::set_var("AGENTSMD_HOME", home);
+
+        common::PackFixture::new("b").write(home);
+        common::PackFixture::new("a").with_dependencies(&["b"]).write(home);
+
+        let resolved = ipc::resolve_selection(vec!["a".to_string()]).unwrap();
+        assert_eq!(
+            resolved,
+            vec![
+                ResolvedPack {
+                    pack_id: "b".to_string(),
+                    selected: false,
+                    pulled_in_by: Some("a".to_string()),
+                },
+                ResolvedPack {
+                    pack_id: "a".to_string(),
+                    selected: true,
+                    pulled_in_by: None,
+                },
+            ]
+        );
+
+        let resolved = ipc::resolve_selection(vec!["a".to_string(), "b".to_string()]).unwrap();
+        assert_eq!(resolved.len(), 2);
+        assert!(resolved.iter().all(|p| p.selected && p.pulled_in_by.is_none()));
+    }
+}
+
+/// Pack dependency version tests
+///
+/// Checks versioned and bare pack dependencies during validation and resolution.
+#[cfg(test)]
+mod version_tests {
+    use agentstoolkit_desktop::ipc;
+    use serde_json::json;
+    use std::path::Path;
+    use tempfile::TempDir;
+    use crate::common;
+
+    fn write_pack(home: &Path, id: &str, version: &str, dependencies: serde_json::Value) {
+        common::PackFixture::new(id)
+            .with_version(version)
+            .with_dependencies_json(dependencies)
+            .write(home);
+    }
+
+    #[test]
+    fn test_unsatisfied_version_requirement_is_reported() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path();
+        std::env::set_var("AGENTSMD_HOME", home);
+
+        write_pack(home, "core", "1.0", json!([]));
+        write_pack(home, "legacy", "1.0.0", json!(["core"]));
+        write_pack(home, "modern", "1.0.0", json!([{ "id": "core", "versionReq": ">=2.0" }]));
+        write_pack(home, "compatible", "1.0.0", json!([{ "id": "core", "versionReq": "^1" }]));
+
+        let result = ipc::validate_pack("modern".to_string()).unwrap();
+        assert!(!result.valid);
+        assert_eq!(
+            result.errors[0].message,
+            "Pack modern requires core >=2.0, but version 1.0 is installed"
+        );
+        let resolution = ipc::resolve_dependencies("modern".to_string()).unwrap();
+        assert!(!resolution.success);
+
+        for pack_id in ["legacy", "compatible"] {
+            assert!(ipc::validate_pack(pack_id.to_string()).unwrap().valid, "{}", pack_id);
+            let resolution = ipc::resolve_dependencies(pack_id.to_string()).unwrap();
+            assert_eq!(resolution.order, vec!["core", pack_id]);
+        }
+
+        let legacy = ipc::load_pack("legacy".to_string()).unwrap();
+        assert_eq!(serde_json::to_value(&legacy.dependencies).unwrap(), json!(["core"]));
+    }
+}
+
+/// Duplicate pack id tests
+///
+/// Seeds two pack directories declaring the same id and checks the diagnostic.
+#[cfg(test)]
+mod duplicate_pack_tests {
+    use agentstoolkit_desktop::{diagnostics, packs};
+    use tempfile::TempDir;
+    use crate::common;
+
+    fn write_pack(home: &std::path::Path, dir: &str, id: &str) {
+        common::PackFixture::new(id)
+            .in_dir(dir)
+            .with_name(&format!("Pack in {}", dir))
+            .write(home);
+    }
+
+    #[test]
+    fn test_duplicate_pack_ids_are_reported() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path();
+        std::env::set_var("AGENTSMD_HOME", home);
+
+        write_pack(home, "core", "core");
+        write_pack(home, "core-fork", "core");
+        write_pack(home, "github-hygiene", "github-hygiene");
+
+        let duplicates = packs::find_duplicate_ids().unwrap();
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].pack_id, "core");
+        assert_eq!(duplicates[0].directories, vec!["core", "core-fork"]);
+
+        let report = diagnostics::full_report().unwrap();
+        assert!(!report.healthy);
+        assert_eq!(report.duplicate_pack_ids, duplicates);
+    }
+}
+
+/// Parallel composition validation tests
+///
+/// Validates a large pack selection and compares it with validating each pack in turn.
+#[cfg(test)]
+mod parallel_composition_validation_tests {
+    use agentstoolkit_desktop::ipc;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_validate_composition_matches_sequential_order() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path().join("agentsmd");
+        std::env::set_var("AGENTSMD_HOME", &home);
+
+        let mut pack_ids = Vec::new();
+        for i in 0..40 {
+            let id = format!("pack-{:02}", i);
+            // Every third pack has an empty file, every fifth a missing file and a missing dependency
+            let content = if i % 3 == 0 { "" } else { "# Rules\n\nBe precise.\n" };
+            let mut pack = common::PackFixture::new(&id)
+                .with_name(&format!("Pack {}", i))
+                .with_description("Generated pack")
+                .with_rules(content);
+            if i % 5 == 0 {
+                pack = pack
+                    .with_missing_file("missing.md")
+                    .with_dependencies(&[&format!("absent-{}", i)]);
+            }
+            pack.write(&home);
+            pack_ids.push(id);
+        }
+        // Validate in a non-sorted order; results still come back sorted by pack id
+        pack_ids.reverse();
+        let mut sorted_ids = pack_ids.clone();
+        sorted_ids.sort();
+
+        let mut expected_errors = Vec::new();
+        let mut expected_warnings = Vec::new();
+        for id in &sorted_ids {
+            let validation = ipc::validate_pack(id.clone()).unwrap();
+            expected_errors.extend(validation.errors.iter().map(|e| format!("[{}] {}", e.pack_id, e.message)));
+            expected_warnings.extend(validation.warnings.iter().map(|w| format!("[{}] {}", w.pack_id, w.message)));
+        }
+        assert_eq!(expected_errors.len(), 16);
+        assert_eq!(expected_warnings.len(), 14);
+
+        for _ in 0..5 {
+            let result = ipc::validate_composition(pack_ids.clone(), None).unwrap();
+            assert!(!result.valid);
+            assert_eq!(result.errors, expected_errors);
+            assert_eq!(result.warnings, expected_warnings);
+        }
+        assert!(expected_errors.first().unwrap().starts_with("[pack-00]"));
+        assert!(expected_errors.last().unwrap().starts_with("[pack-35]"));
+    }
+}
+
+/// Rule pack schema tests
+///
+/// Validates shipped and hand-written `pack.json` documents against the exported schema.
+#[cfg(test)]
+mod schema_tests {
+    use agentstoolkit_desktop::types::rulepack_schema;
+    use jsonschema::JSONSchema;
+    use serde_json::json;
+
+    #[test]
+    fn test_rulepack_schema_requires_core_fields() {
+        let schema = rulepack_schema();
+        let required: Vec<&str> = schema["required"]
+            .as_array()
+            .expect("schema should list required fields")
+            .iter()
+            .filter_map(|v| v.as_str())
+            .collect();
+
+        for field in ["id", "name", "version", "files"] {
+            assert!(required.contains(&field), "{} should be required", field);
+        }
+        assert!(!required.contains(&"outReferences"));
+    }
+
+    #[test]
+    fn test_rulepack_schema_validates_packs() {
+        let schema = JSONSchema::compile(&rulepack_schema()).unwrap();
+
+        let core: serde_json::Value =
+            serde_json::from_str(include_str!("../../../rule-packs/core/pack.json")).unwrap();
+        assert!(schema.is_valid(&core));
+
+        let missing_files = json!({
+            "id": "broken",
+            "name": "Broken",
+            "version": "1.0.0",
+            "description": "",
+            "dependencies": [],
+            "targetAgents": [],
+            "metadata": { "wordCount": 0, "characterCount": 0, "category": "universal", "tags": [] }
+        });
+        assert!(!schema.is_valid(&missing_files));
+    }
+}
+
+/// File size guard tests
+///
+/// Refuses to load pack and out-reference files above the configured size cap.
+#[cfg(test)]
+mod file_size_guard_tests {
+    use agentstoolkit_desktop::fs_manager::{self, FsError};
+    use agentstoolkit_desktop::{ipc, out_reference_manager};
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_oversized_files_are_not_loaded() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path();
+        std::env::set_var("AGENTSMD_HOME", home);
+        assert!(ipc::set_max_file_size(0).is_err());
+        ipc::set_max_file_size(1024).unwrap();
+        assert_eq!(ipc::get_max_file_size(), 1024);
+
+        common::write_pack(home, "huge", &"# Rules\n".repeat(512));
+
+        match fs_manager::read_pack_content("huge".to_string()) {
+            Err(FsError::TooLarge { size, limit, .. }) => {
+                assert_eq!(size, 4096);
+                assert_eq!(limit, 1024);
+            }
+            other => panic!("expected a size error, got {:?}", other.map(|c| c.len())),
+        }
+
+        let error = ipc::load_pack_file("huge".to_string(), "rules.md".to_string()).unwrap_err();
+        assert!(error.contains("File too large"), "{}", error);
+        let validation = ipc::validate_pack("huge".to_string()).unwrap();
+        assert!(!validation.valid);
+        assert!(validation.errors[0].message.contains("File too large"));
+        assert!(validation.warnings.is_empty());
+
+        let out_ref = out_reference_manager::create_out_reference(
+            "Big Example".to_string(),
+            "Large example".to_string(),
+            "examples".to_string(),
+            "x".repeat(2048),
+            "text".to_string(),
+            Vec::new(),
+        )
+        .unwrap();
+        let error = out_reference_manager::read_out_reference_content(out_ref.id).unwrap_err();
+        assert!(error.contains("File too large"), "{}", error);
+
+        fs_manager::set_max_file_size(fs_manager::DEFAULT_MAX_FILE_SIZE);
+        assert!(fs_manager::read_pack_content("huge".to_string()).is_ok());
+    }
+}
+
+/// Budget estimate tests
+///
+/// Builds a small pack graph with a shared dependency and checks marginal costs.
+#[cfg(test)]
+mod budget_tests {
+    use agentstoolkit_desktop::{budget, ipc};
+    use std::path::Path;
+    use tempfile::TempDir;
+    use crate::common;
+
+    fn write_pack(home: &Path, id: &str, dependencies: &[&str], content: &str) {
+        common::PackFixture::new(id)
+            .with_dependencies(dependencies)
+            .with_rules(content)
+            .write(home);
+    }
+
+    #[test]
+    fn test_marginal_cost_skips_shared_dependencies() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path();
+        std::env::set_var("AGENTSMD_HOME", home);
+
+        write_pack(home, "core", &[], "# Core\n\nShared fundamentals for every pack.\n");
+        write_pack(home, "github-hygiene", &["core"], "# GitHub\n\nOpen an issue first.\n");
+        write_pack(home, "azure-devops", &["core"], "# Azure\n\nLink work items.\n");
+
+        let current = vec!["github-hygiene".to_string()];
+        let cost = budget::marginal_cost(&current, "azure-devops", Some("copilot".to_string())).unwrap();
+
+        let azure_content = "# Azure\n\nLink work items.\n";
+        assert_eq!(cost.added_packs, vec!["azure-devops"]);
+        assert_eq!(cost.added_chars, azure_content.len() as u64);
+        assert_eq!(cost.added_words, azure_content.split_whitespace().count() as u64);
+
+        let before = ipc::calculate_budget(current, None).unwrap();
+        assert_eq!(cost.resulting_chars, before.total_chars + cost.added_chars);
+        assert!(cost.resulting_percentage.is_some());
+        assert!(cost.within_limit);
+
+        // With nothing selected, the candidate brings its dependency along
+        let cost = budget::marginal_cost(&[], "azure-devops", None).unwrap();
+        assert_eq!(cost.added_packs, vec!["core", "azure-devops"]);
+        assert_eq!(cost.resulting_percentage, None);
+    }
+}
+
+/// Full budget tests
+///
+/// Breaks a deployment with packs, a command, and out-references into budget sections.
+#[cfg(test)]
+mod full_budget_tests {
+    use agentstoolkit_desktop::budget::{self, BudgetShare};
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    fn section_chars(section: &[BudgetShare]) -> u64 {
+        section.iter().map(|item| item.chars).sum()
+    }
+
+    #[test]
+    fn test_sections_sum_to_total() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path();
+        std::env::set_var("AGENTSMD_HOME", home);
+
+        let out_refs_dir = home.join("out-references");
+        fs::create_dir_all(out_refs_dir.join("templates")).unwrap();
+        common::write_out_reference_metadata(
+            home,
+            &[
+                common::out_reference("pr-template", "pr-template", "templates/pr-template.md"),
+                common::out_reference("checklist", "checklist", "templates/checklist.md"),
+            ],
+        );
+        fs::write(out_refs_dir.join("templates/pr-template.md"), "# PR\n\nDescribe it.\n").unwrap();
+        fs::write(out_refs_dir.join("templates/checklist.md"), "- [ ] Tests\n").unwrap();
+
+        common::write_pack(home, "core", "# Core\n\nShared fundamentals.\n");
+        common::PackFixture::new("review")
+            .with_dependencies(&["core"])
+            .with_out_references(&["templates/checklist.md"])
+            .with_rules("# Review\n\nCheck the list.\n")
+            .write(home);
+        common::write_command(home, "ship", "Ship it.\n\nFill in [the template](templates/pr-template.md).\n");
+
+        let full = budget::full_budget(
+            &["review".to_string()],
+            &["ship".to_string()],
+            Some("copilot".to_string()),
+        )
+        .unwrap();
+
+        let pack_ids: Vec<&str> = full.packs.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(pack_ids, vec!["core", "review"]);
+        assert_eq!(full.commands.len(), 1);
+        assert_eq!(full.commands[0].id, "ship");
+        let out_ref_paths: Vec<&str> = full.out_references.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(out_ref_paths, vec!["templates/checklist.md", "templates/pr-template.md"]);
+        assert_eq!(full.out_references[0].chars, 12);
+
+        assert!(section_chars(&full.packs) > 0);
+        assert!(section_chars(&full.commands) > 0);
+        assert_eq!(
+            section_chars(&full.packs) + section_chars(&full.commands) + section_chars(&full.out_references),
+            full.total_chars
+        );
+        assert_eq!(full.max_chars, Some(8000));
+        assert!(full.within_limit);
+    }
+}
+
+/// Cross-agent budget tests
+///
+/// Checks one composition of packs and commands against small, large and built-in agent limits.
+#[cfg(test)]
+mod agent_budget_report_tests {
+    use agentstoolkit_desktop::budget;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_report_flags_small_limit_agent_only() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path();
+        std::env::set_var("AGENTSMD_HOME", home);
+
+        common::PackFixture::new("testing")
+            .with_name("Testing")
+            .with_rules("Write the test first.\n".repeat(250))
+            .write(home);
+        common::write_command(
+            home,
+            "review",
+            &format!("Review the current diff.\n\n{}", "Check each hunk.\n".repeat(200)),
+        );
+
+        let report = budget::report_for_agents(
+            &["testing".to_string()],
+            &["review".to_string()],
+            &["copilot".to_string(), "cursor".to_string(), "codex".to_string()],
+        )
+        .unwrap();
+
+        let copilot = &report["copilot"];
+        let cursor = &report["cursor"];
+        assert!(copilot.total_chars > 8000);
+        assert_eq!(copilot.total_chars, cursor.total_chars);
+        assert_eq!(copilot.max_chars, Some(8000));
+        assert!(!copilot.within_limit);
+        assert!(cursor.within_limit);
+
+        // Codex has no limit in the registry but is validated against the built-in one
+        let codex = &report["codex"];
+        assert_eq!(codex.max_chars, budget::agent_char_limit("codex"));
+        assert_eq!(codex.max_chars, Some(50_000));
+        assert!(codex.within_limit);
+    }
+}
+
+/// AGENTS.md export tests
+///
+/// Exports a composition to a file outside any agent's config.
+#[cfg(test)]
+mod agents_md_export_tests {
+    use agentstoolkit_desktop::ipc;
+    use agentstoolkit_desktop::types::AgentsMdOptions;
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_export_writes_generated_content() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path().join("agentsmd");
+        std::env::set_var("AGENTSMD_HOME", &home);
+
+        common::PackFixture::new("core")
+            .with_name("Core")
+            .with_description("Core rules")
+            .with_rules("# Core\n\nKeep changes small.\n")
+            .write(&home);
+
+        let pack_ids = vec!["core".to_string()];
+        let dest = temp.path().join("repo").join("AGENTS.md");
+        fs::create_dir_all(dest.parent().unwrap()).unwrap();
+
+        let budget = ipc::export_agents_md(
+            pack_ids.clone(),
+            dest.to_string_lossy().to_string(),
+            Some(true),
+            Some("copilot".to_string()),
+        )
+        .unwrap();
+
+        let options = AgentsMdOptions {
+            inline_content: true,
+            ..Default::default()
+        };
+        let generated = ipc::generate_agents_md(pack_ids.clone(), Some(options)).unwrap();
+        let written = fs::read_to_string(&dest).unwrap();
+        assert_eq!(written, generated.content);
+        assert_eq!(budget.total_chars, generated.budget.total_chars);
+        assert_eq!(budget.max_chars, Some(8000));
+        assert!(budget.within_limit);
+        assert_eq!(fs::read_dir(dest.parent().unwrap()).unwrap().count(), 1);
+
+        // Unknown agents are rejected before anything is written
+        let other = dest.with_file_name("OTHER.md");
+        let error = ipc::export_agents_md(
+            pack_ids.clone(),
+            other.to_string_lossy().to_string(),
+            None,
+            Some("no-such-agent".to_string()),
+        )
+        .unwrap_err();
+        assert!(error.contains("no-such-agent"), "{}", error);
+        assert!(!other.exists());
+
+        // Missing directories and directory targets are rejected
+        let missing = temp.path().join("nope").join("AGENTS.md");
+        assert!(ipc::export_agents_md(pack_ids.clone(), missing.to_string_lossy().to_string(), None, None).is_err());
+        assert!(ipc::export_agents_md(pack_ids, temp.path().to_string_lossy().to_string(), None, None).is_err());
+    }
+}
+
+/// AGENTS.md header tests
+///
+/// Generates AGENTS.md with a custom title, version, and reference link.
+#[cfg(test)]
+mod agents_md_header_tests {
+    use agentstoolkit_desktop::ipc;
+    use agentstoolkit_desktop::types::{AgentsMdHeader, AgentsMdOptions};
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_custom_header_replaces_defaults() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        std::env::set_var("AGENTSMD_HOME", temp.path());
+
+        let header = AgentsMdHeader {
+            title: "Acme Engineering Rules".to_string(),
+            version: "3.1.0".to_string(),
+            reference_link: "handbook/COMMANDS.md".to_string(),
+        };
+        let generated =
+            ipc::generate_agents_md(Vec::new(), Some(AgentsMdOptions { header, ..Default::default() })).unwrap();
+        assert!(generated.success);
+        assert!(generated.content.starts_with("# Acme Engineering Rules\n"));
+        assert!(generated.content.contains("**Version:** 3.1.0 (Modular Rule Packs)"));
+        assert!(generated.content.contains("[COMMANDS.md](handbook/COMMANDS.md)"));
+        assert!(!generated.content.contains("2.0.0"));
+
+        let default = ipc::generate_agents_md(Vec::new(), None).unwrap();
+        assert!(default
+            .content
+            .starts_with("# AGENTS.md — Mandatory Agent Behavior & Workflow Standards\n"));
+        assert!(default.content.contains("**Version:** 2.0.0"));
+        assert!(default.content.contains("[AGENTS_REFERENCE.md](docs/AGENTS_REFERENCE.md)"));
+    }
+}
+
+/// AGENTS.md link rewriting tests
+///
+/// Checks that relative links become absolute paths under the agentsmd home when requested
+/// and in every deployed AGENTS.md.
+#[cfg(test)]
+mod agents_md_link_rewrite_tests {
+    use agentstoolkit_desktop::deployment::{DeploymentConfig, DeploymentManager};
+    use agentstoolkit_desktop::ipc;
+    use agentstoolkit_desktop::types::AgentsMdOptions;
+    use std::fs;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_relative_links_are_rewritten_when_enabled() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path();
+        std::env::set_var("AGENTSMD_HOME", home);
+
+        common::PackFixture::new("docs-pack")
+            .with_name("Docs")
+            .with_rules("# Docs\n\nRead [x](docs/a.md) first.\n")
+            .write(home);
+
+        let pack_ids = vec!["docs-pack".to_string()];
+        let options = AgentsMdOptions {
+            include_metadata: Some(false),
+            inline_content: true,
+            rewrite_links: true,
+            ..Default::default()
+        };
+        let rewritten = ipc::generate_agents_md(pack_ids.clone(), Some(options.clone())).unwrap();
+        assert!(rewritten.success);
+        let expected = format!("[x]({}/docs/a.md)", home.to_string_lossy().trim_end_matches('/'));
+        assert!(rewritten.content.contains(&expected), "{}", rewritten.content);
+        assert!(!rewritten.content.contains("[x](docs/a.md)"));
+
+        let untouched_options = AgentsMdOptions {
+            rewrite_links: false,
+            ..options
+        };
+        let untouched = ipc::generate_agents_md(pack_ids, Some(untouched_options)).unwrap();
+        assert!(untouched.content.contains("[x](docs/a.md)"));
+    }
+
+    #[test]
+    fn test_deployed_agents_md_links_are_rewritten() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path().join("agentsmd");
+        std::env::set_var("HOME", temp.path().join("home"));
+        std::env::set_var("AGENTSMD_HOME", &home);
+
+        common::PackFixture::new("docs-pack")
+            .with_name("Docs")
+            .with_rules("# Docs\n\nRead [x](docs/a.md) first.\n")
+            .write(&home);
+
+        let manager = DeploymentManager::new().unwrap();
+        let config = DeploymentConfig {
+            agent_id: "claude".to_string(),
+            pack_ids: vec!["docs-pack".to_string()],
+            ..Default::default()
+        };
+        manager.deploy(&config).unwrap();
+
+        let deployed = fs::read_to_string(home.join("AGENTS.md")).unwrap();
+        let expected = format!("({}/docs/AGENTS_REFERENCE.md)", home.to_string_lossy());
+        assert!(deployed.contains(&expected), "{}", deployed);
+        assert!(!deployed.contains("](docs/"));
+    }
+}
+
+/// Generated AGENTS.md normalization tests
+///
+/// Checks that the reported budget matches the normalized file that is emitted.
+#[cfg(test)]
+mod agents_md_normalize_tests {
+    use agentstoolkit_desktop::budget;
+    use agentstoolkit_desktop::ipc;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_generated_budget_matches_normalized_content() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        std::env::set_var("AGENTSMD_HOME", temp.path());
+
+        let result = ipc::generate_agents_md(Vec::new(), None).unwrap();
+        assert!(result.success);
+
+        assert!(!result.content.contains("\n\n\n"));
+        assert!(result.content.lines().all(|line| line == line.trim_end()));
+        assert_eq!(result.budget.total_chars, budget::char_count(&result.content));
+    }
+}
+
+/// AGENTS.md split tests
+///
+/// Generates an inline AGENTS.md and splits it back into per-pack sections.
+#[cfg(test)]
+mod agents_md_split_tests {
+    use agentstoolkit_desktop::deployment::converters;
+    use agentstoolkit_desktop::ipc;
+    use agentstoolkit_desktop::types::AgentsMdOptions;
+    use std::path::Path;
+    use tempfile::TempDir;
+    use crate::common;
+
+    fn write_pack(home: &Path, id: &str, version: &str, content: &str) {
+        common::PackFixture::new(id)
+            .with_version(version)
+            .with_description(&format!("{} rules", id))
+            .with_rules(content)
+            .write(home);
+    }
+
+    #[test]
+    fn test_split_inline_agents_md_round_trips() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path();
+        std::env::set_var("AGENTSMD_HOME", home);
+
+        let git_rules = "# Git Workflow\n\n- Branch per issue\n\n---\n\n- Small commits\n";
+        let test_rules = "# Testing\n\nWrite tests first.\n";
+        write_pack(home, "git-workflow", "1.2.0", git_rules);
+        write_pack(home, "testing", "2.0.0", test_rules);
+
+        let options = AgentsMdOptions {
+            inline_content: true,
+            ..Default::default()
+        };
+        let generated =
+            ipc::generate_agents_md(vec!["git-workflow".to_string(), "testing".to_string()], Some(options))
+                .unwrap();
+        assert!(generated.success);
+
+        let sections = converters::split_agents_md(&generated.content).unwrap();
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].pack_id, "git-workflow");
+        assert_eq!(sections[0].version, "1.2.0");
+        assert_eq!(sections[0].content, git_rules);
+        assert_eq!(sections[1].pack_id, "testing");
+        assert_eq!(sections[1].version, "2.0.0");
+        assert_eq!(sections[1].content, test_rules);
+
+        let imports_only = ipc::generate_agents_md(vec!["testing".to_string()], None).unwrap();
+        assert!(converters::split_agents_md(&imports_only.content).is_err());
+    }
+}
+
+/// AGENTS.md table of contents tests
+///
+/// Generates inline AGENTS.md with a TOC and checks each entry links to a pack heading.
+#[cfg(test)]
+mod agents_md_toc_tests {
+    use agentstoolkit_desktop::util;
+    use agentstoolkit_desktop::ipc;
+    use agentstoolkit_desktop::types::AgentsMdOptions;
+    use tempfile::TempDir;
+    use crate::common;
+
+    #[test]
+    fn test_inline_agents_md_toc_links_pack_sections() {
+        let _env = common::lock_env();
+        let temp = TempDir::new().unwrap();
+        let home = temp.path();
+        std::env::set_var("AGENTSMD_HOME", home);
+
+        common::write_pack(home, "git-workflow", "# Git Workflow\n\n- Branch per issue\n");
+        common::write_pack(home, "testing", "# Testing & QA\n\nWrite tests first.\n");
+        common::write_pack(home, "docs", "# Docs (v2)\n\nKeep READMEs current.\n");
+        common::write_pack(home, "qa-extras", "# Testing & QA\n\nRun the smoke suite.\n");
+        common::write_pack(home, "plain", "No headings, just rules.\n");
+        let pack_ids = vec![
+            "git-workflow".to_string(),
+            "testing".to_string(),
+            "docs".to_string(),
+            "qa-extras".to_string(),
+            "plain".to_string(),
+        ];
+
+        let options = AgentsMdOptions {
+            include_metadata: Some(false),
+            inline_content: true,
+            include_toc: true,
+            ..Default::default()
+        };
+        let generated = ipc::generate_agents_md(pack_ids.clone(), Some(options.clone())).unwrap();
+        assert!(generated.success, "{:?}", generated.error);
+
+        let toc: Vec<&str> = generated
+            .content
+            .lines()
+            .skip_while(|line| *line != "## Table of Contents")
+            .skip(1)
+            .filter(|line| !line.is_empty())
+            .take_while(|line| line.starts_with("- "))
+            .collect();
+        // A repeated heading links to the numbered anchor the renderer gives it
+        assert_eq!(
+            toc,
+            vec![
+                "- [Git Workflow](#git-workflow)",
+                "- [Testing & QA](#testing--qa)",
+                "- [Docs (v2)](#docs-v2)",
+                "- [Testing & QA](#testing--qa-1)",
+                "- plain",
+            ]
+        );
+        for heading in ["Git Workflow", "Testing & QA", "Docs (v2)"] {
+            let slug = util::slugify_heading(heading);
+            assert!(toc.iter().any(|entry| entry.ends_with(&format!("(#{})", slug))));
+            assert!(generated.content.contains(&format!("\n# {}\n", heading)));
+        }
+
+        // Imported packs have no sections to link to
+        let imports_options = AgentsMdOptions {
+            inline_content: false,
+            ..options
+        };
+        let imports = ipc::generate_agents_md(pack_ids, Some(imports_options)).unwrap();
+        assert!(!imports.content.contains("## Table of Contents"));
+    }
+}
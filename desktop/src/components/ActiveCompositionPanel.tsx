@@ -6,6 +6,7 @@ import { ValidationAlerts } from './ValidationAlerts';
 import { PresetSelector } from './PresetSelector';
 import { PackExportImport } from './PackExportImport';
 import { packApi } from '@/lib/api';
+import { dependencyId } from '@core/rule-pack-types';
 import { getRecommendedConfigs } from '@/lib/recommendedConfigs';
 
 type ActiveCompositionPanelProps = {
@@ -139,7 +140,7 @@ export function ActiveCompositionPanel({ enabledPackIds }: ActiveCompositionPane
             <div key={pack.id} className="text-sm text-slate-700">
               <span className="font-semibold">{pack.id}</span>
               {pack.dependencies.length > 0 && (
-                <span className="text-slate-500"> → {pack.dependencies.join(' → ')}</span>
+                <span className="text-slate-500"> → {pack.dependencies.map(dependencyId).join(' → ')}</span>
               )}
             </div>
           ))}
@@ -31,6 +31,7 @@ const FORMATS: Array<{ value: FileFormat; label: string }> = [
   { value: 'markdown', label: 'Markdown' },
   { value: 'json', label: 'JSON' },
   { value: 'yaml', label: 'YAML' },
+  { value: 'toml', label: 'TOML' },
   { value: 'text', label: 'Text' },
 ];
 
@@ -146,6 +146,8 @@ function getPlaceholder(format: FileFormat): string {
       return '{\n  "key": "value"\n}';
     case 'yaml':
       return 'key: value\nlist:\n  - item1\n  - item2';
+    case 'toml':
+      return '[section]\nkey = "value"';
     default:
       return 'Enter content here...';
   }
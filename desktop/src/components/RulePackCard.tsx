@@ -1,5 +1,6 @@
 import { Package } from 'lucide-react';
 import type { RulePack } from '@/lib/types';
+import { dependencyId } from '@core/rule-pack-types';
 import { StatusBadge } from './StatusBadge';
 import { CharacterBudget } from './CharacterBudget';
 
@@ -45,8 +46,8 @@ export function RulePackCard({ pack, isEnabled = false, onToggle, onSelect }: Ru
           <p className="text-xs text-slate-500 mb-1">Dependencies:</p>
           <div className="flex flex-wrap gap-1">
             {pack.dependencies.map(dep => (
-              <span key={dep} className="text-xs bg-slate-100 px-2 py-1 rounded">
-                {dep}
+              <span key={dependencyId(dep)} className="text-xs bg-slate-100 px-2 py-1 rounded">
+                {dependencyId(dep)}
               </span>
             ))}
           </div>
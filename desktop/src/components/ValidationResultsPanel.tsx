@@ -92,6 +92,9 @@ export function ValidationResultsPanel({
               </li>
             ))}
           </ul>
+          {report.suggestion && (
+            <p className="mt-2 text-sm text-red-600">{report.suggestion}</p>
+          )}
         </div>
       )}
 
@@ -4,6 +4,8 @@ import { deploymentApi } from '@/lib/api';
 import type { 
   DeploymentConfig, 
   DeploymentOutput, 
+  ErrorCode,
+  IpcError,
   PreparedDeployment, 
   ValidationReport 
 } from '@/lib/types';
@@ -19,10 +21,17 @@ export type DeploymentState =
 
 export interface DeploymentError {
   message: string;
+  /** Error code reported by the backend, when it sent one */
+  code?: ErrorCode;
   details?: string[];
   recoverable: boolean;
 }
 
+/** Whether a rejected invoke value is a structured deployment error */
+function isIpcError(err: unknown): err is IpcError {
+  return typeof err === 'object' && err !== null && 'code' in err && 'message' in err;
+}
+
 interface UseAgentDeploymentReturn {
   /** Current deployment state */
   state: DeploymentState;
@@ -59,6 +68,13 @@ export function useAgentDeployment(): UseAgentDeploymentReturn {
   const isLoading = ['validating', 'previewing', 'deploying'].includes(state);
 
   const parseError = useCallback((err: unknown): DeploymentError => {
+    if (isIpcError(err)) {
+      return {
+        message: err.message,
+        code: err.code,
+        recoverable: err.code !== 'agent_not_found' && err.code !== 'unsupported_operation',
+      };
+    }
     if (err instanceof Error) {
       return {
         message: err.message,
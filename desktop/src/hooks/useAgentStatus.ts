@@ -1,6 +1,6 @@
 import { useQuery, useQueryClient } from '@tanstack/react-query';
 import { deploymentApi } from '@/lib/api';
-import type { AgentStatus } from '@/lib/types';
+import type { AgentDirInfo, AgentStatus } from '@/lib/types';
 
 interface UseAgentStatusOptions {
   /** Polling interval in milliseconds (default: 30000) */
@@ -14,6 +14,8 @@ interface UseAgentStatusOptions {
 interface UseAgentStatusReturn {
   /** Current agent status */
   status: AgentStatus | undefined;
+  /** The agent's config directory, e.g. to warn when it is a symlink */
+  agentDir: AgentDirInfo | undefined;
   /** Whether status is being fetched */
   isLoading: boolean;
   /** Whether status is being refetched in background */
@@ -69,13 +71,14 @@ export function useAgentStatus(
     await queryClient.invalidateQueries({ queryKey });
   };
 
-  const status = data;
+  const status = data?.status;
   const lastUpdated = dataUpdatedAt ? new Date(dataUpdatedAt) : null;
   const statusLabel = status ? STATUS_LABELS[status] : 'Unknown';
   const statusColor = status ? STATUS_COLORS[status] : 'gray';
 
   return {
     status,
+    agentDir: data?.agentDir,
     isLoading,
     isFetching,
     error: error as Error | null,
@@ -114,7 +117,8 @@ export function useAllAgentStatuses(
     const { data, isLoading, error } = useQuery(queries[index]);
     return {
       agentId,
-      status: data,
+      status: data?.status,
+      agentDir: data?.agentDir,
       isLoading,
       error: error as Error | null,
     };
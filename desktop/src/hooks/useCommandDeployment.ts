@@ -2,6 +2,7 @@ import { useState, useCallback } from 'react';
 import { useMutation, useQuery } from '@tanstack/react-query';
 import { commandApi, type CommandMetadata, type CommandBudgetInfo } from '@/lib/commands';
 import { deploymentApi } from '@/lib/api';
+import { formatError } from '@/utils/errorHandling';
 import type { DeploymentConfig, DeploymentOutput, ValidationReport } from '@/lib/types';
 
 interface UseCommandDeploymentResult {
@@ -45,7 +46,7 @@ export function useCommandDeployment(): UseCommandDeploymentResult {
       return deploymentApi.deployToAgent(agentId, config);
     },
     onError: (err) => {
-      setError(err instanceof Error ? err : new Error(String(err)));
+      setError(err instanceof Error ? err : new Error(formatError(err)));
     },
   });
 
@@ -61,7 +62,7 @@ export function useCommandDeployment(): UseCommandDeploymentResult {
       return deploymentApi.validateDeployment(agentId, config);
     },
     onError: (err) => {
-      setError(err instanceof Error ? err : new Error(String(err)));
+      setError(err instanceof Error ? err : new Error(formatError(err)));
     },
   });
 
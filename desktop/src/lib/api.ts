@@ -1,6 +1,7 @@
 import { invoke } from '@tauri-apps/api/core';
 import type {
   AgentDefinition,
+  AgentLinkResult,
   RulePack,
   LoadedPack,
   PackValidationResult,
@@ -11,16 +12,36 @@ import type {
   DeploymentConfig,
   DeploymentOutput,
   PreparedDeployment,
+  CommandPreview,
+  WarpWorkflowPreview,
   ValidationReport,
-  AgentStatus,
+  DeploymentStatusInfo,
   DeploymentState,
+  DeploymentHistoryPage,
+  RollbackPlan,
+  BackupEntry,
+  OutReferencePreview,
+  DeployableAgent,
+  FailedDeployment,
+  AgentValidationResult,
+  AgentRegistryReport,
+  MarginalCost,
+  FullBudgetInfo,
+  PackFit,
+  AgentsMdOptions,
+  ResolvedPack,
+  PackSection,
+  TargetLevel,
+  LinkStrategy,
 } from './types';
 
 // Agent registry API
 export const agentApi = {
   getAllAgents: () => invoke<AgentDefinition[]>('get_all_agents'),
   getAgentById: (id: string) => invoke<AgentDefinition | null>('get_agent_by_id', { id }),
-  validateAgent: (agent: AgentDefinition) => invoke<void>('validate_agent', { agent }),
+  validateAgent: (agent: AgentDefinition) =>
+    invoke<AgentValidationResult>('validate_agent', { agent }),
+  validateRegistry: () => invoke<AgentRegistryReport>('validate_agent_registry'),
 };
 
 // Rule pack API
@@ -29,25 +50,31 @@ export const packApi = {
   loadPack: (packId: string) => invoke<RulePack>('load_pack', { packId }),
   loadPackFull: (packId: string) => invoke<LoadedPack>('load_pack_full', { packId }),
   validatePack: (packId: string) => invoke<PackValidationResult>('validate_pack', { packId }),
+  getRulepackSchema: () => invoke<Record<string, unknown>>('get_rulepack_schema'),
   resolveDependencies: (packId: string) => invoke<DependencyResolution>('resolve_dependencies', { packId }),
+  resolveSelection: (packIds: string[]) => invoke<ResolvedPack[]>('resolve_selection', { packIds }),
   loadPackFile: (packId: string, file: string) =>
     invoke<string>('load_pack_file', { packId, file }),
   updatePackOutReferences: (packId: string, references: string[]) =>
     invoke<RulePack>('update_pack_out_references', { packId, references }),
   calculateBudget: (packIds: string[], agentId?: string | null) =>
     invoke<BudgetInfo>('calculate_budget', { packIds, agentId }),
+  calculateFullBudget: (packIds: string[], commandIds: string[], agentId?: string | null) =>
+    invoke<FullBudgetInfo>('calculate_full_budget', { packIds, commandIds, agentId }),
+  packMarginalCost: (currentPackIds: string[], candidatePackId: string, agentId?: string | null) =>
+    invoke<MarginalCost>('pack_marginal_cost', { currentPackIds, candidatePackId, agentId }),
+  fitPacks: (candidatePackIds: string[], agentId: string) =>
+    invoke<PackFit>('fit_packs', { candidatePackIds, agentId }),
+  budgetForAgents: (packIds: string[], commandIds: string[], agentIds: string[]) =>
+    invoke<Record<string, BudgetInfo>>('budget_for_agents', { packIds, commandIds, agentIds }),
   validateComposition: (packIds: string[], agentId?: string | null) =>
     invoke<ValidationResult>('validate_composition', { packIds, agentId }),
-  generateAgentsMd: (options: {
-    packIds: string[];
-    includeMetadata?: boolean;
-    inlineContent?: boolean;
-  }) =>
-    invoke<GenerateResult>('generate_agents_md', {
-      packIds: options.packIds,
-      includeMetadata: options.includeMetadata,
-      inlineContent: options.inlineContent,
-    }),
+  generateAgentsMd: ({ packIds, ...options }: { packIds: string[] } & AgentsMdOptions) =>
+    invoke<GenerateResult>('generate_agents_md', { packIds, options }),
+  /** Generate AGENTS.md and save it to a path; returns the budget of the written file */
+  exportAgentsMd: (packIds: string[], destPath: string, inlineContent?: boolean, agentId?: string | null) =>
+    invoke<BudgetInfo>('export_agents_md', { packIds, destPath, inlineContent, agentId }),
+  splitAgentsMd: (content: string) => invoke<PackSection[]>('split_agents_md', { content }),
 };
 
 // File system API
@@ -55,13 +82,16 @@ export const fsApi = {
   readAgentsMd: () => invoke<string>('read_agents_md'),
   writeAgentsMd: (content: string) => invoke<void>('write_agents_md', { content }),
   getAgentsMdHome: () => invoke<string>('get_agentsmd_home'),
+  /** Largest pack or out-reference file that will be read, in bytes */
+  getMaxFileSize: () => invoke<number>('get_max_file_size'),
+  setMaxFileSize: (bytes: number) => invoke<void>('set_max_file_size', { bytes }),
   checkAgentInstalled: (agentId: string) => invoke<boolean>('check_agent_installed', { agentId }),
 };
 
 // Legacy symlink API
 export const symlinkApi = {
-  createAgentLink: (agentId: string, force: boolean) => 
-    invoke<[string, string | null]>('create_agent_link', { agentId, force }),
+  createAgentLink: (agentId: string, force: boolean, linkStrategy?: LinkStrategy) => 
+    invoke<AgentLinkResult[]>('create_agent_link', { agentId, force, linkStrategy }),
   removeAgentLink: (agentId: string) => invoke<void>('remove_agent_link', { agentId }),
   checkSymlinkSupport: () => invoke<[boolean, string]>('check_symlink_support'),
 };
@@ -80,21 +110,85 @@ export const deploymentApi = {
   rollbackDeployment: (agentId: string, timestamp?: string) =>
     invoke<void>('rollback_deployment', { agentId, timestamp }),
   
-  /** Get deployment status for an agent */
+  /** List what rolling back a deployment would remove and restore */
+  previewRollback: (agentId: string, timestamp?: string) =>
+    invoke<RollbackPlan>('preview_rollback', { agentId, timestamp }),
+  
+  /** List an agent's backup directories, newest first */
+  listBackups: (agentId: string) =>
+    invoke<BackupEntry[]>('list_backups', { agentId }),
+  
+  /** Restore a backup directory to where its files came from; returns the restored paths */
+  restoreBackup: (agentId: string, backupPath: string) =>
+    invoke<string[]>('restore_backup', { agentId, backupPath }),
+  
+  /** Get deployment status for an agent, including whether its config directory is a symlink */
   getDeploymentStatus: (agentId: string) =>
-    invoke<AgentStatus>('get_deployment_status', { agentId }),
+    invoke<DeploymentStatusInfo>('get_deployment_status', { agentId }),
   
   /** Get deployment history for an agent */
   getDeploymentHistory: (agentId: string) =>
     invoke<DeploymentState[]>('get_deployment_history', { agentId }),
   
+  /** Get a page of deployment history for an agent, newest first */
+  getDeploymentHistoryPage: (agentId: string, offset: number, limit: number) =>
+    invoke<DeploymentHistoryPage>('get_deployment_history_page', { agentId, offset, limit }),
+  
+  /** Collapse repeated identical deployments in an agent's history; returns how many were removed */
+  compactDeploymentHistory: (agentId: string) =>
+    invoke<number>('compact_deployment_history', { agentId }),
+  
+  /** List agents that currently have a deployment recorded */
+  listDeployedAgents: () =>
+    invoke<string[]>('list_deployed_agents'),
+  
+  /** Get the most recent deployments across all agents, newest first */
+  getRecentDeployments: (limit: number) =>
+    invoke<DeploymentState[]>('get_recent_deployments', { limit }),
+  
+  /** Get failed deployment attempts for an agent */
+  getDeploymentFailures: (agentId: string) =>
+    invoke<FailedDeployment[]>('get_deployment_failures', { agentId }),
+  
+  /** Redeploy an agent's latest recorded packs and commands */
+  redeployCurrent: (agentId: string) =>
+    invoke<DeploymentOutput>('redeploy_current', { agentId }),
+  
+  /** Move an agent's latest deployment between user and project level */
+  retargetDeployment: (agentId: string, targetLevel: TargetLevel, projectPath?: string) =>
+    invoke<DeploymentOutput>('retarget_deployment', { agentId, targetLevel, projectPath }),
+  
   /** Preview a deployment without executing it */
   previewDeployment: (agentId: string, config: DeploymentConfig) =>
     invoke<PreparedDeployment>('preview_deployment', { agentId, config }),
   
+  /** Exact content each target path would receive, keyed by path */
+  materializeDeployment: (config: DeploymentConfig) =>
+    invoke<Record<string, string>>('materialize_deployment', { config }),
+  
+  /** List the out-references the selected commands and packs would deploy */
+  previewOutReferences: (commandIds: string[], packIds: string[]) =>
+    invoke<OutReferencePreview[]>('preview_out_references', { commandIds, packIds }),
+  
+  /** Re-copy out-references to an agent's copy-deployed paths */
+  resyncOutReferences: (agentId: string) =>
+    invoke<string[]>('resync_out_references', { agentId }),
+  
+  /** Preview a single command converted for an agent */
+  previewCommandForAgent: (commandId: string, agentId: string) =>
+    invoke<CommandPreview>('preview_command_for_agent', { commandId, agentId }),
+  
+  /** Preview the Warp workflow a command would deploy as, checking that it parses */
+  previewWarpWorkflow: (commandId: string) =>
+    invoke<WarpWorkflowPreview>('preview_warp_workflow', { commandId }),
+  
   /** Get all available agents for deployment */
   getDeployableAgents: () =>
     invoke<string[]>('get_deployable_agents'),
+  
+  /** Get every agent with whether it can take the given deployment config */
+  getDeployableAgentsFor: (config: DeploymentConfig) =>
+    invoke<DeployableAgent[]>('get_deployable_agents_for', { config }),
 };
 
 // Keep old export for backwards compatibility
@@ -20,8 +20,8 @@ export interface CommandMetadata {
   requiresGitHub: boolean;
   /** Array of file paths referenced by this command */
   outReferences: string[];
-  /** Command category */
-  category: 'workflow' | 'git' | 'documentation' | 'utility';
+  /** Command category: 'workflow' | 'git' | 'documentation' | 'utility', or a custom frontmatter value */
+  category: string;
   /** Optional template content for commands that generate files */
   template?: string;
   /** Character count of the command markdown content */
@@ -30,6 +30,35 @@ export interface CommandMetadata {
   wordCount: number;
   /** Path to the source markdown file */
   sourcePath: string;
+  /** Short aliases declared in the command's frontmatter */
+  aliases: string[];
+  /** Folder a nested command lives in ('git' for 'git/status') */
+  subfolder?: string;
+}
+
+/** Server-side filter for listing commands; omitted fields match everything */
+export interface CommandFilter {
+  category?: string;
+  agentId?: string;
+  requiresGitHub?: boolean;
+}
+
+/** One page of filtered commands, sorted by id */
+export interface CommandPage {
+  commands: CommandMetadata[];
+  /** Number of commands matching the filter across all pages */
+  total: number;
+  offset: number;
+  limit: number;
+}
+
+/** Structural problem found in a command's markdown */
+export interface CommandLint {
+  /** Lint rule identifier (e.g. 'missing-description') */
+  rule: string;
+  message: string;
+  /** 1-based line number, when tied to a line */
+  line?: number;
 }
 
 /** Result of command compatibility validation */
@@ -38,6 +67,13 @@ export interface CommandCompatibilityResult {
   reason?: string;
 }
 
+/** Compatibility of a command with one registered agent */
+export interface AgentCommandCompatibility {
+  agentId: string;
+  compatible: boolean;
+  reason?: string;
+}
+
 /** Budget info for commands */
 export interface CommandBudgetItem {
   commandId: string;
@@ -86,6 +122,22 @@ export const commandApi = {
   getCommandsByCategory: (category: string) => 
     invoke<CommandMetadata[]>('get_commands_by_category', { category }),
   
+  /** List one page of commands matching a filter */
+  listCommands: (filter: CommandFilter, offset: number, limit: number) =>
+    invoke<CommandPage>('list_commands', { filter, offset, limit }),
+  
+  /** List the distinct command categories in use */
+  listCommandCategories: () =>
+    invoke<string[]>('list_command_categories'),
+  
+  /** Check a command's markdown structure */
+  lintCommand: (commandId: string) =>
+    invoke<CommandLint[]>('lint_command', { commandId }),
+
+  /** Problems with a command's template section, such as unclosed code fences */
+  validateTemplate: (commandId: string) =>
+    invoke<string[]>('validate_command_template', { commandId }),
+  
   /** Load raw command content (markdown) */
   loadCommandContent: (commandId: string) => 
     invoke<string>('load_command_content', { commandId }),
@@ -93,10 +145,22 @@ export const commandApi = {
   /** Update out-references linked to a command */
   updateOutReferences: (commandId: string, references: string[]) =>
     invoke<CommandMetadata>('update_command_out_references', { commandId, references }),
+
+  /** Drop the override so out-references are read from the command content again */
+  clearOutReferences: (commandId: string) =>
+    invoke<CommandMetadata>('clear_command_out_references', { commandId }),
+
+  /** Rename a command's file, keeping its out-reference override */
+  renameCommand: (oldId: string, newId: string) =>
+    invoke<CommandMetadata>('rename_command', { oldId, newId }),
   
   /** Validate command compatibility with a specific agent */
   validateCommandForAgent: (commandId: string, agentId: string) => 
     invoke<CommandCompatibilityResult>('validate_command_for_agent', { commandId, agentId }),
+
+  /** Check a command against every registered agent */
+  compatibilityMatrix: (commandId: string) =>
+    invoke<AgentCommandCompatibility[]>('command_compatibility_matrix', { commandId }),
   
   /** Calculate budget for a set of commands */
   calculateCommandBudget: (commandIds: string[]) => 
@@ -2,8 +2,10 @@ import { invoke } from '@tauri-apps/api/core';
 import type {
   OutReference,
   OutReferenceValidationReport,
+  FormatValidation,
   ReferenceLink,
   OutReferenceStats,
+  CleanupSuggestion,
 } from './types';
 
 // ============================================================================
@@ -14,9 +16,19 @@ export const outReferenceApi = {
   /** List all out-references */
   listAll: () => invoke<OutReference[]>('list_out_references'),
 
+  /** List out-references with the given format and all of the given tags */
+  listFiltered: (filter: { format?: string | null; tags?: string[] | null }) =>
+    invoke<OutReference[]>('list_out_references_filtered', {
+      format: filter.format ?? null,
+      tags: filter.tags ?? null,
+    }),
+
   /** Get a single out-reference by ID */
   getById: (id: string) => invoke<OutReference>('get_out_reference', { id }),
 
+  /** Get an out-reference by the relative path commands and packs link to */
+  getByPath: (path: string) => invoke<OutReference>('get_out_reference_by_path', { path }),
+
   /** Create a new out-reference */
   create: (params: {
     name: string;
@@ -47,6 +59,14 @@ export const outReferenceApi = {
       tags: updates.tags,
     }),
 
+  /** Track a file a command links to but the store doesn't have yet */
+  importFromCommand: (commandId: string, refPath: string) =>
+    invoke<OutReference>('import_command_out_reference', { commandId, refPath }),
+
+  /** Rename an out-reference's file; pack overrides pointing at it are updated */
+  rename: (id: string, newFileName: string) =>
+    invoke<OutReference>('rename_out_reference', { id, newFileName }),
+
   /** Delete an out-reference */
   delete: (id: string) => invoke<void>('delete_out_reference', { id }),
 
@@ -60,15 +80,41 @@ export const outReferenceApi = {
   /** Validate all out-references */
   validate: () => invoke<OutReferenceValidationReport>('validate_out_references'),
 
+  /** Check that a reference's content parses as its declared format */
+  validateFormat: (id: string) =>
+    invoke<FormatValidation>('validate_out_reference_format', { id }),
+
+  /** Delete untracked files from the out-references directory (dryRun only lists them) */
+  purgeOrphans: (dryRun = false) =>
+    invoke<string[]>('purge_orphaned_out_references', { dryRun }),
+
+  /** Suggest unused references and untracked files to clean up */
+  suggestCleanup: () => invoke<CleanupSuggestion[]>('suggest_out_reference_cleanup'),
+
+  /** Delete selected cleanup suggestions, returning the deleted ids */
+  cleanupUnused: (ids: string[]) => invoke<string[]>('cleanup_unused_out_references', { ids }),
+
   /** Find what references a specific out-reference */
   findReferencesTo: (id: string) => invoke<ReferenceLink[]>('find_references_to', { id }),
 
+  /** Find what references the out-reference at a linked path */
+  findReferencesToPath: (path: string) =>
+    invoke<ReferenceLink[]>('find_references_to_path', { path }),
+
   /** Export out-references to a JSON bundle */
   export: (ids: string[]) => invoke<string>('export_out_references', { ids }),
 
   /** Import out-references from a JSON bundle */
   import: (bundle: string) => invoke<OutReference[]>('import_out_references', { bundle }),
 
+  /** Export out-references to a zip archive at destPath */
+  exportZip: (ids: string[], destPath: string) =>
+    invoke<void>('export_out_references_zip', { ids, destPath }),
+
+  /** Import out-references from a zip archive */
+  importZip: (sourcePath: string) =>
+    invoke<OutReference[]>('import_out_references_zip', { sourcePath }),
+
   /** Get out-reference statistics */
   getStats: () => invoke<OutReferenceStats>('get_out_reference_stats'),
 };
@@ -133,6 +179,7 @@ export function getExtensionFromFormat(format: string): string {
     markdown: 'md',
     json: 'json',
     yaml: 'yaml',
+    toml: 'toml',
     text: 'txt',
   };
   return extensions[format] ?? 'txt';
@@ -147,6 +194,7 @@ export function detectFormatFromExtension(filename: string): string {
     json: 'json',
     yaml: 'yaml',
     yml: 'yaml',
+    toml: 'toml',
     txt: 'text',
   };
   return formats[ext ?? ''] ?? 'text';
@@ -14,6 +14,30 @@ export type {
   CompositionConfig,
 } from '@core/pack-composer-types';
 
+/** Problems found in an agent definition */
+export interface AgentValidationResult {
+  valid: boolean;
+  errors: string[];
+  warnings: string[];
+}
+
+/** Problems found across the whole agent registry */
+export interface AgentRegistryReport {
+  valid: boolean;
+  agentCount: number;
+  errors: string[];
+  warnings: string[];
+}
+
+/** Outcome of linking one of an agent's config paths */
+export interface AgentLinkResult {
+  path: string;
+  /** Link method used, when the link was created */
+  method?: string;
+  warning?: string;
+  error?: string;
+}
+
 // ============================================================================
 // Deployment Types
 // ============================================================================
@@ -35,6 +59,54 @@ export interface DeploymentConfig {
   forceOverwrite: boolean;
   /** Project path for project-level deployments */
   projectPath?: string;
+  /** Add project-level deployed files to the project's .gitignore */
+  updateGitignore?: boolean;
+  /** Fill in missing `name` and `description` command frontmatter from the command itself */
+  fixFrontmatter?: boolean;
+  /** Read deployed files back after writing (default true) */
+  verifyAfterDeploy?: boolean;
+  /** Append out-reference content to AGENTS.md for agents that can't link to it */
+  inlineOutReferences?: boolean;
+  /** Which linking methods deployers may use (default 'auto') */
+  linkStrategy?: LinkStrategy;
+  /** Refuse to deploy when the agent application doesn't appear to be installed */
+  requireAgentInstalled?: boolean;
+  /** Deploy custom commands as skills for agents that support them (Claude) */
+  emitSkills?: boolean;
+  /** How generated instructions are shrunk for agents with tight limits (Copilot) */
+  compaction?: CompactionStrategy;
+}
+
+/** Deploy content as generated, or strip whitespace that doesn't change rendering */
+export type CompactionStrategy = 'none' | 'minify';
+
+/** Symlink with fallbacks, symlink only, or always copy */
+export type LinkStrategy = 'auto' | 'forceSymlink' | 'forceCopy';
+
+/** Stable error codes returned by deployment commands */
+export type ErrorCode =
+  | 'agent_not_found'
+  | 'agent_not_installed'
+  | 'unsupported_operation'
+  | 'validation_failed'
+  | 'budget_exceeded'
+  | 'verification_failed'
+  | 'file_system'
+  | 'format_conversion'
+  | 'configuration'
+  | 'state'
+  | 'backup'
+  | 'rollback_failed'
+  | 'home_dir_unavailable'
+  | 'lock_timeout'
+  | 'serialization'
+  | 'not_initialized'
+  | 'internal';
+
+/** Structured error rejected by deployment commands */
+export interface IpcError {
+  code: ErrorCode;
+  message: string;
 }
 
 /** Result of a successful deployment */
@@ -51,6 +123,59 @@ export interface DeploymentOutput {
   deployedFiles: string[];
   /** Manual steps required (if any) */
   manualSteps: string[];
+  /** Directories created to hold deployed files, removed again on rollback */
+  createdDirs: string[];
+  /** Existing files updated in place, restored from the backup on rollback */
+  modifiedFiles: string[];
+  /** How each out-reference was deployed */
+  outReferenceResults: OutReferenceResult[];
+}
+
+/** How a file was placed at its destination */
+export type LinkMethod = 'symlink' | 'junction' | 'hardlink' | 'copy' | 'existing';
+
+/** Outcome of deploying a single out-reference */
+export interface OutReferenceResult {
+  /** Path relative to the out-references directory */
+  filePath: string;
+  /** How the reference was placed */
+  status: LinkMethod;
+  /** Warning raised while placing it, such as falling back to a copy */
+  warning: string | null;
+}
+
+/** Deployment phase reported by `deployment://progress` events */
+export type DeploymentPhase =
+  | 'prepare'
+  | 'validate'
+  | 'backup'
+  | 'write-agents-md'
+  | 'command'
+  | 'out-reference'
+  | 'done';
+
+/** Payload of `deployment://progress` events */
+export interface DeploymentProgress {
+  /** The agent being deployed to */
+  agentId: string;
+  /** Current phase */
+  phase: DeploymentPhase;
+  /** Step number (1-based) within the whole deployment */
+  current: number;
+  /** Total number of steps, known once the deployment is prepared */
+  total: number;
+  /** Command or out-reference file for per-item phases */
+  item?: string;
+}
+
+/** Payload of `library://changed` events, emitted when command or out-reference files change on disk */
+export interface LibraryChanged {
+  /** Command sources changed (the command cache has been cleared) */
+  commands: boolean;
+  /** Out-reference files changed */
+  outReferences: boolean;
+  /** Changed paths */
+  paths: string[];
 }
 
 /** Prepared deployment artifacts */
@@ -67,6 +192,37 @@ export interface PreparedDeployment {
   characterCount: number;
   /** Format used for commands */
   commandFormat: string;
+  /** Frontmatter keys injected per command file (when fixFrontmatter is enabled) */
+  frontmatterFixes: Record<string, string[]>;
+  /** Problems noticed while preparing that don't stop the deployment */
+  warnings: string[];
+}
+
+/** A single command converted for an agent */
+export interface CommandPreview {
+  /** Filename the command would deploy as */
+  filename: string;
+  /** Converted command content */
+  content: string;
+  /** Format of the converted content */
+  format: FileFormat;
+}
+
+/** Parsed outline of a Warp workflow */
+export interface WarpWorkflowSummary {
+  name: string;
+  description: string;
+  stepCount: number;
+  /** Description of each step, in order */
+  stepDescriptions: (string | null)[];
+}
+
+/** A command converted to a Warp workflow, with its parsed structure */
+export interface WarpWorkflowPreview {
+  filename: string;
+  /** Serialized workflow YAML */
+  content: string;
+  summary: WarpWorkflowSummary;
 }
 
 /** Budget usage information */
@@ -79,6 +235,8 @@ export interface BudgetUsage {
   percentage?: number;
   /** Whether within the limit */
   withinLimit: boolean;
+  /** The validation error reported for exceeding the limit */
+  limitError?: string | null;
 }
 
 /** Validation report */
@@ -91,11 +249,32 @@ export interface ValidationReport {
   warnings: string[];
   /** Budget usage information */
   budgetUsage: BudgetUsage;
+  /** How to get back under the character limit, when over it */
+  suggestion?: string | null;
 }
 
 /** Status of an agent's deployment */
 export type AgentStatus = 'notInstalled' | 'installed' | 'configured' | 'outdated';
 
+/** What an agent's config directory is on disk */
+export interface AgentDirInfo {
+  /** The directory path, after expanding `~` and environment variables */
+  path: string;
+  /** Whether anything exists at the path (following symlinks) */
+  exists: boolean;
+  /** Whether the path itself is a symlink */
+  isSymlink: boolean;
+  /** Where a symlinked directory ultimately points */
+  resolvedTarget?: string;
+}
+
+/** An agent's deployment status along with what its config directory looks like */
+export interface DeploymentStatusInfo {
+  status: AgentStatus;
+  /** The agent's config directory, if its path could be resolved */
+  agentDir?: AgentDirInfo;
+}
+
 /** State of a deployment for history/rollback */
 export interface DeploymentState {
   /** The agent that was deployed to */
@@ -108,14 +287,193 @@ export interface DeploymentState {
   deployedCommands: string[];
   /** Files that were created during deployment */
   filesCreated: string[];
+  /** Hand-edited files left in place instead of being redeployed */
+  filesPreserved: string[];
+  /** Existing files the deployment updated in place, restored rather than removed */
+  filesModified: string[];
+  /** Directories created to hold those files, outermost first */
+  dirsCreated: string[];
   /** Path to backup directory (if any) */
   backupPath?: string;
   /** The deployment method used */
   method: string;
   /** Whether this was a user-level or project-level deployment */
-  targetLevel: string;
+  targetLevel: TargetLevel;
   /** Project path (for project-level deployments) */
   projectPath?: string;
+  /** Content hash of each deployed command file, keyed by path */
+  fileHashes: Record<string, string>;
+  /** Content hash of the generated AGENTS.md */
+  contentHash?: string;
+  /** Options the deployment ran with, repeated by redeploys */
+  options: DeploymentOptions;
+}
+
+/** Deployment options beyond the selection and target, as recorded in deployment state */
+export type DeploymentOptions = Required<
+  Pick<
+    DeploymentConfig,
+    | 'updateGitignore'
+    | 'fixFrontmatter'
+    | 'verifyAfterDeploy'
+    | 'inlineOutReferences'
+    | 'linkStrategy'
+    | 'requireAgentInstalled'
+    | 'emitSkills'
+    | 'compaction'
+  >
+>;
+
+/** One page of deployment history, newest first */
+export interface DeploymentHistoryPage {
+  entries: DeploymentState[];
+  /** Total number of recorded deployments for the agent */
+  total: number;
+  offset: number;
+  limit: number;
+}
+
+/** What rolling back a recorded deployment would change */
+export interface RollbackPlan {
+  agentId: string;
+  /** Timestamp of the deployment being rolled back (ISO timestamp) */
+  timestamp: string;
+  /** Deployed files still on disk that will be removed */
+  filesToRemove: string[];
+  /** Directories created by the deployment, removed when left empty */
+  dirsToRemove: string[];
+  /** Files that will be put back from the deployment's backup */
+  filesToRestore: RestoredFile[];
+  backupPath?: string;
+}
+
+/** A backup directory taken before a deployment */
+export interface BackupEntry {
+  path: string;
+  /** When the backup was taken (ISO timestamp) */
+  timestamp: string;
+  fileCount: number;
+}
+
+/** A file restored from a backup during rollback */
+export interface RestoredFile {
+  /** Where the file is restored to */
+  path: string;
+  /** The backed-up copy it is restored from */
+  backupPath: string;
+}
+
+/** An out-reference a selection of commands and packs would deploy */
+export interface OutReferencePreview {
+  /** Path relative to the out-references directory */
+  filePath: string;
+  /** Absolute path of the source file */
+  sourcePath: string;
+  charCount: number;
+  /** Other references the requested path also matched; the deployment uses this one */
+  ambiguousMatches: string[];
+}
+
+/** Whether an agent can take a deployment config */
+export interface DeployableAgent {
+  agentId: string;
+  deployable: boolean;
+  /** Why the agent is excluded, e.g. "no project-level support" */
+  reason?: string | null;
+}
+
+/** A deployment attempt that failed */
+export interface FailedDeployment {
+  agentId: string;
+  /** When the attempt failed (ISO timestamp) */
+  timestamp: string;
+  /** Phase the deployment failed in */
+  phase: 'prepare' | 'validate' | 'deploy' | 'rollback' | 'backup' | 'restore';
+  error: string;
+}
+
+/** A pack in the effective, dependency-resolved set for a selection */
+export interface ResolvedPack {
+  packId: string;
+  /** Whether the user selected this pack directly */
+  selected: boolean;
+  /** Pack whose dependency list added this one, for packs not selected directly */
+  pulledInBy: string | null;
+}
+
+/** One pack's content recovered from an inline AGENTS.md */
+export interface PackSection {
+  packId: string;
+  version: string;
+  content: string;
+}
+
+// ============================================================================
+// Budget Types
+// ============================================================================
+
+/** Additional budget a candidate pack would add to a composition */
+export interface MarginalCost {
+  candidatePackId: string;
+  /** Packs the candidate would bring in (itself plus new dependencies) */
+  addedPacks: string[];
+  addedChars: number;
+  addedWords: number;
+  /** Total characters with the candidate added */
+  resultingChars: number;
+  /** Percentage of the agent's limit used with the candidate added */
+  resultingPercentage: number | null;
+  withinLimit: boolean;
+}
+
+/** One item's share of a full budget */
+export interface BudgetShare {
+  /** Pack id, command id, or out-reference path */
+  id: string;
+  chars: number;
+  /** Share of the grand total, in percent */
+  percentageOfTotal: number;
+}
+
+/** Budget of a whole deployment: packs, commands, and out-references */
+export interface FullBudgetInfo {
+  totalChars: number;
+  maxChars: number | null;
+  percentage: number | null;
+  withinLimit: boolean;
+  /** Packs in load order, dependencies included */
+  packs: BudgetShare[];
+  commands: BudgetShare[];
+  outReferences: BudgetShare[];
+}
+
+/** How many candidate packs fit within an agent's limit */
+export interface PackFit {
+  /** Candidates that fit, in the order given */
+  fitting: string[];
+  /** The first candidate that would overflow the limit */
+  overflowAt: string | null;
+}
+
+/** Title and version block at the top of a generated AGENTS.md */
+export interface AgentsMdHeader {
+  title: string;
+  version: string;
+  /** Path of the command reference document linked under the version */
+  referenceLink: string;
+}
+
+/** How AGENTS.md is generated from a pack selection */
+export interface AgentsMdOptions {
+  /** Append the pack and budget metadata comment (default true) */
+  includeMetadata?: boolean;
+  inlineContent?: boolean;
+  /** Rewrite relative links to absolute ~/.agentsmd/ paths */
+  rewriteLinks?: boolean;
+  /** Add a table of contents linking each inlined pack section */
+  includeToc?: boolean;
+  /** Title, version, and reference link for the top of the file */
+  header?: AgentsMdHeader;
 }
 
 // ============================================================================
@@ -126,7 +484,7 @@ export interface DeploymentState {
 export type OutReferenceCategory = 'templates' | 'examples' | 'schemas';
 
 /** File format for out-references */
-export type FileFormat = 'markdown' | 'json' | 'yaml' | 'text';
+export type FileFormat = 'markdown' | 'toml' | 'json' | 'yaml' | 'text';
 
 /** Out-reference metadata */
 export interface OutReference {
@@ -166,6 +524,18 @@ export interface OutReferenceValidationReport {
   unusedReferences: string[];
   /** Files on disk not tracked in metadata */
   orphanedFiles: string[];
+  /** References whose content doesn't parse as their declared format */
+  formatErrors: FormatValidation[];
+}
+
+/** Result of parsing an out-reference's content as its declared format */
+export interface FormatValidation {
+  id: string;
+  filePath: string;
+  format: FileFormat;
+  valid: boolean;
+  /** Parser error, when the content is invalid */
+  error?: string;
 }
 
 /** A broken link in the out-reference system */
@@ -192,6 +562,13 @@ export interface ReferenceLink {
   linkCount: number;
 }
 
+/** An out-reference or untracked file that can likely be removed */
+export interface CleanupSuggestion {
+  /** Reference id, or the relative path for an untracked file */
+  id: string;
+  reason: string;
+}
+
 /** Statistics about out-references */
 export interface OutReferenceStats {
   /** Total number of out-references */
@@ -21,5 +21,8 @@ export async function withRetry<T>(fn: () => Promise<T>, options: RetryOptions =
 }
 
 export function formatError(err: unknown): string {
-  return err instanceof Error ? err.message : String(err);
+  if (err instanceof Error) return err.message;
+  // Deployment commands reject with a structured { code, message } error
+  if (typeof err === 'object' && err !== null && 'message' in err) return String(err.message);
+  return String(err);
 }
@@ -60,7 +60,7 @@ export function AgentsView() {
   const statusMap = useMemo(() => {
     const map = new Map<string, AgentStatus>();
     agents.forEach((agent, index) => {
-      const status = statusQueries[index]?.data;
+      const status = statusQueries[index]?.data?.status;
       if (status) {
         map.set(agent.id, status);
       }
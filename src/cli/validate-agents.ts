@@ -11,6 +11,7 @@ import {
   getPacksDirectory
 } from "../core/rule-pack-loader";
 import {calculateBudget, validateComposition} from "../core/pack-composer";
+import {dependencyId} from "../core/rule-pack-types";
 
 type ValidationResult = {
   id: string;
@@ -131,7 +132,7 @@ function printPackBudgets(baseDir?: string): void {
     try {
       const loaded = loadPackFull(pack.id, baseDir);
       const deps = pack.dependencies.length > 0 
-        ? ` (requires: ${pack.dependencies.join(", ")})` 
+        ? ` (requires: ${pack.dependencies.map(dependencyId).join(", ")})` 
         : "";
       console.log(
         `  ${pack.id}${deps}\n` +
@@ -17,6 +17,7 @@ export type AgentDefinition = {
   requiresFrontmatter?: boolean;
   sandboxScriptPath?: string | null;
   notes?: string | null;
+  rulesFilename?: string | null;
 };
 
 const ajv = new Ajv({allErrors: true});
@@ -34,6 +35,7 @@ const AGENTS: AgentDefinition[] = [
     buildOutput: "cursor/commands",
     fileFormat: "markdown",
     requiresFrontmatter: false,
+    rulesFilename: "rules.md",
     notes: "Commands are Markdown files; AGENTS.md via User Rule."
   },
   {
@@ -47,6 +49,7 @@ const AGENTS: AgentDefinition[] = [
     buildOutput: "claude/commands",
     fileFormat: "markdown",
     requiresFrontmatter: true,
+    rulesFilename: "CLAUDE.md",
     notes: "Requires YAML frontmatter in command files."
   },
   {
@@ -60,6 +63,7 @@ const AGENTS: AgentDefinition[] = [
     buildOutput: "copilot/instructions",
     fileFormat: "markdown",
     requiresFrontmatter: false,
+    rulesFilename: "copilot-instructions.md",
     notes: "Instructions live in-repo; no global command execution."
   },
   {
@@ -152,6 +156,7 @@ const AGENTS: AgentDefinition[] = [
     buildOutput: "codex/prompts",
     fileFormat: "markdown",
     requiresFrontmatter: true,
+    rulesFilename: "agents.md",
     notes: "Commands invoked as /prompts:<name>."
   },
   {
@@ -166,6 +171,7 @@ const AGENTS: AgentDefinition[] = [
     fileFormat: "toml",
     requiresFrontmatter: false,
     sandboxScriptPath: "~/.gemini/scripts",
+    rulesFilename: "GEMINI.md",
     notes: "Uses TOML format for commands; sandbox-safe script path."
   },
   {
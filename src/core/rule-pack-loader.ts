@@ -15,6 +15,7 @@ import type {
   PackValidationError,
   DependencyResolution
 } from './rule-pack-types';
+import { dependencyId } from './rule-pack-types';
 import rulePackSchema from '../../schemas/rule-pack.schema.json';
 
 /** Default rule packs directory relative to project root */
@@ -92,7 +93,8 @@ export function resolveDependencies(
     const order: string[] = [];
 
     // Resolve dependencies first
-    for (const depId of pack.dependencies) {
+    for (const dep of pack.dependencies) {
+      const depId = dependencyId(dep);
       const depResult = resolveDependencies(depId, baseDir, new Set(visited), [...path]);
       if (!depResult.success) {
         return depResult;
@@ -218,7 +220,8 @@ export function validatePack(packId: string, baseDir?: string): PackValidationRe
     }
 
     // Check dependencies exist
-    for (const depId of pack.dependencies) {
+    for (const dep of pack.dependencies) {
+      const depId = dependencyId(dep);
       try {
         loadPack(depId, baseDir);
       } catch {
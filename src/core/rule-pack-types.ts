@@ -23,6 +23,18 @@ export interface PackMetadata {
   tags: string[];
 }
 
+/**
+ * Pack dependency: a bare pack ID, or an ID with a semver version requirement
+ */
+export type PackDependency = string | { id: string; versionReq?: string };
+
+/**
+ * Pack ID of a dependency entry
+ */
+export function dependencyId(dep: PackDependency): string {
+  return typeof dep === "string" ? dep : dep.id;
+}
+
 /**
  * Rule pack definition as stored in pack.json
  */
@@ -35,8 +47,8 @@ export interface RulePack {
   version: string;
   /** Purpose and scope of the pack */
   description: string;
-  /** Array of pack IDs this pack requires */
-  dependencies: string[];
+  /** Packs this pack requires, as bare IDs or with a semver requirement */
+  dependencies: PackDependency[];
   /** Array of agent IDs or ['*'] for all agents */
   targetAgents: string[];
   /** Array of markdown files in load order */
@@ -85,6 +97,18 @@ export interface PackValidationResult {
   errors: PackValidationError[];
   /** List of warnings found */
   warnings: PackValidationError[];
+  /** Status of each file listed in pack.json */
+  files?: PackFileStatus[];
+}
+
+/**
+ * Whether a file listed by a pack exists and contributes content
+ */
+export interface PackFileStatus {
+  file: string;
+  exists: boolean;
+  /** The file has content beyond whitespace */
+  nonEmpty: boolean;
 }
 
 /**